@@ -1,11 +1,10 @@
 extern crate capnpc;
 
 fn main() {
-    pkg_config::Config::new().probe("depth-meta").unwrap();
-
     ::capnpc::CompilerCommand::new()
         .src_prefix("src/schema")
         .file("src/schema/camera_meta.capnp")
+        .file("src/schema/imu.capnp")
         .run()
         .expect("compiling schema");
 }
@@ -7,6 +7,6 @@ class GstDepthMetaRs(GstRustProject):
         "rust/[^1.0.0]",
         "capnproto/[^0.8.0]",
     )
-    requires = (
-        f"gst-depth-meta/{branch()}",
-    )
\ No newline at end of file
+
+    def requirements(self):
+        self.requires(f"gst-plugins-base/[~{self.settings.gstreamer}]")
\ No newline at end of file
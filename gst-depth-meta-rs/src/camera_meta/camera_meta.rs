@@ -17,6 +17,49 @@ pub struct CameraMeta {
     pub extrinsics: HashMap<(String, String), Transformation>,
     /// Scaling factor of the depth map, in metres.
     pub depth_scale: f32,
+    /// Identity of the device that produced the calibration.
+    #[serde(default)]
+    pub device: DeviceInfo,
+}
+
+/// Identity of a camera device. Entries are empty if unknown.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    /// Model name of the device.
+    pub model: String,
+    /// Serial number of the device.
+    pub serial_number: String,
+    /// Version of the firmware running on the device.
+    pub firmware_version: String,
+}
+
+impl DeviceInfo {
+    /// Create new DeviceInfo.
+    ///
+    /// # Arguments
+    /// * `model` - Model name of the device.
+    /// * `serial_number` - Serial number of the device.
+    /// * `firmware_version` - Version of the firmware running on the device.
+    ///
+    /// # Returns
+    /// * Newly created DeviceInfo.
+    pub fn new(model: String, serial_number: String, firmware_version: String) -> Self {
+        Self {
+            model,
+            serial_number,
+            firmware_version,
+        }
+    }
+}
+
+impl Display for DeviceInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "model='{}', serial='{}', firmware='{}'",
+            self.model, self.serial_number, self.firmware_version
+        )
+    }
 }
 
 impl Display for CameraMeta {
@@ -37,8 +80,8 @@ impl Display for CameraMeta {
 
         write!(
             f,
-            "Intrinsics:\n{}\nExtrinsics:\n{}\nDepth scale: {}",
-            intrinsics, extrinsics, self.depth_scale
+            "Device: {}\nIntrinsics:\n{}\nExtrinsics:\n{}\nDepth scale: {}",
+            self.device, intrinsics, extrinsics, self.depth_scale
         )
     }
 }
@@ -78,12 +121,51 @@ impl CameraMeta {
             intrinsics,
             extrinsics,
             depth_scale,
+            device: DeviceInfo::default(),
         }
     }
 
-    /// Get extrinsics from `source` to `target` camera. Transformation tree is not followed and
-    /// therefore an explicit transformation from `source` to `target` or from `target` to `source`
-    /// must be contained within CameraMeta.
+    /// Set the identity of the device that produced the calibration.
+    ///
+    /// # Arguments
+    /// * `device` - Identity of the device.
+    ///
+    /// # Returns
+    /// * CameraMeta with the given device identity.
+    pub fn with_device(mut self, device: DeviceInfo) -> Self {
+        self.device = device;
+        self
+    }
+
+    /// Get intrinsics of `camera` that are valid for an image of the given resolution. The stored
+    /// intrinsics are rescaled if their reference resolution differs.
+    ///
+    /// # Arguments
+    /// * `camera` - Camera for which to get the intrinsics.
+    /// * `width` - Width of the image, in pixels.
+    /// * `height` - Height of the image, in pixels.
+    ///
+    /// # Returns
+    /// * `Some(Intrinsics)` valid for the given resolution.
+    /// * `None` if `camera` is unknown or its intrinsics cannot be rescaled because their
+    ///   reference resolution is unknown.
+    pub fn intrinsics_for_resolution(
+        &self,
+        camera: &str,
+        width: u32,
+        height: u32,
+    ) -> Option<Intrinsics> {
+        let intrinsics = self.intrinsics.get(camera)?;
+        if intrinsics.width == width && intrinsics.height == height {
+            Some(intrinsics.clone())
+        } else {
+            intrinsics.rescaled(width, height)
+        }
+    }
+
+    /// Get extrinsics from `source` to `target` camera. Transformation graph is followed, so that
+    /// extrinsics are found even if only a chain of transformations connects `source` and `target`.
+    /// See [resolve_extrinsics](#method.resolve_extrinsics) for details about the failure.
     ///
     /// # Arguments
     /// * `source` - Source coordinate frame.
@@ -92,13 +174,7 @@ impl CameraMeta {
     /// # Returns
     /// * Transformation from `source` to `target`, if known.
     pub fn get_extrinsics(&self, source: String, target: String) -> Option<Transformation> {
-        if let Some(transformation) = self.extrinsics.get(&(source.clone(), target.clone())) {
-            Some(transformation.clone())
-        } else {
-            self.extrinsics
-                .get(&(target, source))
-                .map(|transformation| transformation.inverse())
-        }
+        self.resolve_extrinsics(&source, &target).ok()
     }
 
     /// Serialise CameraMeta by the use of Cap'n Proto.
@@ -129,6 +205,8 @@ impl CameraMeta {
                 intrinsics_instance.set_fy(camera_intrinsics.fy);
                 intrinsics_instance.set_cx(camera_intrinsics.cx);
                 intrinsics_instance.set_cy(camera_intrinsics.cy);
+                intrinsics_instance.set_width(camera_intrinsics.width);
+                intrinsics_instance.set_height(camera_intrinsics.height);
 
                 // Set the corresponding distortion as union
                 let mut union_builder = capnp::message::Builder::new_default();
@@ -214,6 +292,12 @@ impl CameraMeta {
 
             // Set the depth scale
             camera_meta_serialised.set_depth_scale(self.depth_scale);
+
+            // Set the device identity
+            let mut device = camera_meta_serialised.reborrow().init_device();
+            device.set_model(&self.device.model);
+            device.set_serial_number(&self.device.serial_number);
+            device.set_firmware_version(&self.device.firmware_version);
         }
 
         let mut serialised_config: Vec<u8> = Vec::new();
@@ -329,6 +413,8 @@ impl CameraMeta {
                     cx: intrinsics_instance.get_cx(),
                     cy: intrinsics_instance.get_cy(),
                     distortion,
+                    width: intrinsics_instance.get_width(),
+                    height: intrinsics_instance.get_height(),
                 },
             );
         }
@@ -364,11 +450,20 @@ impl CameraMeta {
             );
         }
 
+        // Deserialise device identity, which is empty for CameraMeta serialised without it
+        let serialised_device = camera_meta.get_device()?;
+        let device = DeviceInfo {
+            model: serialised_device.get_model()?.to_string(),
+            serial_number: serialised_device.get_serial_number()?.to_string(),
+            firmware_version: serialised_device.get_firmware_version()?.to_string(),
+        };
+
         // Return CameraMeta containing the deserialised properties
         Ok(Self {
             extrinsics,
             intrinsics,
             depth_scale: camera_meta.get_depth_scale(),
+            device,
         })
     }
 }
@@ -376,6 +471,7 @@ impl CameraMeta {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::tests::nearly_equal_f32;
     use rand::random;
     const TEST_ITERATIONS: usize = 100;
 
@@ -396,6 +492,8 @@ mod tests {
             fy: random(),
             cx: random(),
             cy: random(),
+            width: random(),
+            height: random(),
             distortion: Distortion::None,
         };
         intrinsics.insert("depth".to_string(), intrinsics_depth);
@@ -405,6 +503,8 @@ mod tests {
             fy: random(),
             cx: random(),
             cy: random(),
+            width: random(),
+            height: random(),
             distortion: Distortion::RsBrownConrady {
                 0: RsCoefficients {
                     a1: random(),
@@ -422,6 +522,8 @@ mod tests {
             fy: random(),
             cx: random(),
             cy: random(),
+            width: random(),
+            height: random(),
             distortion: Distortion::K4aBrownConrady {
                 0: K4aCoefficients {
                     k1: random(),
@@ -489,6 +591,11 @@ mod tests {
             intrinsics,
             extrinsics,
             depth_scale: random(),
+            device: DeviceInfo::new(
+                "Intel RealSense D435".to_string(),
+                random::<u32>().to_string(),
+                "05.12.07.100".to_string(),
+            ),
         }
     }
 
@@ -506,6 +613,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn deserialise_without_resolution_and_device() {
+        // Serialise CameraMeta by the use of the schema fields that preceded resolution and device
+        let mut builder = capnp::message::Builder::new_default();
+        {
+            let mut camera_meta_serialised = builder.init_root::<camera_meta::Builder>();
+            let mut intrinsics_instance =
+                camera_meta_serialised.reborrow().init_intrinsics(1).get(0);
+            intrinsics_instance.set_camera("depth");
+            intrinsics_instance.set_fx(615.0);
+            intrinsics_instance.set_fy(616.0);
+            intrinsics_instance.set_cx(320.5);
+            intrinsics_instance.set_cy(240.25);
+            intrinsics_instance.get_distortion().set_none(());
+            camera_meta_serialised.set_depth_scale(0.001);
+        }
+        let mut serialised_camera_meta: Vec<u8> = Vec::new();
+        capnp::serialize::write_message(&mut serialised_camera_meta, &builder).unwrap();
+
+        let camera_meta = CameraMeta::deserialise(&serialised_camera_meta).unwrap();
+        let intrinsics = &camera_meta.intrinsics["depth"];
+        assert!(!intrinsics.has_resolution());
+        assert_eq!(
+            *intrinsics,
+            Intrinsics::new(615.0, 616.0, 320.5, 240.25, Distortion::None)
+        );
+        assert_eq!(camera_meta.device, DeviceInfo::default());
+        assert!(camera_meta
+            .intrinsics_for_resolution("depth", 320, 240)
+            .is_none());
+    }
+
+    #[test]
+    fn intrinsics_for_resolution() {
+        let mut intrinsics = HashMap::new();
+        intrinsics.insert(
+            "color".to_string(),
+            Intrinsics::new(615.0, 616.0, 320.5, 240.25, Distortion::None)
+                .with_resolution(640, 480),
+        );
+        let camera_meta = CameraMeta::new(intrinsics, HashMap::new(), 0.001);
+
+        assert_eq!(
+            camera_meta.intrinsics_for_resolution("color", 640, 480),
+            camera_meta.intrinsics.get("color").cloned()
+        );
+        let rescaled = camera_meta
+            .intrinsics_for_resolution("color", 1280, 960)
+            .unwrap();
+        assert!(nearly_equal_f32(rescaled.fx, 1230.0));
+        assert!(nearly_equal_f32(rescaled.cx, 641.5));
+        assert!(camera_meta
+            .intrinsics_for_resolution("depth", 640, 480)
+            .is_none());
+    }
+
     #[test]
     fn get_extrinsics_depth_ir_color_and_vice_versa() {
         let camera_meta = initialise_random_camera_meta();
@@ -0,0 +1,448 @@
+use crate::camera_meta::CameraMeta;
+use crate::transformation::*;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+/// Default tolerance used when checking extrinsics for inconsistent cycles.
+pub const DEFAULT_EXTRINSICS_TOLERANCE: f32 = 1e-4;
+
+/// ExtrinsicsError represents all possible errors that may occur while resolving extrinsics
+/// across the transformation graph of a CameraMeta.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtrinsicsError {
+    /// The coordinate frame is not contained in neither intrinsics nor extrinsics.
+    UnknownFrame(String),
+    /// There is no chain of extrinsics that connects `source` and `target` coordinate frames.
+    Disconnected {
+        /// Source coordinate frame.
+        source: String,
+        /// Target coordinate frame.
+        target: String,
+        /// All coordinate frames that can be reached from `source`, including `source` itself.
+        reachable: Vec<String>,
+    },
+    /// The stored extrinsics from `source` to `target` disagree with the chain of other extrinsics
+    /// that connect the same coordinate frames.
+    InconsistentCycle {
+        /// Source coordinate frame of the conflicting extrinsics.
+        source: String,
+        /// Target coordinate frame of the conflicting extrinsics.
+        target: String,
+        /// Euclidean distance between the stored and the chained translation, in metres.
+        translation_error: f32,
+        /// Frobenius norm of the difference between the stored and the chained rotation.
+        rotation_error: f32,
+    },
+    /// The stored extrinsics from `source` to `target` are not a valid rigid body transformation.
+    InvalidTransformation {
+        /// Source coordinate frame of the invalid extrinsics.
+        source: String,
+        /// Target coordinate frame of the invalid extrinsics.
+        target: String,
+        /// Reason for the extrinsics being invalid.
+        error: RotationError,
+    },
+}
+impl std::error::Error for ExtrinsicsError {}
+impl std::fmt::Display for ExtrinsicsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtrinsicsError::UnknownFrame(frame) => {
+                write!(f, "camera_meta: Unknown coordinate frame `{}`", frame)
+            }
+            ExtrinsicsError::Disconnected {
+                source,
+                target,
+                reachable,
+            } => write!(
+                f,
+                "camera_meta: No extrinsics connect `{}` to `{}`. Frames reachable from `{}`: {:?}",
+                source, target, source, reachable
+            ),
+            ExtrinsicsError::InconsistentCycle {
+                source,
+                target,
+                translation_error,
+                rotation_error,
+            } => write!(
+                f,
+                "camera_meta: Extrinsics `{}` -> `{}` are inconsistent with the rest of the \
+                 transformation graph (translation error: {}, rotation error: {})",
+                source, target, translation_error, rotation_error
+            ),
+            ExtrinsicsError::InvalidTransformation {
+                source,
+                target,
+                error,
+            } => write!(
+                f,
+                "camera_meta: Extrinsics `{}` -> `{}` are invalid: {}",
+                source, target, error
+            ),
+        }
+    }
+}
+
+/// Adjacency list of the transformation graph. Each stored extrinsics entry is represented by
+/// two directed edges, where the reverse edge carries the inverted Transformation.
+type ExtrinsicsGraph<'a> = BTreeMap<&'a str, Vec<(&'a str, Transformation)>>;
+
+impl CameraMeta {
+    /// Resolve extrinsics from `source` to `target` camera by following the transformation graph.
+    /// Transformations along the shortest chain of known extrinsics are inverted and composed as
+    /// needed. Explicit extrinsics from `source` to `target` (or its inverse) take precedence.
+    ///
+    /// # Arguments
+    /// * `source` - Source coordinate frame.
+    /// * `target` - Target coordinate frame.
+    ///
+    /// # Returns
+    /// * `Ok(Transformation)` from `source` to `target` on success.
+    /// * `Err(ExtrinsicsError::UnknownFrame)` if either of the frames is not known.
+    /// * `Err(ExtrinsicsError::Disconnected)` if there is no chain between the two frames.
+    pub fn resolve_extrinsics(
+        &self,
+        source: &str,
+        target: &str,
+    ) -> Result<Transformation, ExtrinsicsError> {
+        let frames = self.frames();
+        for frame in [source, target] {
+            if !frames.contains(frame) {
+                return Err(ExtrinsicsError::UnknownFrame(frame.to_string()));
+            }
+        }
+
+        if source == target {
+            return Ok(Transformation::default());
+        }
+
+        // Prefer the explicit entries, so that no rounding error is introduced by the search
+        let key = (source.to_string(), target.to_string());
+        if let Some(transformation) = self.extrinsics.get(&key) {
+            return Ok(transformation.clone());
+        }
+        if let Some(transformation) = self.extrinsics.get(&(key.1, key.0)) {
+            return Ok(transformation.inverse());
+        }
+
+        // Breadth-first search, where pose of each visited frame is kept relative to `source`
+        let poses = Self::poses_relative_to(&self.extrinsics_graph(), source);
+        poses
+            .get(target)
+            .cloned()
+            .ok_or_else(|| ExtrinsicsError::Disconnected {
+                source: source.to_string(),
+                target: target.to_string(),
+                reachable: poses.keys().map(|frame| frame.to_string()).collect(),
+            })
+    }
+
+    /// Group all coordinate frames of CameraMeta into sets that are connected by extrinsics.
+    /// CameraMeta with a fully connected transformation graph results in a single group.
+    ///
+    /// # Returns
+    /// * Sorted groups of mutually reachable coordinate frames.
+    pub fn frame_groups(&self) -> Vec<Vec<String>> {
+        let graph = self.extrinsics_graph();
+        let mut visited: BTreeSet<&str> = BTreeSet::new();
+        let mut groups = Vec::new();
+
+        for frame in self.frames() {
+            if visited.contains(frame) {
+                continue;
+            }
+            let group = Self::poses_relative_to(&graph, frame);
+            visited.extend(group.keys());
+            groups.push(group.keys().map(|frame| frame.to_string()).collect());
+        }
+
+        groups
+    }
+
+    /// Make sure that all extrinsics within CameraMeta are valid rigid body transformations and
+    /// that they agree with each other, i.e. that every cycle in the transformation graph
+    /// composes into identity.
+    ///
+    /// # Arguments
+    /// * `tolerance` - Maximum allowed translation (in metres) and rotation (Frobenius norm)
+    ///   error, see [DEFAULT_EXTRINSICS_TOLERANCE](constant.DEFAULT_EXTRINSICS_TOLERANCE.html).
+    ///   Rotations are validated with [DEFAULT_ROTATION_TOLERANCE](constant.DEFAULT_ROTATION_TOLERANCE.html).
+    ///
+    /// # Returns
+    /// * `Ok()` if the extrinsics are consistent.
+    /// * `Err(ExtrinsicsError::InvalidTransformation)` for the first extrinsics that are invalid.
+    /// * `Err(ExtrinsicsError::InconsistentCycle)` for the first extrinsics that do not agree.
+    pub fn validate_extrinsics(&self, tolerance: f32) -> Result<(), ExtrinsicsError> {
+        let mut extrinsics: Vec<_> = self.extrinsics.iter().collect();
+        extrinsics.sort_by(|a, b| a.0.cmp(b.0));
+
+        // Composing invalid rotations would make the cycle check meaningless
+        for ((source, target), transformation) in extrinsics.iter() {
+            transformation
+                .validate(DEFAULT_ROTATION_TOLERANCE)
+                .map_err(|error| ExtrinsicsError::InvalidTransformation {
+                    source: source.clone(),
+                    target: target.clone(),
+                    error,
+                })?;
+        }
+
+        let graph = self.extrinsics_graph();
+
+        // Span a tree over each group of frames, in which poses are relative to the group's root
+        let mut poses: HashMap<&str, Transformation> = HashMap::new();
+        for frame in graph.keys() {
+            if !poses.contains_key(frame) {
+                poses.extend(Self::poses_relative_to(&graph, frame));
+            }
+        }
+
+        // Every stored extrinsics must match `source` -> root -> `target`
+        for ((source, target), transformation) in extrinsics {
+            let chained = poses[source.as_str()]
+                .inverse()
+                .then(&poses[target.as_str()]);
+            let (translation_error, rotation_error) = Self::difference(transformation, &chained);
+            if translation_error > tolerance || rotation_error > tolerance {
+                return Err(ExtrinsicsError::InconsistentCycle {
+                    source: source.clone(),
+                    target: target.clone(),
+                    translation_error,
+                    rotation_error,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get all coordinate frames that are referenced by either intrinsics or extrinsics.
+    fn frames(&self) -> BTreeSet<&str> {
+        self.intrinsics
+            .keys()
+            .map(String::as_str)
+            .chain(
+                self.extrinsics
+                    .keys()
+                    .flat_map(|(source, target)| [source.as_str(), target.as_str()]),
+            )
+            .collect()
+    }
+
+    /// Build adjacency list of the transformation graph with deterministic ordering of edges.
+    fn extrinsics_graph(&self) -> ExtrinsicsGraph<'_> {
+        let mut graph = ExtrinsicsGraph::new();
+        for frame in self.frames() {
+            graph.insert(frame, Vec::new());
+        }
+
+        let mut extrinsics: Vec<_> = self.extrinsics.iter().collect();
+        extrinsics.sort_by(|a, b| a.0.cmp(b.0));
+        for ((source, target), transformation) in extrinsics {
+            if let Some(edges) = graph.get_mut(source.as_str()) {
+                edges.push((target.as_str(), transformation.clone()));
+            }
+            if let Some(edges) = graph.get_mut(target.as_str()) {
+                edges.push((source.as_str(), transformation.inverse()));
+            }
+        }
+
+        graph
+    }
+
+    /// Breadth-first traversal of `graph` from `root`, which determines the shortest chain of
+    /// transformations from `root` to every reachable frame.
+    ///
+    /// # Returns
+    /// * Transformation from `root` to each reachable frame, including `root` itself.
+    fn poses_relative_to<'a>(
+        graph: &ExtrinsicsGraph<'a>,
+        root: &'a str,
+    ) -> BTreeMap<&'a str, Transformation> {
+        let mut poses = BTreeMap::new();
+        poses.insert(root, Transformation::default());
+
+        let mut queue = VecDeque::from([root]);
+        while let Some(frame) = queue.pop_front() {
+            let pose = poses[frame].clone();
+            for (neighbour, edge) in graph.get(frame).into_iter().flatten() {
+                if !poses.contains_key(neighbour) {
+                    poses.insert(*neighbour, pose.then(edge));
+                    queue.push_back(*neighbour);
+                }
+            }
+        }
+
+        poses
+    }
+
+    /// Compute translation and rotation error between two Transformations.
+    fn difference(a: &Transformation, b: &Transformation) -> (f32, f32) {
+        let (ta, tb) = (&a.translation, &b.translation);
+        let translation_error =
+            ((ta.x - tb.x).powi(2) + (ta.y - tb.y).powi(2) + (ta.z - tb.z).powi(2)).sqrt();
+
+        let (ra, rb) = (&a.rotation, &b.rotation);
+        let rotation_error = [
+            ra.r11 - rb.r11,
+            ra.r12 - rb.r12,
+            ra.r13 - rb.r13,
+            ra.r21 - rb.r21,
+            ra.r22 - rb.r22,
+            ra.r23 - rb.r23,
+            ra.r31 - rb.r31,
+            ra.r32 - rb.r32,
+            ra.r33 - rb.r33,
+        ]
+        .iter()
+        .map(|d| d * d)
+        .sum::<f32>()
+        .sqrt();
+
+        (translation_error, rotation_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intrinsics::*;
+
+    /// Create Transformation that rotates around z axis by `angle` radians and then translates.
+    fn transformation_z(angle: f32, x: f32, y: f32, z: f32) -> Transformation {
+        let (sin, cos) = angle.sin_cos();
+        Transformation::new(
+            Translation::new(x, y, z),
+            RotationMatrix::new(cos, -sin, 0.0, sin, cos, 0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    /// Initialise CameraMeta with extrinsics `depth` -> `infra2`, `depth` -> `color` and an
+    /// isolated `imu` frame.
+    fn initialise_camera_meta() -> CameraMeta {
+        let mut intrinsics = HashMap::new();
+        for camera in ["depth", "infra2", "color", "imu"] {
+            intrinsics.insert(
+                camera.to_string(),
+                Intrinsics::new(1.0, 1.0, 0.0, 0.0, Distortion::None),
+            );
+        }
+
+        let mut extrinsics = HashMap::new();
+        extrinsics.insert(
+            ("depth".to_string(), "infra2".to_string()),
+            transformation_z(0.0, -0.05, 0.0, 0.0),
+        );
+        extrinsics.insert(
+            ("depth".to_string(), "color".to_string()),
+            transformation_z(0.1, 0.015, 0.001, -0.002),
+        );
+
+        CameraMeta::new(intrinsics, extrinsics, 0.001)
+    }
+
+    #[test]
+    fn resolve_through_common_frame() {
+        let camera_meta = initialise_camera_meta();
+
+        let infra2_color = camera_meta.resolve_extrinsics("infra2", "color").unwrap();
+        let expected = camera_meta.extrinsics[&("depth".to_string(), "infra2".to_string())]
+            .inverse()
+            .then(&camera_meta.extrinsics[&("depth".to_string(), "color".to_string())]);
+
+        let (translation_error, rotation_error) = CameraMeta::difference(&infra2_color, &expected);
+        assert!(translation_error < DEFAULT_EXTRINSICS_TOLERANCE);
+        assert!(rotation_error < DEFAULT_EXTRINSICS_TOLERANCE);
+
+        // Going there and back again must result in identity
+        let color_infra2 = camera_meta.resolve_extrinsics("color", "infra2").unwrap();
+        let (translation_error, rotation_error) = CameraMeta::difference(
+            &infra2_color.then(&color_infra2),
+            &Transformation::default(),
+        );
+        assert!(translation_error < DEFAULT_EXTRINSICS_TOLERANCE);
+        assert!(rotation_error < DEFAULT_EXTRINSICS_TOLERANCE);
+
+        assert!(camera_meta
+            .get_extrinsics("infra2".to_string(), "color".to_string())
+            .is_some());
+    }
+
+    #[test]
+    fn resolve_disconnected_and_unknown() {
+        let camera_meta = initialise_camera_meta();
+
+        assert_eq!(
+            camera_meta.resolve_extrinsics("color", "imu"),
+            Err(ExtrinsicsError::Disconnected {
+                source: "color".to_string(),
+                target: "imu".to_string(),
+                reachable: vec![
+                    "color".to_string(),
+                    "depth".to_string(),
+                    "infra2".to_string()
+                ],
+            })
+        );
+        assert_eq!(
+            camera_meta.resolve_extrinsics("depth", "fisheye"),
+            Err(ExtrinsicsError::UnknownFrame("fisheye".to_string()))
+        );
+        assert_eq!(
+            camera_meta.frame_groups(),
+            vec![
+                vec![
+                    "color".to_string(),
+                    "depth".to_string(),
+                    "infra2".to_string()
+                ],
+                vec!["imu".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_cycles() {
+        let mut camera_meta = initialise_camera_meta();
+        assert!(camera_meta
+            .validate_extrinsics(DEFAULT_EXTRINSICS_TOLERANCE)
+            .is_ok());
+
+        // Closing the cycle with consistent extrinsics is fine
+        let infra2_color = camera_meta.resolve_extrinsics("infra2", "color").unwrap();
+        camera_meta.extrinsics.insert(
+            ("infra2".to_string(), "color".to_string()),
+            infra2_color.clone(),
+        );
+        assert!(camera_meta
+            .validate_extrinsics(DEFAULT_EXTRINSICS_TOLERANCE)
+            .is_ok());
+
+        // Closing the cycle with extrinsics that disagree is detected
+        let mut inconsistent = infra2_color;
+        inconsistent.translation.x += 0.01;
+        camera_meta
+            .extrinsics
+            .insert(("infra2".to_string(), "color".to_string()), inconsistent);
+        match camera_meta.validate_extrinsics(DEFAULT_EXTRINSICS_TOLERANCE) {
+            Err(ExtrinsicsError::InconsistentCycle {
+                translation_error, ..
+            }) => assert!((translation_error - 0.01).abs() < DEFAULT_EXTRINSICS_TOLERANCE),
+            other => panic!("Expected inconsistent cycle, got {:?}", other),
+        }
+
+        // Extrinsics with scaled rotation are detected before the cycles are checked
+        camera_meta
+            .extrinsics
+            .get_mut(&("depth".to_string(), "color".to_string()))
+            .unwrap()
+            .rotation
+            .r11 *= 1.1;
+        assert!(matches!(
+            camera_meta.validate_extrinsics(DEFAULT_EXTRINSICS_TOLERANCE),
+            Err(ExtrinsicsError::InvalidTransformation {
+                error: RotationError::NotOrthonormal { .. },
+                ..
+            })
+        ));
+    }
+}
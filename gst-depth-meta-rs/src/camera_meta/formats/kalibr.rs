@@ -0,0 +1,374 @@
+use super::*;
+use crate::camera_meta::CameraMeta;
+use crate::transformation::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Single camera of Kalibr camchain YAML.
+#[derive(Debug, Deserialize)]
+struct KalibrCamera {
+    /// Projection model of the camera, only `pinhole` is supported.
+    camera_model: String,
+    /// Intrinsics of the projection model, i.e. `[fu, fv, pu, pv]` for `pinhole`.
+    intrinsics: Vec<f32>,
+    /// Distortion model, i.e. `none`, `radtan`, `equidistant` or `fov`.
+    distortion_model: String,
+    /// Distortion coefficients of the model.
+    distortion_coeffs: Vec<f32>,
+    /// Resolution of the camera in the form of `[width, height]`.
+    resolution: [u32; 2],
+    /// ROS topic of the camera images.
+    #[serde(default)]
+    rostopic: Option<String>,
+    /// Homogeneous transformation from the previous camera of the chain into this camera.
+    #[serde(rename = "T_cn_cnm1", default)]
+    t_cn_cnm1: Option<Vec<Vec<f32>>>,
+}
+
+impl KalibrCamera {
+    /// Determine the name of the camera. It is the namespace that contains the image topic, e.g.
+    /// `color` for `/camera/color/image_raw`. The `key` of the camera is used if there is no topic.
+    ///
+    /// # Arguments
+    /// * `key` - Key of the camera in the camchain, e.g. `cam0`.
+    ///
+    /// # Returns
+    /// * Name of the camera.
+    fn name(&self, key: &str) -> String {
+        self.rostopic
+            .as_ref()
+            .and_then(|topic| topic.trim_matches('/').rsplit('/').nth(1))
+            .unwrap_or(key)
+            .to_string()
+    }
+
+    /// Convert the camera into Intrinsics.
+    ///
+    /// # Returns
+    /// * `Ok(Intrinsics)` on success.
+    /// * `Err(CalibrationFormatError)` if the camera is invalid or cannot be represented.
+    fn intrinsics(&self) -> Result<Intrinsics, CalibrationFormatError> {
+        if self.camera_model != "pinhole" {
+            return Err(CalibrationFormatError::UnsupportedModel(
+                self.camera_model.clone(),
+            ));
+        }
+        if self.intrinsics.len() != 4 {
+            return Err(CalibrationFormatError::Invalid(format!(
+                "pinhole intrinsics must contain 4 entries, but they contain {}",
+                self.intrinsics.len()
+            )));
+        }
+
+        let expected_coefficients = match self.distortion_model.as_str() {
+            "none" => 0,
+            "radtan" | "equidistant" => 4,
+            "fov" => 1,
+            model => return Err(CalibrationFormatError::UnsupportedModel(model.to_string())),
+        };
+        if self.distortion_coeffs.len() != expected_coefficients {
+            return Err(CalibrationFormatError::Invalid(format!(
+                "`{}` distortion must contain {} coefficients, but it contains {}",
+                self.distortion_model,
+                expected_coefficients,
+                self.distortion_coeffs.len()
+            )));
+        }
+
+        let c = &self.distortion_coeffs;
+        let distortion = match self.distortion_model.as_str() {
+            "radtan" => distortion_from_ros("plumb_bob", c)?,
+            "equidistant" => distortion_from_ros("equidistant", c)?,
+            "fov" => Distortion::RsFTheta(RsCoefficients::new(c[0], 0.0, 0.0, 0.0, 0.0)),
+            _ => Distortion::None,
+        };
+
+        let i = &self.intrinsics;
+        Ok(Intrinsics::new(i[0], i[1], i[2], i[3], distortion)
+            .with_resolution(self.resolution[0], self.resolution[1]))
+    }
+
+    /// Convert `T_cn_cnm1` into Transformation from the previous camera into this camera.
+    ///
+    /// # Returns
+    /// * `Ok(Some(Transformation))` if the camera contains `T_cn_cnm1`.
+    /// * `Ok(None)` if it does not.
+    /// * `Err(CalibrationFormatError::Invalid)` if it is not a homogeneous transformation.
+    fn transformation(&self) -> Result<Option<Transformation>, CalibrationFormatError> {
+        let t = match &self.t_cn_cnm1 {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+        if t.len() != 4
+            || t.iter().any(|row| row.len() != 4)
+            || t[3]
+                .iter()
+                .zip([0.0, 0.0, 0.0, 1.0].iter())
+                .any(|(actual, expected)| (actual - expected).abs() > REPRESENTATION_TOLERANCE)
+        {
+            return Err(CalibrationFormatError::Invalid(format!(
+                "`T_cn_cnm1` must be a 4x4 homogeneous transformation, but it is {:?}",
+                t
+            )));
+        }
+
+        Ok(Some(Transformation::new(
+            Translation::new(t[0][3], t[1][3], t[2][3]),
+            RotationMatrix::new(
+                t[0][0], t[0][1], t[0][2], t[1][0], t[1][1], t[1][2], t[2][0], t[2][1], t[2][2],
+            ),
+        )))
+    }
+}
+
+/// Convert distortion into Kalibr distortion model and coefficients.
+///
+/// # Arguments
+/// * `distortion` - Distortion to convert.
+///
+/// # Returns
+/// * `Ok((&str, Vec<f32>))` containing the model name and its coefficients.
+/// * `Err(CalibrationFormatError::Lossy)` if the distortion has no Kalibr equivalent.
+fn distortion_to_kalibr(
+    distortion: &Distortion,
+) -> Result<(&'static str, Vec<f32>), CalibrationFormatError> {
+    match distortion {
+        Distortion::None => Ok(("none", Vec::new())),
+        Distortion::RsBrownConrady(c) => {
+            ensure_zero_coefficients("radtan", &[c.a5])?;
+            Ok(("radtan", vec![c.a1, c.a2, c.a3, c.a4]))
+        }
+        Distortion::RsKannalaBrandt4(c) => {
+            ensure_zero_coefficients("equidistant", &[c.a5])?;
+            Ok(("equidistant", vec![c.a1, c.a2, c.a3, c.a4]))
+        }
+        // Kalibr FOV model is the same as RealSense F-Theta
+        Distortion::RsFTheta(c) => {
+            ensure_zero_coefficients("fov", &[c.a2, c.a3, c.a4, c.a5])?;
+            Ok(("fov", vec![c.a1]))
+        }
+        distortion => Err(CalibrationFormatError::Lossy(format!(
+            "distortion `{}` has no equivalent in Kalibr",
+            distortion
+        ))),
+    }
+}
+
+impl CameraMeta {
+    /// Create CameraMeta from Kalibr camchain YAML. Cameras are named by the namespace that
+    /// contains their image topic, e.g. `color` for `/camera/color/image_raw`, or by their key if
+    /// there is no topic. Extrinsics are created between every pair of consecutive cameras of the
+    /// chain. Camera to IMU calibration is not represented by CameraMeta and is ignored.
+    ///
+    /// # Arguments
+    /// * `yaml` - Content of the camchain YAML file.
+    /// * `depth_scale` - Scaling factor of the depth map, in metres, which is not part of camchain.
+    ///
+    /// # Returns
+    /// * `Ok(CameraMeta)` on success.
+    /// * `Err(CalibrationFormatError)` if the content is invalid or cannot be represented.
+    pub fn from_kalibr_camchain(
+        yaml: &str,
+        depth_scale: f32,
+    ) -> Result<Self, CalibrationFormatError> {
+        let camchain: HashMap<String, KalibrCamera> = serde_yaml::from_str(yaml)?;
+
+        // Order cameras by their index within the chain
+        let mut ordered_cameras = BTreeMap::new();
+        for (key, camera) in camchain.iter() {
+            let index = key
+                .strip_prefix("cam")
+                .and_then(|index| index.parse::<usize>().ok())
+                .ok_or_else(|| {
+                    CalibrationFormatError::Invalid(format!(
+                        "camchain key `{}` is not in the form of `cam<index>`",
+                        key
+                    ))
+                })?;
+            ordered_cameras.insert(index, (camera.name(key), camera));
+        }
+
+        let mut intrinsics = HashMap::new();
+        let mut extrinsics = HashMap::new();
+        let mut previous: Option<&String> = None;
+        for (index, (name, camera)) in ordered_cameras.iter() {
+            if intrinsics
+                .insert(name.clone(), camera.intrinsics()?)
+                .is_some()
+            {
+                return Err(CalibrationFormatError::Invalid(format!(
+                    "camera `{}` of `cam{}` is contained in camchain more than once",
+                    name, index
+                )));
+            }
+
+            match (previous, camera.transformation()?) {
+                (Some(previous), Some(transformation)) => {
+                    extrinsics.insert((previous.clone(), name.clone()), transformation);
+                }
+                (Some(_), None) => {
+                    return Err(CalibrationFormatError::Invalid(format!(
+                        "`cam{}` is missing `T_cn_cnm1`",
+                        index
+                    )))
+                }
+                (None, _) => {}
+            }
+            previous = Some(name);
+        }
+
+        Ok(CameraMeta::new(intrinsics, extrinsics, depth_scale))
+    }
+
+    /// Convert CameraMeta into Kalibr camchain YAML, with `cameras` in the given order. Extrinsics
+    /// between consecutive cameras are resolved across the transformation graph. Depth scale and
+    /// device identity are not part of camchain.
+    ///
+    /// # Arguments
+    /// * `cameras` - Cameras to include in the camchain, in the order of the chain.
+    ///
+    /// # Returns
+    /// * `Ok(String)` containing the camchain YAML.
+    /// * `Err(CalibrationFormatError)` if any of the cameras cannot be converted.
+    pub fn to_kalibr_camchain(&self, cameras: &[&str]) -> Result<String, CalibrationFormatError> {
+        let mut yaml = String::new();
+        for (index, camera) in cameras.iter().enumerate() {
+            let intrinsics = self
+                .intrinsics
+                .get(*camera)
+                .ok_or_else(|| CalibrationFormatError::UnknownCamera(camera.to_string()))?;
+            if !intrinsics.has_resolution() {
+                return Err(CalibrationFormatError::MissingResolution(
+                    camera.to_string(),
+                ));
+            }
+            let (model, coefficients) = distortion_to_kalibr(&intrinsics.distortion)?;
+
+            yaml.push_str(&format!(
+                "cam{}:
+  camera_model: pinhole
+  intrinsics: {}
+  distortion_model: {}
+  distortion_coeffs: {}
+  resolution: [{}, {}]
+  rostopic: /{}/image_raw
+",
+                index,
+                format_sequence(&[intrinsics.fx, intrinsics.fy, intrinsics.cx, intrinsics.cy]),
+                model,
+                format_sequence(&coefficients),
+                intrinsics.width,
+                intrinsics.height,
+                camera
+            ));
+
+            if index > 0 {
+                let t = self.resolve_extrinsics(cameras[index - 1], camera)?;
+                let (r, t) = (t.rotation, t.translation);
+                yaml.push_str(&format!(
+                    "  T_cn_cnm1:\n  - {}\n  - {}\n  - {}\n  - {}\n",
+                    format_sequence(&[r.r11, r.r12, r.r13, t.x]),
+                    format_sequence(&[r.r21, r.r22, r.r23, t.y]),
+                    format_sequence(&[r.r31, r.r32, r.r33, t.z]),
+                    format_sequence(&[0.0, 0.0, 0.0, 1.0])
+                ));
+            }
+        }
+        Ok(yaml)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Camchain as produced by Kalibr for a stereo setup.
+    const CAMCHAIN: &str = "cam0:
+  cam_overlaps: [1]
+  camera_model: pinhole
+  distortion_coeffs: [-0.28, 0.07, 0.0002, 0.00002]
+  distortion_model: radtan
+  intrinsics: [461.6, 460.3, 363.0, 248.1]
+  resolution: [752, 480]
+  rostopic: /cam0/image_raw
+cam1:
+  T_cn_cnm1:
+  - [0.99999, 0.0023, 0.0003, -0.11]
+  - [-0.0023, 0.99996, 0.0086, 0.0004]
+  - [-0.0003, -0.0086, 0.99996, -0.0008]
+  - [0.0, 0.0, 0.0, 1.0]
+  T_cam_imu:
+  - [0.01, 0.99, 0.0, 0.05]
+  - [-0.99, 0.01, 0.02, -0.06]
+  - [0.02, -0.0, 0.99, 0.0]
+  - [0.0, 0.0, 0.0, 1.0]
+  cam_overlaps: [0]
+  camera_model: pinhole
+  distortion_coeffs: [0.9]
+  distortion_model: fov
+  intrinsics: [457.6, 456.1, 379.9, 255.2]
+  resolution: [752, 480]
+  rostopic: /camera/right/image_raw
+";
+
+    #[test]
+    fn kalibr_import() {
+        let camera_meta = CameraMeta::from_kalibr_camchain(CAMCHAIN, 0.001).unwrap();
+
+        assert_eq!(
+            camera_meta.intrinsics["cam0"],
+            Intrinsics::new(
+                461.6,
+                460.3,
+                363.0,
+                248.1,
+                Distortion::RsBrownConrady(RsCoefficients::new(-0.28, 0.07, 0.0002, 0.00002, 0.0)),
+            )
+            .with_resolution(752, 480)
+        );
+        assert_eq!(
+            camera_meta.intrinsics["right"].distortion,
+            Distortion::RsFTheta(RsCoefficients::new(0.9, 0.0, 0.0, 0.0, 0.0))
+        );
+
+        let transformation = &camera_meta.extrinsics[&("cam0".to_string(), "right".to_string())];
+        assert_eq!(
+            transformation.translation,
+            Translation::new(-0.11, 0.0004, -0.0008)
+        );
+        assert_eq!(transformation.rotation.r23, 0.0086);
+    }
+
+    #[test]
+    fn kalibr_round_trip() {
+        let camera_meta = CameraMeta::from_kalibr_camchain(CAMCHAIN, 0.001).unwrap();
+        let yaml = camera_meta.to_kalibr_camchain(&["cam0", "right"]).unwrap();
+        assert_eq!(
+            CameraMeta::from_kalibr_camchain(&yaml, 0.001).unwrap(),
+            camera_meta
+        );
+    }
+
+    #[test]
+    fn kalibr_errors() {
+        let camera_meta = CameraMeta::from_kalibr_camchain(CAMCHAIN, 0.001).unwrap();
+        assert_eq!(
+            camera_meta.to_kalibr_camchain(&["cam0", "left"]),
+            Err(CalibrationFormatError::UnknownCamera("left".to_string()))
+        );
+
+        // Radtan of Kalibr does not support the third radial coefficient
+        let mut lossy = camera_meta;
+        lossy.intrinsics.get_mut("cam0").unwrap().distortion =
+            Distortion::RsBrownConrady(RsCoefficients::new(-0.28, 0.07, 0.0, 0.0, 0.01));
+        assert!(matches!(
+            lossy.to_kalibr_camchain(&["cam0"]),
+            Err(CalibrationFormatError::Lossy(_))
+        ));
+
+        assert_eq!(
+            CameraMeta::from_kalibr_camchain(&CAMCHAIN.replace("pinhole", "omni"), 0.001),
+            Err(CalibrationFormatError::UnsupportedModel("omni".to_string()))
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! Conversion of CameraMeta from and into calibration formats of other tools, i.e. OpenCV
+//! `FileStorage` YAML, ROS `sensor_msgs/CameraInfo` YAML and Kalibr camchain YAML.
+//!
+//! Conversions never silently drop calibration parameters. If the target format cannot represent
+//! the calibration exactly, e.g. RealSense distortion model that has no equivalent in the target
+//! format, `CalibrationFormatError::Lossy` is returned instead.
+pub mod kalibr;
+pub mod opencv;
+pub mod ros;
+
+use crate::extrinsics::ExtrinsicsError;
+use crate::intrinsics::*;
+use serde::Deserialize;
+
+/// Tolerance used when checking whether matrix entries that cannot be represented are zero.
+const REPRESENTATION_TOLERANCE: f32 = 1e-6;
+
+/// CalibrationFormatError represents all possible errors that may occur while converting
+/// CameraMeta from or into calibration formats of other tools.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalibrationFormatError {
+    /// The input could not be parsed as YAML of the expected structure.
+    Parse(String),
+    /// The input is valid YAML, but its content is not a valid calibration.
+    Invalid(String),
+    /// The input utilises a camera or distortion model that is not supported.
+    UnsupportedModel(String),
+    /// The conversion would lose calibration parameters that cannot be represented in the target.
+    Lossy(String),
+    /// The requested camera is not contained in CameraMeta.
+    UnknownCamera(String),
+    /// The target format requires image resolution, but the intrinsics of the camera do not have it.
+    MissingResolution(String),
+    /// The target format requires extrinsics that cannot be resolved from CameraMeta.
+    Extrinsics(ExtrinsicsError),
+}
+
+impl std::error::Error for CalibrationFormatError {}
+
+impl std::fmt::Display for CalibrationFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CalibrationFormatError::Parse(msg) => write!(f, "Cannot parse calibration: {}", msg),
+            CalibrationFormatError::Invalid(msg) => write!(f, "Invalid calibration: {}", msg),
+            CalibrationFormatError::UnsupportedModel(model) => {
+                write!(f, "Unsupported camera or distortion model `{}`", model)
+            }
+            CalibrationFormatError::Lossy(msg) => write!(f, "Lossy conversion: {}", msg),
+            CalibrationFormatError::UnknownCamera(camera) => {
+                write!(f, "Camera `{}` is not contained in CameraMeta", camera)
+            }
+            CalibrationFormatError::MissingResolution(camera) => write!(
+                f,
+                "Resolution of camera `{}` is unknown, but it is required by the format",
+                camera
+            ),
+            CalibrationFormatError::Extrinsics(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<ExtrinsicsError> for CalibrationFormatError {
+    fn from(err: ExtrinsicsError) -> Self {
+        CalibrationFormatError::Extrinsics(err)
+    }
+}
+
+impl From<serde_yaml::Error> for CalibrationFormatError {
+    fn from(err: serde_yaml::Error) -> Self {
+        CalibrationFormatError::Parse(err.to_string())
+    }
+}
+
+/// Matrix in the form that is shared by OpenCV `FileStorage` and ROS CameraInfo YAML.
+#[derive(Debug, Deserialize)]
+struct YamlMatrix {
+    /// Number of rows.
+    rows: usize,
+    /// Number of columns.
+    cols: usize,
+    /// Entries of the matrix in row-major order.
+    data: Vec<f32>,
+}
+
+impl YamlMatrix {
+    /// Get the entries of the matrix, while making sure that it has the expected dimensions.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the matrix, used for error reporting.
+    /// * `rows` - Expected number of rows.
+    /// * `cols` - Expected number of columns.
+    ///
+    /// # Returns
+    /// * `Ok(&[f32])` containing `rows * cols` entries in row-major order.
+    /// * `Err(CalibrationFormatError::Invalid)` if the dimensions do not match.
+    fn data(&self, name: &str, rows: usize, cols: usize) -> Result<&[f32], CalibrationFormatError> {
+        if self.rows != rows || self.cols != cols || self.data.len() != rows * cols {
+            return Err(CalibrationFormatError::Invalid(format!(
+                "`{}` must be a {}x{} matrix, but it is {}x{} with {} entries",
+                name,
+                rows,
+                cols,
+                self.rows,
+                self.cols,
+                self.data.len()
+            )));
+        }
+        Ok(&self.data)
+    }
+
+    /// Get the entries of a vector, which can be stored either as a single row or a single column.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the vector, used for error reporting.
+    ///
+    /// # Returns
+    /// * `Ok(&[f32])` containing the entries.
+    /// * `Err(CalibrationFormatError::Invalid)` if the matrix is not a vector.
+    fn vector(&self, name: &str) -> Result<&[f32], CalibrationFormatError> {
+        if (self.rows != 1 && self.cols != 1) || self.data.len() != self.rows * self.cols {
+            return Err(CalibrationFormatError::Invalid(format!(
+                "`{}` must be a vector, but it is {}x{} with {} entries",
+                name,
+                self.rows,
+                self.cols,
+                self.data.len()
+            )));
+        }
+        Ok(&self.data)
+    }
+}
+
+/// Format entries of a matrix or vector as YAML flow sequence.
+///
+/// # Arguments
+/// * `data` - Entries to format.
+///
+/// # Returns
+/// * String in the form of `[ a, b, c ]`.
+fn format_sequence(data: &[f32]) -> String {
+    format!(
+        "[ {} ]",
+        data.iter()
+            .map(|value| format!("{:?}", value))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+/// Create intrinsics from the entries of a 3x3 camera matrix in row-major order.
+///
+/// # Arguments
+/// * `camera_matrix` - Entries of the camera matrix.
+/// * `distortion` - The distortion model of the camera optics.
+///
+/// # Returns
+/// * `Ok(Intrinsics)` on success.
+/// * `Err(CalibrationFormatError::Lossy)` if the camera matrix contains skew.
+/// * `Err(CalibrationFormatError::Invalid)` if the camera matrix is not a valid camera matrix.
+fn intrinsics_from_camera_matrix(
+    camera_matrix: &[f32],
+    distortion: Distortion,
+) -> Result<Intrinsics, CalibrationFormatError> {
+    if camera_matrix[1].abs() > REPRESENTATION_TOLERANCE {
+        return Err(CalibrationFormatError::Lossy(format!(
+            "camera matrix has skew of {}, which cannot be represented by Intrinsics",
+            camera_matrix[1]
+        )));
+    }
+    if camera_matrix[3].abs() > REPRESENTATION_TOLERANCE
+        || camera_matrix[6].abs() > REPRESENTATION_TOLERANCE
+        || camera_matrix[7].abs() > REPRESENTATION_TOLERANCE
+        || (camera_matrix[8] - 1.0).abs() > REPRESENTATION_TOLERANCE
+    {
+        return Err(CalibrationFormatError::Invalid(format!(
+            "{:?} is not a valid camera matrix",
+            camera_matrix
+        )));
+    }
+
+    Ok(Intrinsics::new(
+        camera_matrix[0],
+        camera_matrix[4],
+        camera_matrix[2],
+        camera_matrix[5],
+        distortion,
+    ))
+}
+
+/// Get the entries of a 3x3 camera matrix in row-major order that corresponds to `intrinsics`.
+///
+/// # Arguments
+/// * `intrinsics` - Intrinsics to convert.
+///
+/// # Returns
+/// * Entries of the camera matrix.
+fn camera_matrix_from_intrinsics(intrinsics: &Intrinsics) -> [f32; 9] {
+    [
+        intrinsics.fx,
+        0.0,
+        intrinsics.cx,
+        0.0,
+        intrinsics.fy,
+        intrinsics.cy,
+        0.0,
+        0.0,
+        1.0,
+    ]
+}
+
+/// Make sure that all `coefficients` that cannot be represented by the target model are zero.
+///
+/// # Arguments
+/// * `model` - Name of the target model, used for error reporting.
+/// * `coefficients` - Coefficients that would be dropped by the conversion.
+///
+/// # Returns
+/// * `Ok()` if all coefficients are zero.
+/// * `Err(CalibrationFormatError::Lossy)` otherwise.
+fn ensure_zero_coefficients(
+    model: &str,
+    coefficients: &[f32],
+) -> Result<(), CalibrationFormatError> {
+    if coefficients
+        .iter()
+        .any(|coefficient| coefficient.abs() > REPRESENTATION_TOLERANCE)
+    {
+        return Err(CalibrationFormatError::Lossy(format!(
+            "distortion coefficients {:?} cannot be represented by `{}` model",
+            coefficients, model
+        )));
+    }
+    Ok(())
+}
+
+/// Convert distortion into a model name and coefficients in the convention that is shared by
+/// OpenCV and ROS, i.e. `plumb_bob`, `rational_polynomial` and `equidistant`.
+///
+/// # Arguments
+/// * `distortion` - Distortion to convert.
+///
+/// # Returns
+/// * `Ok((&str, Vec<f32>))` containing the model name and its coefficients.
+/// * `Err(CalibrationFormatError::Lossy)` if the distortion has no equivalent model.
+fn distortion_to_ros(
+    distortion: &Distortion,
+) -> Result<(&'static str, Vec<f32>), CalibrationFormatError> {
+    match distortion {
+        Distortion::None => Ok(("plumb_bob", vec![0.0; 5])),
+        // RealSense Brown-Conrady is equivalent to OpenCV model with `[k1, k2, p1, p2, k3]`
+        Distortion::RsBrownConrady(c) => Ok(("plumb_bob", vec![c.a1, c.a2, c.a3, c.a4, c.a5])),
+        Distortion::K4aBrownConrady(c) => Ok((
+            "rational_polynomial",
+            vec![c.k1, c.k2, c.p1, c.p2, c.k3, c.k4, c.k5, c.k6],
+        )),
+        Distortion::RsKannalaBrandt4(c) => {
+            ensure_zero_coefficients("equidistant", &[c.a5])?;
+            Ok(("equidistant", vec![c.a1, c.a2, c.a3, c.a4]))
+        }
+        distortion => Err(CalibrationFormatError::Lossy(format!(
+            "distortion `{}` has no equivalent in OpenCV and ROS",
+            distortion
+        ))),
+    }
+}
+
+/// Convert a model name and coefficients in the convention that is shared by OpenCV and ROS into
+/// distortion. Missing trailing coefficients are treated as zero.
+///
+/// # Arguments
+/// * `model` - Name of the distortion model.
+/// * `coefficients` - Distortion coefficients.
+///
+/// # Returns
+/// * `Ok(Distortion)` on success.
+/// * `Err(CalibrationFormatError::UnsupportedModel)` if the model is not known.
+/// * `Err(CalibrationFormatError::Lossy)` if the coefficients cannot be represented.
+fn distortion_from_ros(
+    model: &str,
+    coefficients: &[f32],
+) -> Result<Distortion, CalibrationFormatError> {
+    let padded = |length: usize| -> Result<Vec<f32>, CalibrationFormatError> {
+        if coefficients.len() > length {
+            ensure_zero_coefficients(model, &coefficients[length..])?;
+        }
+        let mut padded = coefficients
+            .iter()
+            .copied()
+            .take(length)
+            .collect::<Vec<f32>>();
+        padded.resize(length, 0.0);
+        Ok(padded)
+    };
+
+    match model {
+        "plumb_bob" => {
+            let c = padded(5)?;
+            if c.iter().all(|coefficient| *coefficient == 0.0) {
+                Ok(Distortion::None)
+            } else {
+                Ok(Distortion::RsBrownConrady(RsCoefficients::new(
+                    c[0], c[1], c[2], c[3], c[4],
+                )))
+            }
+        }
+        "rational_polynomial" => {
+            let c = padded(8)?;
+            Ok(Distortion::K4aBrownConrady(K4aCoefficients::new(
+                c[0], c[1], c[4], c[5], c[6], c[7], c[2], c[3],
+            )))
+        }
+        "equidistant" => {
+            let c = padded(4)?;
+            Ok(Distortion::RsKannalaBrandt4(RsCoefficients::new(
+                c[0], c[1], c[2], c[3], 0.0,
+            )))
+        }
+        model => Err(CalibrationFormatError::UnsupportedModel(model.to_string())),
+    }
+}
@@ -0,0 +1,184 @@
+use super::*;
+use serde::Deserialize;
+
+/// Calibration of a single camera in OpenCV `FileStorage` YAML, as written by OpenCV calibration
+/// samples.
+#[derive(Debug, Deserialize)]
+struct OpenCvCalibration {
+    /// 3x3 camera matrix.
+    camera_matrix: YamlMatrix,
+    /// Distortion coefficients in OpenCV order, i.e. `[k1, k2, p1, p2, k3, k4, k5, k6, ...]`.
+    distortion_coefficients: YamlMatrix,
+    /// Optional distortion model in ROS convention. OpenCV itself does not store it, in which case
+    /// it is determined from the number of coefficients.
+    #[serde(default)]
+    distortion_model: Option<String>,
+    /// Width of the calibrated image, in pixels.
+    #[serde(default)]
+    image_width: u32,
+    /// Height of the calibrated image, in pixels.
+    #[serde(default)]
+    image_height: u32,
+}
+
+impl Intrinsics {
+    /// Create Intrinsics from OpenCV `FileStorage` YAML that contains `camera_matrix` and
+    /// `distortion_coefficients`. Without `distortion_model` entry, coefficients are interpreted
+    /// as `plumb_bob` if there are at most 5 of them, or as `rational_polynomial` otherwise.
+    ///
+    /// # Arguments
+    /// * `yaml` - Content of the OpenCV YAML file.
+    ///
+    /// # Returns
+    /// * `Ok(Intrinsics)` on success.
+    /// * `Err(CalibrationFormatError)` if the content is invalid or cannot be represented.
+    pub fn from_opencv_yaml(yaml: &str) -> Result<Self, CalibrationFormatError> {
+        // OpenCV writes `%YAML:1.0` directive, which is not valid YAML
+        let yaml = yaml
+            .lines()
+            .filter(|line| !line.starts_with("%YAML"))
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let calibration: OpenCvCalibration = serde_yaml::from_str(&yaml)?;
+
+        let coefficients = calibration
+            .distortion_coefficients
+            .vector("distortion_coefficients")?;
+        let model = match &calibration.distortion_model {
+            Some(model) => model.as_str(),
+            None if coefficients.len() > 5 => "rational_polynomial",
+            None => "plumb_bob",
+        };
+
+        Ok(intrinsics_from_camera_matrix(
+            calibration.camera_matrix.data("camera_matrix", 3, 3)?,
+            distortion_from_ros(model, coefficients)?,
+        )?
+        .with_resolution(calibration.image_width, calibration.image_height))
+    }
+
+    /// Convert Intrinsics into OpenCV `FileStorage` YAML, which can be read by `cv::FileStorage`.
+    /// Image resolution is included only if it is known.
+    ///
+    /// # Returns
+    /// * `Ok(String)` containing the OpenCV YAML.
+    /// * `Err(CalibrationFormatError::Lossy)` if the distortion has no OpenCV equivalent.
+    pub fn to_opencv_yaml(&self) -> Result<String, CalibrationFormatError> {
+        let (model, coefficients) = distortion_to_ros(&self.distortion)?;
+
+        let mut yaml = "%YAML:1.0\n---\n".to_string();
+        if self.has_resolution() {
+            yaml.push_str(&format!(
+                "image_width: {}\nimage_height: {}\n",
+                self.width, self.height
+            ));
+        }
+        yaml.push_str(&format!(
+            "camera_matrix: !!opencv-matrix\n   rows: 3\n   cols: 3\n   dt: f\n   data: {}\n",
+            format_sequence(&camera_matrix_from_intrinsics(self))
+        ));
+        yaml.push_str(&format!("distortion_model: {}\n", model));
+        yaml.push_str(&format!(
+            "distortion_coefficients: !!opencv-matrix\n   rows: 1\n   cols: {}\n   dt: f\n   data: {}\n",
+            coefficients.len(),
+            format_sequence(&coefficients)
+        ));
+        Ok(yaml)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opencv_round_trip() {
+        let distortions = [
+            Distortion::None,
+            Distortion::RsBrownConrady(RsCoefficients::new(0.1, -0.05, 0.001, -0.002, 0.01)),
+            Distortion::K4aBrownConrady(K4aCoefficients::new(
+                0.5, -2.6, 1.5, 0.4, -2.4, 1.4, 0.0007, -0.0002,
+            )),
+            Distortion::RsKannalaBrandt4(RsCoefficients::new(-0.01, 0.04, -0.04, 0.007, 0.0)),
+        ];
+        for distortion in distortions.iter() {
+            let intrinsics = Intrinsics::new(615.1, 616.2, 320.5, 240.25, distortion.clone())
+                .with_resolution(640, 480);
+            let yaml = intrinsics.to_opencv_yaml().unwrap();
+            assert_eq!(Intrinsics::from_opencv_yaml(&yaml).unwrap(), intrinsics);
+        }
+    }
+
+    #[test]
+    fn opencv_from_calibration_sample() {
+        let yaml = "%YAML:1.0
+---
+calibration_time: \"Mon 01 Jan 2021 10:00:00 CET\"
+image_width: 1280
+image_height: 720
+camera_matrix: !!opencv-matrix
+   rows: 3
+   cols: 3
+   dt: d
+   data: [ 9.1e+02, 0., 6.4e+02, 0., 9.2e+02,
+       3.6e+02, 0., 0., 1. ]
+distortion_coefficients: !!opencv-matrix
+   rows: 5
+   cols: 1
+   dt: d
+   data: [ 1.0e-01, -2.0e-01, 0., 0., 0. ]
+";
+        let intrinsics = Intrinsics::from_opencv_yaml(yaml).unwrap();
+        assert_eq!(
+            intrinsics,
+            Intrinsics::new(
+                910.0,
+                920.0,
+                640.0,
+                360.0,
+                Distortion::RsBrownConrady(RsCoefficients::new(0.1, -0.2, 0.0, 0.0, 0.0)),
+            )
+            .with_resolution(1280, 720)
+        );
+    }
+
+    #[test]
+    fn opencv_lossy_conversion() {
+        let f_theta = Intrinsics::new(
+            615.0,
+            616.0,
+            320.5,
+            240.25,
+            Distortion::RsFTheta(RsCoefficients::new(0.9, 0.0, 0.0, 0.0, 0.0)),
+        );
+        assert!(matches!(
+            f_theta.to_opencv_yaml(),
+            Err(CalibrationFormatError::Lossy(_))
+        ));
+
+        // Thin prism coefficients cannot be represented by any Distortion
+        let thin_prism = "camera_matrix:
+   rows: 3
+   cols: 3
+   data: [ 615., 0., 320., 0., 616., 240., 0., 0., 1. ]
+distortion_coefficients:
+   rows: 1
+   cols: 12
+   data: [ 0.1, 0.01, 0., 0., 0.001, 0., 0., 0., 0.01, 0., 0., 0. ]
+";
+        assert!(matches!(
+            Intrinsics::from_opencv_yaml(thin_prism),
+            Err(CalibrationFormatError::Lossy(_))
+        ));
+
+        // Skew cannot be represented by Intrinsics
+        let skew = thin_prism
+            .replace("615., 0., 320.", "615., 0.5, 320.")
+            .replace("12", "5")
+            .replace(", 0., 0., 0., 0.01, 0., 0., 0. ]", " ]");
+        assert!(matches!(
+            Intrinsics::from_opencv_yaml(&skew),
+            Err(CalibrationFormatError::Lossy(_))
+        ));
+    }
+}
@@ -0,0 +1,206 @@
+use super::*;
+use serde::Deserialize;
+
+/// Identity rotation matrix in row-major order.
+const IDENTITY: [f32; 9] = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+
+/// `sensor_msgs/CameraInfo` in the YAML form of `camera_calibration_parsers`.
+#[derive(Debug, Deserialize)]
+struct RosCameraInfo {
+    /// Width of the calibrated image, in pixels.
+    image_width: u32,
+    /// Height of the calibrated image, in pixels.
+    image_height: u32,
+    /// Name of the camera.
+    #[serde(default)]
+    camera_name: String,
+    /// 3x3 camera matrix.
+    camera_matrix: YamlMatrix,
+    /// Distortion model, e.g. `plumb_bob`.
+    distortion_model: String,
+    /// Distortion coefficients of the model.
+    distortion_coefficients: YamlMatrix,
+    /// 3x3 rectification matrix of stereo cameras.
+    #[serde(default)]
+    rectification_matrix: Option<YamlMatrix>,
+    /// 3x4 projection matrix of the rectified image.
+    #[serde(default)]
+    projection_matrix: Option<YamlMatrix>,
+}
+
+impl Intrinsics {
+    /// Create Intrinsics from ROS `sensor_msgs/CameraInfo` YAML, as written by
+    /// `camera_calibration_parsers`. The projection matrix of the rectified image is derived from
+    /// the intrinsics and is therefore not imported. However, stereo rectification and baseline
+    /// cannot be represented, so they must be identity and zero respectively.
+    ///
+    /// # Arguments
+    /// * `yaml` - Content of the CameraInfo YAML file.
+    ///
+    /// # Returns
+    /// * `Ok((String, Intrinsics))` containing the name of the camera and its intrinsics.
+    /// * `Err(CalibrationFormatError)` if the content is invalid or cannot be represented.
+    pub fn from_ros_camera_info(yaml: &str) -> Result<(String, Self), CalibrationFormatError> {
+        let camera_info: RosCameraInfo = serde_yaml::from_str(yaml)?;
+
+        if let Some(rectification_matrix) = &camera_info.rectification_matrix {
+            let rectification = rectification_matrix.data("rectification_matrix", 3, 3)?;
+            if rectification
+                .iter()
+                .zip(IDENTITY.iter())
+                .any(|(actual, identity)| (actual - identity).abs() > REPRESENTATION_TOLERANCE)
+            {
+                return Err(CalibrationFormatError::Lossy(
+                    "stereo rectification cannot be represented by Intrinsics".to_string(),
+                ));
+            }
+        }
+        if let Some(projection_matrix) = &camera_info.projection_matrix {
+            let projection = projection_matrix.data("projection_matrix", 3, 4)?;
+            if projection[3].abs() > REPRESENTATION_TOLERANCE
+                || projection[7].abs() > REPRESENTATION_TOLERANCE
+            {
+                return Err(CalibrationFormatError::Lossy(
+                    "stereo baseline of the projection matrix cannot be represented by Intrinsics"
+                        .to_string(),
+                ));
+            }
+        }
+
+        let intrinsics = intrinsics_from_camera_matrix(
+            camera_info.camera_matrix.data("camera_matrix", 3, 3)?,
+            distortion_from_ros(
+                &camera_info.distortion_model,
+                camera_info
+                    .distortion_coefficients
+                    .vector("distortion_coefficients")?,
+            )?,
+        )?
+        .with_resolution(camera_info.image_width, camera_info.image_height);
+
+        Ok((camera_info.camera_name, intrinsics))
+    }
+
+    /// Convert Intrinsics into ROS `sensor_msgs/CameraInfo` YAML, as read by
+    /// `camera_calibration_parsers`. The rectification matrix is identity and the projection
+    /// matrix is equal to the camera matrix.
+    ///
+    /// # Arguments
+    /// * `camera_name` - Name of the camera.
+    ///
+    /// # Returns
+    /// * `Ok(String)` containing the CameraInfo YAML.
+    /// * `Err(CalibrationFormatError::MissingResolution)` if the resolution is unknown.
+    /// * `Err(CalibrationFormatError::Lossy)` if the distortion has no ROS equivalent.
+    pub fn to_ros_camera_info(&self, camera_name: &str) -> Result<String, CalibrationFormatError> {
+        if !self.has_resolution() {
+            return Err(CalibrationFormatError::MissingResolution(
+                camera_name.to_string(),
+            ));
+        }
+        let (model, coefficients) = distortion_to_ros(&self.distortion)?;
+        let projection = [
+            self.fx, 0.0, self.cx, 0.0, 0.0, self.fy, self.cy, 0.0, 0.0, 0.0, 1.0, 0.0,
+        ];
+
+        Ok(format!(
+            "image_width: {}
+image_height: {}
+camera_name: {}
+camera_matrix:
+  rows: 3
+  cols: 3
+  data: {}
+distortion_model: {}
+distortion_coefficients:
+  rows: 1
+  cols: {}
+  data: {}
+rectification_matrix:
+  rows: 3
+  cols: 3
+  data: {}
+projection_matrix:
+  rows: 3
+  cols: 4
+  data: {}
+",
+            self.width,
+            self.height,
+            camera_name,
+            format_sequence(&camera_matrix_from_intrinsics(self)),
+            model,
+            coefficients.len(),
+            format_sequence(&coefficients),
+            format_sequence(&IDENTITY),
+            format_sequence(&projection)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ros_round_trip() {
+        let intrinsics = Intrinsics::new(
+            615.1,
+            616.2,
+            320.5,
+            240.25,
+            Distortion::RsBrownConrady(RsCoefficients::new(0.1, -0.05, 0.001, -0.002, 0.01)),
+        )
+        .with_resolution(640, 480);
+
+        let yaml = intrinsics.to_ros_camera_info("color").unwrap();
+        let (camera_name, deserialised) = Intrinsics::from_ros_camera_info(&yaml).unwrap();
+        assert_eq!(camera_name, "color");
+        assert_eq!(deserialised, intrinsics);
+    }
+
+    #[test]
+    fn ros_requires_resolution() {
+        let intrinsics = Intrinsics::new(615.0, 616.0, 320.5, 240.25, Distortion::None);
+        assert_eq!(
+            intrinsics.to_ros_camera_info("depth"),
+            Err(CalibrationFormatError::MissingResolution(
+                "depth".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn ros_stereo_is_lossy() {
+        let yaml = "image_width: 640
+image_height: 480
+camera_name: right
+camera_matrix:
+  rows: 3
+  cols: 3
+  data: [615, 0, 320, 0, 616, 240, 0, 0, 1]
+distortion_model: plumb_bob
+distortion_coefficients:
+  rows: 1
+  cols: 5
+  data: [0, 0, 0, 0, 0]
+rectification_matrix:
+  rows: 3
+  cols: 3
+  data: [1, 0, 0, 0, 1, 0, 0, 0, 1]
+projection_matrix:
+  rows: 3
+  cols: 4
+  data: [615, 0, 320, -30.75, 0, 616, 240, 0, 0, 0, 1, 0]
+";
+        assert!(matches!(
+            Intrinsics::from_ros_camera_info(yaml),
+            Err(CalibrationFormatError::Lossy(_))
+        ));
+
+        let (_, intrinsics) =
+            Intrinsics::from_ros_camera_info(&yaml.replace("-30.75", "0")).unwrap();
+        assert_eq!(intrinsics.distortion, Distortion::None);
+        assert_eq!((intrinsics.width, intrinsics.height), (640, 480));
+    }
+}
@@ -15,6 +15,12 @@ pub struct Intrinsics {
     pub cy: f32,
     /// The distortion model of the camera optics.
     pub distortion: Distortion,
+    /// Width of the image for which the intrinsics are valid, in pixels. Zero if unknown.
+    #[serde(default)]
+    pub width: u32,
+    /// Height of the image for which the intrinsics are valid, in pixels. Zero if unknown.
+    #[serde(default)]
+    pub height: u32,
 }
 
 impl Intrinsics {
@@ -36,16 +42,104 @@ impl Intrinsics {
             cx,
             cy,
             distortion,
+            width: 0,
+            height: 0,
         }
     }
+
+    /// Set the resolution of the image for which these intrinsics are valid.
+    ///
+    /// # Arguments
+    /// * `width` - Width of the image, in pixels.
+    /// * `height` - Height of the image, in pixels.
+    ///
+    /// # Returns
+    /// * Intrinsics with the given reference resolution.
+    pub fn with_resolution(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Determine whether the resolution for which these intrinsics are valid is known.
+    ///
+    /// # Returns
+    /// * `true` if both `width` and `height` are non-zero.
+    pub fn has_resolution(&self) -> bool {
+        self.width != 0 && self.height != 0
+    }
+
+    /// Rescale the intrinsics to an image of a different resolution, e.g. after the image was
+    /// resized. The principal point is scaled about pixel centres, so that the same scene point
+    /// maps onto the same relative location of the resized image. Distortion coefficients operate on
+    /// normalised coordinates and are therefore kept unchanged.
+    ///
+    /// # Arguments
+    /// * `width` - Width of the resized image, in pixels.
+    /// * `height` - Height of the resized image, in pixels.
+    ///
+    /// # Returns
+    /// * `Some(Intrinsics)` valid for the new resolution.
+    /// * `None` if the reference resolution of `self` is unknown or the new resolution is zero.
+    pub fn rescaled(&self, width: u32, height: u32) -> Option<Self> {
+        if !self.has_resolution() || width == 0 || height == 0 {
+            return None;
+        }
+
+        let scale_x = width as f32 / self.width as f32;
+        let scale_y = height as f32 / self.height as f32;
+        Some(Self {
+            fx: self.fx * scale_x,
+            fy: self.fy * scale_y,
+            cx: (self.cx + 0.5) * scale_x - 0.5,
+            cy: (self.cy + 0.5) * scale_y - 0.5,
+            distortion: self.distortion.clone(),
+            width,
+            height,
+        })
+    }
+
+    /// Project a 3D `point` in the camera coordinate frame onto the image plane, while applying
+    /// the distortion model of the camera optics. `Distortion::Unknown` is treated as rectilinear.
+    ///
+    /// # Arguments
+    /// * `point` - Point in the camera coordinate frame, in the form of `[x, y, z]`. Its `z`
+    ///   coordinate must be positive.
+    ///
+    /// # Returns
+    /// * Pixel coordinates of the projected point, in the form of `[u, v]`.
+    pub fn project(&self, point: [f32; 3]) -> [f32; 2] {
+        let [x, y] = self
+            .distortion
+            .distort([point[0] / point[2], point[1] / point[2]]);
+        [x * self.fx + self.cx, y * self.fy + self.cy]
+    }
+
+    /// Deproject a `pixel` with the given `depth` into a 3D point in the camera coordinate frame,
+    /// while compensating for the distortion model of the camera optics. `Distortion::Unknown` is
+    /// treated as rectilinear.
+    ///
+    /// # Arguments
+    /// * `pixel` - Pixel coordinates, in the form of `[u, v]`.
+    /// * `depth` - Distance of the point along the optical axis, i.e. its `z` coordinate.
+    ///
+    /// # Returns
+    /// * Point in the camera coordinate frame, in the form of `[x, y, z]`.
+    pub fn deproject(&self, pixel: [f32; 2], depth: f32) -> [f32; 3] {
+        let [x, y] = self.distortion.undistort([
+            (pixel[0] - self.cx) / self.fx,
+            (pixel[1] - self.cy) / self.fy,
+        ]);
+        [x * depth, y * depth, depth]
+    }
 }
 
 impl Display for Intrinsics {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "fx={}, fy={}, cy={}, cx={}, dist={}",
-            self.fx, self.fy, self.cx, self.cy, self.distortion
+            "fx={}, fy={}, cy={}, cx={}, dist={}, resolution={}x{}",
+            self.fx, self.fy, self.cx, self.cy, self.distortion, self.width, self.height
         )
     }
 }
@@ -101,6 +195,174 @@ impl Display for Distortion {
     }
 }
 
+/// Number of iterations used to invert distortion models that have no closed-form inverse.
+const UNDISTORTION_ITERATIONS: usize = 20;
+
+/// Smallest radius from the principal point that is considered by fish-eye distortion models.
+const MIN_FISHEYE_RADIUS: f32 = f32::EPSILON;
+
+impl Distortion {
+    /// Apply distortion to a point on the normalised image plane, i.e. a point with `z = 1`.
+    /// Models are implemented equivalently to librealsense (`rsutil.h`) and OpenCV.
+    ///
+    /// # Arguments
+    /// * `point` - Undistorted point on the normalised image plane, in the form of `[x, y]`.
+    ///
+    /// # Returns
+    /// * Distorted point on the normalised image plane.
+    pub fn distort(&self, point: [f32; 2]) -> [f32; 2] {
+        use Distortion::*;
+        let [x, y] = point;
+        match self {
+            Unknown | None => point,
+            RsBrownConrady(c) => {
+                let r2 = x * x + y * y;
+                let f = 1.0 + c.a1 * r2 + c.a2 * r2 * r2 + c.a5 * r2 * r2 * r2;
+                let (dx, dy) = Self::rs_tangential(c, x, y, r2);
+                [x * f + dx, y * f + dy]
+            }
+            RsModifiedBrownConrady(c) => {
+                let r2 = x * x + y * y;
+                let f = 1.0 + c.a1 * r2 + c.a2 * r2 * r2 + c.a5 * r2 * r2 * r2;
+                let (x, y) = (x * f, y * f);
+                let (dx, dy) = Self::rs_tangential(c, x, y, r2);
+                [x + dx, y + dy]
+            }
+            RsInverseBrownConrady(c) => Self::undistort_rs_brown_conrady(c, point),
+            RsKannalaBrandt4(c) => {
+                let r = (x * x + y * y).sqrt().max(MIN_FISHEYE_RADIUS);
+                let theta = r.atan();
+                let theta2 = theta * theta;
+                let series =
+                    1.0 + theta2 * (c.a1 + theta2 * (c.a2 + theta2 * (c.a3 + theta2 * c.a4)));
+                let rd = theta * series;
+                [x * rd / r, y * rd / r]
+            }
+            RsFTheta(c) => {
+                let r = (x * x + y * y).sqrt().max(MIN_FISHEYE_RADIUS);
+                let rd = (2.0 * r * (c.a1 / 2.0).tan()).atan() / c.a1;
+                [x * rd / r, y * rd / r]
+            }
+            K4aBrownConrady(c) => {
+                let r2 = x * x + y * y;
+                let f = Self::k4a_radial(c, r2);
+                let (dx, dy) = Self::k4a_tangential(c, x, y, r2);
+                [x * f + dx, y * f + dy]
+            }
+        }
+    }
+
+    /// Compensate distortion of a point on the normalised image plane, i.e. a point with `z = 1`.
+    /// Models without a closed-form inverse are inverted iteratively.
+    ///
+    /// # Arguments
+    /// * `point` - Distorted point on the normalised image plane, in the form of `[x, y]`.
+    ///
+    /// # Returns
+    /// * Undistorted point on the normalised image plane.
+    pub fn undistort(&self, point: [f32; 2]) -> [f32; 2] {
+        use Distortion::*;
+        let [xo, yo] = point;
+        match self {
+            Unknown | None => point,
+            RsBrownConrady(c) => Self::undistort_rs_brown_conrady(c, point),
+            RsModifiedBrownConrady(c) => {
+                let (mut x, mut y) = (xo, yo);
+                for _ in 0..UNDISTORTION_ITERATIONS {
+                    let r2 = x * x + y * y;
+                    let f = 1.0 + c.a1 * r2 + c.a2 * r2 * r2 + c.a5 * r2 * r2 * r2;
+                    let (dx, dy) = Self::rs_tangential(c, x * f, y * f, r2);
+                    x = (xo - dx) / f;
+                    y = (yo - dy) / f;
+                }
+                [x, y]
+            }
+            RsInverseBrownConrady(c) => {
+                let r2 = xo * xo + yo * yo;
+                let f = 1.0 + c.a1 * r2 + c.a2 * r2 * r2 + c.a5 * r2 * r2 * r2;
+                let (dx, dy) = Self::rs_tangential(c, xo, yo, r2);
+                [xo * f + dx, yo * f + dy]
+            }
+            RsKannalaBrandt4(c) => {
+                let rd = (xo * xo + yo * yo).sqrt().max(MIN_FISHEYE_RADIUS);
+                // Newton's method for `theta * series(theta) = rd`
+                let mut theta = rd;
+                for _ in 0..UNDISTORTION_ITERATIONS {
+                    let theta2 = theta * theta;
+                    let f = theta
+                        * (1.0
+                            + theta2 * (c.a1 + theta2 * (c.a2 + theta2 * (c.a3 + theta2 * c.a4))))
+                        - rd;
+                    if f.abs() < f32::EPSILON {
+                        break;
+                    }
+                    let df = 1.0
+                        + theta2
+                            * (3.0 * c.a1
+                                + theta2
+                                    * (5.0 * c.a2 + theta2 * (7.0 * c.a3 + 9.0 * theta2 * c.a4)));
+                    theta -= f / df;
+                }
+                let r = theta.tan();
+                [xo * r / rd, yo * r / rd]
+            }
+            RsFTheta(c) => {
+                let rd = (xo * xo + yo * yo).sqrt().max(MIN_FISHEYE_RADIUS);
+                let r = (c.a1 * rd).tan() / (2.0 * (c.a1 / 2.0).tan());
+                [xo * r / rd, yo * r / rd]
+            }
+            K4aBrownConrady(c) => {
+                let (mut x, mut y) = (xo, yo);
+                for _ in 0..UNDISTORTION_ITERATIONS {
+                    let r2 = x * x + y * y;
+                    let (dx, dy) = Self::k4a_tangential(c, x, y, r2);
+                    let f = Self::k4a_radial(c, r2);
+                    x = (xo - dx) / f;
+                    y = (yo - dy) / f;
+                }
+                [x, y]
+            }
+        }
+    }
+
+    /// Iteratively invert RealSense Brown-Conrady distortion, which is used both to undistort
+    /// `RsBrownConrady` and to distort `RsInverseBrownConrady`.
+    fn undistort_rs_brown_conrady(c: &RsCoefficients, point: [f32; 2]) -> [f32; 2] {
+        let [xo, yo] = point;
+        let (mut x, mut y) = (xo, yo);
+        for _ in 0..UNDISTORTION_ITERATIONS {
+            let r2 = x * x + y * y;
+            let icdist = 1.0 / (1.0 + ((c.a5 * r2 + c.a2) * r2 + c.a1) * r2);
+            let (dx, dy) = Self::rs_tangential(c, x, y, r2);
+            x = (xo - dx) * icdist;
+            y = (yo - dy) * icdist;
+        }
+        [x, y]
+    }
+
+    /// Tangential component of the RealSense Brown-Conrady distortion models.
+    fn rs_tangential(c: &RsCoefficients, x: f32, y: f32, r2: f32) -> (f32, f32) {
+        (
+            2.0 * c.a3 * x * y + c.a4 * (r2 + 2.0 * x * x),
+            2.0 * c.a4 * x * y + c.a3 * (r2 + 2.0 * y * y),
+        )
+    }
+
+    /// Radial component of the K4A Brown-Conrady (rational) distortion model.
+    fn k4a_radial(c: &K4aCoefficients, r2: f32) -> f32 {
+        (1.0 + r2 * (c.k1 + r2 * (c.k2 + r2 * c.k3)))
+            / (1.0 + r2 * (c.k4 + r2 * (c.k5 + r2 * c.k6)))
+    }
+
+    /// Tangential component of the K4A Brown-Conrady (rational) distortion model.
+    fn k4a_tangential(c: &K4aCoefficients, x: f32, y: f32, r2: f32) -> (f32, f32) {
+        (
+            2.0 * c.p1 * x * y + c.p2 * (r2 + 2.0 * x * x),
+            c.p1 * (r2 + 2.0 * y * y) + 2.0 * c.p2 * x * y,
+        )
+    }
+}
+
 /// RealSense distortion coefficients. The use of these coefficients depend on the utilised distrortion model.
 #[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct RsCoefficients {
@@ -247,6 +509,111 @@ mod tests {
     use super::*;
     use crate::common::tests::nearly_equal_f32;
 
+    /// Point used for projection tests, in the form of `[x, y, z]`.
+    const POINT: [f32; 3] = [0.3, -0.2, 1.5];
+    /// Maximum allowed error of projection, in pixels.
+    const PIXEL_TOLERANCE: f32 = 1e-3;
+    /// Maximum allowed error of deprojection, in metres.
+    const POINT_TOLERANCE: f32 = 1e-4;
+
+    fn intrinsics_with(distortion: Distortion) -> Intrinsics {
+        Intrinsics::new(615.0, 616.0, 320.5, 240.25, distortion)
+    }
+
+    fn rs_coefficients() -> RsCoefficients {
+        RsCoefficients::new(0.1, -0.05, 0.001, -0.002, 0.01)
+    }
+
+    /// Project `POINT` and compare it to `expected` pixel, then deproject it back again.
+    fn assert_round_trip(intrinsics: &Intrinsics, expected: [f32; 2]) {
+        let pixel = intrinsics.project(POINT);
+        assert!(
+            (pixel[0] - expected[0]).abs() < PIXEL_TOLERANCE
+                && (pixel[1] - expected[1]).abs() < PIXEL_TOLERANCE,
+            "Projected {:?}, expected {:?} for {}",
+            pixel,
+            expected,
+            intrinsics.distortion
+        );
+
+        let point = intrinsics.deproject(pixel, POINT[2]);
+        for (actual, expected) in point.iter().zip(POINT.iter()) {
+            assert!(
+                (actual - expected).abs() < POINT_TOLERANCE,
+                "Deprojected {:?}, expected {:?} for {}",
+                point,
+                POINT,
+                intrinsics.distortion
+            );
+        }
+    }
+
+    #[test]
+    fn project_deproject_none() {
+        assert_round_trip(&intrinsics_with(Distortion::None), [443.5, 158.116_67]);
+        assert_round_trip(&intrinsics_with(Distortion::Unknown), [443.5, 158.116_67]);
+    }
+
+    #[test]
+    fn project_deproject_rs_brown_conrady() {
+        assert_round_trip(
+            &intrinsics_with(Distortion::RsBrownConrady(rs_coefficients())),
+            [443.988_1, 157.778_87],
+        );
+    }
+
+    #[test]
+    fn project_deproject_rs_modified_brown_conrady() {
+        assert_round_trip(
+            &intrinsics_with(Distortion::RsModifiedBrownConrady(rs_coefficients())),
+            [443.986_63, 157.779_86],
+        );
+    }
+
+    #[test]
+    fn project_deproject_rs_inverse_brown_conrady() {
+        let intrinsics = intrinsics_with(Distortion::RsInverseBrownConrady(rs_coefficients()));
+
+        // Deprojection of inverse Brown-Conrady is the closed-form direction
+        let point = intrinsics.deproject([400.0, 180.0], 1.5);
+        assert!((point[0] - 0.194_188_27).abs() < POINT_TOLERANCE);
+        assert!((point[1] + 0.146_949_16).abs() < POINT_TOLERANCE);
+
+        let pixel = intrinsics.project(point);
+        assert!((pixel[0] - 400.0).abs() < PIXEL_TOLERANCE);
+        assert!((pixel[1] - 180.0).abs() < PIXEL_TOLERANCE);
+    }
+
+    #[test]
+    fn project_deproject_rs_kannala_brandt4() {
+        assert_round_trip(
+            &intrinsics_with(Distortion::RsKannalaBrandt4(RsCoefficients::new(
+                -0.04, 0.004, -0.001, 0.0002, 0.0,
+            ))),
+            [440.942_78, 159.824_25],
+        );
+    }
+
+    #[test]
+    fn project_deproject_rs_f_theta() {
+        assert_round_trip(
+            &intrinsics_with(Distortion::RsFTheta(RsCoefficients::new(
+                0.9, 0.0, 0.0, 0.0, 0.0,
+            ))),
+            [450.235_55, 153.619],
+        );
+    }
+
+    #[test]
+    fn project_deproject_k4a_brown_conrady() {
+        assert_round_trip(
+            &intrinsics_with(Distortion::K4aBrownConrady(K4aCoefficients::new(
+                0.5, -0.02, 0.001, 0.85, 0.05, 0.002, 0.0005, -0.0003,
+            ))),
+            [441.060_25, 159.756_49],
+        );
+    }
+
     #[test]
     fn rs_coefficients_from_slice() {
         let rs_coefficients_slice = [1.1, 2.2, 3.3, 4.4, 5.5];
@@ -275,4 +642,31 @@ mod tests {
         assert!(nearly_equal_f32(k4a_coefficients.p1, 7.7));
         assert!(nearly_equal_f32(k4a_coefficients.p2, 8.8));
     }
+
+    #[test]
+    fn rescale_intrinsics() {
+        let intrinsics = intrinsics_with(Distortion::None).with_resolution(640, 480);
+
+        // Unknown reference resolution or empty target cannot be rescaled
+        assert!(intrinsics_with(Distortion::None)
+            .rescaled(320, 240)
+            .is_none());
+        assert!(intrinsics.rescaled(0, 240).is_none());
+
+        let half = intrinsics.rescaled(320, 240).unwrap();
+        assert_eq!((half.width, half.height), (320, 240));
+        assert!(nearly_equal_f32(half.fx, 307.5));
+        assert!(nearly_equal_f32(half.fy, 308.0));
+        assert!(nearly_equal_f32(half.cx, 160.0));
+        assert!(nearly_equal_f32(half.cy, 119.875));
+
+        // Rescaling back must restore the original intrinsics
+        assert_eq!(half.rescaled(640, 480).unwrap(), intrinsics);
+
+        // The same point must project onto the same relative location of the image
+        let pixel = intrinsics.project(POINT);
+        let pixel_half = half.project(POINT);
+        assert!((pixel_half[0] - ((pixel[0] + 0.5) / 2.0 - 0.5)).abs() < PIXEL_TOLERANCE);
+        assert!((pixel_half[1] - ((pixel[1] + 0.5) / 2.0 - 0.5)).abs() < PIXEL_TOLERANCE);
+    }
 }
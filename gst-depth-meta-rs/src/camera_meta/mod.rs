@@ -1,9 +1,13 @@
 #[allow(clippy::module_inception)]
 pub mod camera_meta;
+pub mod extrinsics;
+pub mod formats;
 pub mod intrinsics;
 pub mod transformation;
 
 pub use camera_meta::*;
+pub use extrinsics::*;
+pub use formats::*;
 pub use intrinsics::*;
 pub use transformation::*;
 
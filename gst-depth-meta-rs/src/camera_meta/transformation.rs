@@ -1,5 +1,52 @@
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
+use std::ops::Mul;
+
+/// Default tolerance used when validating RotationMatrix.
+pub const DEFAULT_ROTATION_TOLERANCE: f32 = 1e-3;
+
+/// Maximum number of iterations used for orthonormalisation of RotationMatrix.
+const ORTHONORMALISATION_ITERATIONS: usize = 32;
+
+/// RotationError represents all possible reasons for a matrix not being a valid rotation matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RotationError {
+    /// Some of the entries are either NaN or infinite.
+    NotFinite,
+    /// The matrix is not orthonormal, e.g. it contains scaling or shear.
+    NotOrthonormal {
+        /// Largest absolute deviation of `R * R^T` from identity.
+        error: f32,
+    },
+    /// The matrix is a reflection, i.e. its determinant is negative.
+    Reflection {
+        /// Determinant of the matrix.
+        determinant: f32,
+    },
+}
+impl std::error::Error for RotationError {}
+impl std::fmt::Display for RotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RotationError::NotFinite => {
+                write!(
+                    f,
+                    "camera_meta: Rotation matrix contains non-finite entries"
+                )
+            }
+            RotationError::NotOrthonormal { error } => write!(
+                f,
+                "camera_meta: Rotation matrix is not orthonormal (error: {})",
+                error
+            ),
+            RotationError::Reflection { determinant } => write!(
+                f,
+                "camera_meta: Rotation matrix is a reflection (determinant: {})",
+                determinant
+            ),
+        }
+    }
+}
 /// Transformation in 3D.
 #[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Transformation {
@@ -59,6 +106,96 @@ impl Transformation {
             rotation: r_inv,
         }
     }
+
+    /// Chains Transformation with `next`, such that the resulting Transformation first applies
+    /// `self` and then `next`, e.g. `a` -> `b` followed by `b` -> `c` results in `a` -> `c`.
+    ///
+    /// # Arguments
+    /// * `next` - Transformation to apply after `self`.
+    ///
+    /// # Returns
+    /// * Chained Transformation.
+    pub fn then(&self, next: &Self) -> Self {
+        let t = &self.translation;
+        let r = &next.rotation;
+        Self {
+            translation: Translation {
+                x: r.r11 * t.x + r.r12 * t.y + r.r13 * t.z + next.translation.x,
+                y: r.r21 * t.x + r.r22 * t.y + r.r23 * t.z + next.translation.y,
+                z: r.r31 * t.x + r.r32 * t.y + r.r33 * t.z + next.translation.z,
+            },
+            rotation: &next.rotation * &self.rotation,
+        }
+    }
+
+    /// Apply Transformation to a `point`, i.e. express a point given in `source` coordinate frame
+    /// in `target` coordinate frame.
+    ///
+    /// # Arguments
+    /// * `point` - Point in the form of `[x, y, z]`.
+    ///
+    /// # Returns
+    /// * Transformed point.
+    pub fn transform_point(&self, point: [f32; 3]) -> [f32; 3] {
+        let [x, y, z] = self.rotation.rotate_point(point);
+        [
+            x + self.translation.x,
+            y + self.translation.y,
+            z + self.translation.z,
+        ]
+    }
+
+    /// Apply Transformation to all `points` in-place.
+    ///
+    /// # Arguments
+    /// * `points` - Points in the form of `[x, y, z]`.
+    pub fn transform_points(&self, points: &mut [[f32; 3]]) {
+        for point in points.iter_mut() {
+            *point = self.transform_point(*point);
+        }
+    }
+
+    /// Make sure that Transformation is a valid rigid body transformation.
+    ///
+    /// # Arguments
+    /// * `tolerance` - Largest allowed deviation of the rotation from an orthonormal matrix.
+    ///
+    /// # Returns
+    /// * `Ok()` if the Transformation is valid.
+    /// * `Err(RotationError)` describing the first problem that was found.
+    pub fn validate(&self, tolerance: f32) -> Result<(), RotationError> {
+        let t = &self.translation;
+        if !(t.x.is_finite() && t.y.is_finite() && t.z.is_finite()) {
+            return Err(RotationError::NotFinite);
+        }
+        self.rotation.validate(tolerance)
+    }
+}
+
+impl Mul for &Transformation {
+    type Output = Transformation;
+    /// Composes two Transformations, such that `(a * b)` applies `b` first and then `a`. This
+    /// matches multiplication of the corresponding homogeneous matrices, i.e. `a.then(&b)` is
+    /// equal to `b * a`.
+    fn mul(self, rhs: Self) -> Transformation {
+        rhs.then(self)
+    }
+}
+
+impl Mul for Transformation {
+    type Output = Transformation;
+    /// Composes two Transformations, such that `(a * b)` applies `b` first and then `a`.
+    fn mul(self, rhs: Self) -> Transformation {
+        &self * &rhs
+    }
+}
+
+impl Mul<[f32; 3]> for &Transformation {
+    type Output = [f32; 3];
+    /// Applies Transformation to a point in the form of `[x, y, z]`.
+    fn mul(self, rhs: [f32; 3]) -> [f32; 3] {
+        self.transform_point(rhs)
+    }
 }
 
 /// Translation vector, in metres.
@@ -176,6 +313,459 @@ impl RotationMatrix {
             r33: self.r33,
         }
     }
+
+    /// Rotate a `point` by the RotationMatrix.
+    ///
+    /// # Arguments
+    /// * `point` - Point in the form of `[x, y, z]`.
+    ///
+    /// # Returns
+    /// * Rotated point.
+    pub fn rotate_point(&self, point: [f32; 3]) -> [f32; 3] {
+        let [x, y, z] = point;
+        [
+            self.r11 * x + self.r12 * y + self.r13 * z,
+            self.r21 * x + self.r22 * y + self.r23 * z,
+            self.r31 * x + self.r32 * y + self.r33 * z,
+        ]
+    }
+
+    /// Determinant of the matrix, which is 1 for a valid rotation.
+    pub fn determinant(&self) -> f32 {
+        self.r11 * (self.r22 * self.r33 - self.r23 * self.r32)
+            - self.r12 * (self.r21 * self.r33 - self.r23 * self.r31)
+            + self.r13 * (self.r21 * self.r32 - self.r22 * self.r31)
+    }
+
+    /// Make sure that the matrix is a valid rotation matrix, i.e. it is orthonormal and its
+    /// determinant is positive.
+    ///
+    /// # Arguments
+    /// * `tolerance` - Largest allowed absolute deviation of `R * R^T` from identity.
+    ///
+    /// # Returns
+    /// * `Ok()` if the matrix is a valid rotation matrix.
+    /// * `Err(RotationError)` describing the first problem that was found.
+    pub fn validate(&self, tolerance: f32) -> Result<(), RotationError> {
+        let entries = self.to_array();
+        if entries.iter().flatten().any(|entry| !entry.is_finite()) {
+            return Err(RotationError::NotFinite);
+        }
+
+        let determinant = self.determinant();
+        if determinant < 0.0 {
+            return Err(RotationError::Reflection { determinant });
+        }
+
+        let product = self * &self.inverse();
+        let error = product
+            .to_array()
+            .iter()
+            .zip(Self::default().to_array().iter())
+            .flat_map(|(row, identity)| row.iter().zip(identity.iter()))
+            .map(|(actual, expected)| (actual - expected).abs())
+            .fold(0.0, f32::max);
+        if error > tolerance {
+            return Err(RotationError::NotOrthonormal { error });
+        }
+
+        Ok(())
+    }
+
+    /// Find the closest valid rotation matrix, e.g. to compensate for rounding errors of
+    /// calibration files. Polar decomposition is used, which yields the orthonormal matrix that
+    /// is closest in Frobenius norm.
+    ///
+    /// # Returns
+    /// * `Ok(RotationMatrix)` that is orthonormal.
+    /// * `Err(RotationError)` if the matrix contains non-finite entries, is a reflection or is
+    ///   singular, in which case there is no meaningful closest rotation.
+    pub fn orthonormalised(&self) -> Result<Self, RotationError> {
+        let entries = self.to_array();
+        if entries.iter().flatten().any(|entry| !entry.is_finite()) {
+            return Err(RotationError::NotFinite);
+        }
+        let determinant = self.determinant();
+        if determinant < 0.0 {
+            return Err(RotationError::Reflection { determinant });
+        }
+
+        // Iterate `X = (X + X^-T) / 2`, which converges to the orthonormal polar factor
+        let mut x = entries.map(|row| row.map(f64::from));
+        for _ in 0..ORTHONORMALISATION_ITERATIONS {
+            let inverse_transposed = match inverse_transposed(&x) {
+                Some(inverse_transposed) => inverse_transposed,
+                None => {
+                    return Err(RotationError::NotOrthonormal {
+                        error: f32::INFINITY,
+                    })
+                }
+            };
+            let mut change: f64 = 0.0;
+            for (row, inverse_row) in x.iter_mut().zip(inverse_transposed.iter()) {
+                for (entry, inverse_entry) in row.iter_mut().zip(inverse_row.iter()) {
+                    let updated = 0.5 * (*entry + inverse_entry);
+                    change = change.max((updated - *entry).abs());
+                    *entry = updated;
+                }
+            }
+            if change < f64::EPSILON {
+                break;
+            }
+        }
+
+        Ok(Self::from_array(x.map(|row| row.map(|entry| entry as f32))))
+    }
+
+    /// Get the entries of the matrix as rows.
+    fn to_array(&self) -> [[f32; 3]; 3] {
+        [
+            [self.r11, self.r12, self.r13],
+            [self.r21, self.r22, self.r23],
+            [self.r31, self.r32, self.r33],
+        ]
+    }
+
+    /// Create a matrix from its rows.
+    fn from_array(rows: [[f32; 3]; 3]) -> Self {
+        let [[r11, r12, r13], [r21, r22, r23], [r31, r32, r33]] = rows;
+        Self::new(r11, r12, r13, r21, r22, r23, r31, r32, r33)
+    }
+}
+
+/// Compute the transposed inverse of a 3x3 matrix by the use of its cofactors.
+///
+/// # Arguments
+/// * `m` - Rows of the matrix.
+///
+/// # Returns
+/// * `Some` transposed inverse of the matrix.
+/// * `None` if the matrix is singular.
+fn inverse_transposed(m: &[[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let cofactors = [
+        [
+            cofactor(1, 2, 1, 2),
+            -cofactor(1, 2, 0, 2),
+            cofactor(1, 2, 0, 1),
+        ],
+        [
+            -cofactor(0, 2, 1, 2),
+            cofactor(0, 2, 0, 2),
+            -cofactor(0, 2, 0, 1),
+        ],
+        [
+            cofactor(0, 1, 1, 2),
+            -cofactor(0, 1, 0, 2),
+            cofactor(0, 1, 0, 1),
+        ],
+    ];
+    let determinant =
+        m[0][0] * cofactors[0][0] + m[0][1] * cofactors[0][1] + m[0][2] * cofactors[0][2];
+    if determinant.abs() < f64::EPSILON {
+        return None;
+    }
+    Some(cofactors.map(|row| row.map(|entry| entry / determinant)))
+}
+
+impl Mul for &RotationMatrix {
+    type Output = RotationMatrix;
+    /// Matrix product of `self` and `rhs`, i.e. rotation by `rhs` followed by rotation by `self`.
+    fn mul(self, rhs: Self) -> RotationMatrix {
+        RotationMatrix {
+            r11: self.r11 * rhs.r11 + self.r12 * rhs.r21 + self.r13 * rhs.r31,
+            r12: self.r11 * rhs.r12 + self.r12 * rhs.r22 + self.r13 * rhs.r32,
+            r13: self.r11 * rhs.r13 + self.r12 * rhs.r23 + self.r13 * rhs.r33,
+            r21: self.r21 * rhs.r11 + self.r22 * rhs.r21 + self.r23 * rhs.r31,
+            r22: self.r21 * rhs.r12 + self.r22 * rhs.r22 + self.r23 * rhs.r32,
+            r23: self.r21 * rhs.r13 + self.r22 * rhs.r23 + self.r23 * rhs.r33,
+            r31: self.r31 * rhs.r11 + self.r32 * rhs.r21 + self.r33 * rhs.r31,
+            r32: self.r31 * rhs.r12 + self.r32 * rhs.r22 + self.r33 * rhs.r32,
+            r33: self.r31 * rhs.r13 + self.r32 * rhs.r23 + self.r33 * rhs.r33,
+        }
+    }
+}
+
+impl Mul for RotationMatrix {
+    type Output = RotationMatrix;
+    /// Matrix product of `self` and `rhs`, i.e. rotation by `rhs` followed by rotation by `self`.
+    fn mul(self, rhs: Self) -> RotationMatrix {
+        &self * &rhs
+    }
+}
+
+impl Mul<[f32; 3]> for &RotationMatrix {
+    type Output = [f32; 3];
+    /// Rotates a point in the form of `[x, y, z]`.
+    fn mul(self, rhs: [f32; 3]) -> [f32; 3] {
+        self.rotate_point(rhs)
+    }
+}
+
+/// Unit quaternion representing a rotation, in the form of `w + xi + yj + zk`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Quaternion {
+    /// Real (scalar) part.
+    pub w: f32,
+    /// First imaginary part.
+    pub x: f32,
+    /// Second imaginary part.
+    pub y: f32,
+    /// Third imaginary part.
+    pub z: f32,
+}
+
+impl Quaternion {
+    /// Create a new Quaternion.
+    ///
+    /// # Arguments
+    /// * `w` - Real (scalar) part.
+    /// * `x` - First imaginary part.
+    /// * `y` - Second imaginary part.
+    /// * `z` - Third imaginary part.
+    ///
+    /// # Returns
+    /// * Newly created Quaternion.
+    pub fn new(w: f32, x: f32, y: f32, z: f32) -> Self {
+        Self { w, x, y, z }
+    }
+
+    /// Get Quaternion scaled to unit length, which is required to represent a rotation.
+    ///
+    /// # Returns
+    /// * Normalised Quaternion, or identity if the Quaternion has zero length.
+    pub fn normalised(&self) -> Self {
+        let norm = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        if norm == 0.0 {
+            return Self::default();
+        }
+        Self::new(self.w / norm, self.x / norm, self.y / norm, self.z / norm)
+    }
+}
+
+impl Default for Quaternion {
+    /// Default implementation for Quaternion that returns identity, i.e. no rotation.
+    fn default() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+}
+
+impl From<&Quaternion> for RotationMatrix {
+    /// Implements conversion from Quaternion, which is normalised first.
+    fn from(quaternion: &Quaternion) -> Self {
+        let Quaternion { w, x, y, z } = quaternion.normalised();
+        Self {
+            r11: 1.0 - 2.0 * (y * y + z * z),
+            r12: 2.0 * (x * y - w * z),
+            r13: 2.0 * (x * z + w * y),
+            r21: 2.0 * (x * y + w * z),
+            r22: 1.0 - 2.0 * (x * x + z * z),
+            r23: 2.0 * (y * z - w * x),
+            r31: 2.0 * (x * z - w * y),
+            r32: 2.0 * (y * z + w * x),
+            r33: 1.0 - 2.0 * (x * x + y * y),
+        }
+    }
+}
+
+impl From<&RotationMatrix> for Quaternion {
+    /// Implements conversion from RotationMatrix, which is expected to be valid. The resulting
+    /// Quaternion has non-negative real part.
+    fn from(r: &RotationMatrix) -> Self {
+        // Shepperd's method, which picks the numerically most stable branch
+        let trace = r.r11 + r.r22 + r.r33;
+        let quaternion = if trace > 0.0 {
+            let s = 2.0 * (1.0 + trace).sqrt();
+            Self::new(
+                0.25 * s,
+                (r.r32 - r.r23) / s,
+                (r.r13 - r.r31) / s,
+                (r.r21 - r.r12) / s,
+            )
+        } else if r.r11 > r.r22 && r.r11 > r.r33 {
+            let s = 2.0 * (1.0 + r.r11 - r.r22 - r.r33).sqrt();
+            Self::new(
+                (r.r32 - r.r23) / s,
+                0.25 * s,
+                (r.r12 + r.r21) / s,
+                (r.r13 + r.r31) / s,
+            )
+        } else if r.r22 > r.r33 {
+            let s = 2.0 * (1.0 + r.r22 - r.r11 - r.r33).sqrt();
+            Self::new(
+                (r.r13 - r.r31) / s,
+                (r.r12 + r.r21) / s,
+                0.25 * s,
+                (r.r23 + r.r32) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + r.r33 - r.r11 - r.r22).sqrt();
+            Self::new(
+                (r.r21 - r.r12) / s,
+                (r.r13 + r.r31) / s,
+                (r.r23 + r.r32) / s,
+                0.25 * s,
+            )
+        };
+
+        let quaternion = quaternion.normalised();
+        if quaternion.w < 0.0 {
+            Self::new(-quaternion.w, -quaternion.x, -quaternion.y, -quaternion.z)
+        } else {
+            quaternion
+        }
+    }
+}
+
+/// Rotation by `angle` around `axis`.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AxisAngle {
+    /// Unit vector of the rotation axis, in the form of `[x, y, z]`.
+    pub axis: [f32; 3],
+    /// Angle of the rotation, in radians.
+    pub angle: f32,
+}
+
+impl AxisAngle {
+    /// Create a new AxisAngle.
+    ///
+    /// # Arguments
+    /// * `axis` - Vector of the rotation axis, in the form of `[x, y, z]`. It is normalised.
+    /// * `angle` - Angle of the rotation, in radians.
+    ///
+    /// # Returns
+    /// * Newly created AxisAngle. Axis of zero length results in no rotation around x axis.
+    pub fn new(axis: [f32; 3], angle: f32) -> Self {
+        let norm = (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt();
+        if norm == 0.0 {
+            return Self {
+                axis: [1.0, 0.0, 0.0],
+                angle: 0.0,
+            };
+        }
+        Self {
+            axis: [axis[0] / norm, axis[1] / norm, axis[2] / norm],
+            angle,
+        }
+    }
+
+    /// Create AxisAngle from rotation vector, i.e. axis scaled by the angle as used by OpenCV
+    /// `Rodrigues()`.
+    ///
+    /// # Arguments
+    /// * `rotation_vector` - Rotation vector in the form of `[x, y, z]`.
+    ///
+    /// # Returns
+    /// * Newly created AxisAngle.
+    pub fn from_rotation_vector(rotation_vector: [f32; 3]) -> Self {
+        let [x, y, z] = rotation_vector;
+        Self::new(rotation_vector, (x * x + y * y + z * z).sqrt())
+    }
+
+    /// Get rotation vector, i.e. axis scaled by the angle as used by OpenCV `Rodrigues()`.
+    ///
+    /// # Returns
+    /// * Rotation vector in the form of `[x, y, z]`.
+    pub fn rotation_vector(&self) -> [f32; 3] {
+        self.axis.map(|component| component * self.angle)
+    }
+}
+
+impl From<&AxisAngle> for Quaternion {
+    /// Implements conversion from AxisAngle.
+    fn from(axis_angle: &AxisAngle) -> Self {
+        let (sin, cos) = (0.5 * axis_angle.angle).sin_cos();
+        let [x, y, z] = axis_angle.axis;
+        Self::new(cos, x * sin, y * sin, z * sin).normalised()
+    }
+}
+
+impl From<&Quaternion> for AxisAngle {
+    /// Implements conversion from Quaternion. The resulting angle is within `[0, pi]`.
+    fn from(quaternion: &Quaternion) -> Self {
+        let mut q = quaternion.normalised();
+        if q.w < 0.0 {
+            q = Quaternion::new(-q.w, -q.x, -q.y, -q.z);
+        }
+        let sin = (q.x * q.x + q.y * q.y + q.z * q.z).sqrt();
+        Self::new([q.x, q.y, q.z], 2.0 * sin.atan2(q.w))
+    }
+}
+
+impl From<&AxisAngle> for RotationMatrix {
+    /// Implements conversion from AxisAngle.
+    fn from(axis_angle: &AxisAngle) -> Self {
+        Self::from(&Quaternion::from(axis_angle))
+    }
+}
+
+impl From<&RotationMatrix> for AxisAngle {
+    /// Implements conversion from RotationMatrix, which is expected to be valid.
+    fn from(rotation: &RotationMatrix) -> Self {
+        Self::from(&Quaternion::from(rotation))
+    }
+}
+
+/// Rotation described by Euler angles in radians. The convention matches ROS, i.e. rotation about
+/// z axis by `yaw`, followed by rotation about the new y axis by `pitch` and new x axis by `roll`,
+/// such that `R = Rz(yaw) * Ry(pitch) * Rx(roll)`.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct EulerAngles {
+    /// Rotation about x axis, in radians.
+    pub roll: f32,
+    /// Rotation about y axis, in radians.
+    pub pitch: f32,
+    /// Rotation about z axis, in radians.
+    pub yaw: f32,
+}
+
+impl EulerAngles {
+    /// Create new EulerAngles.
+    ///
+    /// # Arguments
+    /// * `roll` - Rotation about x axis, in radians.
+    /// * `pitch` - Rotation about y axis, in radians.
+    /// * `yaw` - Rotation about z axis, in radians.
+    ///
+    /// # Returns
+    /// * Newly created EulerAngles.
+    pub fn new(roll: f32, pitch: f32, yaw: f32) -> Self {
+        Self { roll, pitch, yaw }
+    }
+}
+
+impl From<&EulerAngles> for RotationMatrix {
+    /// Implements conversion from EulerAngles.
+    fn from(euler_angles: &EulerAngles) -> Self {
+        let (sr, cr) = euler_angles.roll.sin_cos();
+        let (sp, cp) = euler_angles.pitch.sin_cos();
+        let (sy, cy) = euler_angles.yaw.sin_cos();
+        Self {
+            r11: cy * cp,
+            r12: cy * sp * sr - sy * cr,
+            r13: cy * sp * cr + sy * sr,
+            r21: sy * cp,
+            r22: sy * sp * sr + cy * cr,
+            r23: sy * sp * cr - cy * sr,
+            r31: -sp,
+            r32: cp * sr,
+            r33: cp * cr,
+        }
+    }
+}
+
+impl From<&RotationMatrix> for EulerAngles {
+    /// Implements conversion from RotationMatrix, which is expected to be valid. The resulting
+    /// pitch is within `[-pi/2, pi/2]`. In gimbal lock, i.e. pitch of `+-pi/2`, roll is zero.
+    fn from(r: &RotationMatrix) -> Self {
+        let pitch = (-r.r31).clamp(-1.0, 1.0).asin();
+        if r.r31.abs() < 1.0 - f32::EPSILON {
+            Self::new(r.r32.atan2(r.r33), pitch, r.r21.atan2(r.r11))
+        } else {
+            Self::new(0.0, pitch, (-r.r12).atan2(r.r22))
+        }
+    }
 }
 
 impl From<[f32; 9]> for RotationMatrix {
@@ -218,6 +808,8 @@ mod tests {
     use crate::common::tests::nearly_equal_f32;
     use rand::random;
     const TEST_ITERATIONS: usize = 100;
+    /// Maximum allowed error of the tested conversions.
+    const TOLERANCE: f32 = 1e-5;
 
     fn initialise_random_transformation() -> Transformation {
         Transformation {
@@ -231,6 +823,165 @@ mod tests {
         }
     }
 
+    /// Create a valid RotationMatrix with random orientation.
+    fn initialise_random_rotation() -> RotationMatrix {
+        RotationMatrix::from(&Quaternion::new(
+            random::<f32>() - 0.5,
+            random::<f32>() - 0.5,
+            random::<f32>() - 0.5,
+            random::<f32>() - 0.5,
+        ))
+    }
+
+    fn assert_nearly_equal_points(a: [f32; 3], b: [f32; 3]) {
+        for (a, b) in a.iter().zip(b.iter()) {
+            assert!((a - b).abs() < TOLERANCE, "{:?} != {:?}", a, b);
+        }
+    }
+
+    fn assert_nearly_equal_rotations(a: &RotationMatrix, b: &RotationMatrix) {
+        for (a, b) in a.to_array().iter().zip(b.to_array().iter()) {
+            assert_nearly_equal_points(*a, *b);
+        }
+    }
+
+    #[test]
+    fn compose_and_transform_points() {
+        for _ in 0..TEST_ITERATIONS {
+            let a = Transformation::new(
+                Translation::new(random(), random(), random()),
+                initialise_random_rotation(),
+            );
+            let b = Transformation::new(
+                Translation::new(random(), random(), random()),
+                initialise_random_rotation(),
+            );
+            let point = [random(), random(), random()];
+
+            assert_nearly_equal_points(&(&a * &b) * point, &a * (&b * point));
+            assert_eq!(a.then(&b), &b * &a);
+
+            let mut points = [point, [0.0; 3]];
+            a.transform_points(&mut points);
+            assert_eq!(
+                points,
+                [a.transform_point(point), a.transform_point([0.0; 3])]
+            );
+
+            // Transforming there and back again results in the same point
+            assert_nearly_equal_points(&(a.inverse() * a) * point, point);
+        }
+    }
+
+    #[test]
+    fn quaternion_round_trip() {
+        for _ in 0..TEST_ITERATIONS {
+            let rotation = initialise_random_rotation();
+            assert!(rotation.validate(TOLERANCE).is_ok());
+
+            let quaternion = Quaternion::from(&rotation);
+            assert!(quaternion.w >= 0.0);
+            assert_nearly_equal_rotations(&RotationMatrix::from(&quaternion), &rotation);
+        }
+
+        // Rotation by pi/2 around z axis
+        let quaternion = Quaternion::new(0.5_f32.sqrt(), 0.0, 0.0, 0.5_f32.sqrt());
+        let rotation = RotationMatrix::from(&quaternion);
+        assert_nearly_equal_points(rotation.rotate_point([1.0, 0.0, 0.0]), [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn axis_angle_round_trip() {
+        for _ in 0..TEST_ITERATIONS {
+            let rotation = initialise_random_rotation();
+            let axis_angle = AxisAngle::from(&rotation);
+            assert!(axis_angle.angle >= 0.0 && axis_angle.angle <= std::f32::consts::PI);
+            assert_nearly_equal_rotations(&RotationMatrix::from(&axis_angle), &rotation);
+
+            let rotation_vector = axis_angle.rotation_vector();
+            let from_rotation_vector = AxisAngle::from_rotation_vector(rotation_vector);
+            assert_nearly_equal_rotations(&RotationMatrix::from(&from_rotation_vector), &rotation);
+        }
+
+        let axis_angle = AxisAngle::new([0.0, 0.0, 2.0], std::f32::consts::FRAC_PI_2);
+        assert_eq!(axis_angle.axis, [0.0, 0.0, 1.0]);
+        assert_nearly_equal_points(
+            RotationMatrix::from(&axis_angle).rotate_point([1.0, 0.0, 0.0]),
+            [0.0, 1.0, 0.0],
+        );
+        assert_eq!(
+            AxisAngle::from(&RotationMatrix::default()).rotation_vector(),
+            [0.0; 3]
+        );
+    }
+
+    #[test]
+    fn euler_angles_round_trip() {
+        for _ in 0..TEST_ITERATIONS {
+            let rotation = initialise_random_rotation();
+            let euler_angles = EulerAngles::from(&rotation);
+            assert!(euler_angles.pitch.abs() <= std::f32::consts::FRAC_PI_2);
+            assert_nearly_equal_rotations(&RotationMatrix::from(&euler_angles), &rotation);
+        }
+
+        // Yaw only
+        let rotation = RotationMatrix::from(&EulerAngles::new(0.0, 0.0, 0.3));
+        assert_nearly_equal_rotations(
+            &rotation,
+            &RotationMatrix::from(&AxisAngle::new([0.0, 0.0, 1.0], 0.3)),
+        );
+
+        // Gimbal lock still yields the same rotation
+        let gimbal_lock =
+            RotationMatrix::from(&EulerAngles::new(0.2, std::f32::consts::FRAC_PI_2, 0.5));
+        assert_nearly_equal_rotations(
+            &RotationMatrix::from(&EulerAngles::from(&gimbal_lock)),
+            &gimbal_lock,
+        );
+    }
+
+    #[test]
+    fn validate_and_orthonormalise() {
+        assert!(RotationMatrix::default().validate(TOLERANCE).is_ok());
+
+        let scaled = RotationMatrix::from([1.1, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+        assert!(matches!(
+            scaled.validate(DEFAULT_ROTATION_TOLERANCE),
+            Err(RotationError::NotOrthonormal { .. })
+        ));
+
+        let reflection = RotationMatrix::from([-1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+        assert!(matches!(
+            reflection.validate(DEFAULT_ROTATION_TOLERANCE),
+            Err(RotationError::Reflection { .. })
+        ));
+        assert!(reflection.orthonormalised().is_err());
+
+        let not_finite = RotationMatrix::from([f32::NAN, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(
+            not_finite.validate(DEFAULT_ROTATION_TOLERANCE),
+            Err(RotationError::NotFinite)
+        );
+
+        for _ in 0..TEST_ITERATIONS {
+            let rotation = initialise_random_rotation();
+            let mut noisy = rotation.to_array();
+            for entry in noisy.iter_mut().flatten() {
+                *entry += (random::<f32>() - 0.5) * 1e-3;
+            }
+            let orthonormalised = RotationMatrix::from_array(noisy).orthonormalised().unwrap();
+            assert!(orthonormalised.validate(TOLERANCE).is_ok());
+            for (a, b) in orthonormalised
+                .to_array()
+                .iter()
+                .flatten()
+                .zip(rotation.to_array().iter().flatten())
+            {
+                assert!((a - b).abs() < 1e-3);
+            }
+        }
+    }
+
     #[test]
     fn inverse_rotation_matrix() {
         for _ in 0..TEST_ITERATIONS {
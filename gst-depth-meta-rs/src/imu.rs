@@ -0,0 +1,214 @@
+use crate::imu_capnp::*;
+use serde::{Deserialize, Serialize};
+
+/// Media type of `imu` streams, which contain Cap'n Proto serialised ImuSamples.
+pub const IMU_MEDIA_TYPE: &str = "application/x-capnp";
+/// Value of the `schema` field of the caps of `imu` streams.
+pub const IMU_SCHEMA: &str = "imu";
+
+/// Three-dimensional vector in the coordinate frame of the sensor.
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct ImuVector {
+    /// Component along x axis.
+    pub x: f32,
+    /// Component along y axis.
+    pub y: f32,
+    /// Component along z axis.
+    pub z: f32,
+}
+
+impl ImuVector {
+    /// Create new ImuVector.
+    ///
+    /// # Arguments
+    /// * `x` - Component along x axis.
+    /// * `y` - Component along y axis.
+    /// * `z` - Component along z axis.
+    ///
+    /// # Returns
+    /// * Newly created ImuVector.
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+}
+
+/// A single sample of an inertial measurement unit (IMU).
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct ImuSample {
+    /// Acceleration, in metres per second squared.
+    pub acc: ImuVector,
+    /// Timestamp of the accelerometer sample on the device clock, in microseconds.
+    pub acc_timestamp_usec: u64,
+    /// Angular velocity, in radians per second.
+    pub gyro: ImuVector,
+    /// Timestamp of the gyroscope sample on the device clock, in microseconds.
+    pub gyro_timestamp_usec: u64,
+    /// Temperature of the sensor, in degrees Celsius.
+    pub temperature: f32,
+}
+
+/// Batch of samples of an inertial measurement unit (IMU), which forms a single buffer of an
+/// `imu` stream, e.g. all samples queued since the previous frame.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ImuSamples {
+    /// Samples in the order they were measured.
+    pub samples: Vec<ImuSample>,
+}
+
+impl ImuSamples {
+    /// Create new ImuSamples.
+    ///
+    /// # Arguments
+    /// * `samples` - Samples in the order they were measured.
+    ///
+    /// # Returns
+    /// * Newly created ImuSamples.
+    pub fn new(samples: Vec<ImuSample>) -> Self {
+        Self { samples }
+    }
+
+    /// Serialise ImuSamples by the use of Cap'n Proto.
+    ///
+    /// # Returns
+    /// * Serialised ImuSamples.
+    pub fn serialise(&self) -> Result<Vec<u8>, capnp::Error> {
+        // Create new cap'n proto builder
+        let mut builder = capnp::message::Builder::new_default();
+        {
+            // Create root for the cap'n proto buffer
+            let imu_samples_serialised = builder.init_root::<imu_samples::Builder>();
+
+            // Initialise cap'n proto samples with the appropriate length
+            let mut samples_serialised =
+                imu_samples_serialised.init_samples(self.samples.len() as u32);
+
+            // Iterate over all samples and serialise them
+            for (index, sample) in self.samples.iter().enumerate() {
+                // Get access to the next member
+                let mut sample_instance = samples_serialised.reborrow().get(index as u32);
+
+                Self::serialise_vector(sample_instance.reborrow().init_acc(), &sample.acc);
+                sample_instance.set_acc_timestamp_usec(sample.acc_timestamp_usec);
+                Self::serialise_vector(sample_instance.reborrow().init_gyro(), &sample.gyro);
+                sample_instance.set_gyro_timestamp_usec(sample.gyro_timestamp_usec);
+                sample_instance.set_temperature(sample.temperature);
+            }
+        }
+
+        let mut serialised_samples: Vec<u8> = Vec::new();
+        capnp::serialize::write_message(&mut serialised_samples, &builder)?;
+
+        Ok(serialised_samples)
+    }
+
+    /// Serialises ImuVector into Cap'n Proto compatible struct.
+    ///
+    /// # Arguments
+    /// * `builder` - Builder of the struct to fill.
+    /// * `vector` - ImuVector to serialise.
+    fn serialise_vector(mut builder: vector3::Builder, vector: &ImuVector) {
+        builder.set_x(vector.x);
+        builder.set_y(vector.y);
+        builder.set_z(vector.z);
+    }
+
+    /// Deserialise ImuSamples by the use of Cap'n Proto, e.g. from a buffer of an `imu` stream.
+    ///
+    /// # Arguments
+    /// * `buffer` - Serialised ImuSamples.
+    ///
+    /// # Returns
+    /// * Deserialised ImuSamples.
+    pub fn deserialise(buffer: &[u8]) -> Result<Self, capnp::Error> {
+        // Read the serialised message from the byte array
+        let serialised_message =
+            capnp::serialize::read_message(buffer, capnp::message::ReaderOptions::new())?;
+
+        // Get reader for the serialised message
+        let imu_samples = serialised_message.get_root::<imu_samples::Reader>()?;
+        let serialised_samples = imu_samples.get_samples()?;
+
+        // Deserialise all samples
+        let mut samples = Vec::with_capacity(serialised_samples.len() as usize);
+        for sample_instance in serialised_samples.iter() {
+            samples.push(ImuSample {
+                acc: ImuVector::from(sample_instance.get_acc()?),
+                acc_timestamp_usec: sample_instance.get_acc_timestamp_usec(),
+                gyro: ImuVector::from(sample_instance.get_gyro()?),
+                gyro_timestamp_usec: sample_instance.get_gyro_timestamp_usec(),
+                temperature: sample_instance.get_temperature(),
+            });
+        }
+
+        Ok(Self { samples })
+    }
+}
+
+impl From<vector3::Reader<'_>> for ImuVector {
+    fn from(vector: vector3::Reader) -> Self {
+        Self {
+            x: vector.get_x(),
+            y: vector.get_y(),
+            z: vector.get_z(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::random;
+
+    const TEST_ITERATIONS: usize = 100;
+
+    fn random_vector() -> ImuVector {
+        ImuVector::new(random(), random(), random())
+    }
+
+    fn initialise_random_imu_samples() -> ImuSamples {
+        let sample_count = random::<usize>() % 32;
+        ImuSamples::new(
+            (0..sample_count)
+                .map(|_| ImuSample {
+                    acc: random_vector(),
+                    acc_timestamp_usec: random(),
+                    gyro: random_vector(),
+                    gyro_timestamp_usec: random(),
+                    temperature: random(),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn serialise_deserialise() {
+        for _ in 0..TEST_ITERATIONS {
+            let imu_samples = initialise_random_imu_samples();
+
+            let serialised_imu_samples = imu_samples.serialise().unwrap();
+            let deserialised_imu_samples =
+                ImuSamples::deserialise(&serialised_imu_samples).unwrap();
+
+            assert_eq!(deserialised_imu_samples, imu_samples);
+        }
+    }
+
+    #[test]
+    fn serialise_deserialise_empty() {
+        let serialised_imu_samples = ImuSamples::default().serialise().unwrap();
+        let deserialised_imu_samples = ImuSamples::deserialise(&serialised_imu_samples).unwrap();
+
+        assert!(deserialised_imu_samples.samples.is_empty());
+    }
+
+    #[test]
+    fn deserialise_truncated() {
+        let imu_samples = ImuSamples::new(vec![ImuSample::default(); 4]);
+        let serialised_imu_samples = imu_samples.serialise().unwrap();
+
+        assert!(ImuSamples::deserialise(
+            &serialised_imu_samples[..serialised_imu_samples.len() / 2]
+        )
+        .is_err());
+    }
+}
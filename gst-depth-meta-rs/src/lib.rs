@@ -9,12 +9,19 @@ pub(crate) mod camera_meta_capnp {
     #![allow(clippy::redundant_field_names)]
     include!(concat!(env!("OUT_DIR"), "/camera_meta_capnp.rs"));
 }
+#[allow(clippy::all)]
+pub(crate) mod imu_capnp {
+    #![allow(dead_code)]
+    #![allow(clippy::redundant_field_names)]
+    include!(concat!(env!("OUT_DIR"), "/imu_capnp.rs"));
+}
 
 mod common;
 
 pub mod camera_meta;
-pub mod dddq_roi_tags;
+pub mod imu;
 pub mod rgbd;
 
 pub use camera_meta::*;
+pub use imu::*;
 pub use rgbd::*;
@@ -1,4 +1,3 @@
-use glib::translate::from_glib;
 use gst::glib;
 use gst::meta::*;
 use gst::BufferRef;
@@ -9,6 +8,16 @@ pub use crate::rgbd::sys::BufferMeta;
 
 /// The BufferMeta API is intended to allow developers to add gst buffers as metadata onto buffers.
 /// This represents a very generic container format that can hold basically any data.
+///
+/// # Transformations
+/// When the main buffer is copied or transformed by an element, the BufferMeta follows it:
+/// * Deep copies of the whole buffer, e.g. `gst::Buffer::copy_deep()`, also deep copy the
+/// contained buffer, such that the copy shares no memory with the original.
+/// * All other copies and transforms share the contained buffer, i.e. plain copies (e.g.
+/// `gst::Buffer::copy()`, `queue` and `tee`), copies of a region (e.g. crop) and elements that
+/// write their output into new memory of a different size (e.g. `videoconvert` and `videoscale`).
+/// Use [buffer_mut](#method.buffer_mut) to modify the contained buffer without affecting the
+/// other buffers that share it.
 impl BufferMeta {
     /// Add the given `meta_buffer` onto the given `buffer`, effectively stacking `meta_buffer` onto
     /// `buffer`.
@@ -69,7 +78,7 @@ unsafe impl MetaAPI for BufferMeta {
     type GstType = BufferMeta;
 
     fn meta_api() -> glib::Type {
-        unsafe { from_glib(sys::buffer_meta_api_get_type()) }
+        sys::buffer_meta_api_get_type()
     }
 }
 
@@ -84,6 +93,61 @@ impl fmt::Debug for BufferMeta {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rgbd::{attach_aux_buffer_and_tag, get_tag, tag_buffer_with_title};
+    use glib::translate::from_glib;
+
+    const WIDTH: usize = 4;
+    const HEIGHT: usize = 4;
+    const COLOR_VALUE: u8 = 42;
+
+    /// Create a `video/rgbd` buffer with `depth` stream as the main buffer and `color` stream as
+    /// an auxiliary buffer.
+    fn rgbd_buffer() -> gst::Buffer {
+        let mut main_buffer = gst::Buffer::from_mut_slice(vec![1u8; WIDTH * HEIGHT * 2]);
+        tag_buffer_with_title(main_buffer.make_mut(), "depth").unwrap();
+        let mut color_buffer = gst::Buffer::from_mut_slice(vec![COLOR_VALUE; WIDTH * HEIGHT * 3]);
+        attach_aux_buffer_and_tag(main_buffer.make_mut(), &mut color_buffer, "color").unwrap();
+        main_buffer
+    }
+
+    /// Assert that `buffer` is tagged as `depth` and contains the `color` stream unchanged.
+    /// # Returns
+    /// * Pointer to the contained `color` buffer.
+    fn assert_rgbd_buffer(buffer: &gst::BufferRef) -> *mut gst_sys::GstBuffer {
+        assert_eq!(get_tag(buffer).unwrap(), "depth");
+        let metas = buffer.iter_meta::<BufferMeta>().collect::<Vec<_>>();
+        assert_eq!(metas.len(), 1, "Found a wrong number of buffers");
+        let color_buffer = metas[0].buffer();
+        assert_eq!(get_tag(color_buffer).unwrap(), "color");
+        assert!(color_buffer
+            .map_readable()
+            .unwrap()
+            .iter()
+            .all(|value| *value == COLOR_VALUE));
+        metas[0].buffer
+    }
+
+    /// Get the first memory of the `color` buffer contained in `buffer`.
+    fn color_memory(buffer: &gst::BufferRef) -> *mut gst_sys::GstMemory {
+        let meta = buffer.meta::<BufferMeta>().unwrap();
+        meta.buffer().peek_memory(0).as_mut_ptr()
+    }
+
+    /// Push a `video/rgbd` buffer through the element(s) described by `launch_line`.
+    /// # Returns
+    /// * Tuple of the input and the output buffer.
+    fn push_through(
+        launch_line: &str,
+        src_caps: &str,
+        sink_caps: &str,
+    ) -> (gst::Buffer, gst::Buffer) {
+        let mut harness = gst_check::Harness::new_parse(launch_line);
+        harness.set_src_caps_str(src_caps);
+        harness.set_sink_caps_str(sink_caps);
+        let input = rgbd_buffer();
+        let output = harness.push_and_pull(input.clone()).unwrap();
+        (input, output)
+    }
 
     #[test]
     fn add_and_get_expect_buffers_equal() {
@@ -165,4 +229,132 @@ mod tests {
             assert_eq!(false, true, "A BufferMeta was found on buffer: {:#?}", b);
         }
     }
+
+    #[test]
+    fn meta_registered_with_c_compatible_names_and_layout() {
+        // Arrange
+        gst::init().unwrap();
+
+        // Act
+        let info = unsafe { &*sys::buffer_meta_get_info() };
+        let implementation: glib::Type = unsafe { from_glib(info.type_) };
+
+        // Assert
+        assert_eq!(BufferMeta::meta_api().name(), "BufferMetaAPI");
+        assert_eq!(implementation.name(), "BufferMeta");
+        assert_eq!(
+            info.size,
+            std::mem::size_of::<gst_sys::GstMeta>()
+                + std::mem::size_of::<*mut gst_sys::GstBuffer>()
+        );
+    }
+
+    #[test]
+    fn copy_expect_buffer_shared_with_copy() {
+        // Arrange
+        gst::init().unwrap();
+        let mut buffer = gst::Buffer::new();
+        let mut meta_buffer = gst::Buffer::from_slice([9, 8, 7, 6]);
+        BufferMeta::add(buffer.make_mut(), &mut meta_buffer);
+
+        // Act
+        let copy = buffer.copy();
+
+        // Assert
+        let metas = copy.iter_meta::<BufferMeta>().collect::<Vec<_>>();
+        assert_eq!(metas.len(), 1);
+        assert_eq!(metas[0].buffer, meta_buffer.as_mut_ptr());
+    }
+
+    #[test]
+    fn copy_expect_memory_shared_with_copy() {
+        // Arrange
+        gst::init().unwrap();
+        let buffer = rgbd_buffer();
+
+        // Act
+        let copy = buffer.copy();
+
+        // Assert
+        assert_eq!(assert_rgbd_buffer(&copy), assert_rgbd_buffer(&buffer));
+        assert_eq!(color_memory(&copy), color_memory(&buffer));
+    }
+
+    #[test]
+    fn copy_deep_expect_memory_not_shared_with_copy() {
+        // Arrange
+        gst::init().unwrap();
+        let buffer = rgbd_buffer();
+
+        // Act
+        let copy = buffer.copy_deep().unwrap();
+
+        // Assert
+        assert_ne!(assert_rgbd_buffer(&copy), assert_rgbd_buffer(&buffer));
+        assert_ne!(color_memory(&copy), color_memory(&buffer));
+    }
+
+    #[test]
+    fn copy_region_expect_buffer_shared_with_copy() {
+        // Arrange
+        gst::init().unwrap();
+        let buffer = rgbd_buffer();
+
+        // Act
+        let copy = buffer
+            .copy_region(gst::BufferCopyFlags::all(), 0, Some(WIDTH * 2))
+            .unwrap();
+
+        // Assert
+        assert_eq!(assert_rgbd_buffer(&copy), assert_rgbd_buffer(&buffer));
+    }
+
+    #[test]
+    fn push_through_queue_and_tee_expect_buffer_shared() {
+        // Arrange
+        gst::init().unwrap();
+        let caps = "video/rgbd,streams=<depth,color>,framerate=30/1,\
+                    depth_format=GRAY16_LE,depth_width=4,depth_height=4,\
+                    color_format=RGB,color_width=4,color_height=4";
+
+        // Act
+        let (input, output) = push_through("queue ! tee ! identity", caps, caps);
+
+        // Assert
+        assert_eq!(assert_rgbd_buffer(&output), assert_rgbd_buffer(&input));
+    }
+
+    #[test]
+    fn push_through_videoconvert_expect_buffer_shared() {
+        // Arrange
+        gst::init().unwrap();
+
+        // Act
+        let (input, output) = push_through(
+            "videoconvert",
+            "video/x-raw,format=GRAY16_LE,width=4,height=4,framerate=30/1",
+            "video/x-raw,format=GRAY8,width=4,height=4,framerate=30/1",
+        );
+
+        // Assert
+        assert_eq!(output.size(), WIDTH * HEIGHT);
+        assert_eq!(assert_rgbd_buffer(&output), assert_rgbd_buffer(&input));
+    }
+
+    #[test]
+    fn push_through_videoscale_expect_buffer_shared() {
+        // Arrange
+        gst::init().unwrap();
+
+        // Act
+        let (input, output) = push_through(
+            "videoscale",
+            "video/x-raw,format=GRAY16_LE,width=4,height=4,framerate=30/1",
+            "video/x-raw,format=GRAY16_LE,width=8,height=8,framerate=30/1",
+        );
+
+        // Assert
+        assert_eq!(output.size(), WIDTH * HEIGHT * 2 * 4);
+        assert_eq!(assert_rgbd_buffer(&output), assert_rgbd_buffer(&input));
+    }
 }
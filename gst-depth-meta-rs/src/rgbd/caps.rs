@@ -0,0 +1,915 @@
+use crate::RgbdError;
+use gst::prelude::*;
+
+/// Media type of `video/rgbd` caps.
+pub const RGBD_MEDIA_TYPE: &str = "video/rgbd";
+/// Media type of streams that contain raw video.
+const RAW_VIDEO_MEDIA_TYPE: &str = "video/x-raw";
+/// Media type of streams that contain JPEG compressed video.
+const JPEG_MEDIA_TYPE: &str = "image/jpeg";
+/// Media type of streams that contain losslessly compressed depth video, see `rvlenc`.
+pub const RVL_MEDIA_TYPE: &str = "video/x-rvl";
+/// Media type of streams without `{stream}_format` field, e.g. `camerameta`.
+const META_MEDIA_TYPE: &str = "meta/x-klv";
+/// Prefix of media types of arbitrary auxiliary streams, e.g. `application/x-capnp`.
+const APPLICATION_MEDIA_TYPE_PREFIX: &str = "application/x-";
+/// Field that stores the media type of a stream whose elementary caps have a `format` field of
+/// their own, which then occupies `{stream}_format`.
+const MEDIA_TYPE_FIELD: &str = "media_type";
+
+/// Caps of a single stream within `video/rgbd` caps, i.e. the caps of its elementary stream,
+/// without the framerate that is shared by all streams.
+///
+/// Within `video/rgbd` caps, each field of the elementary caps is prefixed with the name of the
+/// stream, e.g. `width` of `depth` stream is stored as `depth_width`. The media type of the
+/// elementary caps is stored in `{stream}_format`, with the following exceptions:
+/// * `video/x-raw` streams store their video format instead, e.g. `depth_format=GRAY16_LE`.
+/// * `meta/x-klv` streams have no `{stream}_format` field, e.g. `camerameta`.
+/// * Streams with a `format` field of their own, e.g. `application/x-capnp, format=packed`,
+///   store their media type in `{stream}_media_type` instead.
+///
+/// Besides video, auxiliary streams of any `application/x-*` media type are supported, e.g.
+/// `application/x-capnp, schema=imu` is stored as `imu_format=application/x-capnp` and
+/// `imu_schema=imu`.
+///
+/// A stream whose framerate differs from the shared framerate, e.g. `color` at 15 fps next to
+/// `depth` at 30 fps, stores its own framerate in the optional `{stream}_framerate` field. So do
+/// streams without video that have a framerate, as the shared framerate applies only to video.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbdStreamCaps {
+    /// Name of the stream, e.g. `depth`.
+    name: String,
+    /// Structure of the elementary caps, without `framerate` field.
+    structure: gst::Structure,
+    /// Framerate of the stream, if it differs from the framerate shared by all streams.
+    framerate: Option<gst::Fraction>,
+}
+
+impl RgbdStreamCaps {
+    /// Create caps of a stream from the structure of its elementary caps. The `framerate` field
+    /// is not part of stream caps and it is therefore ignored.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the stream, e.g. `depth`.
+    /// * `structure` - Structure of the elementary caps, e.g. `video/x-raw, format=GRAY16_LE`.
+    ///
+    /// # Returns
+    /// * New instance of RgbdStreamCaps.
+    pub fn new(name: &str, mut structure: gst::Structure) -> Self {
+        structure.remove_field("framerate");
+        Self {
+            name: name.to_string(),
+            structure,
+            framerate: None,
+        }
+    }
+
+    /// Set the own framerate of the stream, which is used instead of the framerate shared by all
+    /// streams.
+    ///
+    /// # Arguments
+    /// * `framerate` - The framerate of the stream, e.g. 15/1 for `color` next to 30/1 `depth`.
+    pub fn with_framerate(mut self, framerate: gst::Fraction) -> Self {
+        self.framerate = Some(framerate);
+        self
+    }
+
+    /// Create caps of a stream from its elementary caps, e.g. CAPS of a `rgbdmux` sink pad.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the stream, e.g. `depth`.
+    /// * `caps` - The elementary caps of the stream.
+    ///
+    /// # Returns
+    /// * `Ok(RgbdStreamCaps)` on success.
+    /// * `Err(RgbdError::InvalidStreamCaps)` if the `caps` are empty or their media type cannot
+    ///   be contained in `video/rgbd` caps, see `is_supported_media_type()`.
+    pub fn from_caps(name: &str, caps: &gst::CapsRef) -> Result<Self, RgbdError> {
+        let structure = caps
+            .structure(0)
+            .ok_or_else(|| RgbdError::InvalidStreamCaps {
+                stream_name: name.to_string(),
+                reason: "CAPS are empty".to_string(),
+            })?;
+        if !Self::is_supported_media_type(structure.name()) {
+            return Err(RgbdError::InvalidStreamCaps {
+                stream_name: name.to_string(),
+                reason: format!("unsupported media type `{}`", structure.name()),
+            });
+        }
+        Ok(Self::new(name, structure.to_owned()))
+    }
+
+    /// Determine whether streams of the given media type can be contained in `video/rgbd` caps,
+    /// i.e. `video/x-raw`, `image/jpeg`, `video/x-rvl`, `meta/x-klv` and any `application/x-*`
+    /// media type.
+    ///
+    /// # Arguments
+    /// * `media_type` - The media type, e.g. `application/x-capnp`.
+    pub fn is_supported_media_type(media_type: &str) -> bool {
+        match media_type {
+            RAW_VIDEO_MEDIA_TYPE | JPEG_MEDIA_TYPE | RVL_MEDIA_TYPE | META_MEDIA_TYPE => true,
+            media_type => {
+                media_type.starts_with(APPLICATION_MEDIA_TYPE_PREFIX)
+                    && media_type.len() > APPLICATION_MEDIA_TYPE_PREFIX.len()
+            }
+        }
+    }
+
+    /// Get the name of the stream.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the media type of the elementary caps, e.g. `video/x-raw`.
+    pub fn media_type(&self) -> &str {
+        self.structure.name()
+    }
+
+    /// Determine whether the stream contains raw video.
+    pub fn is_video(&self) -> bool {
+        self.media_type() == RAW_VIDEO_MEDIA_TYPE
+    }
+
+    /// Determine whether the stream receives the framerate shared by all streams, which is the
+    /// case for streams that contain video, raw or compressed. Other streams have a framerate
+    /// only if they have their own.
+    pub fn has_shared_framerate(&self) -> bool {
+        self.is_video() || matches!(self.media_type(), JPEG_MEDIA_TYPE | RVL_MEDIA_TYPE)
+    }
+
+    /// Get the own framerate of the stream, if it differs from the framerate shared by all
+    /// streams.
+    pub fn framerate(&self) -> Option<gst::Fraction> {
+        self.framerate
+    }
+
+    /// Get the structure of the elementary caps, without `framerate` field.
+    pub fn structure(&self) -> &gst::StructureRef {
+        &self.structure
+    }
+
+    /// Get the mutable structure of the elementary caps, e.g. to add extra fields.
+    pub fn structure_mut(&mut self) -> &mut gst::StructureRef {
+        &mut self.structure
+    }
+
+    /// Get the elementary caps of the stream.
+    ///
+    /// # Arguments
+    /// * `framerate` - The framerate shared by all streams, if any. It is ignored for streams
+    ///   that do not contain video, and for streams with their own framerate.
+    ///
+    /// # Returns
+    /// * The elementary caps, e.g. `video/x-raw, format=GRAY16_LE, width=640, height=480,
+    ///   framerate=30/1`.
+    pub fn to_caps(&self, framerate: Option<&gst::glib::SendValue>) -> gst::Caps {
+        let mut structure = self.structure.clone();
+        if let Some(own_framerate) = self.framerate {
+            structure.set("framerate", own_framerate);
+        } else if let Some(framerate) = framerate {
+            if self.has_shared_framerate() {
+                structure.set_value("framerate", framerate.clone());
+            }
+        }
+
+        let mut caps = gst::Caps::new_empty();
+        caps.get_mut().unwrap().append_structure(structure);
+        caps
+    }
+
+    /// Write the stream caps into `video/rgbd` structure, using fields prefixed by the stream
+    /// name.
+    fn write_to(&self, rgbd_structure: &mut gst::StructureRef) {
+        match self.media_type() {
+            RAW_VIDEO_MEDIA_TYPE | META_MEDIA_TYPE => {}
+            // The `format` field of the elementary caps is written to `{stream}_format` below
+            media_type if self.structure.has_field("format") => {
+                rgbd_structure.set(&format!("{}_{}", self.name, MEDIA_TYPE_FIELD), media_type)
+            }
+            media_type => rgbd_structure.set(&format!("{}_format", self.name), media_type),
+        }
+        for (field, value) in self.structure.iter() {
+            rgbd_structure.set_value(&format!("{}_{}", self.name, field), value.clone());
+        }
+        if let Some(framerate) = self.framerate {
+            rgbd_structure.set(&format!("{}_framerate", self.name), framerate);
+        }
+    }
+
+    /// Read the stream caps from fields of `video/rgbd` structure, which have already been
+    /// stripped of the stream name prefix.
+    fn read_from(name: &str, fields: Vec<(&str, gst::glib::SendValue)>) -> Self {
+        // Determine the media type, see the documentation of `RgbdStreamCaps` for the conventions
+        let format = fields
+            .iter()
+            .find(|(field, _)| *field == "format")
+            .map(|(_, value)| value.get::<&str>().ok());
+        let explicit_media_type = fields
+            .iter()
+            .find(|(field, _)| *field == MEDIA_TYPE_FIELD)
+            .and_then(|(_, value)| value.get::<&str>().ok());
+        let (media_type, keep_format) = match (explicit_media_type, format) {
+            (Some(media_type), _) => (media_type, true),
+            (None, format) => match format {
+                None => (META_MEDIA_TYPE, false),
+                // `image/jpeg` streams used to be written with a variety of format names
+                Some(Some(format)) if format.contains("jpeg") => (JPEG_MEDIA_TYPE, false),
+                Some(Some(format)) if format.contains('/') => (format, false),
+                // Raw video format, or a list of them if the caps are not fixed
+                Some(_) => (RAW_VIDEO_MEDIA_TYPE, true),
+            },
+        };
+
+        let framerate = fields
+            .iter()
+            .find(|(field, _)| *field == "framerate")
+            .and_then(|(_, value)| value.get::<gst::Fraction>().ok());
+
+        let mut structure = gst::Structure::new_empty(media_type);
+        if media_type == META_MEDIA_TYPE {
+            structure.set("parsed", true);
+        }
+        for (field, value) in fields {
+            if field == MEDIA_TYPE_FIELD || (field == "format" && !keep_format) {
+                continue;
+            }
+            structure.set_value(field, value);
+        }
+
+        let stream = Self::new(name, structure);
+        match framerate {
+            Some(framerate) => stream.with_framerate(framerate),
+            None => stream,
+        }
+    }
+}
+
+/// Typed representation of `video/rgbd` caps, which consist of a `streams` array with the names
+/// of all contained streams, a `framerate` shared by all streams, and the fields of each stream
+/// prefixed by its name, e.g. `depth_format`, `depth_width` and `depth_height`.
+///
+/// # Example
+/// ```
+/// use gst_depth_meta::rgbd::{RgbdCaps, RgbdStreamCaps};
+/// gst::init().unwrap();
+/// let depth = gst::Structure::builder("video/x-raw")
+///     .field("format", &"GRAY16_LE")
+///     .field("width", &640i32)
+///     .field("height", &480i32)
+///     .build();
+/// let caps = RgbdCaps::new()
+///     .with_framerate(gst::Fraction::new(30, 1))
+///     .with_stream(RgbdStreamCaps::new("depth", depth))
+///     .to_caps();
+/// assert_eq!(RgbdCaps::from_caps(&caps).unwrap().video_info("depth").unwrap().width(), 640);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbdCaps {
+    /// Structure with fields that do not belong to any stream, e.g. `framerate`.
+    structure: gst::Structure,
+    /// Caps of all streams, in the order of the `streams` field.
+    streams: Vec<RgbdStreamCaps>,
+}
+
+impl Default for RgbdCaps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RgbdCaps {
+    /// Create new `video/rgbd` caps without any streams.
+    pub fn new() -> Self {
+        Self {
+            structure: gst::Structure::new_empty(RGBD_MEDIA_TYPE),
+            streams: Vec::new(),
+        }
+    }
+
+    /// Set the framerate shared by all streams.
+    ///
+    /// # Arguments
+    /// * `framerate` - The framerate, e.g. `gst::Fraction` or `gst::FractionRange`.
+    pub fn with_framerate<T: ToSendValue + Sync>(mut self, framerate: T) -> Self {
+        self.structure.set("framerate", framerate);
+        self
+    }
+
+    /// Add a stream. If a stream with the same name already exists, it is replaced in place.
+    ///
+    /// # Arguments
+    /// * `stream` - Caps of the stream.
+    pub fn with_stream(mut self, stream: RgbdStreamCaps) -> Self {
+        match self.streams.iter_mut().find(|s| s.name == stream.name) {
+            Some(existing) => *existing = stream,
+            None => self.streams.push(stream),
+        }
+        self
+    }
+
+    /// Parse `video/rgbd` caps.
+    ///
+    /// # Arguments
+    /// * `caps` - The caps to parse. Only the first structure is considered.
+    ///
+    /// # Returns
+    /// * `Ok(RgbdCaps)` on success.
+    /// * `Err(RgbdError)` if the caps are not valid `video/rgbd` caps.
+    pub fn from_caps(caps: &gst::CapsRef) -> Result<Self, RgbdError> {
+        let structure = caps
+            .structure(0)
+            .ok_or_else(|| RgbdError::NotRgbdCaps("EMPTY".to_string()))?;
+        Self::from_structure(structure)
+    }
+
+    /// Parse structure of `video/rgbd` caps.
+    ///
+    /// # Arguments
+    /// * `structure` - The structure to parse.
+    ///
+    /// # Returns
+    /// * `Ok(RgbdCaps)` on success.
+    /// * `Err(RgbdError::NotRgbdCaps)` if the structure is not `video/rgbd`.
+    /// * `Err(RgbdError::MissingCapsField)` if there is no `streams` field.
+    /// * `Err(RgbdError::WrongCapsFormat)` if `streams` is not an array of strings.
+    /// * `Err(RgbdError::NoStreams)` if `streams` is empty.
+    /// * `Err(RgbdError::DuplicateStream)` if `streams` contains the same stream more than once.
+    pub fn from_structure(structure: &gst::StructureRef) -> Result<Self, RgbdError> {
+        if structure.name() != RGBD_MEDIA_TYPE {
+            return Err(RgbdError::NotRgbdCaps(structure.name().to_string()));
+        }
+        let stream_names = Self::parse_stream_names(structure)?;
+
+        // Assign each field to the stream with the longest matching prefix, so that e.g.
+        // `depth_aligned_width` belongs to `depth_aligned` stream rather than to `depth` stream
+        let mut rgbd_structure = gst::Structure::new_empty(RGBD_MEDIA_TYPE);
+        let mut stream_fields = vec![Vec::new(); stream_names.len()];
+        for (field, value) in structure.iter().filter(|(field, _)| *field != "streams") {
+            let stream = stream_names
+                .iter()
+                .enumerate()
+                .filter(|(_, name)| {
+                    field.len() > name.len() + 1
+                        && field.starts_with(name.as_str())
+                        && field.as_bytes()[name.len()] == b'_'
+                })
+                .max_by_key(|(_, name)| name.len());
+            match stream {
+                Some((index, name)) => {
+                    stream_fields[index].push((&field[name.len() + 1..], value.clone()))
+                }
+                None => rgbd_structure.set_value(field, value.clone()),
+            }
+        }
+
+        let streams = stream_names
+            .iter()
+            .zip(stream_fields)
+            .map(|(name, fields)| RgbdStreamCaps::read_from(name, fields))
+            .collect();
+
+        Ok(Self {
+            structure: rgbd_structure,
+            streams,
+        })
+    }
+
+    /// Get the names of all streams from the `streams` field of `video/rgbd` structure.
+    fn parse_stream_names(structure: &gst::StructureRef) -> Result<Vec<String>, RgbdError> {
+        if !structure.has_field("streams") {
+            return Err(RgbdError::MissingCapsField("streams".to_string()));
+        }
+        let wrong_format = || RgbdError::WrongCapsFormat {
+            name: "streams".to_string(),
+            type_: "gst::Array of String",
+        };
+        let stream_names = structure
+            .get::<gst::Array>("streams")
+            .map_err(|_| wrong_format())?
+            .as_slice()
+            .iter()
+            .map(|stream| stream.get::<String>().map_err(|_| wrong_format()))
+            .collect::<Result<Vec<String>, RgbdError>>()?;
+
+        if stream_names.is_empty() {
+            return Err(RgbdError::NoStreams);
+        }
+        for (index, name) in stream_names.iter().enumerate() {
+            if stream_names[..index].contains(name) {
+                return Err(RgbdError::DuplicateStream(name.clone()));
+            }
+        }
+        Ok(stream_names)
+    }
+
+    /// Convert into `video/rgbd` caps.
+    pub fn to_caps(&self) -> gst::Caps {
+        let mut caps = gst::Caps::new_empty();
+        caps.get_mut()
+            .unwrap()
+            .append_structure(self.to_structure());
+        caps
+    }
+
+    /// Convert into structure of `video/rgbd` caps.
+    pub fn to_structure(&self) -> gst::Structure {
+        let stream_names = self.streams.iter().map(|s| s.name.to_send_value());
+        let mut structure = gst::Structure::builder(RGBD_MEDIA_TYPE)
+            .field("streams", &gst::Array::from_values(stream_names))
+            .build();
+        for (field, value) in self.structure.iter() {
+            structure.set_value(field, value.clone());
+        }
+        for stream in self.streams.iter() {
+            stream.write_to(&mut structure);
+        }
+        structure
+    }
+
+    /// Get caps of all streams, in the order of the `streams` field.
+    pub fn streams(&self) -> impl Iterator<Item = &RgbdStreamCaps> {
+        self.streams.iter()
+    }
+
+    /// Get names of all streams, in the order of the `streams` field.
+    pub fn stream_names(&self) -> impl Iterator<Item = &str> {
+        self.streams.iter().map(|stream| stream.name())
+    }
+
+    /// Get caps of the stream with the given name.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `depth`.
+    ///
+    /// # Returns
+    /// * `Ok(&RgbdStreamCaps)` on success.
+    /// * `Err(RgbdError::MissingStream)` if the caps do not contain the stream.
+    pub fn stream(&self, stream_name: &str) -> Result<&RgbdStreamCaps, RgbdError> {
+        self.streams
+            .iter()
+            .find(|stream| stream.name == stream_name)
+            .ok_or_else(|| RgbdError::MissingStream(stream_name.to_string()))
+    }
+
+    /// Get the framerate shared by all streams, if it is fixed.
+    pub fn framerate(&self) -> Option<gst::Fraction> {
+        self.structure.get::<gst::Fraction>("framerate").ok()
+    }
+
+    /// Get the framerate of the stream with the given name, which is either its own framerate
+    /// or the framerate shared by all streams.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `depth`.
+    ///
+    /// # Returns
+    /// * `Ok(Some(gst::Fraction))` if the stream has a fixed framerate.
+    /// * `Ok(None)` if neither the stream nor the caps have a fixed framerate.
+    /// * `Err(RgbdError::MissingStream)` if the caps do not contain the stream.
+    pub fn stream_framerate(&self, stream_name: &str) -> Result<Option<gst::Fraction>, RgbdError> {
+        Ok(self
+            .stream(stream_name)?
+            .framerate()
+            .or_else(|| self.framerate()))
+    }
+
+    /// Get the elementary caps of the stream with the given name, including its own framerate or
+    /// the shared framerate.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `depth`.
+    ///
+    /// # Returns
+    /// * `Ok(gst::Caps)` on success.
+    /// * `Err(RgbdError::MissingStream)` if the caps do not contain the stream.
+    pub fn stream_caps(&self, stream_name: &str) -> Result<gst::Caps, RgbdError> {
+        Ok(self
+            .stream(stream_name)?
+            .to_caps(self.structure.value("framerate").ok()))
+    }
+
+    /// Get video information of the stream with the given name. The caps must be fixed.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `depth`.
+    ///
+    /// # Returns
+    /// * `Ok(VideoInfo)` on success.
+    /// * `Err(RgbdError::MissingStream)` if the caps do not contain the stream.
+    /// * `Err(RgbdError::NotVideoStream)` if the stream does not contain raw video.
+    /// * `Err(RgbdError::MissingCapsField)` if the format or resolution of the stream is missing.
+    /// * `Err(RgbdError::InvalidStreamCaps)` if the stream caps do not describe valid video.
+    pub fn video_info(&self, stream_name: &str) -> Result<gst_video::VideoInfo, RgbdError> {
+        let stream = self.stream(stream_name)?;
+        if !stream.is_video() {
+            return Err(RgbdError::NotVideoStream(stream_name.to_string()));
+        }
+        for field in ["format", "width", "height"].iter() {
+            if !stream.structure.has_field(field) {
+                return Err(RgbdError::MissingCapsField(format!(
+                    "{}_{}",
+                    stream_name, field
+                )));
+            }
+        }
+
+        gst_video::VideoInfo::from_caps(&self.stream_caps(stream_name)?).map_err(|e| {
+            RgbdError::InvalidStreamCaps {
+                stream_name: stream_name.to_string(),
+                reason: e.to_string(),
+            }
+        })
+    }
+
+    /// Determine whether all fields of the caps have fixed values.
+    pub fn is_fixed(&self) -> bool {
+        self.to_caps().is_fixed()
+    }
+
+    /// Fixate the caps, i.e. choose a fixed value for each field that has a range or list of
+    /// values.
+    ///
+    /// # Returns
+    /// * `Ok(RgbdCaps)` containing the fixated caps.
+    /// * `Err(RgbdError)` if the fixated caps cannot be parsed, which happens only if a list of
+    ///   media types is fixated into a different kind of stream.
+    pub fn fixate(&self) -> Result<Self, RgbdError> {
+        let mut caps = self.to_caps();
+        caps.fixate();
+        Self::from_caps(&caps)
+    }
+
+    /// Intersect the caps with `other` caps. Both caps must contain the same streams, but they
+    /// may be listed in a different order. The order of `self` is used for the intersection.
+    ///
+    /// # Arguments
+    /// * `other` - The caps to intersect with.
+    ///
+    /// # Returns
+    /// * `Some(RgbdCaps)` containing the intersection.
+    /// * `None` if the caps contain different streams or if any of their fields do not intersect.
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        if self.streams.len() != other.streams.len() {
+            return None;
+        }
+        let mut other_streams = Vec::with_capacity(other.streams.len());
+        for stream in self.streams.iter() {
+            other_streams.push(other.stream(&stream.name).ok()?.clone());
+        }
+        let other = Self {
+            structure: other.structure.clone(),
+            streams: other_streams,
+        };
+
+        let intersection = self.to_caps().intersect(&other.to_caps());
+        if intersection.is_empty() {
+            return None;
+        }
+        Self::from_caps(&intersection).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth_structure() -> gst::Structure {
+        gst::Structure::builder("video/x-raw")
+            .field("format", &"GRAY16_LE")
+            .field("width", &640i32)
+            .field("height", &480i32)
+            .field("framerate", &gst::Fraction::new(30, 1))
+            .build()
+    }
+
+    fn color_structure() -> gst::Structure {
+        gst::Structure::builder("video/x-raw")
+            .field("format", &"RGB")
+            .field("width", &1280i32)
+            .field("height", &720i32)
+            .field("pixel-aspect-ratio", &gst::Fraction::new(1, 1))
+            .field("colorimetry", &"sRGB")
+            .build()
+    }
+
+    fn rgbd_caps() -> RgbdCaps {
+        RgbdCaps::new()
+            .with_framerate(gst::Fraction::new(30, 1))
+            .with_stream(RgbdStreamCaps::new("depth", depth_structure()))
+            .with_stream(RgbdStreamCaps::new("color", color_structure()))
+            .with_stream(RgbdStreamCaps::new(
+                "color_jpeg",
+                gst::Structure::builder("image/jpeg")
+                    .field("width", &1280i32)
+                    .field("height", &720i32)
+                    .build(),
+            ))
+            .with_stream(RgbdStreamCaps::new(
+                "camerameta",
+                gst::Structure::builder("meta/x-klv")
+                    .field("parsed", &true)
+                    .build(),
+            ))
+    }
+
+    #[test]
+    fn caps_round_trip() {
+        gst::init().unwrap();
+
+        let rgbd_caps = rgbd_caps();
+        let caps = rgbd_caps.to_caps();
+        let structure = caps.structure(0).unwrap();
+        assert_eq!(structure.get::<&str>("depth_format").unwrap(), "GRAY16_LE");
+        assert_eq!(structure.get::<i32>("color_width").unwrap(), 1280);
+        assert_eq!(
+            structure.get::<&str>("color_jpeg_format").unwrap(),
+            "image/jpeg"
+        );
+        assert_eq!(structure.get::<&str>("color_colorimetry").unwrap(), "sRGB");
+        assert!(!structure.has_field("depth_framerate"));
+        assert!(!structure.has_field("camerameta_format"));
+
+        let parsed = RgbdCaps::from_caps(&caps).unwrap();
+        assert_eq!(parsed, rgbd_caps);
+        assert_eq!(
+            parsed.stream_names().collect::<Vec<&str>>(),
+            ["depth", "color", "color_jpeg", "camerameta"]
+        );
+        assert_eq!(
+            parsed.stream("color_jpeg").unwrap().media_type(),
+            "image/jpeg"
+        );
+        assert_eq!(parsed.framerate(), Some(gst::Fraction::new(30, 1)));
+    }
+
+    #[test]
+    fn stream_caps_and_video_info() {
+        gst::init().unwrap();
+
+        let rgbd_caps = rgbd_caps();
+        assert_eq!(
+            rgbd_caps.stream_caps("depth").unwrap(),
+            gst::Caps::builder("video/x-raw")
+                .field("format", &"GRAY16_LE")
+                .field("width", &640i32)
+                .field("height", &480i32)
+                .field("framerate", &gst::Fraction::new(30, 1))
+                .build()
+        );
+        assert!(!rgbd_caps
+            .stream_caps("camerameta")
+            .unwrap()
+            .structure(0)
+            .unwrap()
+            .has_field("framerate"));
+
+        let video_info = rgbd_caps.video_info("color").unwrap();
+        assert_eq!(video_info.format(), gst_video::VideoFormat::Rgb);
+        assert_eq!(video_info.fps(), gst::Fraction::new(30, 1));
+        assert_eq!(
+            rgbd_caps.video_info("color_jpeg").unwrap_err(),
+            RgbdError::NotVideoStream("color_jpeg".to_string())
+        );
+        assert_eq!(
+            rgbd_caps.video_info("ir").unwrap_err(),
+            RgbdError::MissingStream("ir".to_string())
+        );
+
+        let mut caps = rgbd_caps.to_caps();
+        caps.make_mut()
+            .structure_mut(0)
+            .unwrap()
+            .remove_field("depth_height");
+        assert_eq!(
+            RgbdCaps::from_caps(&caps)
+                .unwrap()
+                .video_info("depth")
+                .unwrap_err(),
+            RgbdError::MissingCapsField("depth_height".to_string())
+        );
+    }
+
+    #[test]
+    fn per_stream_framerate() {
+        gst::init().unwrap();
+
+        let rgbd_caps = rgbd_caps()
+            .with_stream(
+                RgbdStreamCaps::new("color", color_structure())
+                    .with_framerate(gst::Fraction::new(15, 1)),
+            )
+            .with_stream(
+                RgbdStreamCaps::new(
+                    "imu",
+                    gst::Structure::builder("meta/x-klv")
+                        .field("parsed", &true)
+                        .build(),
+                )
+                .with_framerate(gst::Fraction::new(208, 1)),
+            );
+        let caps = rgbd_caps.to_caps();
+        let structure = caps.structure(0).unwrap();
+        assert_eq!(
+            structure.get::<gst::Fraction>("color_framerate").unwrap(),
+            gst::Fraction::new(15, 1)
+        );
+        assert!(!structure.has_field("depth_framerate"));
+
+        let parsed = RgbdCaps::from_caps(&caps).unwrap();
+        assert_eq!(parsed, rgbd_caps);
+        assert_eq!(
+            parsed.stream("color").unwrap().framerate(),
+            Some(gst::Fraction::new(15, 1))
+        );
+        assert_eq!(parsed.stream("depth").unwrap().framerate(), None);
+        assert_eq!(
+            parsed.stream_framerate("depth").unwrap(),
+            Some(gst::Fraction::new(30, 1))
+        );
+        assert_eq!(
+            parsed.video_info("color").unwrap().fps(),
+            gst::Fraction::new(15, 1)
+        );
+        assert_eq!(
+            parsed
+                .stream_caps("imu")
+                .unwrap()
+                .structure(0)
+                .unwrap()
+                .get::<gst::Fraction>("framerate")
+                .unwrap(),
+            gst::Fraction::new(208, 1)
+        );
+    }
+
+    #[test]
+    fn application_streams() {
+        gst::init().unwrap();
+
+        let capnp = gst::Structure::builder("application/x-capnp")
+            .field("schema", &"imu")
+            .build();
+        let packed = gst::Structure::builder("application/x-custom")
+            .field("format", &"packed")
+            .field("version", &2i32)
+            .build();
+        let rgbd_caps = rgbd_caps()
+            .with_stream(RgbdStreamCaps::new("imu", capnp.clone()))
+            .with_stream(RgbdStreamCaps::new("custom", packed.clone()));
+
+        let caps = rgbd_caps.to_caps();
+        let structure = caps.structure(0).unwrap();
+        assert_eq!(
+            structure.get::<&str>("imu_format").unwrap(),
+            "application/x-capnp"
+        );
+        assert_eq!(
+            structure.get::<&str>("custom_media_type").unwrap(),
+            "application/x-custom"
+        );
+        assert_eq!(structure.get::<&str>("custom_format").unwrap(), "packed");
+
+        let parsed = RgbdCaps::from_caps(&caps).unwrap();
+        assert_eq!(parsed, rgbd_caps);
+        assert_eq!(
+            parsed.stream_caps("imu").unwrap(),
+            gst::Caps::builder_full().structure(capnp).build()
+        );
+        assert_eq!(
+            parsed.stream_caps("custom").unwrap(),
+            gst::Caps::builder_full().structure(packed).build()
+        );
+    }
+
+    #[test]
+    fn rvl_streams() {
+        gst::init().unwrap();
+
+        let rvl = gst::Structure::builder(RVL_MEDIA_TYPE)
+            .field("width", &640i32)
+            .field("height", &480i32)
+            .field("compression", &"zstd")
+            .build();
+        let rgbd_caps = RgbdCaps::new()
+            .with_framerate(gst::Fraction::new(30, 1))
+            .with_stream(RgbdStreamCaps::new("depth", rvl.clone()))
+            .with_stream(RgbdStreamCaps::new("color", color_structure()));
+
+        let caps = rgbd_caps.to_caps();
+        let structure = caps.structure(0).unwrap();
+        assert_eq!(
+            structure.get::<&str>("depth_format").unwrap(),
+            RVL_MEDIA_TYPE
+        );
+        assert_eq!(structure.get::<&str>("depth_compression").unwrap(), "zstd");
+
+        let parsed = RgbdCaps::from_caps(&caps).unwrap();
+        assert_eq!(parsed, rgbd_caps);
+        assert!(parsed.stream("depth").unwrap().has_shared_framerate());
+        let mut expected = rvl;
+        expected.set("framerate", gst::Fraction::new(30, 1));
+        assert_eq!(
+            parsed.stream_caps("depth").unwrap(),
+            gst::Caps::builder_full().structure(expected).build()
+        );
+    }
+
+    #[test]
+    fn unsupported_media_type() {
+        gst::init().unwrap();
+
+        assert!(RgbdStreamCaps::is_supported_media_type(
+            "application/x-capnp"
+        ));
+        assert!(!RgbdStreamCaps::is_supported_media_type("application/x-"));
+        assert!(!RgbdStreamCaps::is_supported_media_type("audio/x-raw"));
+        assert!(matches!(
+            RgbdStreamCaps::from_caps("audio", &gst::Caps::new_simple("audio/x-raw", &[])),
+            Err(RgbdError::InvalidStreamCaps { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_errors() {
+        gst::init().unwrap();
+
+        let caps = gst::Caps::new_simple("video/x-raw", &[]);
+        assert_eq!(
+            RgbdCaps::from_caps(&caps).unwrap_err(),
+            RgbdError::NotRgbdCaps("video/x-raw".to_string())
+        );
+
+        let caps = gst::Caps::new_simple("video/rgbd", &[]);
+        assert_eq!(
+            RgbdCaps::from_caps(&caps).unwrap_err(),
+            RgbdError::MissingCapsField("streams".to_string())
+        );
+
+        let caps = gst::Caps::new_simple("video/rgbd", &[("streams", &"depth,color")]);
+        assert!(matches!(
+            RgbdCaps::from_caps(&caps).unwrap_err(),
+            RgbdError::WrongCapsFormat { .. }
+        ));
+
+        let streams = ["depth", "depth"].iter().map(|s| s.to_send_value());
+        let caps = gst::Caps::new_simple(
+            "video/rgbd",
+            &[("streams", &gst::Array::from_values(streams))],
+        );
+        assert_eq!(
+            RgbdCaps::from_caps(&caps).unwrap_err(),
+            RgbdError::DuplicateStream("depth".to_string())
+        );
+    }
+
+    #[test]
+    fn intersect_and_fixate() {
+        gst::init().unwrap();
+
+        let mut depth = depth_structure();
+        depth.set("width", &gst::IntRange::<i32>::new(320, 1280));
+        depth.set("height", &gst::IntRange::<i32>::new(240, 720));
+        let unfixed = RgbdCaps::new()
+            .with_framerate(gst::FractionRange::new(
+                gst::Fraction::new(5, 1),
+                gst::Fraction::new(30, 1),
+            ))
+            .with_stream(RgbdStreamCaps::new("color", color_structure()))
+            .with_stream(RgbdStreamCaps::new("depth", depth));
+        assert!(!unfixed.is_fixed());
+
+        // Intersection follows the order of streams of `self`
+        let intersection = rgbd_caps()
+            .intersect(
+                &unfixed
+                    .clone()
+                    .with_stream(RgbdStreamCaps::new(
+                        "color_jpeg",
+                        gst::Structure::new_empty("image/jpeg"),
+                    ))
+                    .with_stream(RgbdStreamCaps::new(
+                        "camerameta",
+                        gst::Structure::new_empty("meta/x-klv"),
+                    )),
+            )
+            .unwrap();
+        assert_eq!(intersection, rgbd_caps());
+
+        // Different streams do not intersect
+        assert!(rgbd_caps().intersect(&unfixed).is_none());
+
+        // Incompatible fields do not intersect
+        let mut small_depth = depth_structure();
+        small_depth.set("width", &160i32);
+        let small = unfixed
+            .clone()
+            .with_stream(RgbdStreamCaps::new("depth", small_depth));
+        assert!(unfixed.intersect(&small).is_none());
+
+        let fixated = unfixed.fixate().unwrap();
+        assert!(fixated.is_fixed());
+        assert_eq!(fixated.framerate(), Some(gst::Fraction::new(5, 1)));
+        assert_eq!(fixated.video_info("depth").unwrap().width(), 320);
+    }
+}
@@ -0,0 +1,578 @@
+use crate::camera_meta::CameraMeta;
+use crate::RgbdError;
+
+/// Number of bytes of a single depth value.
+const DEPTH_VALUE_SIZE: usize = 2;
+
+/// Convert a raw depth value into metres.
+/// # Arguments
+/// * `raw` - The raw depth value, where 0 indicates invalid depth.
+/// * `depth_scale` - Scaling factor of the depth map, in metres, see `CameraMeta::depth_scale`.
+/// # Returns
+/// * Depth in metres, 0.0 for invalid depth.
+#[inline]
+pub fn raw_to_metres(raw: u16, depth_scale: f32) -> f32 {
+    raw as f32 * depth_scale
+}
+
+/// Convert depth in metres into a raw depth value. The result is rounded to the nearest raw value
+/// and saturated to the range of u16. Negative and NaN depth is converted into 0, i.e. invalid.
+/// # Arguments
+/// * `metres` - Depth in metres.
+/// * `depth_scale` - Scaling factor of the depth map, in metres, see `CameraMeta::depth_scale`.
+/// # Returns
+/// * The raw depth value.
+#[inline]
+pub fn metres_to_raw(metres: f32, depth_scale: f32) -> u16 {
+    // Float to int casts saturate and map NaN to 0
+    (metres / depth_scale + 0.5) as u16
+}
+
+/// Convert a slice of raw depth values into metres, see [raw_to_metres](fn.raw_to_metres.html).
+/// # Arguments
+/// * `raw` - The raw depth values.
+/// * `depth_scale` - Scaling factor of the depth map, in metres.
+/// * `metres` - Output depth in metres.
+/// # Panics
+/// * If `raw` and `metres` have different lengths.
+pub fn raw_slice_to_metres(raw: &[u16], depth_scale: f32, metres: &mut [f32]) {
+    assert_eq!(
+        raw.len(),
+        metres.len(),
+        "Depth slices must be of equal length"
+    );
+    metres
+        .iter_mut()
+        .zip(raw)
+        .for_each(|(m, r)| *m = raw_to_metres(*r, depth_scale));
+}
+
+/// Convert a slice of depth in metres into raw depth values, see
+/// [metres_to_raw](fn.metres_to_raw.html).
+/// # Arguments
+/// * `metres` - The depth in metres.
+/// * `depth_scale` - Scaling factor of the depth map, in metres.
+/// * `raw` - Output raw depth values.
+/// # Panics
+/// * If `metres` and `raw` have different lengths.
+pub fn metres_slice_to_raw(metres: &[f32], depth_scale: f32, raw: &mut [u16]) {
+    assert_eq!(
+        raw.len(),
+        metres.len(),
+        "Depth slices must be of equal length"
+    );
+    raw.iter_mut()
+        .zip(metres)
+        .for_each(|(r, m)| *r = metres_to_raw(*m, depth_scale));
+}
+
+/// Parameters of a rectified stereo pair, which relate depth and disparity by
+/// `disparity = focal_length * baseline / depth`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StereoParameters {
+    /// Horizontal focal length of the rectified cameras, in pixels.
+    pub focal_length: f32,
+    /// Distance between the optical centres of the cameras, in metres.
+    pub baseline: f32,
+}
+
+impl StereoParameters {
+    /// Create new StereoParameters.
+    /// # Arguments
+    /// * `focal_length` - Horizontal focal length of the rectified cameras, in pixels.
+    /// * `baseline` - Distance between the optical centres of the cameras, in metres.
+    pub fn new(focal_length: f32, baseline: f32) -> Self {
+        Self {
+            focal_length,
+            baseline,
+        }
+    }
+
+    /// Get StereoParameters of two cameras from CameraMeta. The focal length is taken from the
+    /// intrinsics of `left` and the baseline is the length of the translation of the extrinsics
+    /// from `left` to `right`.
+    /// # Arguments
+    /// * `camera_meta` - The calibration of the cameras.
+    /// * `left` - Name of the left camera, e.g. `infra1`.
+    /// * `right` - Name of the right camera, e.g. `infra2`.
+    /// # Returns
+    /// * `Some(StereoParameters)` on success.
+    /// * `None` if the intrinsics of `left` or extrinsics between the cameras are unknown.
+    pub fn from_camera_meta(camera_meta: &CameraMeta, left: &str, right: &str) -> Option<Self> {
+        let intrinsics = camera_meta.intrinsics.get(left)?;
+        let translation = camera_meta
+            .get_extrinsics(left.to_string(), right.to_string())?
+            .translation;
+        let baseline =
+            (translation.x.powi(2) + translation.y.powi(2) + translation.z.powi(2)).sqrt();
+        Some(Self::new(intrinsics.fx, baseline))
+    }
+
+    /// Convert depth into disparity.
+    /// # Arguments
+    /// * `depth` - Depth in metres, where 0.0 indicates invalid depth.
+    /// # Returns
+    /// * Disparity in pixels, 0.0 for invalid depth.
+    #[inline]
+    pub fn depth_to_disparity(&self, depth: f32) -> f32 {
+        if depth > 0.0 {
+            self.focal_length * self.baseline / depth
+        } else {
+            0.0
+        }
+    }
+
+    /// Convert disparity into depth.
+    /// # Arguments
+    /// * `disparity` - Disparity in pixels, where 0.0 indicates invalid disparity.
+    /// # Returns
+    /// * Depth in metres, 0.0 for invalid disparity.
+    #[inline]
+    pub fn disparity_to_depth(&self, disparity: f32) -> f32 {
+        if disparity > 0.0 {
+            self.focal_length * self.baseline / disparity
+        } else {
+            0.0
+        }
+    }
+
+    /// Convert a slice of depth into disparity, see
+    /// [depth_to_disparity](#method.depth_to_disparity).
+    /// # Panics
+    /// * If `depth` and `disparity` have different lengths.
+    pub fn depth_slice_to_disparity(&self, depth: &[f32], disparity: &mut [f32]) {
+        assert_eq!(
+            depth.len(),
+            disparity.len(),
+            "Slices must be of equal length"
+        );
+        disparity
+            .iter_mut()
+            .zip(depth)
+            .for_each(|(d, z)| *d = self.depth_to_disparity(*z));
+    }
+
+    /// Convert a slice of disparity into depth, see
+    /// [disparity_to_depth](#method.disparity_to_depth).
+    /// # Panics
+    /// * If `disparity` and `depth` have different lengths.
+    pub fn disparity_slice_to_depth(&self, disparity: &[f32], depth: &mut [f32]) {
+        assert_eq!(
+            depth.len(),
+            disparity.len(),
+            "Slices must be of equal length"
+        );
+        depth
+            .iter_mut()
+            .zip(disparity)
+            .for_each(|(z, d)| *z = self.disparity_to_depth(*d));
+    }
+}
+
+/// Memory layout of 16-bit depth values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthLayout {
+    /// Little-endian 16-bit values, i.e. GStreamer `GRAY16_LE`.
+    Gray16Le,
+    /// Big-endian 16-bit values, i.e. GStreamer `GRAY16_BE`.
+    Gray16Be,
+    /// Little-endian 16-bit values as produced by RealSense and K4A devices, i.e. `Z16`.
+    Z16,
+}
+
+impl DepthLayout {
+    /// Parse depth layout from the name of its format.
+    /// # Arguments
+    /// * `format` - Name of the format, i.e. `GRAY16_LE`, `GRAY16_BE` or `Z16`.
+    /// # Returns
+    /// * `Ok(DepthLayout)` on success.
+    /// * `Err(RgbdError::UnsupportedDepthFormat)` if the format is not a depth format.
+    pub fn from_format(format: &str) -> Result<Self, RgbdError> {
+        match format {
+            "GRAY16_LE" => Ok(DepthLayout::Gray16Le),
+            "GRAY16_BE" => Ok(DepthLayout::Gray16Be),
+            "Z16" => Ok(DepthLayout::Z16),
+            format => Err(RgbdError::UnsupportedDepthFormat(format.to_string())),
+        }
+    }
+
+    /// Get depth layout of a video format.
+    /// # Arguments
+    /// * `format` - The video format.
+    /// # Returns
+    /// * `Ok(DepthLayout)` on success.
+    /// * `Err(RgbdError::UnsupportedDepthFormat)` if the format is not a depth format.
+    pub fn from_video_format(format: gst_video::VideoFormat) -> Result<Self, RgbdError> {
+        match format {
+            gst_video::VideoFormat::Gray16Le => Ok(DepthLayout::Gray16Le),
+            gst_video::VideoFormat::Gray16Be => Ok(DepthLayout::Gray16Be),
+            format => Err(RgbdError::UnsupportedDepthFormat(
+                format.to_str().to_string(),
+            )),
+        }
+    }
+
+    /// Determine whether the depth values are stored in little-endian byte order.
+    pub fn is_little_endian(self) -> bool {
+        !matches!(self, DepthLayout::Gray16Be)
+    }
+
+    /// Decode a single depth value.
+    #[inline]
+    fn decode(self, bytes: [u8; DEPTH_VALUE_SIZE]) -> u16 {
+        if self.is_little_endian() {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    }
+
+    /// Encode a single depth value.
+    #[inline]
+    fn encode(self, value: u16) -> [u8; DEPTH_VALUE_SIZE] {
+        if self.is_little_endian() {
+            value.to_le_bytes()
+        } else {
+            value.to_be_bytes()
+        }
+    }
+}
+
+/// Layout and dimensions of a depth frame in memory, which allows conversion between the bytes of
+/// a buffer and contiguous depth values regardless of byte order and row padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthFormat {
+    /// Memory layout of the depth values.
+    pub layout: DepthLayout,
+    /// Width of the frame, in pixels.
+    pub width: usize,
+    /// Height of the frame, in pixels.
+    pub height: usize,
+    /// Number of bytes between the starts of two consecutive rows.
+    pub stride: usize,
+}
+
+impl DepthFormat {
+    /// Create a new DepthFormat without row padding.
+    /// # Arguments
+    /// * `layout` - Memory layout of the depth values.
+    /// * `width` - Width of the frame, in pixels.
+    /// * `height` - Height of the frame, in pixels.
+    pub fn new(layout: DepthLayout, width: usize, height: usize) -> Self {
+        Self {
+            layout,
+            width,
+            height,
+            stride: width * DEPTH_VALUE_SIZE,
+        }
+    }
+
+    /// Set the number of bytes between the starts of two consecutive rows.
+    /// # Arguments
+    /// * `stride` - The stride, in bytes, which must be at least `2 * width`.
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    /// Get DepthFormat of a depth stream from its video info.
+    /// # Arguments
+    /// * `video_info` - Video info of the depth stream.
+    /// # Returns
+    /// * `Ok(DepthFormat)` on success.
+    /// * `Err(RgbdError::UnsupportedDepthFormat)` if the stream does not contain depth.
+    pub fn from_video_info(video_info: &gst_video::VideoInfo) -> Result<Self, RgbdError> {
+        Ok(Self::new(
+            DepthLayout::from_video_format(video_info.format())?,
+            video_info.width() as usize,
+            video_info.height() as usize,
+        )
+        .with_stride(video_info.stride()[0] as usize))
+    }
+
+    /// Get the number of pixels of the frame.
+    pub fn pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Get the minimum number of bytes that a buffer containing the frame must have.
+    pub fn size(&self) -> usize {
+        if self.height == 0 {
+            0
+        } else {
+            self.stride * (self.height - 1) + self.width * DEPTH_VALUE_SIZE
+        }
+    }
+
+    /// Decode the frame into contiguous raw depth values in native byte order.
+    /// # Arguments
+    /// * `data` - The bytes of the frame.
+    /// * `depth` - Output raw depth values, which must contain `width * height` values.
+    /// # Returns
+    /// * `Ok()` on success.
+    /// * `Err(RgbdError::InvalidDepthFrame)` if `data`, `depth` or the stride are too small.
+    pub fn unpack(&self, data: &[u8], depth: &mut [u16]) -> Result<(), RgbdError> {
+        self.check(data.len(), depth.len())?;
+        for (row, depth_row) in self.rows(data).zip(depth.chunks_exact_mut(self.width)) {
+            depth_row
+                .iter_mut()
+                .zip(row.chunks_exact(DEPTH_VALUE_SIZE))
+                .for_each(|(value, bytes)| *value = self.layout.decode([bytes[0], bytes[1]]));
+        }
+        Ok(())
+    }
+
+    /// Encode contiguous raw depth values in native byte order into the frame. Row padding is
+    /// left untouched.
+    /// # Arguments
+    /// * `depth` - The raw depth values, which must contain `width * height` values.
+    /// * `data` - Output bytes of the frame.
+    /// # Returns
+    /// * `Ok()` on success.
+    /// * `Err(RgbdError::InvalidDepthFrame)` if `data`, `depth` or the stride are too small.
+    pub fn pack(&self, depth: &[u16], data: &mut [u8]) -> Result<(), RgbdError> {
+        self.check(data.len(), depth.len())?;
+        for (row, depth_row) in data
+            .chunks_mut(self.stride)
+            .zip(depth.chunks_exact(self.width))
+        {
+            row[..self.width * DEPTH_VALUE_SIZE]
+                .chunks_exact_mut(DEPTH_VALUE_SIZE)
+                .zip(depth_row)
+                .for_each(|(bytes, value)| bytes.copy_from_slice(&self.layout.encode(*value)));
+        }
+        Ok(())
+    }
+
+    /// Decode the frame into contiguous depth in metres.
+    /// # Arguments
+    /// * `data` - The bytes of the frame.
+    /// * `depth_scale` - Scaling factor of the depth map, in metres.
+    /// * `metres` - Output depth in metres, which must contain `width * height` values.
+    /// # Returns
+    /// * `Ok()` on success.
+    /// * `Err(RgbdError::InvalidDepthFrame)` if `data`, `metres` or the stride are too small.
+    pub fn unpack_metres(
+        &self,
+        data: &[u8],
+        depth_scale: f32,
+        metres: &mut [f32],
+    ) -> Result<(), RgbdError> {
+        self.check(data.len(), metres.len())?;
+        for (row, metres_row) in self.rows(data).zip(metres.chunks_exact_mut(self.width)) {
+            metres_row
+                .iter_mut()
+                .zip(row.chunks_exact(DEPTH_VALUE_SIZE))
+                .for_each(|(value, bytes)| {
+                    *value = raw_to_metres(self.layout.decode([bytes[0], bytes[1]]), depth_scale)
+                });
+        }
+        Ok(())
+    }
+
+    /// Encode contiguous depth in metres into the frame, see
+    /// [metres_to_raw](fn.metres_to_raw.html). Row padding is left untouched.
+    /// # Arguments
+    /// * `metres` - Depth in metres, which must contain `width * height` values.
+    /// * `depth_scale` - Scaling factor of the depth map, in metres.
+    /// * `data` - Output bytes of the frame.
+    /// # Returns
+    /// * `Ok()` on success.
+    /// * `Err(RgbdError::InvalidDepthFrame)` if `data`, `metres` or the stride are too small.
+    pub fn pack_metres(
+        &self,
+        metres: &[f32],
+        depth_scale: f32,
+        data: &mut [u8],
+    ) -> Result<(), RgbdError> {
+        self.check(data.len(), metres.len())?;
+        for (row, metres_row) in data
+            .chunks_mut(self.stride)
+            .zip(metres.chunks_exact(self.width))
+        {
+            row[..self.width * DEPTH_VALUE_SIZE]
+                .chunks_exact_mut(DEPTH_VALUE_SIZE)
+                .zip(metres_row)
+                .for_each(|(bytes, value)| {
+                    bytes.copy_from_slice(&self.layout.encode(metres_to_raw(*value, depth_scale)))
+                });
+        }
+        Ok(())
+    }
+
+    /// Iterate over the rows of the frame, excluding row padding.
+    fn rows<'a>(&self, data: &'a [u8]) -> impl Iterator<Item = &'a [u8]> {
+        let row_size = self.width * DEPTH_VALUE_SIZE;
+        data.chunks(self.stride)
+            .take(self.height)
+            .map(move |row| &row[..row_size])
+    }
+
+    /// Make sure that the frame can be converted between buffers of the given sizes.
+    /// # Arguments
+    /// * `data_size` - Number of bytes of the frame.
+    /// * `values` - Number of contiguous depth values.
+    fn check(&self, data_size: usize, values: usize) -> Result<(), RgbdError> {
+        let invalid = |reason: String| Err(RgbdError::InvalidDepthFrame(reason));
+        if self.width == 0 || self.height == 0 {
+            return invalid(format!("{}x{} frame is empty", self.width, self.height));
+        }
+        if self.stride < self.width * DEPTH_VALUE_SIZE {
+            return invalid(format!(
+                "stride of {} bytes is smaller than a row of {} pixels",
+                self.stride, self.width
+            ));
+        }
+        if data_size < self.size() {
+            return invalid(format!(
+                "{} bytes are required, but the buffer has only {} bytes",
+                self.size(),
+                data_size
+            ));
+        }
+        if values != self.pixel_count() {
+            return invalid(format!(
+                "{} depth values are required, but there are {}",
+                self.pixel_count(),
+                values
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera_meta::{Distortion, Intrinsics, RotationMatrix, Transformation, Translation};
+    use crate::common::tests::nearly_equal_f32;
+    use std::collections::HashMap;
+
+    const DEPTH_SCALE: f32 = 0.001;
+
+    #[test]
+    fn raw_and_metres_round_trip() {
+        let raw = [0u16, 1, 1000, 1234, u16::MAX];
+        let mut metres = [0.0f32; 5];
+        let mut raw_out = [0u16; 5];
+
+        raw_slice_to_metres(&raw, DEPTH_SCALE, &mut metres);
+        metres_slice_to_raw(&metres, DEPTH_SCALE, &mut raw_out);
+
+        assert!(nearly_equal_f32(metres[0], 0.0));
+        assert!(nearly_equal_f32(metres[2], 1.0));
+        assert_eq!(raw, raw_out);
+    }
+
+    #[test]
+    fn metres_to_raw_saturates() {
+        assert_eq!(metres_to_raw(-1.0, DEPTH_SCALE), 0);
+        assert_eq!(metres_to_raw(f32::NAN, DEPTH_SCALE), 0);
+        assert_eq!(metres_to_raw(100.0, DEPTH_SCALE), u16::MAX);
+        assert_eq!(metres_to_raw(0.0014, DEPTH_SCALE), 1);
+        assert_eq!(metres_to_raw(0.0016, DEPTH_SCALE), 2);
+    }
+
+    #[test]
+    fn depth_and_disparity_round_trip() {
+        let stereo = StereoParameters::new(640.0, 0.05);
+        let depth = [0.0, 0.5, 1.0, 4.0];
+        let mut disparity = [0.0; 4];
+        let mut depth_out = [0.0; 4];
+
+        stereo.depth_slice_to_disparity(&depth, &mut disparity);
+        stereo.disparity_slice_to_depth(&disparity, &mut depth_out);
+
+        assert!(nearly_equal_f32(disparity[0], 0.0));
+        assert!(nearly_equal_f32(disparity[2], 32.0));
+        for (expected, actual) in depth.iter().zip(depth_out.iter()) {
+            assert!((expected - actual).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn stereo_parameters_from_camera_meta() {
+        let mut intrinsics = HashMap::new();
+        intrinsics.insert(
+            "infra1".to_string(),
+            Intrinsics::new(640.0, 640.0, 320.0, 240.0, Distortion::None),
+        );
+        let mut extrinsics = HashMap::new();
+        extrinsics.insert(
+            ("infra1".to_string(), "infra2".to_string()),
+            Transformation::new(
+                Translation::new(-0.03, 0.0, 0.04),
+                RotationMatrix::default(),
+            ),
+        );
+        let camera_meta = CameraMeta::new(intrinsics, extrinsics, DEPTH_SCALE);
+
+        let stereo = StereoParameters::from_camera_meta(&camera_meta, "infra1", "infra2").unwrap();
+
+        assert!(nearly_equal_f32(stereo.focal_length, 640.0));
+        assert!((stereo.baseline - 0.05).abs() < 1e-6);
+        assert!(StereoParameters::from_camera_meta(&camera_meta, "infra1", "color").is_none());
+    }
+
+    #[test]
+    fn unpack_and_pack_with_stride() {
+        let depth = [1u16, 2, 3, 0x0102, 0x0304, 0x0506];
+        for layout in [
+            DepthLayout::Gray16Le,
+            DepthLayout::Gray16Be,
+            DepthLayout::Z16,
+        ] {
+            let format = DepthFormat::new(layout, 3, 2).with_stride(8);
+            let mut data = vec![0xffu8; format.size()];
+            let mut depth_out = [0u16; 6];
+
+            format.pack(&depth, &mut data).unwrap();
+            format.unpack(&data, &mut depth_out).unwrap();
+
+            assert_eq!(data.len(), 14);
+            assert_eq!(&data[6..8], &[0xff, 0xff], "Row padding was modified");
+            if layout.is_little_endian() {
+                assert_eq!(&data[8..10], &[0x02, 0x01]);
+            } else {
+                assert_eq!(&data[8..10], &[0x01, 0x02]);
+            }
+            assert_eq!(depth, depth_out);
+        }
+    }
+
+    #[test]
+    fn unpack_and_pack_metres() {
+        let format = DepthFormat::new(DepthLayout::Gray16Be, 2, 2);
+        let metres = [0.0, 0.5, 1.0, 2.5];
+        let mut data = vec![0u8; format.size()];
+        let mut metres_out = [0.0; 4];
+
+        format.pack_metres(&metres, DEPTH_SCALE, &mut data).unwrap();
+        format
+            .unpack_metres(&data, DEPTH_SCALE, &mut metres_out)
+            .unwrap();
+
+        assert_eq!(&data[2..4], &500u16.to_be_bytes());
+        for (expected, actual) in metres.iter().zip(metres_out.iter()) {
+            assert!((expected - actual).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let format = DepthFormat::new(DepthLayout::Z16, 4, 2);
+        let mut depth = [0u16; 8];
+        assert!(format.unpack(&[0u8; 15], &mut depth).is_err());
+        assert!(format.unpack(&[0u8; 16], &mut depth[..7]).is_err());
+        assert!(format
+            .with_stride(6)
+            .unpack(&[0u8; 16], &mut depth)
+            .is_err());
+        assert!(format.unpack(&[0u8; 16], &mut depth).is_ok());
+        assert_eq!(
+            DepthLayout::from_format("RGB"),
+            Err(RgbdError::UnsupportedDepthFormat("RGB".to_string()))
+        );
+        assert_eq!(DepthLayout::from_format("Z16"), Ok(DepthLayout::Z16));
+    }
+}
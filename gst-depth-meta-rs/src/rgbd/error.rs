@@ -21,6 +21,42 @@ pub enum RgbdError {
     },
     /// The input buffer is not properly aligned to contain depth video.
     BufferNotAligned,
+    /// A buffer of the video/rgbd stream is not tagged with the name of its stream.
+    MissingTag,
+    /// The stream with the given name is not contained in the video/rgbd caps or buffer.
+    MissingStream(String),
+    /// The stream with the given name does not contain raw video, but it was accessed as video.
+    NotVideoStream(String),
+    /// The buffer of a stream could not be mapped.
+    MappingFailed {
+        /// The name of the stream.
+        stream_name: String,
+        /// The reason of the failure.
+        reason: String,
+    },
+    /// The CameraMeta attached to the video/rgbd buffer could not be deserialised.
+    InvalidCameraMeta(String),
+    /// GStreamer error: The CAPS with the given media type are not video/rgbd CAPS.
+    NotRgbdCaps(String),
+    /// GStreamer error: The `streams` field of the video/rgbd CAPS is empty.
+    NoStreams,
+    /// GStreamer error: The `streams` field of the video/rgbd CAPS contains the given stream twice.
+    DuplicateStream(String),
+    /// GStreamer error: The CAPS of a stream within the video/rgbd CAPS are not valid.
+    InvalidStreamCaps {
+        /// The name of the stream.
+        stream_name: String,
+        /// The reason why the CAPS are not valid.
+        reason: String,
+    },
+    /// The format with the given name does not contain 16-bit depth values.
+    UnsupportedDepthFormat(String),
+    /// The depth frame cannot be converted for the given reason, e.g. the buffer is too small.
+    InvalidDepthFrame(String),
+    /// The colour image cannot be used for the given reason, e.g. its format is not RGB.
+    InvalidColourImage(String),
+    /// CameraMeta does not contain the described calibration, e.g. intrinsics of a camera.
+    MissingCalibration(String),
 }
 impl std::error::Error for RgbdError {}
 impl std::fmt::Display for RgbdError {
@@ -38,6 +74,45 @@ impl std::fmt::Display for RgbdError {
                 field, expected
             ),
             RgbdError::BufferNotAligned => "Input buffer is not aligned to u16".to_string(),
+            RgbdError::MissingTag => "RGBD: Buffer is not tagged with its stream".to_string(),
+            RgbdError::MissingStream(stream) => format!("RGBD: Missing stream `{}`", stream),
+            RgbdError::NotVideoStream(stream) => {
+                format!("RGBD: Stream `{}` does not contain raw video", stream)
+            }
+            RgbdError::MappingFailed {
+                stream_name,
+                reason,
+            } => format!(
+                "RGBD: Cannot map buffer of stream `{}`: {}",
+                stream_name, reason
+            ),
+            RgbdError::InvalidCameraMeta(reason) => {
+                format!("RGBD: Cannot deserialise CameraMeta: {}", reason)
+            }
+            RgbdError::NotRgbdCaps(media_type) => {
+                format!("RGBD: Expected `video/rgbd` CAPS, but got `{}`", media_type)
+            }
+            RgbdError::NoStreams => "RGBD: CAPS field `streams` is empty".to_string(),
+            RgbdError::DuplicateStream(stream) => format!(
+                "RGBD: CAPS field `streams` contains stream `{}` more than once",
+                stream
+            ),
+            RgbdError::InvalidStreamCaps {
+                stream_name,
+                reason,
+            } => format!("RGBD: Invalid CAPS of stream `{}`: {}", stream_name, reason),
+            RgbdError::UnsupportedDepthFormat(format) => {
+                format!("RGBD: Format `{}` is not a supported depth format", format)
+            }
+            RgbdError::InvalidDepthFrame(reason) => {
+                format!("RGBD: Invalid depth frame: {}", reason)
+            }
+            RgbdError::InvalidColourImage(reason) => {
+                format!("RGBD: Invalid colour image: {}", reason)
+            }
+            RgbdError::MissingCalibration(calibration) => {
+                format!("RGBD: CameraMeta does not contain {}", calibration)
+            }
         };
 
         write!(f, "{}", msg)
@@ -0,0 +1,724 @@
+use crate::buffer::BufferMeta;
+use crate::camera_meta::CameraMeta;
+use crate::rgbd::{get_tag, to_depth_buffer, to_depth_buffer_mut, RgbdCaps};
+use crate::RgbdError;
+use gst::glib;
+
+/// Name of the stream that contains depth video.
+pub const DEPTH_STREAM_NAME: &str = "depth";
+/// Name of the stream that contains serialised CameraMeta.
+pub const CAMERA_META_STREAM_NAME: &str = "camerameta";
+
+/// Information about a single stream contained in `video/rgbd` caps.
+#[derive(Debug, Clone)]
+pub struct RgbdStreamInfo {
+    /// Name of the stream, which is also the title tag of its buffers, e.g. `depth`.
+    pub name: String,
+    /// Information about the video of the stream. It is `None` for streams that do not contain
+    /// raw video, e.g. `camerameta` or `image/jpeg` compressed color.
+    pub video_info: Option<gst_video::VideoInfo>,
+}
+
+/// Information about all streams contained in `video/rgbd` caps. It should be created once per
+/// caps negotiation and used to map each of the subsequent buffers with
+/// [RgbdFrame](struct.RgbdFrame.html) or [RgbdFrameMut](struct.RgbdFrameMut.html).
+#[derive(Debug, Clone)]
+pub struct RgbdFrameInfo {
+    /// Streams in the order of the `streams` field.
+    streams: Vec<RgbdStreamInfo>,
+}
+
+impl RgbdFrameInfo {
+    /// Create RgbdFrameInfo from `video/rgbd` caps.
+    ///
+    /// # Arguments
+    /// * `caps` - Fixed `video/rgbd` caps.
+    ///
+    /// # Returns
+    /// * `Ok(RgbdFrameInfo)` on success.
+    /// * `Err(RgbdError)` if the caps are not valid `video/rgbd` caps, or if any of the raw video
+    ///   streams has incomplete caps.
+    pub fn from_caps(caps: &gst::CapsRef) -> Result<Self, RgbdError> {
+        Self::from_rgbd_caps(&RgbdCaps::from_caps(caps)?)
+    }
+
+    /// Create RgbdFrameInfo from the structure of `video/rgbd` caps.
+    ///
+    /// # Arguments
+    /// * `caps` - Structure of fixed `video/rgbd` caps.
+    ///
+    /// # Returns
+    /// * `Ok(RgbdFrameInfo)` on success.
+    /// * `Err(RgbdError)` if the caps are incomplete, see [from_caps](#method.from_caps).
+    pub fn from_structure(caps: &gst::StructureRef) -> Result<Self, RgbdError> {
+        Self::from_rgbd_caps(&RgbdCaps::from_structure(caps)?)
+    }
+
+    /// Create RgbdFrameInfo from parsed `video/rgbd` caps.
+    ///
+    /// # Arguments
+    /// * `caps` - Fixed `video/rgbd` caps.
+    ///
+    /// # Returns
+    /// * `Ok(RgbdFrameInfo)` on success.
+    /// * `Err(RgbdError)` if any of the raw video streams has incomplete caps.
+    pub fn from_rgbd_caps(caps: &RgbdCaps) -> Result<Self, RgbdError> {
+        let streams = caps
+            .streams()
+            .map(|stream| {
+                let video_info = if stream.is_video() {
+                    Some(caps.video_info(stream.name())?)
+                } else {
+                    None
+                };
+                Ok(RgbdStreamInfo {
+                    name: stream.name().to_string(),
+                    video_info,
+                })
+            })
+            .collect::<Result<Vec<RgbdStreamInfo>, RgbdError>>()?;
+
+        Ok(Self { streams })
+    }
+
+    /// Get information about all streams, in the order of the `streams` field.
+    pub fn streams(&self) -> impl Iterator<Item = &RgbdStreamInfo> {
+        self.streams.iter()
+    }
+
+    /// Get information about the stream with the given name.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `depth`.
+    ///
+    /// # Returns
+    /// * `Some(&RgbdStreamInfo)` if the stream is contained in the caps.
+    /// * `None` otherwise.
+    pub fn stream(&self, stream_name: &str) -> Option<&RgbdStreamInfo> {
+        self.streams
+            .iter()
+            .find(|stream| stream.name == stream_name)
+    }
+
+    /// Get video information of the stream with the given name.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `depth`.
+    ///
+    /// # Returns
+    /// * `Ok(&VideoInfo)` on success.
+    /// * `Err(RgbdError::MissingStream)` if the stream is not contained in the caps.
+    /// * `Err(RgbdError::NotVideoStream)` if the stream does not contain raw video.
+    pub fn video_info(&self, stream_name: &str) -> Result<&gst_video::VideoInfo, RgbdError> {
+        self.stream(stream_name)
+            .ok_or_else(|| RgbdError::MissingStream(stream_name.to_string()))?
+            .video_info
+            .as_ref()
+            .ok_or_else(|| RgbdError::NotVideoStream(stream_name.to_string()))
+    }
+}
+
+/// Read-only view over a `video/rgbd` buffer, which gives access to the buffers of individual
+/// streams by their name instead of by their title tag.
+#[derive(Debug)]
+pub struct RgbdFrame<'a> {
+    /// Information about the streams, based on the negotiated caps.
+    info: &'a RgbdFrameInfo,
+    /// Buffers of all streams contained in the main buffer, including the main buffer itself.
+    streams: Vec<(String, &'a gst::BufferRef)>,
+}
+
+impl<'a> RgbdFrame<'a> {
+    /// Create a read-only view over `main_buffer`.
+    ///
+    /// # Arguments
+    /// * `main_buffer` - The main buffer of `video/rgbd` stream, which contains aux buffers.
+    /// * `info` - Information about the streams, based on the negotiated caps.
+    ///
+    /// # Returns
+    /// * `Ok(RgbdFrame)` on success.
+    /// * `Err(RgbdError::MissingTag)` if any of the buffers is not tagged with its stream name.
+    pub fn from_buffer_ref(
+        main_buffer: &'a gst::BufferRef,
+        info: &'a RgbdFrameInfo,
+    ) -> Result<Self, RgbdError> {
+        let mut streams = vec![(stream_tag(main_buffer)?, main_buffer)];
+        for meta in main_buffer.iter_meta::<BufferMeta>() {
+            // The aux buffer is owned by its meta, which lives as long as the main buffer
+            let buffer = unsafe { gst::BufferRef::from_ptr(meta.buffer) };
+            streams.push((stream_tag(buffer)?, buffer));
+        }
+
+        Ok(Self { info, streams })
+    }
+
+    /// Get information about the streams of this frame.
+    pub fn info(&self) -> &'a RgbdFrameInfo {
+        self.info
+    }
+
+    /// Get the buffers of all streams contained in this frame, with the main buffer first. Note
+    /// that a buffer might be tagged with a stream that is not contained in the caps.
+    pub fn buffers(&self) -> impl Iterator<Item = (&str, &'a gst::BufferRef)> + '_ {
+        self.streams
+            .iter()
+            .map(|(stream_name, buffer)| (stream_name.as_str(), *buffer))
+    }
+
+    /// Get the buffer of the stream with the given name.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `depth`.
+    ///
+    /// # Returns
+    /// * `Ok(&gst::BufferRef)` on success.
+    /// * `Err(RgbdError::MissingStream)` if this frame has no buffer for the stream.
+    pub fn buffer(&self, stream_name: &str) -> Result<&'a gst::BufferRef, RgbdError> {
+        self.streams
+            .iter()
+            .find(|(name, _)| name == stream_name)
+            .map(|(_, buffer)| *buffer)
+            .ok_or_else(|| RgbdError::MissingStream(stream_name.to_string()))
+    }
+
+    /// Map the buffer of the stream with the given name as a readable video frame.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `color`.
+    ///
+    /// # Returns
+    /// * `Ok(VideoFrameRef)` on success.
+    /// * `Err(RgbdError)` if the stream does not contain raw video or cannot be mapped.
+    pub fn video_frame(
+        &self,
+        stream_name: &str,
+    ) -> Result<gst_video::VideoFrameRef<&'a gst::BufferRef>, RgbdError> {
+        map_video_frame(self.buffer(stream_name)?, self.info, stream_name)
+    }
+
+    /// Map the buffer of `depth` stream.
+    ///
+    /// # Returns
+    /// * `Ok(DepthFrame)` on success.
+    /// * `Err(RgbdError)` if there is no `GRAY16_LE` depth stream or if it cannot be mapped.
+    pub fn depth(&self) -> Result<DepthFrame<'a>, RgbdError> {
+        DepthFrame::new(self.video_frame(DEPTH_STREAM_NAME)?)
+    }
+
+    /// Deserialise CameraMeta that is attached to this frame as `camerameta` stream.
+    ///
+    /// # Returns
+    /// * `Ok(CameraMeta)` on success.
+    /// * `Err(RgbdError::MissingStream)` if there is no CameraMeta attached.
+    /// * `Err(RgbdError::InvalidCameraMeta)` if the CameraMeta cannot be deserialised.
+    pub fn camera_meta(&self) -> Result<CameraMeta, RgbdError> {
+        deserialise_camera_meta(self.buffer(CAMERA_META_STREAM_NAME)?)
+    }
+}
+
+/// Writable view over a `video/rgbd` buffer, which gives access to the buffers of individual
+/// streams by their name instead of by their title tag.
+///
+/// The buffers of the streams are looked up by their tag on every access, as the main buffer
+/// given out by [buffer_mut](#method.buffer_mut) might be used to add, remove or replace any of
+/// the aux buffers.
+#[derive(Debug)]
+pub struct RgbdFrameMut<'a> {
+    /// The main buffer, which is exclusively borrowed for the lifetime of the frame.
+    main_buffer: &'a mut gst::BufferRef,
+    /// Information about the streams, based on the negotiated caps.
+    info: &'a RgbdFrameInfo,
+}
+
+impl<'a> RgbdFrameMut<'a> {
+    /// Create a writable view over `main_buffer`.
+    ///
+    /// # Arguments
+    /// * `main_buffer` - The main buffer of `video/rgbd` stream, which contains aux buffers.
+    /// * `info` - Information about the streams, based on the negotiated caps.
+    ///
+    /// # Returns
+    /// * `Ok(RgbdFrameMut)` on success.
+    /// * `Err(RgbdError::MissingTag)` if any of the buffers is not tagged with its stream name.
+    pub fn from_buffer_ref(
+        main_buffer: &'a mut gst::BufferRef,
+        info: &'a RgbdFrameInfo,
+    ) -> Result<Self, RgbdError> {
+        stream_tag(main_buffer)?;
+        for meta in main_buffer.iter_meta::<BufferMeta>() {
+            stream_tag(meta.buffer())?;
+        }
+
+        Ok(Self { main_buffer, info })
+    }
+
+    /// Get information about the streams of this frame.
+    pub fn info(&self) -> &'a RgbdFrameInfo {
+        self.info
+    }
+
+    /// Get names of all streams contained in this frame, with the stream of the main buffer first.
+    /// Buffers without tag are skipped.
+    pub fn stream_names(&self) -> Vec<String> {
+        std::iter::once(stream_tag(self.main_buffer))
+            .chain(
+                self.main_buffer
+                    .iter_meta::<BufferMeta>()
+                    .map(|meta| stream_tag(meta.buffer())),
+            )
+            .filter_map(Result::ok)
+            .collect()
+    }
+
+    /// Get the buffer of the stream with the given name.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `depth`.
+    ///
+    /// # Returns
+    /// * `Ok(&gst::BufferRef)` on success.
+    /// * `Err(RgbdError::MissingStream)` if this frame has no buffer for the stream.
+    pub fn buffer(&self, stream_name: &str) -> Result<&gst::BufferRef, RgbdError> {
+        if is_stream(self.main_buffer, stream_name) {
+            return Ok(&*self.main_buffer);
+        }
+        self.main_buffer
+            .iter_meta::<BufferMeta>()
+            .find(|meta| is_stream(meta.buffer(), stream_name))
+            // The aux buffer is owned by its meta, which cannot be removed while the main buffer
+            // is borrowed by `&self`
+            .map(|meta| unsafe { gst::BufferRef::from_ptr(meta.buffer().as_ptr()) })
+            .ok_or_else(|| RgbdError::MissingStream(stream_name.to_string()))
+    }
+
+    /// Get the writable buffer of the stream with the given name. Aux buffers that are shared
+    /// with other buffers are copied first, so that the main buffer gets the modified copy.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `depth`.
+    ///
+    /// # Returns
+    /// * `Ok(&mut gst::BufferRef)` on success.
+    /// * `Err(RgbdError::MissingStream)` if this frame has no buffer for the stream.
+    pub fn buffer_mut(&mut self, stream_name: &str) -> Result<&mut gst::BufferRef, RgbdError> {
+        if is_stream(self.main_buffer, stream_name) {
+            return Ok(&mut *self.main_buffer);
+        }
+        for mut meta in self.main_buffer.iter_meta_mut::<BufferMeta>() {
+            if is_stream(meta.buffer(), stream_name) {
+                let buffer = meta.buffer_mut().as_mut_ptr();
+                // The aux buffer is owned by its meta, which cannot be removed while the main
+                // buffer is borrowed by `&mut self`
+                return Ok(unsafe { gst::BufferRef::from_mut_ptr(buffer) });
+            }
+        }
+        Err(RgbdError::MissingStream(stream_name.to_string()))
+    }
+
+    /// Map the buffer of the stream with the given name as a readable video frame.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `color`.
+    ///
+    /// # Returns
+    /// * `Ok(VideoFrameRef)` on success.
+    /// * `Err(RgbdError)` if the stream does not contain raw video or cannot be mapped.
+    pub fn video_frame(
+        &self,
+        stream_name: &str,
+    ) -> Result<gst_video::VideoFrameRef<&gst::BufferRef>, RgbdError> {
+        map_video_frame(self.buffer(stream_name)?, self.info, stream_name)
+    }
+
+    /// Map the buffer of the stream with the given name as a writable video frame.
+    ///
+    /// # Arguments
+    /// * `stream_name` - Name of the stream, e.g. `color`.
+    ///
+    /// # Returns
+    /// * `Ok(VideoFrameRef)` on success.
+    /// * `Err(RgbdError)` if the stream does not contain raw video or cannot be mapped.
+    pub fn video_frame_mut(
+        &mut self,
+        stream_name: &str,
+    ) -> Result<gst_video::VideoFrameRef<&mut gst::BufferRef>, RgbdError> {
+        let info = self.info;
+        let video_info = info.video_info(stream_name)?;
+        gst_video::VideoFrameRef::from_buffer_ref_writable(
+            self.buffer_mut(stream_name)?,
+            video_info,
+        )
+        .map_err(|e| mapping_failed(stream_name, e))
+    }
+
+    /// Map the buffer of `depth` stream.
+    ///
+    /// # Returns
+    /// * `Ok(DepthFrame)` on success.
+    /// * `Err(RgbdError)` if there is no `GRAY16_LE` depth stream or if it cannot be mapped.
+    pub fn depth(&self) -> Result<DepthFrame<'_>, RgbdError> {
+        DepthFrame::new(self.video_frame(DEPTH_STREAM_NAME)?)
+    }
+
+    /// Map the buffer of `depth` stream as writable.
+    ///
+    /// # Returns
+    /// * `Ok(DepthFrameMut)` on success.
+    /// * `Err(RgbdError)` if there is no `GRAY16_LE` depth stream or if it cannot be mapped.
+    pub fn depth_mut(&mut self) -> Result<DepthFrameMut<'_>, RgbdError> {
+        DepthFrameMut::new(self.video_frame_mut(DEPTH_STREAM_NAME)?)
+    }
+
+    /// Deserialise CameraMeta that is attached to this frame as `camerameta` stream.
+    ///
+    /// # Returns
+    /// * `Ok(CameraMeta)` on success.
+    /// * `Err(RgbdError::MissingStream)` if there is no CameraMeta attached.
+    /// * `Err(RgbdError::InvalidCameraMeta)` if the CameraMeta cannot be deserialised.
+    pub fn camera_meta(&self) -> Result<CameraMeta, RgbdError> {
+        deserialise_camera_meta(self.buffer(CAMERA_META_STREAM_NAME)?)
+    }
+}
+
+/// Mapped depth video frame with typed access to its depth values.
+pub struct DepthFrame<'a> {
+    /// The mapped `GRAY16_LE` video frame.
+    frame: gst_video::VideoFrameRef<&'a gst::BufferRef>,
+}
+
+impl<'a> DepthFrame<'a> {
+    /// Wrap a mapped video frame, while making sure that it contains depth.
+    fn new(frame: gst_video::VideoFrameRef<&'a gst::BufferRef>) -> Result<Self, RgbdError> {
+        ensure_depth_format(frame.info())?;
+        to_depth_buffer(
+            frame
+                .plane_data(0)
+                .map_err(|e| mapping_failed(DEPTH_STREAM_NAME, e))?,
+        )?;
+        Ok(Self { frame })
+    }
+
+    /// Get depth values of the frame, row by row. Each row contains `stride()` values, out of
+    /// which the first `width()` are valid.
+    pub fn data(&self) -> &[u16] {
+        // Both plane and alignment were checked when the frame was created
+        to_depth_buffer(self.frame.plane_data(0).unwrap()).unwrap()
+    }
+
+    /// Get the width of the frame, in pixels.
+    pub fn width(&self) -> usize {
+        self.frame.width() as usize
+    }
+
+    /// Get the height of the frame, in pixels.
+    pub fn height(&self) -> usize {
+        self.frame.height() as usize
+    }
+
+    /// Get the number of depth values in each row of the frame, including padding.
+    pub fn stride(&self) -> usize {
+        self.frame.plane_stride()[0] as usize / std::mem::size_of::<u16>()
+    }
+
+    /// Get the underlying mapped video frame.
+    pub fn video_frame(&self) -> &gst_video::VideoFrameRef<&'a gst::BufferRef> {
+        &self.frame
+    }
+}
+
+/// Mapped writable depth video frame with typed access to its depth values.
+pub struct DepthFrameMut<'a> {
+    /// The mapped `GRAY16_LE` video frame.
+    frame: gst_video::VideoFrameRef<&'a mut gst::BufferRef>,
+}
+
+impl<'a> DepthFrameMut<'a> {
+    /// Wrap a mapped video frame, while making sure that it contains depth.
+    fn new(mut frame: gst_video::VideoFrameRef<&'a mut gst::BufferRef>) -> Result<Self, RgbdError> {
+        ensure_depth_format(frame.info())?;
+        to_depth_buffer_mut(
+            frame
+                .plane_data_mut(0)
+                .map_err(|e| mapping_failed(DEPTH_STREAM_NAME, e))?,
+        )?;
+        Ok(Self { frame })
+    }
+
+    /// Get depth values of the frame, row by row. Each row contains `stride()` values, out of
+    /// which the first `width()` are valid.
+    pub fn data(&self) -> &[u16] {
+        // Both plane and alignment were checked when the frame was created
+        to_depth_buffer(self.frame.plane_data(0).unwrap()).unwrap()
+    }
+
+    /// Get mutable depth values of the frame, row by row. Each row contains `stride()` values,
+    /// out of which the first `width()` are valid.
+    pub fn data_mut(&mut self) -> &mut [u16] {
+        // Both plane and alignment were checked when the frame was created
+        to_depth_buffer_mut(self.frame.plane_data_mut(0).unwrap()).unwrap()
+    }
+
+    /// Get the width of the frame, in pixels.
+    pub fn width(&self) -> usize {
+        self.frame.width() as usize
+    }
+
+    /// Get the height of the frame, in pixels.
+    pub fn height(&self) -> usize {
+        self.frame.height() as usize
+    }
+
+    /// Get the number of depth values in each row of the frame, including padding.
+    pub fn stride(&self) -> usize {
+        self.frame.plane_stride()[0] as usize / std::mem::size_of::<u16>()
+    }
+}
+
+/// Get the stream name, with which the `buffer` is tagged.
+fn stream_tag(buffer: &gst::BufferRef) -> Result<String, RgbdError> {
+    get_tag(buffer).map_err(|_| RgbdError::MissingTag)
+}
+
+/// Check whether the buffer is tagged with the given stream name.
+fn is_stream(buffer: &gst::BufferRef, stream_name: &str) -> bool {
+    get_tag(buffer).map_or(false, |tag| tag == stream_name)
+}
+
+/// Map the buffer of the stream with the given name as a readable video frame.
+fn map_video_frame<'a>(
+    buffer: &'a gst::BufferRef,
+    info: &RgbdFrameInfo,
+    stream_name: &str,
+) -> Result<gst_video::VideoFrameRef<&'a gst::BufferRef>, RgbdError> {
+    gst_video::VideoFrameRef::from_buffer_ref_readable(buffer, info.video_info(stream_name)?)
+        .map_err(|e| mapping_failed(stream_name, e))
+}
+
+/// Make sure that video with the given info contains depth.
+fn ensure_depth_format(video_info: &gst_video::VideoInfo) -> Result<(), RgbdError> {
+    if video_info.format() != gst_video::VideoFormat::Gray16Le {
+        return Err(RgbdError::WrongCapsFormat {
+            name: format!("{}_format", DEPTH_STREAM_NAME),
+            type_: "GRAY16_LE",
+        });
+    }
+    Ok(())
+}
+
+/// Deserialise CameraMeta that is contained in the given buffer.
+fn deserialise_camera_meta(buffer: &gst::BufferRef) -> Result<CameraMeta, RgbdError> {
+    let map = buffer
+        .map_readable()
+        .map_err(|e| mapping_failed(CAMERA_META_STREAM_NAME, e))?;
+    CameraMeta::deserialise(&map).map_err(|e| RgbdError::InvalidCameraMeta(e.to_string()))
+}
+
+/// Create an error for a buffer that could not be mapped.
+fn mapping_failed(stream_name: &str, error: glib::BoolError) -> RgbdError {
+    RgbdError::MappingFailed {
+        stream_name: stream_name.to_string(),
+        reason: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rgbd::{attach_aux_buffer_and_tag, tag_buffer_with_title};
+    use gst::prelude::*;
+
+    const DEPTH_WIDTH: u32 = 4;
+    const DEPTH_HEIGHT: u32 = 2;
+
+    fn rgbd_caps() -> gst::Caps {
+        let streams = ["depth", "color", "camerameta"]
+            .iter()
+            .map(|stream| stream.to_send_value());
+        gst::Caps::builder("video/rgbd")
+            .field("streams", &gst::Array::from_values(streams))
+            .field("framerate", &gst::Fraction::new(30, 1))
+            .field("depth_format", &"GRAY16_LE")
+            .field("depth_width", &(DEPTH_WIDTH as i32))
+            .field("depth_height", &(DEPTH_HEIGHT as i32))
+            .field("color_format", &"RGB")
+            .field("color_width", &2i32)
+            .field("color_height", &2i32)
+            .build()
+    }
+
+    fn rgbd_buffer(info: &RgbdFrameInfo, camera_meta: &CameraMeta) -> gst::Buffer {
+        let depth_size = info.video_info("depth").unwrap().size();
+        let depth_values = (0..(depth_size / 2) as u16)
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let mut main_buffer = gst::Buffer::from_mut_slice(depth_values);
+        tag_buffer_with_title(main_buffer.make_mut(), "depth").unwrap();
+
+        let color_size = info.video_info("color").unwrap().size();
+        let mut color_buffer = gst::Buffer::from_mut_slice(vec![42u8; color_size]);
+        attach_aux_buffer_and_tag(main_buffer.make_mut(), &mut color_buffer, "color").unwrap();
+
+        let mut camera_meta_buffer =
+            gst::Buffer::from_mut_slice(camera_meta.clone().serialise().unwrap());
+        attach_aux_buffer_and_tag(
+            main_buffer.make_mut(),
+            &mut camera_meta_buffer,
+            "camerameta",
+        )
+        .unwrap();
+
+        main_buffer
+    }
+
+    #[test]
+    fn frame_info_from_caps() {
+        gst::init().unwrap();
+
+        let info = RgbdFrameInfo::from_caps(&rgbd_caps()).unwrap();
+        let streams = info
+            .streams()
+            .map(|stream| stream.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(streams, ["depth", "color", "camerameta"]);
+        assert_eq!(info.video_info("depth").unwrap().width(), DEPTH_WIDTH);
+        assert_eq!(
+            info.video_info("color").unwrap().format(),
+            gst_video::VideoFormat::Rgb
+        );
+        assert_eq!(
+            info.video_info("camerameta").unwrap_err(),
+            RgbdError::NotVideoStream("camerameta".to_string())
+        );
+        assert_eq!(
+            info.video_info("ir").unwrap_err(),
+            RgbdError::MissingStream("ir".to_string())
+        );
+
+        let mut caps = rgbd_caps();
+        caps.make_mut()
+            .structure_mut(0)
+            .unwrap()
+            .remove_field("color_height");
+        assert_eq!(
+            RgbdFrameInfo::from_caps(&caps).unwrap_err(),
+            RgbdError::MissingCapsField("color_height".to_string())
+        );
+    }
+
+    #[test]
+    fn read_frame() {
+        gst::init().unwrap();
+
+        let info = RgbdFrameInfo::from_caps(&rgbd_caps()).unwrap();
+        let camera_meta = CameraMeta::new(Default::default(), Default::default(), 0.001);
+        let main_buffer = rgbd_buffer(&info, &camera_meta);
+        let frame = RgbdFrame::from_buffer_ref(&main_buffer, &info).unwrap();
+
+        let streams = frame
+            .buffers()
+            .map(|(stream_name, _)| stream_name)
+            .collect::<Vec<&str>>();
+        assert_eq!(streams, ["depth", "color", "camerameta"]);
+
+        let depth = frame.depth().unwrap();
+        assert_eq!(depth.width(), DEPTH_WIDTH as usize);
+        assert_eq!(depth.height(), DEPTH_HEIGHT as usize);
+        for y in 0..depth.height() {
+            for x in 0..depth.width() {
+                let value = depth.data()[y * depth.stride() + x] as usize;
+                assert_eq!(value, y * depth.stride() + x);
+            }
+        }
+
+        let color = frame.video_frame("color").unwrap();
+        assert!(color
+            .plane_data(0)
+            .unwrap()
+            .iter()
+            .all(|value| *value == 42));
+        assert_eq!(frame.camera_meta().unwrap(), camera_meta);
+        assert!(matches!(
+            frame.video_frame("camerameta"),
+            Err(RgbdError::NotVideoStream(_))
+        ));
+    }
+
+    #[test]
+    fn write_frame() {
+        gst::init().unwrap();
+
+        let info = RgbdFrameInfo::from_caps(&rgbd_caps()).unwrap();
+        let camera_meta = CameraMeta::new(Default::default(), Default::default(), 0.001);
+        let mut main_buffer = rgbd_buffer(&info, &camera_meta);
+        // Keep a reference to the original, so that modifications must not propagate into it
+        let original_buffer = main_buffer.copy();
+
+        {
+            let mut frame = RgbdFrameMut::from_buffer_ref(main_buffer.make_mut(), &info).unwrap();
+            frame
+                .depth_mut()
+                .unwrap()
+                .data_mut()
+                .iter_mut()
+                .for_each(|d| *d = 1000);
+            frame
+                .video_frame_mut("color")
+                .unwrap()
+                .plane_data_mut(0)
+                .unwrap()
+                .iter_mut()
+                .for_each(|c| *c = 7);
+        }
+
+        let frame = RgbdFrame::from_buffer_ref(&main_buffer, &info).unwrap();
+        assert!(frame.depth().unwrap().data().iter().all(|d| *d == 1000));
+        let color = frame.video_frame("color").unwrap();
+        assert!(color.plane_data(0).unwrap().iter().all(|c| *c == 7));
+
+        let original_frame = RgbdFrame::from_buffer_ref(&original_buffer, &info).unwrap();
+        let original_color = original_frame.video_frame("color").unwrap();
+        assert!(original_color
+            .plane_data(0)
+            .unwrap()
+            .iter()
+            .all(|c| *c == 42));
+    }
+
+    #[test]
+    fn remove_aux_buffers_through_main_buffer() {
+        gst::init().unwrap();
+
+        let info = RgbdFrameInfo::from_caps(&rgbd_caps()).unwrap();
+        let camera_meta = CameraMeta::new(Default::default(), Default::default(), 0.001);
+        let mut main_buffer = rgbd_buffer(&info, &camera_meta);
+
+        let mut frame = RgbdFrameMut::from_buffer_ref(main_buffer.make_mut(), &info).unwrap();
+        assert_eq!(frame.stream_names(), ["depth", "color", "camerameta"]);
+        assert!(frame.buffer_mut("color").is_ok());
+
+        // Aux buffers are looked up anew, so removing them must not leave dangling references
+        crate::rgbd::remove_aux_buffers(frame.buffer_mut("depth").unwrap());
+        assert_eq!(frame.stream_names(), ["depth"]);
+        assert_eq!(
+            frame.buffer_mut("color").unwrap_err(),
+            RgbdError::MissingStream("color".to_string())
+        );
+        assert!(matches!(
+            frame.video_frame_mut("color"),
+            Err(RgbdError::MissingStream(_))
+        ));
+        assert!(frame.depth_mut().is_ok());
+    }
+
+    #[test]
+    fn untagged_buffer() {
+        gst::init().unwrap();
+
+        let info = RgbdFrameInfo::from_caps(&rgbd_caps()).unwrap();
+        let main_buffer = gst::Buffer::new();
+        assert_eq!(
+            RgbdFrame::from_buffer_ref(&main_buffer, &info).unwrap_err(),
+            RgbdError::MissingTag
+        );
+    }
+}
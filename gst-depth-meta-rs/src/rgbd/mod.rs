@@ -1,14 +1,24 @@
 mod sys;
 
 pub mod buffer;
+pub mod caps;
+pub mod depth;
 pub mod error;
+pub mod frame;
+pub mod pointcloud;
 #[allow(clippy::module_inception)]
 pub mod rgbd;
+pub mod roi;
 pub mod tags;
 
 pub use buffer::*;
+pub use caps::*;
+pub use depth::*;
 pub use error::*;
+pub use frame::*;
+pub use pointcloud::*;
 pub use rgbd::*;
+pub use roi::*;
 pub use tags::*;
 
 pub use gst::meta::{Meta, MetaAPI, MetaRef, MetaRefMut};
@@ -0,0 +1,800 @@
+use crate::camera_meta::{CameraMeta, Intrinsics, Transformation};
+use crate::rgbd::depth::raw_to_metres;
+use crate::rgbd::frame::{DepthFrame, RgbdFrame, DEPTH_STREAM_NAME};
+use crate::RgbdError;
+use std::io::{self, Write};
+
+/// Colour that is assigned to points that are not visible in the colour image.
+pub const NO_COLOUR: [u8; 3] = [0, 0, 0];
+
+/// Layout of the points within a [PointCloud](struct.PointCloud.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointCloudLayout {
+    /// Points keep the 2D structure of the depth image. Pixels without valid depth are
+    /// represented by points with NaN coordinates.
+    Organised,
+    /// Only points with valid depth are kept, in row-major order of the depth image.
+    Unorganised,
+}
+
+/// Encoding of an exported [PointCloud](struct.PointCloud.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointCloudEncoding {
+    /// Human-readable text.
+    Ascii,
+    /// Little-endian binary.
+    Binary,
+}
+
+/// Set of 3D points in metres, optionally coloured, expressed in the coordinate frame of the
+/// depth camera.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointCloud {
+    /// Number of points in each row. Equal to the number of points for unorganised point clouds.
+    width: usize,
+    /// Number of rows. Always 1 for unorganised point clouds.
+    height: usize,
+    /// Layout of the points.
+    layout: PointCloudLayout,
+    /// Points in the form of `[x, y, z]`, in row-major order.
+    points: Vec<[f32; 3]>,
+    /// RGB colour of each point, if the point cloud is coloured.
+    colours: Option<Vec<[u8; 3]>>,
+}
+
+/// Read-only view of a packed 8-bit RGB image, which is used to colour a
+/// [PointCloud](struct.PointCloud.html).
+#[derive(Debug, Clone, Copy)]
+pub struct ColourImage<'a> {
+    /// Bytes of the image.
+    data: &'a [u8],
+    /// Width of the image, in pixels.
+    width: usize,
+    /// Height of the image, in pixels.
+    height: usize,
+    /// Number of bytes of each row, including padding.
+    stride: usize,
+    /// Number of bytes of each pixel.
+    pixel_stride: usize,
+    /// Byte offsets of the red, green and blue components within a pixel.
+    offsets: [usize; 3],
+}
+
+impl<'a> ColourImage<'a> {
+    /// Create a new view of a packed 8-bit RGB image.
+    /// # Arguments
+    /// * `data` - Bytes of the image.
+    /// * `width` - Width of the image, in pixels.
+    /// * `height` - Height of the image, in pixels.
+    /// * `stride` - Number of bytes of each row, including padding.
+    /// * `pixel_stride` - Number of bytes of each pixel, e.g. 3 for `RGB` and 4 for `BGRA`.
+    /// * `offsets` - Byte offsets of the red, green and blue components within a pixel, e.g.
+    /// `[2, 1, 0]` for `BGRA`.
+    /// # Returns
+    /// * `Ok(ColourImage)` on success.
+    /// * `Err(RgbdError::InvalidColourImage)` if `data` is too small or the offsets exceed a pixel.
+    pub fn new(
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+        stride: usize,
+        pixel_stride: usize,
+        offsets: [usize; 3],
+    ) -> Result<Self, RgbdError> {
+        let invalid = |reason: String| Err(RgbdError::InvalidColourImage(reason));
+        if offsets.iter().any(|offset| *offset >= pixel_stride) {
+            return invalid(format!(
+                "Component offsets {:?} exceed the pixel stride of {} bytes",
+                offsets, pixel_stride
+            ));
+        }
+        if stride < width * pixel_stride {
+            return invalid(format!(
+                "Stride of {} bytes is smaller than a row of {} pixels",
+                stride, width
+            ));
+        }
+        if height > 0 && data.len() < stride * (height - 1) + width * pixel_stride {
+            return invalid(format!(
+                "{} bytes are too few for a {}x{} image",
+                data.len(),
+                width,
+                height
+            ));
+        }
+        Ok(Self {
+            data,
+            width,
+            height,
+            stride,
+            pixel_stride,
+            offsets,
+        })
+    }
+
+    /// Create a view of a mapped video frame, which must contain packed 8-bit RGB, e.g. `RGB`,
+    /// `BGR`, `RGBA` or `BGRx`.
+    /// # Arguments
+    /// * `frame` - The mapped video frame.
+    /// # Returns
+    /// * `Ok(ColourImage)` on success.
+    /// * `Err(RgbdError::InvalidColourImage)` if the format is not packed 8-bit RGB.
+    pub fn from_video_frame(
+        frame: &'a gst_video::VideoFrameRef<&gst::BufferRef>,
+    ) -> Result<Self, RgbdError> {
+        let format_info = frame.info().format_info();
+        let is_packed_rgb = format_info.is_rgb()
+            && format_info.n_planes() == 1
+            && format_info.bits() == 8
+            && format_info.n_components() >= 3
+            && format_info.plane()[..3].iter().all(|plane| *plane == 0);
+        if !is_packed_rgb {
+            return Err(RgbdError::InvalidColourImage(format!(
+                "Format {:?} is not packed 8-bit RGB",
+                frame.format()
+            )));
+        }
+        let poffset = format_info.poffset();
+        Self::new(
+            frame
+                .plane_data(0)
+                .map_err(|e| RgbdError::InvalidColourImage(e.to_string()))?,
+            frame.width() as usize,
+            frame.height() as usize,
+            frame.plane_stride()[0] as usize,
+            format_info.pixel_stride()[0] as usize,
+            [
+                poffset[0] as usize,
+                poffset[1] as usize,
+                poffset[2] as usize,
+            ],
+        )
+    }
+
+    /// Get the colour of the pixel at the given coordinates.
+    /// # Arguments
+    /// * `pixel` - Pixel coordinates, in the form of `[u, v]`.
+    /// # Returns
+    /// * `Some([r, g, b])` if the nearest pixel lies within the image.
+    /// * `None` otherwise.
+    pub fn sample(&self, pixel: [f32; 2]) -> Option<[u8; 3]> {
+        let (u, v) = (pixel[0].round(), pixel[1].round());
+        // Ranges do not contain NaN
+        if !(0.0..self.width as f32).contains(&u) || !(0.0..self.height as f32).contains(&v) {
+            return None;
+        }
+        let idx = v as usize * self.stride + u as usize * self.pixel_stride;
+        Some([
+            self.data[idx + self.offsets[0]],
+            self.data[idx + self.offsets[1]],
+            self.data[idx + self.offsets[2]],
+        ])
+    }
+}
+
+impl PointCloud {
+    /// Generate a PointCloud by deprojecting each pixel of a depth image.
+    /// # Arguments
+    /// * `depth` - Raw depth values, row by row. Each row contains `stride` values, out of which
+    /// the first `width` are valid.
+    /// * `width` - Width of the depth image, in pixels.
+    /// * `height` - Height of the depth image, in pixels.
+    /// * `stride` - Number of depth values in each row, including padding.
+    /// * `intrinsics` - Intrinsics of the depth camera, valid for the resolution of the image.
+    /// * `depth_scale` - Scaling factor of the depth map, in metres.
+    /// * `layout` - Layout of the generated points.
+    /// # Returns
+    /// * `Ok(PointCloud)` on success.
+    /// * `Err(RgbdError::InvalidDepthFrame)` if `depth` is too small or the stride is too small.
+    pub fn from_depth(
+        depth: &[u16],
+        width: usize,
+        height: usize,
+        stride: usize,
+        intrinsics: &Intrinsics,
+        depth_scale: f32,
+        layout: PointCloudLayout,
+    ) -> Result<Self, RgbdError> {
+        if stride < width {
+            return Err(RgbdError::InvalidDepthFrame(format!(
+                "Stride of {} values is smaller than the width of {} pixels",
+                stride, width
+            )));
+        }
+        if height > 0 && depth.len() < stride * (height - 1) + width {
+            return Err(RgbdError::InvalidDepthFrame(format!(
+                "{} depth values are too few for a {}x{} frame",
+                depth.len(),
+                width,
+                height
+            )));
+        }
+
+        let mut points = Vec::with_capacity(width * height);
+        for (v, row) in depth.chunks(stride).take(height).enumerate() {
+            for (u, raw) in row[..width].iter().enumerate() {
+                if *raw == 0 {
+                    if layout == PointCloudLayout::Organised {
+                        points.push([f32::NAN; 3]);
+                    }
+                    continue;
+                }
+                points.push(
+                    intrinsics.deproject([u as f32, v as f32], raw_to_metres(*raw, depth_scale)),
+                );
+            }
+        }
+
+        let (width, height) = match layout {
+            PointCloudLayout::Organised => (width, height),
+            PointCloudLayout::Unorganised => (points.len(), 1),
+        };
+        Ok(Self {
+            width,
+            height,
+            layout,
+            points,
+            colours: None,
+        })
+    }
+
+    /// Generate a PointCloud from a mapped depth frame, see [from_depth](#method.from_depth).
+    /// # Arguments
+    /// * `frame` - The mapped depth frame.
+    /// * `intrinsics` - Intrinsics of the depth camera, valid for the resolution of the frame.
+    /// * `depth_scale` - Scaling factor of the depth map, in metres.
+    /// * `layout` - Layout of the generated points.
+    pub fn from_depth_frame(
+        frame: &DepthFrame,
+        intrinsics: &Intrinsics,
+        depth_scale: f32,
+        layout: PointCloudLayout,
+    ) -> Result<Self, RgbdError> {
+        Self::from_depth(
+            frame.data(),
+            frame.width(),
+            frame.height(),
+            frame.stride(),
+            intrinsics,
+            depth_scale,
+            layout,
+        )
+    }
+
+    /// Generate a PointCloud from the `depth` stream of a `video/rgbd` frame, using the
+    /// calibration stored in CameraMeta. If `colour_stream` is given, the points are coloured
+    /// from that stream.
+    /// # Arguments
+    /// * `frame` - The `video/rgbd` frame.
+    /// * `camera_meta` - Calibration of the cameras, e.g. from `RgbdFrame::camera_meta()`.
+    /// * `colour_stream` - Name of the stream to colour the points with, e.g. `color`.
+    /// * `layout` - Layout of the generated points.
+    /// # Returns
+    /// * `Ok(PointCloud)` on success.
+    /// * `Err(RgbdError::MissingCalibration)` if intrinsics or extrinsics are not known.
+    /// * `Err(RgbdError)` if the streams are missing or cannot be mapped.
+    pub fn from_rgbd_frame(
+        frame: &RgbdFrame,
+        camera_meta: &CameraMeta,
+        colour_stream: Option<&str>,
+        layout: PointCloudLayout,
+    ) -> Result<Self, RgbdError> {
+        let depth = frame.depth()?;
+        let intrinsics = intrinsics_for(
+            camera_meta,
+            DEPTH_STREAM_NAME,
+            depth.width() as u32,
+            depth.height() as u32,
+        )?;
+        let mut point_cloud =
+            Self::from_depth_frame(&depth, &intrinsics, camera_meta.depth_scale, layout)?;
+
+        if let Some(colour_stream) = colour_stream {
+            let colour_frame = frame.video_frame(colour_stream)?;
+            let colour_intrinsics = intrinsics_for(
+                camera_meta,
+                colour_stream,
+                colour_frame.width(),
+                colour_frame.height(),
+            )?;
+            let extrinsics = camera_meta
+                .get_extrinsics(DEPTH_STREAM_NAME.to_string(), colour_stream.to_string())
+                .ok_or_else(|| {
+                    RgbdError::MissingCalibration(format!(
+                        "extrinsics from `{}` to `{}`",
+                        DEPTH_STREAM_NAME, colour_stream
+                    ))
+                })?;
+            point_cloud.colourise(
+                &ColourImage::from_video_frame(&colour_frame)?,
+                &colour_intrinsics,
+                &extrinsics,
+            );
+        }
+        Ok(point_cloud)
+    }
+
+    /// Colour all points by projecting them into a colour image. Points that are not visible in
+    /// the colour image are assigned [NO_COLOUR](constant.NO_COLOUR.html).
+    /// # Arguments
+    /// * `image` - The colour image.
+    /// * `intrinsics` - Intrinsics of the colour camera, valid for the resolution of the image.
+    /// * `extrinsics` - Transformation from the depth camera to the colour camera.
+    pub fn colourise(
+        &mut self,
+        image: &ColourImage,
+        intrinsics: &Intrinsics,
+        extrinsics: &Transformation,
+    ) {
+        let colours = self
+            .points
+            .iter()
+            .map(|point| {
+                let point = extrinsics.transform_point(*point);
+                if point[2].is_nan() || point[2] <= 0.0 {
+                    return NO_COLOUR;
+                }
+                image.sample(intrinsics.project(point)).unwrap_or(NO_COLOUR)
+            })
+            .collect();
+        self.colours = Some(colours);
+    }
+
+    /// Get the number of points in each row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get the number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get the layout of the points.
+    pub fn layout(&self) -> PointCloudLayout {
+        self.layout
+    }
+
+    /// Get all points in the form of `[x, y, z]`, in row-major order. Organised point clouds
+    /// contain NaN points for pixels without valid depth.
+    pub fn points(&self) -> &[[f32; 3]] {
+        &self.points
+    }
+
+    /// Get the RGB colour of each point, if the point cloud is coloured.
+    pub fn colours(&self) -> Option<&[[u8; 3]]> {
+        self.colours.as_deref()
+    }
+
+    /// Get the number of points with valid coordinates.
+    pub fn valid_point_count(&self) -> usize {
+        self.valid_points().count()
+    }
+
+    /// Iterate over all points with valid coordinates together with their colour.
+    fn valid_points(&self) -> impl Iterator<Item = ([f32; 3], Option<[u8; 3]>)> + '_ {
+        self.points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| !point[2].is_nan())
+            .map(move |(idx, point)| (*point, self.colours.as_ref().map(|c| c[idx])))
+    }
+
+    /// Export the PointCloud in the PLY format. PLY has no notion of organised point clouds,
+    /// hence only points with valid coordinates are written.
+    /// # Arguments
+    /// * `writer` - Destination of the PLY data.
+    /// * `encoding` - Encoding of the vertices.
+    /// # Returns
+    /// * `Ok()` on success.
+    /// * `Err(io::Error)` if writing fails.
+    pub fn write_ply<W: Write>(
+        &self,
+        writer: &mut W,
+        encoding: PointCloudEncoding,
+    ) -> io::Result<()> {
+        let format = match encoding {
+            PointCloudEncoding::Ascii => "ascii",
+            PointCloudEncoding::Binary => "binary_little_endian",
+        };
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format {} 1.0", format)?;
+        writeln!(writer, "element vertex {}", self.valid_point_count())?;
+        writeln!(writer, "property float x")?;
+        writeln!(writer, "property float y")?;
+        writeln!(writer, "property float z")?;
+        if self.colours.is_some() {
+            writeln!(writer, "property uchar red")?;
+            writeln!(writer, "property uchar green")?;
+            writeln!(writer, "property uchar blue")?;
+        }
+        writeln!(writer, "end_header")?;
+
+        for ([x, y, z], colour) in self.valid_points() {
+            match encoding {
+                PointCloudEncoding::Ascii => {
+                    write!(writer, "{} {} {}", x, y, z)?;
+                    if let Some([r, g, b]) = colour {
+                        write!(writer, " {} {} {}", r, g, b)?;
+                    }
+                    writeln!(writer)?;
+                }
+                PointCloudEncoding::Binary => {
+                    for coordinate in &[x, y, z] {
+                        writer.write_all(&coordinate.to_le_bytes())?;
+                    }
+                    if let Some(colour) = colour {
+                        writer.write_all(&colour)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Export the PointCloud in the PCD v0.7 format of the Point Cloud Library. Organised point
+    /// clouds keep their structure, including NaN points. Colours are packed into a single `rgb`
+    /// field of type `U` as `0x00RRGGBB`.
+    /// # Arguments
+    /// * `writer` - Destination of the PCD data.
+    /// * `encoding` - Encoding of the points.
+    /// # Returns
+    /// * `Ok()` on success.
+    /// * `Err(io::Error)` if writing fails.
+    pub fn write_pcd<W: Write>(
+        &self,
+        writer: &mut W,
+        encoding: PointCloudEncoding,
+    ) -> io::Result<()> {
+        let coloured = self.colours.is_some();
+        writeln!(writer, "# .PCD v0.7 - Point Cloud Data file format")?;
+        writeln!(writer, "VERSION 0.7")?;
+        if coloured {
+            writeln!(writer, "FIELDS x y z rgb")?;
+            writeln!(writer, "SIZE 4 4 4 4")?;
+            writeln!(writer, "TYPE F F F U")?;
+            writeln!(writer, "COUNT 1 1 1 1")?;
+        } else {
+            writeln!(writer, "FIELDS x y z")?;
+            writeln!(writer, "SIZE 4 4 4")?;
+            writeln!(writer, "TYPE F F F")?;
+            writeln!(writer, "COUNT 1 1 1")?;
+        }
+        writeln!(writer, "WIDTH {}", self.width)?;
+        writeln!(writer, "HEIGHT {}", self.height)?;
+        writeln!(writer, "VIEWPOINT 0 0 0 1 0 0 0")?;
+        writeln!(writer, "POINTS {}", self.points.len())?;
+        writeln!(
+            writer,
+            "DATA {}",
+            match encoding {
+                PointCloudEncoding::Ascii => "ascii",
+                PointCloudEncoding::Binary => "binary",
+            }
+        )?;
+
+        for (idx, [x, y, z]) in self.points.iter().enumerate() {
+            let rgb = self
+                .colours
+                .as_ref()
+                .map(|c| u32::from_be_bytes([0, c[idx][0], c[idx][1], c[idx][2]]));
+            match encoding {
+                PointCloudEncoding::Ascii => {
+                    // PCL expects lowercase `nan`
+                    let fmt = |v: f32| {
+                        if v.is_nan() {
+                            "nan".to_string()
+                        } else {
+                            v.to_string()
+                        }
+                    };
+                    write!(writer, "{} {} {}", fmt(*x), fmt(*y), fmt(*z))?;
+                    if let Some(rgb) = rgb {
+                        write!(writer, " {}", rgb)?;
+                    }
+                    writeln!(writer)?;
+                }
+                PointCloudEncoding::Binary => {
+                    for coordinate in &[x, y, z] {
+                        writer.write_all(&coordinate.to_le_bytes())?;
+                    }
+                    if let Some(rgb) = rgb {
+                        writer.write_all(&rgb.to_le_bytes())?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Get intrinsics of `camera` that are valid for the given resolution.
+fn intrinsics_for(
+    camera_meta: &CameraMeta,
+    camera: &str,
+    width: u32,
+    height: u32,
+) -> Result<Intrinsics, RgbdError> {
+    camera_meta
+        .intrinsics_for_resolution(camera, width, height)
+        .ok_or_else(|| {
+            RgbdError::MissingCalibration(format!(
+                "intrinsics of `{}` for {}x{}",
+                camera, width, height
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera_meta::{Distortion, RotationMatrix, Translation};
+    use crate::common::tests::nearly_equal_f32;
+
+    const DEPTH_SCALE: f32 = 0.001;
+
+    fn intrinsics() -> Intrinsics {
+        Intrinsics::new(2.0, 2.0, 1.0, 1.0, Distortion::None).with_resolution(3, 2)
+    }
+
+    /// 3x2 depth image with a stride of 4 values, in which pixel (1, 0) has no depth.
+    fn depth() -> Vec<u16> {
+        vec![1000, 0, 2000, 0xFFFF, 1000, 1000, 4000, 0xFFFF]
+    }
+
+    fn identity() -> Transformation {
+        Transformation::new(Translation::new(0.0, 0.0, 0.0), RotationMatrix::default())
+    }
+
+    #[test]
+    fn from_depth_organised() {
+        // Act
+        let point_cloud = PointCloud::from_depth(
+            &depth(),
+            3,
+            2,
+            4,
+            &intrinsics(),
+            DEPTH_SCALE,
+            PointCloudLayout::Organised,
+        )
+        .unwrap();
+
+        // Assert
+        assert_eq!(point_cloud.width(), 3);
+        assert_eq!(point_cloud.height(), 2);
+        assert_eq!(point_cloud.points().len(), 6);
+        assert_eq!(point_cloud.valid_point_count(), 5);
+        assert!(point_cloud.points()[1].iter().all(|v| v.is_nan()));
+        let [x, y, z] = point_cloud.points()[5];
+        assert!(nearly_equal_f32(x, 2.0));
+        assert!(nearly_equal_f32(y, 0.0));
+        assert!(nearly_equal_f32(z, 4.0));
+    }
+
+    #[test]
+    fn from_depth_unorganised() {
+        // Act
+        let point_cloud = PointCloud::from_depth(
+            &depth(),
+            3,
+            2,
+            4,
+            &intrinsics(),
+            DEPTH_SCALE,
+            PointCloudLayout::Unorganised,
+        )
+        .unwrap();
+
+        // Assert
+        assert_eq!(point_cloud.width(), 5);
+        assert_eq!(point_cloud.height(), 1);
+        let [x, y, z] = point_cloud.points()[0];
+        assert!(nearly_equal_f32(x, -0.5));
+        assert!(nearly_equal_f32(y, -0.5));
+        assert!(nearly_equal_f32(z, 1.0));
+        let [x, y, z] = point_cloud.points()[1];
+        assert!(nearly_equal_f32(x, 1.0));
+        assert!(nearly_equal_f32(y, -1.0));
+        assert!(nearly_equal_f32(z, 2.0));
+    }
+
+    #[test]
+    fn from_depth_too_small() {
+        // Act
+        let result = PointCloud::from_depth(
+            &depth()[..6],
+            3,
+            2,
+            4,
+            &intrinsics(),
+            DEPTH_SCALE,
+            PointCloudLayout::Organised,
+        );
+
+        // Assert
+        assert!(matches!(result, Err(RgbdError::InvalidDepthFrame(_))));
+    }
+
+    #[test]
+    fn colourise_with_identity_extrinsics() {
+        // Arrange
+        let mut point_cloud = PointCloud::from_depth(
+            &depth(),
+            3,
+            2,
+            4,
+            &intrinsics(),
+            DEPTH_SCALE,
+            PointCloudLayout::Organised,
+        )
+        .unwrap();
+        // 3x2 BGRx image, in which each pixel has its index as blue component
+        let data: Vec<u8> = (0..6).flat_map(|i| vec![i, 0, 255, 0]).collect();
+        let image = ColourImage::new(&data, 3, 2, 12, 4, [2, 1, 0]).unwrap();
+
+        // Act
+        point_cloud.colourise(&image, &intrinsics(), &identity());
+
+        // Assert
+        let colours = point_cloud.colours().unwrap();
+        assert_eq!(colours[0], [255, 0, 0]);
+        assert_eq!(colours[1], NO_COLOUR);
+        assert_eq!(colours[2], [255, 0, 2]);
+        assert_eq!(colours[5], [255, 0, 5]);
+    }
+
+    #[test]
+    fn colourise_outside_of_image() {
+        // Arrange
+        let mut point_cloud = PointCloud::from_depth(
+            &depth(),
+            3,
+            2,
+            4,
+            &intrinsics(),
+            DEPTH_SCALE,
+            PointCloudLayout::Unorganised,
+        )
+        .unwrap();
+        let data = vec![255u8; 3 * 2 * 3];
+        let image = ColourImage::new(&data, 3, 2, 9, 3, [0, 1, 2]).unwrap();
+        let extrinsics =
+            Transformation::new(Translation::new(10.0, 0.0, 0.0), RotationMatrix::default());
+
+        // Act
+        point_cloud.colourise(&image, &intrinsics(), &extrinsics);
+
+        // Assert
+        assert!(point_cloud
+            .colours()
+            .unwrap()
+            .iter()
+            .all(|colour| *colour == NO_COLOUR));
+    }
+
+    #[test]
+    fn write_ply_ascii() {
+        // Arrange
+        let point_cloud = PointCloud::from_depth(
+            &[0, 1000],
+            2,
+            1,
+            2,
+            &Intrinsics::new(1.0, 1.0, 1.0, 0.0, Distortion::None),
+            DEPTH_SCALE,
+            PointCloudLayout::Organised,
+        )
+        .unwrap();
+        let mut output = Vec::new();
+
+        // Act
+        point_cloud
+            .write_ply(&mut output, PointCloudEncoding::Ascii)
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\n\
+            property float z\nend_header\n0 0 1\n"
+        );
+    }
+
+    #[test]
+    fn write_ply_binary_coloured() {
+        // Arrange
+        let mut point_cloud = PointCloud::from_depth(
+            &depth(),
+            3,
+            2,
+            4,
+            &intrinsics(),
+            DEPTH_SCALE,
+            PointCloudLayout::Organised,
+        )
+        .unwrap();
+        let data = vec![7u8; 3 * 2 * 3];
+        let image = ColourImage::new(&data, 3, 2, 9, 3, [0, 1, 2]).unwrap();
+        point_cloud.colourise(&image, &intrinsics(), &identity());
+        let mut output = Vec::new();
+
+        // Act
+        point_cloud
+            .write_ply(&mut output, PointCloudEncoding::Binary)
+            .unwrap();
+
+        // Assert
+        let header = b"ply\nformat binary_little_endian 1.0\nelement vertex 5\n";
+        assert!(output.starts_with(header));
+        let end_header = b"end_header\n";
+        let body = output
+            .windows(end_header.len())
+            .position(|w| w == end_header)
+            .unwrap()
+            + end_header.len();
+        assert_eq!(output.len() - body, 5 * (3 * 4 + 3));
+        assert_eq!(&output[body + 12..body + 15], &[7, 7, 7]);
+    }
+
+    #[test]
+    fn write_pcd_ascii_organised() {
+        // Arrange
+        let mut point_cloud = PointCloud::from_depth(
+            &[0, 1000],
+            2,
+            1,
+            2,
+            &Intrinsics::new(1.0, 1.0, 1.0, 0.0, Distortion::None),
+            DEPTH_SCALE,
+            PointCloudLayout::Organised,
+        )
+        .unwrap();
+        let data = [1u8, 2, 3, 4, 5, 6];
+        let image = ColourImage::new(&data, 2, 1, 6, 3, [0, 1, 2]).unwrap();
+        point_cloud.colourise(
+            &image,
+            &Intrinsics::new(1.0, 1.0, 1.0, 0.0, Distortion::None),
+            &identity(),
+        );
+        let mut output = Vec::new();
+
+        // Act
+        point_cloud
+            .write_pcd(&mut output, PointCloudEncoding::Ascii)
+            .unwrap();
+
+        // Assert
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("FIELDS x y z rgb\n"));
+        assert!(output.contains("WIDTH 2\nHEIGHT 1\n"));
+        assert!(output.contains("POINTS 2\nDATA ascii\n"));
+        assert!(output.ends_with(&format!("nan nan nan 0\n0 0 1 {}\n", 0x0004_0506)));
+    }
+
+    #[test]
+    fn write_pcd_binary() {
+        // Arrange
+        let point_cloud = PointCloud::from_depth(
+            &depth(),
+            3,
+            2,
+            4,
+            &intrinsics(),
+            DEPTH_SCALE,
+            PointCloudLayout::Organised,
+        )
+        .unwrap();
+        let mut output = Vec::new();
+
+        // Act
+        point_cloud
+            .write_pcd(&mut output, PointCloudEncoding::Binary)
+            .unwrap();
+
+        // Assert
+        let data = b"DATA binary\n";
+        let body = output.windows(data.len()).position(|w| w == data).unwrap() + data.len();
+        assert_eq!(output.len() - body, 6 * 3 * 4);
+    }
+}
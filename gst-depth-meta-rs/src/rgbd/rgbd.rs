@@ -1,4 +1,5 @@
 use crate::buffer::BufferMeta;
+use crate::caps::RgbdCaps;
 use crate::tags::TagsMeta;
 use crate::RgbdError;
 use gst::CoreError;
@@ -211,43 +212,22 @@ pub fn remove_aux_buffers(main_buffer: &mut gst::BufferRef) {
     });
 }
 
-/// Converts the given `caps` and `framerate` into a `gst::VideoInfo` for the stream with the given
+/// Converts the given `video/rgbd` caps into a `gst::VideoInfo` for the stream with the given
 /// name.
 /// # Arguments
 /// * `caps` - The caps to convert.
-/// * `framerate` - The framerate of the stream.
+/// * `stream_name` - The name of the stream.
 /// # Returns
 /// * `Ok(VideoInfo)` - If the CAPS were successfully converted.
-/// * `Err(RgbdError::MissingCapsField)` - If any required fields on the CAPS do not exist.
-/// * `Err(RgbdError::WrongCapsFormat)` - If a field on the CAPS are not of correct type.
-/// * `Err(RgbdError::NoVideoInfo)` - If `caps` could not be converted into `VideoInfo`.
+/// * `Err(gst::ErrorMessage)` - If the CAPS are not valid `video/rgbd` CAPS, or if they do not
+/// contain a raw video stream with the given name, see [RgbdCaps](../caps/struct.RgbdCaps.html).
 pub fn get_video_info(
     caps: &gst::StructureRef,
     stream_name: &str,
 ) -> Result<gst_video::VideoInfo, gst::ErrorMessage> {
-    let framerate = caps
-        .get::<gst::Fraction>("framerate")
-        .map_err(|e| gst::error_msg!(CoreError::Caps, ["{}", e]))?;
-    let stream_width = caps
-        .get::<i32>(&format!("{}_width", stream_name))
-        .map_err(|e| gst::error_msg!(CoreError::Caps, ["{}", e]))?;
-    let stream_height = caps
-        .get::<i32>(&format!("{}_height", stream_name))
-        .map_err(|e| gst::error_msg!(CoreError::Caps, ["{}", e]))?;
-    let stream_format = caps
-        .get::<&str>(&format!("{}_format", stream_name))
-        .map_err(|e| gst::error_msg!(CoreError::Caps, ["{}", e]))?;
-
-    gst_video::VideoInfo::builder(
-        stream_format
-            .parse()
-            .map_err(|e| gst::error_msg!(CoreError::Caps, ["{}", e]))?,
-        stream_width as u32,
-        stream_height as u32,
-    )
-    .fps(framerate)
-    .build()
-    .map_err(|e| gst::error_msg!(CoreError::Caps, ["{}", e]))
+    RgbdCaps::from_structure(caps)
+        .and_then(|caps| caps.video_info(stream_name))
+        .map_err(|e| gst::error_msg!(CoreError::Caps, ["{}", e]))
 }
 
 /// Aligns `buffer` to u16, such that it can be used to store depth video.
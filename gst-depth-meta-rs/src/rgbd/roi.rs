@@ -0,0 +1,450 @@
+use gst::glib;
+use gst::meta::*;
+use gst::BufferRef;
+use gst_video::VideoRegionOfInterestMeta;
+use once_cell::sync::Lazy;
+use std::{fmt, mem, ptr};
+
+use core::ops::ControlFlow::*;
+use gst::buffer::BufferMetaForeachAction::*;
+
+use crate::rgbd::sys;
+
+/// Name of the `GstVideoRegionOfInterestMeta` param structure that contains the confidence.
+const DETECTION_PARAM: &str = "detection";
+/// Name of the field in the `detection` param structure that contains the confidence.
+const CONFIDENCE_FIELD: &str = "confidence";
+
+/// A labelled rectangular region of interest in a video frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionOfInterest {
+    /// Label of the region, e.g. the class of a detected object.
+    pub label: String,
+    /// Confidence of the region within [0.0, 1.0], if known.
+    pub confidence: Option<f64>,
+    /// Horizontal offset of the top left corner in pixels.
+    pub x: u32,
+    /// Vertical offset of the top left corner in pixels.
+    pub y: u32,
+    /// Width of the region in pixels.
+    pub width: u32,
+    /// Height of the region in pixels.
+    pub height: u32,
+}
+
+impl RegionOfInterest {
+    /// Create a new region of interest with unknown confidence.
+    /// # Arguments
+    /// * `label` - Label of the region.
+    /// * `x` - Horizontal offset of the top left corner in pixels.
+    /// * `y` - Vertical offset of the top left corner in pixels.
+    /// * `width` - Width of the region in pixels.
+    /// * `height` - Height of the region in pixels.
+    pub fn new(label: &str, x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            label: label.to_string(),
+            confidence: None,
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Set the confidence of the region.
+    /// # Arguments
+    /// * `confidence` - Confidence of the region within [0.0, 1.0].
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = Some(confidence);
+        self
+    }
+
+    /// Convert `GstVideoRegionOfInterestMeta` into a region of interest. The `roi_type` is used as
+    /// label and the confidence is read from the `confidence` field of the `detection` param.
+    /// # Arguments
+    /// * `meta` - The meta to convert.
+    pub fn from_video_roi_meta(meta: &VideoRegionOfInterestMeta) -> Self {
+        let (x, y, width, height) = meta.rect();
+        let confidence = meta
+            .param(DETECTION_PARAM)
+            .and_then(|param| param.get::<f64>(CONFIDENCE_FIELD).ok());
+        Self {
+            label: meta.roi_type().to_string(),
+            confidence,
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Add the region of interest as `GstVideoRegionOfInterestMeta` onto `buffer`.
+    /// # Arguments
+    /// * `buffer` - The buffer onto which the meta should be added.
+    pub fn add_video_roi_meta<'a>(
+        &self,
+        buffer: &'a mut BufferRef,
+    ) -> MetaRefMut<'a, VideoRegionOfInterestMeta, Standalone> {
+        let mut meta = VideoRegionOfInterestMeta::add(
+            buffer,
+            &self.label,
+            (self.x, self.y, self.width, self.height),
+        );
+        if let Some(confidence) = self.confidence {
+            meta.add_param(
+                gst::Structure::builder(DETECTION_PARAM)
+                    .field(CONFIDENCE_FIELD, &confidence)
+                    .build(),
+            );
+        }
+        meta
+    }
+}
+
+/// The RoiMeta API is intended to carry regions of interest of a single stream of a `video/rgbd`
+/// frame. The coordinates of the regions refer to the stream with the name
+/// [stream_name](#method.stream_name), which might be different from the stream of the buffer that
+/// carries the meta, e.g. regions detected in the `color` stream can be attached to the `depth`
+/// buffer. Therefore, RoiMeta is copied unchanged by all transforms.
+#[repr(C)]
+pub struct RoiMeta {
+    meta: gst_sys::GstMeta,
+    stream_name: String,
+    regions: Vec<RegionOfInterest>,
+}
+
+unsafe impl Send for RoiMeta {}
+unsafe impl Sync for RoiMeta {}
+
+/// Parameters that are used to initialise RoiMeta.
+struct RoiMetaParams {
+    stream_name: String,
+    regions: Vec<RegionOfInterest>,
+}
+
+impl RoiMeta {
+    /// Add regions of interest of a stream as metadata onto the given `buffer`.
+    /// # Arguments
+    /// * `buffer` - The buffer onto which the regions should be added.
+    /// * `stream_name` - Name of the stream, to which the coordinates of the regions refer.
+    /// * `regions` - The regions of interest.
+    /// # Example
+    /// ```
+    /// use gst_depth_meta::roi::{RegionOfInterest, RoiMeta};
+    /// gst::init().unwrap();
+    /// let mut main_buffer = gst::Buffer::new();
+    /// RoiMeta::add(
+    ///    main_buffer.make_mut(),
+    ///    "color",
+    ///    vec![RegionOfInterest::new("person", 10, 20, 100, 200).with_confidence(0.9)],
+    /// );
+    /// ```
+    pub fn add<'a>(
+        buffer: &'a mut BufferRef,
+        stream_name: &str,
+        regions: Vec<RegionOfInterest>,
+    ) -> MetaRefMut<'a, Self, Standalone> {
+        unsafe {
+            let mut params = mem::ManuallyDrop::new(RoiMetaParams {
+                stream_name: stream_name.to_string(),
+                regions,
+            });
+            let meta = gst_sys::gst_buffer_add_meta(
+                buffer.as_mut_ptr(),
+                roi_meta_get_info(),
+                &mut *params as *mut RoiMetaParams as glib_sys::gpointer,
+            ) as *mut RoiMeta;
+            Self::from_mut_ptr(buffer, meta)
+        }
+    }
+
+    /// Add regions of interest of a stream onto the given `buffer`. If `buffer` already contains
+    /// RoiMeta for the stream, the regions are appended to it.
+    /// # Arguments
+    /// * `buffer` - The buffer onto which the regions should be added.
+    /// * `stream_name` - Name of the stream, to which the coordinates of the regions refer.
+    /// * `regions` - The regions of interest.
+    pub fn merge(buffer: &mut BufferRef, stream_name: &str, regions: Vec<RegionOfInterest>) {
+        if regions.is_empty() {
+            return;
+        }
+        let mut regions = Some(regions);
+        for mut meta in buffer.iter_meta_mut::<Self>() {
+            if meta.stream_name == stream_name {
+                meta.regions.append(regions.as_mut().unwrap());
+                return;
+            }
+        }
+        Self::add(buffer, stream_name, regions.take().unwrap());
+    }
+
+    /// Get the RoiMeta of the given stream attached onto `buffer`.
+    /// # Arguments
+    /// * `buffer` - The buffer to get the RoiMeta from.
+    /// * `stream_name` - Name of the stream.
+    pub fn get<'a>(buffer: &'a BufferRef, stream_name: &str) -> Option<MetaRef<'a, Self>> {
+        buffer
+            .iter_meta::<Self>()
+            .find(|meta| meta.stream_name == stream_name)
+    }
+
+    /// Get the name of the stream, to which the coordinates of the regions refer.
+    pub fn stream_name(&self) -> &str {
+        &self.stream_name
+    }
+
+    /// Get the regions of interest.
+    pub fn regions(&self) -> &[RegionOfInterest] {
+        &self.regions
+    }
+
+    /// Get a mutable reference to the regions of interest.
+    pub fn regions_mut(&mut self) -> &mut Vec<RegionOfInterest> {
+        &mut self.regions
+    }
+}
+
+unsafe impl MetaAPI for RoiMeta {
+    type GstType = RoiMeta;
+
+    fn meta_api() -> glib::Type {
+        roi_meta_api_get_type()
+    }
+}
+
+impl fmt::Debug for RoiMeta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RoiMeta")
+            .field("stream_name", &self.stream_name)
+            .field("regions", &self.regions)
+            .finish()
+    }
+}
+
+/// Replace all `GstVideoRegionOfInterestMeta` on `buffer` with RoiMeta of the given stream.
+/// # Arguments
+/// * `buffer` - The buffer of the stream.
+/// * `stream_name` - Name of the stream.
+pub fn video_roi_metas_to_roi_meta(buffer: &mut BufferRef, stream_name: &str) {
+    let regions = buffer
+        .iter_meta::<VideoRegionOfInterestMeta>()
+        .map(|meta| RegionOfInterest::from_video_roi_meta(&meta))
+        .collect::<Vec<RegionOfInterest>>();
+    buffer.foreach_meta_mut(|meta| {
+        if meta
+            .as_ref()
+            .downcast_ref::<VideoRegionOfInterestMeta>()
+            .is_some()
+        {
+            Continue(Remove)
+        } else {
+            Continue(Keep)
+        }
+    });
+    RoiMeta::merge(buffer, stream_name, regions);
+}
+
+/// Add all regions of the given stream as `GstVideoRegionOfInterestMeta` onto `buffer`, such that
+/// they can be used by elements that are not aware of RoiMeta.
+/// # Arguments
+/// * `buffer` - The buffer of the stream, which contains RoiMeta.
+/// * `stream_name` - Name of the stream.
+pub fn roi_meta_to_video_roi_metas(buffer: &mut BufferRef, stream_name: &str) {
+    let regions = RoiMeta::get(buffer, stream_name)
+        .map(|meta| meta.regions().to_vec())
+        .unwrap_or_default();
+    for region in regions.iter() {
+        region.add_video_roi_meta(buffer);
+    }
+}
+
+/// Move all RoiMeta from `buffer` onto `main_buffer`, e.g. when `buffer` is attached to
+/// `main_buffer` as an auxiliary buffer.
+/// # Arguments
+/// * `buffer` - The buffer to remove the RoiMeta from.
+/// * `main_buffer` - The buffer onto which the RoiMeta should be added.
+pub fn move_roi_metas(buffer: &mut BufferRef, main_buffer: &mut BufferRef) {
+    copy_roi_metas(buffer, main_buffer);
+    remove_roi_metas(buffer);
+}
+
+/// Copy all RoiMeta from `main_buffer` onto `buffer`, e.g. when `buffer` is demuxed from
+/// `main_buffer`.
+/// # Arguments
+/// * `main_buffer` - The buffer to copy the RoiMeta from.
+/// * `buffer` - The buffer onto which the RoiMeta should be added.
+pub fn copy_roi_metas(main_buffer: &BufferRef, buffer: &mut BufferRef) {
+    for meta in main_buffer.iter_meta::<RoiMeta>() {
+        RoiMeta::merge(buffer, meta.stream_name(), meta.regions().to_vec());
+    }
+}
+
+/// Removes all RoiMeta attached to `buffer`.
+pub fn remove_roi_metas(buffer: &mut BufferRef) {
+    buffer.foreach_meta_mut(|meta| match meta.as_ref().downcast_ref::<RoiMeta>() {
+        Some(_) => Continue(Remove),
+        _ => Continue(Keep),
+    });
+}
+
+fn roi_meta_api_get_type() -> glib::Type {
+    static TYPE: Lazy<glib::Type> = Lazy::new(|| sys::register_meta_api("RoiMetaAPI"));
+    *TYPE
+}
+
+unsafe extern "C" fn roi_meta_init(
+    meta: *mut gst_sys::GstMeta,
+    params: glib_sys::gpointer,
+    _buffer: *mut gst_sys::GstBuffer,
+) -> glib_sys::gboolean {
+    assert!(!params.is_null());
+    let meta = &mut *(meta as *mut RoiMeta);
+    let params = ptr::read(params as *const RoiMetaParams);
+    ptr::write(&mut meta.stream_name, params.stream_name);
+    ptr::write(&mut meta.regions, params.regions);
+    glib_sys::GTRUE
+}
+
+unsafe extern "C" fn roi_meta_free(meta: *mut gst_sys::GstMeta, _buffer: *mut gst_sys::GstBuffer) {
+    let meta = &mut *(meta as *mut RoiMeta);
+    ptr::drop_in_place(&mut meta.stream_name);
+    ptr::drop_in_place(&mut meta.regions);
+}
+
+unsafe extern "C" fn roi_meta_transform(
+    dest: *mut gst_sys::GstBuffer,
+    meta: *mut gst_sys::GstMeta,
+    _buffer: *mut gst_sys::GstBuffer,
+    _type: glib_sys::GQuark,
+    _data: glib_sys::gpointer,
+) -> glib_sys::gboolean {
+    let meta = &*(meta as *mut RoiMeta);
+    RoiMeta::add(
+        BufferRef::from_mut_ptr(dest),
+        &meta.stream_name,
+        meta.regions.clone(),
+    );
+    glib_sys::GTRUE
+}
+
+fn roi_meta_get_info() -> *const gst_sys::GstMetaInfo {
+    static META_INFO: Lazy<sys::MetaInfo> = Lazy::new(|| {
+        sys::register_meta(
+            roi_meta_api_get_type(),
+            "RoiMeta",
+            mem::size_of::<RoiMeta>(),
+            Some(roi_meta_init),
+            Some(roi_meta_free),
+            Some(roi_meta_transform),
+        )
+    });
+    META_INFO.0.as_ptr()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regions() -> Vec<RegionOfInterest> {
+        vec![
+            RegionOfInterest::new("person", 10, 20, 100, 200).with_confidence(0.75),
+            RegionOfInterest::new("box", 0, 0, 5, 5),
+        ]
+    }
+
+    #[test]
+    fn add_and_get_expect_regions_equal() {
+        // Arrange
+        gst::init().unwrap();
+        let mut buffer = gst::Buffer::new();
+
+        // Act
+        RoiMeta::add(buffer.make_mut(), "color", regions());
+
+        // Assert
+        let meta = RoiMeta::get(&buffer, "color").unwrap();
+        assert_eq!(meta.stream_name(), "color");
+        assert_eq!(meta.regions(), regions().as_slice());
+        assert!(RoiMeta::get(&buffer, "depth").is_none());
+    }
+
+    #[test]
+    fn merge_expect_regions_appended() {
+        // Arrange
+        gst::init().unwrap();
+        let mut buffer = gst::Buffer::new();
+        RoiMeta::add(buffer.make_mut(), "color", regions());
+
+        // Act
+        RoiMeta::merge(buffer.make_mut(), "color", regions());
+        RoiMeta::merge(buffer.make_mut(), "depth", regions());
+
+        // Assert
+        assert_eq!(buffer.iter_meta::<RoiMeta>().count(), 2);
+        assert_eq!(RoiMeta::get(&buffer, "color").unwrap().regions().len(), 4);
+        assert_eq!(RoiMeta::get(&buffer, "depth").unwrap().regions().len(), 2);
+    }
+
+    #[test]
+    fn copy_expect_regions_present_on_copy() {
+        // Arrange
+        gst::init().unwrap();
+        let mut buffer = gst::Buffer::new();
+        RoiMeta::add(buffer.make_mut(), "color", regions());
+
+        // Act
+        let copy = buffer.copy();
+
+        // Assert
+        let meta = RoiMeta::get(&copy, "color").unwrap();
+        assert_eq!(meta.regions(), regions().as_slice());
+    }
+
+    #[test]
+    fn convert_video_roi_metas_expect_regions_equal() {
+        // Arrange
+        gst::init().unwrap();
+        let mut buffer = gst::Buffer::new();
+        for region in regions() {
+            region.add_video_roi_meta(buffer.make_mut());
+        }
+
+        // Act
+        video_roi_metas_to_roi_meta(buffer.make_mut(), "color");
+
+        // Assert
+        assert_eq!(buffer.iter_meta::<VideoRegionOfInterestMeta>().count(), 0);
+        let meta = RoiMeta::get(&buffer, "color").unwrap();
+        assert_eq!(meta.regions(), regions().as_slice());
+
+        // Act
+        roi_meta_to_video_roi_metas(buffer.make_mut(), "color");
+
+        // Assert
+        let converted = buffer
+            .iter_meta::<VideoRegionOfInterestMeta>()
+            .map(|meta| RegionOfInterest::from_video_roi_meta(&meta))
+            .collect::<Vec<RegionOfInterest>>();
+        assert_eq!(converted, regions());
+    }
+
+    #[test]
+    fn move_and_copy_expect_regions_on_other_stream() {
+        // Arrange
+        gst::init().unwrap();
+        let mut color_buffer = gst::Buffer::new();
+        RoiMeta::add(color_buffer.make_mut(), "color", regions());
+        let mut main_buffer = gst::Buffer::new();
+        let mut depth_buffer = gst::Buffer::new();
+
+        // Act
+        move_roi_metas(color_buffer.make_mut(), main_buffer.make_mut());
+        copy_roi_metas(&main_buffer, depth_buffer.make_mut());
+
+        // Assert
+        assert!(RoiMeta::get(&color_buffer, "color").is_none());
+        assert!(RoiMeta::get(&main_buffer, "color").is_some());
+        let meta = RoiMeta::get(&depth_buffer, "color").unwrap();
+        assert_eq!(meta.regions(), regions().as_slice());
+    }
+}
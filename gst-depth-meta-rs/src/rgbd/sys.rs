@@ -1,3 +1,6 @@
+//! Native implementation of the `BufferMeta` and `TagsMeta` GstMeta types. The API names,
+//! implementation names and memory layout are identical to those of the `gst-depth-meta` C library,
+//! such that C and Rust elements can exchange buffers within one pipeline.
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
@@ -5,7 +8,13 @@
 
 extern crate glib_sys;
 extern crate gst_sys;
-extern crate gst_video_sys;
+
+use gst::glib;
+use gst::glib::translate::{from_glib, IntoGlib};
+use once_cell::sync::Lazy;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::{mem, ptr};
 
 pub type BufferMeta = _BufferMeta;
 pub type TagsMeta = _TagsMeta;
@@ -30,19 +39,276 @@ pub struct _TagsMeta {
 unsafe impl Sync for _TagsMeta {}
 unsafe impl Send for _TagsMeta {}
 
-extern "C" {
-    pub fn buffer_meta_api_get_type() -> glib_sys::GType;
-    pub fn buffer_meta_get_info() -> *const gst_sys::GstMetaInfo;
-    pub fn buffer_meta_get(buffer: *mut gst_sys::GstBuffer) -> *mut BufferMeta;
-    pub fn buffer_meta_add(
-        buffer: *mut gst_sys::GstBuffer,
-        buffer_meta: *mut gst_sys::GstBuffer,
-    ) -> *mut BufferMeta;
-    pub fn tags_meta_api_get_type() -> glib_sys::GType;
-    pub fn tags_meta_get_info() -> *const gst_sys::GstMetaInfo;
-    pub fn tags_meta_get(buffer: *mut gst_sys::GstBuffer) -> *mut TagsMeta;
-    pub fn tags_meta_add(
-        buffer: *mut gst_sys::GstBuffer,
-        tags_meta: *mut gst_sys::GstTagList,
-    ) -> *mut TagsMeta;
+/// Registered `GstMetaInfo`, which is never modified after registration and can therefore be
+/// shared between threads.
+pub(crate) struct MetaInfo(pub(crate) ptr::NonNull<gst_sys::GstMetaInfo>);
+
+unsafe impl Send for MetaInfo {}
+unsafe impl Sync for MetaInfo {}
+
+/// Register a GstMeta API with the given name. If the API was already registered, e.g. by the C
+/// library, the existing type is returned instead.
+/// # Arguments
+/// * `api` - Name of the API, e.g. `BufferMetaAPI`.
+/// # Returns
+/// * The type of the API.
+pub(crate) fn register_meta_api(api: &str) -> glib::Type {
+    if let Some(api_type) = glib::Type::from_name(api) {
+        return api_type;
+    }
+
+    let api = CString::new(api).unwrap();
+    let tags = [ptr::null::<c_char>()];
+    let api_type: glib::Type = unsafe {
+        from_glib(gst_sys::gst_meta_api_type_register(
+            api.as_ptr(),
+            tags.as_ptr() as *mut *const c_char,
+        ))
+    };
+    assert_ne!(api_type, glib::Type::INVALID, "Failed to register meta API");
+    api_type
+}
+
+/// Register a GstMeta implementation with the given name. If the implementation was already
+/// registered, e.g. by the C library, the existing info is returned instead.
+/// # Arguments
+/// * `api` - Type of the API that is implemented.
+/// * `implementation` - Name of the implementation, e.g. `BufferMeta`.
+/// * `size` - Size of the meta struct.
+/// * `init` - Function that initialises the meta.
+/// * `free` - Function that frees the meta.
+/// * `transform` - Function that transforms the meta onto a new buffer.
+/// # Panics
+/// * If the implementation cannot be registered or if an existing implementation has a different
+/// size, i.e. its memory layout is not compatible.
+pub(crate) fn register_meta(
+    api: glib::Type,
+    implementation: &str,
+    size: usize,
+    init: gst_sys::GstMetaInitFunction,
+    free: gst_sys::GstMetaFreeFunction,
+    transform: gst_sys::GstMetaTransformFunction,
+) -> MetaInfo {
+    let implementation = CString::new(implementation).unwrap();
+    unsafe {
+        let mut info = gst_sys::gst_meta_get_info(implementation.as_ptr());
+        if info.is_null() {
+            info = gst_sys::gst_meta_register(
+                api.into_glib(),
+                implementation.as_ptr(),
+                size,
+                init,
+                free,
+                transform,
+            );
+        }
+        let info = ptr::NonNull::new(info as *mut gst_sys::GstMetaInfo)
+            .expect("Failed to register meta implementation");
+        assert_eq!(
+            info.as_ref().size,
+            size,
+            "Meta implementation {:?} is registered with incompatible layout",
+            implementation
+        );
+        MetaInfo(info)
+    }
+}
+
+/// Determine whether a meta transform is part of a deep copy of the whole buffer, e.g.
+/// `gst_buffer_copy_deep()`, in which case the contained data is deep copied as well. GStreamer
+/// does not pass the copy flags on to meta transforms, therefore a deep copy is detected by every
+/// memory of `dest` being a separate copy of the memory of `src` with the same size.
+/// # Arguments
+/// * `dest` - The buffer onto which the meta is transformed.
+/// * `src` - The buffer that contains the meta.
+/// * `type_` - The type of the transform.
+/// * `data` - The data of the transform.
+/// # Returns
+/// * `true` if the transform is a deep copy of the whole buffer.
+/// * `false` for plain copies, copies of a region (e.g. crop), buffers without memory and all
+/// other transforms (e.g. scale), which share the contained data.
+unsafe fn is_deep_copy(
+    dest: *mut gst_sys::GstBuffer,
+    src: *mut gst_sys::GstBuffer,
+    type_: glib_sys::GQuark,
+    data: glib_sys::gpointer,
+) -> bool {
+    let copy_quark = glib_sys::g_quark_from_static_string(b"gst-copy\0".as_ptr() as *const _);
+    if type_ != copy_quark || data.is_null() {
+        return false;
+    }
+    if (*(data as *const gst_sys::GstMetaTransformCopy)).region != glib_sys::GFALSE {
+        return false;
+    }
+
+    let n_memory = gst_sys::gst_buffer_n_memory(src);
+    if n_memory == 0 || gst_sys::gst_buffer_n_memory(dest) != n_memory {
+        return false;
+    }
+    (0..n_memory).all(|idx| {
+        let src_memory = gst_sys::gst_buffer_peek_memory(src, idx);
+        let dest_memory = gst_sys::gst_buffer_peek_memory(dest, idx);
+        dest_memory != src_memory
+            && (*dest_memory).parent != src_memory
+            && ((*src_memory).parent.is_null() || (*dest_memory).parent != (*src_memory).parent)
+            && (*dest_memory).size == (*src_memory).size
+    })
+}
+
+pub fn buffer_meta_api_get_type() -> glib::Type {
+    static TYPE: Lazy<glib::Type> = Lazy::new(|| register_meta_api("BufferMetaAPI"));
+    *TYPE
+}
+
+unsafe extern "C" fn buffer_meta_init(
+    meta: *mut gst_sys::GstMeta,
+    _params: glib_sys::gpointer,
+    _buffer: *mut gst_sys::GstBuffer,
+) -> glib_sys::gboolean {
+    (*(meta as *mut BufferMeta)).buffer = ptr::null_mut();
+    glib_sys::GTRUE
+}
+
+unsafe extern "C" fn buffer_meta_free(
+    meta: *mut gst_sys::GstMeta,
+    _buffer: *mut gst_sys::GstBuffer,
+) {
+    let meta = &mut *(meta as *mut BufferMeta);
+    if !meta.buffer.is_null() {
+        gst_sys::gst_mini_object_unref(meta.buffer as *mut _);
+        meta.buffer = ptr::null_mut();
+    }
+}
+
+unsafe extern "C" fn buffer_meta_transform(
+    dest: *mut gst_sys::GstBuffer,
+    meta: *mut gst_sys::GstMeta,
+    buffer: *mut gst_sys::GstBuffer,
+    type_: glib_sys::GQuark,
+    data: glib_sys::gpointer,
+) -> glib_sys::gboolean {
+    let src = &*(meta as *mut BufferMeta);
+    let dest_meta = gst_sys::gst_buffer_add_meta(dest, buffer_meta_get_info(), ptr::null_mut())
+        as *mut BufferMeta;
+    if dest_meta.is_null() {
+        return glib_sys::GFALSE;
+    }
+
+    // Deep copies get their own copy of the contained buffer, all other transforms share it, such
+    // that it is copied on write by `BufferMeta::buffer_mut()`
+    (*dest_meta).buffer = if is_deep_copy(dest, buffer, type_, data) {
+        gst_sys::gst_buffer_copy_deep(src.buffer)
+    } else {
+        gst_sys::gst_mini_object_ref(src.buffer as *mut _) as *mut _
+    };
+    glib_sys::GTRUE
+}
+
+pub fn buffer_meta_get_info() -> *const gst_sys::GstMetaInfo {
+    static META_INFO: Lazy<MetaInfo> = Lazy::new(|| {
+        register_meta(
+            buffer_meta_api_get_type(),
+            "BufferMeta",
+            mem::size_of::<BufferMeta>(),
+            Some(buffer_meta_init),
+            Some(buffer_meta_free),
+            Some(buffer_meta_transform),
+        )
+    });
+    META_INFO.0.as_ptr()
+}
+
+pub unsafe fn buffer_meta_get(buffer: *mut gst_sys::GstBuffer) -> *mut BufferMeta {
+    gst_sys::gst_buffer_get_meta(buffer, buffer_meta_api_get_type().into_glib()) as *mut BufferMeta
+}
+
+pub unsafe fn buffer_meta_add(
+    buffer: *mut gst_sys::GstBuffer,
+    buffer_meta: *mut gst_sys::GstBuffer,
+) -> *mut BufferMeta {
+    let meta = gst_sys::gst_buffer_add_meta(buffer, buffer_meta_get_info(), ptr::null_mut())
+        as *mut BufferMeta;
+    if meta.is_null() {
+        return meta;
+    }
+
+    (*meta).buffer = gst_sys::gst_mini_object_ref(buffer_meta as *mut _) as *mut _;
+    meta
+}
+
+pub fn tags_meta_api_get_type() -> glib::Type {
+    static TYPE: Lazy<glib::Type> = Lazy::new(|| register_meta_api("TagsMetaAPI"));
+    *TYPE
+}
+
+unsafe extern "C" fn tags_meta_init(
+    meta: *mut gst_sys::GstMeta,
+    _params: glib_sys::gpointer,
+    _buffer: *mut gst_sys::GstBuffer,
+) -> glib_sys::gboolean {
+    (*(meta as *mut TagsMeta)).tags = ptr::null_mut();
+    glib_sys::GTRUE
+}
+
+unsafe extern "C" fn tags_meta_free(meta: *mut gst_sys::GstMeta, _buffer: *mut gst_sys::GstBuffer) {
+    let meta = &mut *(meta as *mut TagsMeta);
+    if !meta.tags.is_null() {
+        gst_sys::gst_mini_object_unref(meta.tags as *mut _);
+        meta.tags = ptr::null_mut();
+    }
+}
+
+unsafe extern "C" fn tags_meta_transform(
+    dest: *mut gst_sys::GstBuffer,
+    meta: *mut gst_sys::GstMeta,
+    buffer: *mut gst_sys::GstBuffer,
+    type_: glib_sys::GQuark,
+    data: glib_sys::gpointer,
+) -> glib_sys::gboolean {
+    let src = &*(meta as *mut TagsMeta);
+    let dest_meta =
+        gst_sys::gst_buffer_add_meta(dest, tags_meta_get_info(), ptr::null_mut()) as *mut TagsMeta;
+    if dest_meta.is_null() {
+        return glib_sys::GFALSE;
+    }
+
+    // Deep copies get their own copy of the tags, all other transforms share them
+    (*dest_meta).tags = if is_deep_copy(dest, buffer, type_, data) {
+        gst_sys::gst_mini_object_copy(src.tags as *const _) as *mut _
+    } else {
+        gst_sys::gst_mini_object_ref(src.tags as *mut _) as *mut _
+    };
+    glib_sys::GTRUE
+}
+
+pub fn tags_meta_get_info() -> *const gst_sys::GstMetaInfo {
+    static META_INFO: Lazy<MetaInfo> = Lazy::new(|| {
+        register_meta(
+            tags_meta_api_get_type(),
+            "TagsMeta",
+            mem::size_of::<TagsMeta>(),
+            Some(tags_meta_init),
+            Some(tags_meta_free),
+            Some(tags_meta_transform),
+        )
+    });
+    META_INFO.0.as_ptr()
+}
+
+pub unsafe fn tags_meta_get(buffer: *mut gst_sys::GstBuffer) -> *mut TagsMeta {
+    gst_sys::gst_buffer_get_meta(buffer, tags_meta_api_get_type().into_glib()) as *mut TagsMeta
+}
+
+pub unsafe fn tags_meta_add(
+    buffer: *mut gst_sys::GstBuffer,
+    tags_meta: *mut gst_sys::GstTagList,
+) -> *mut TagsMeta {
+    let meta = gst_sys::gst_buffer_add_meta(buffer, tags_meta_get_info(), ptr::null_mut())
+        as *mut TagsMeta;
+    if meta.is_null() {
+        return meta;
+    }
+
+    (*meta).tags = gst_sys::gst_mini_object_ref(tags_meta as *mut _) as *mut _;
+    meta
 }
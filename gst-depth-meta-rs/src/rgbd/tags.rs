@@ -1,4 +1,3 @@
-use glib::translate::from_glib;
 use gst::glib;
 use gst::meta::*;
 use gst::BufferRef;
@@ -9,6 +8,9 @@ pub use crate::rgbd::sys::TagsMeta;
 
 /// The TagsMeta API is intended to allow developers to add Tags onto gst buffers, which can be used
 /// to identify different buffers from each other.
+///
+/// TagsMeta is transformed in the same manner as [BufferMeta](../buffer/struct.BufferMeta.html),
+/// i.e. the TagList is copied on deep copies of the whole buffer and shared otherwise.
 impl TagsMeta {
     /// Adds a TagList as metadata onto the given `buffer`.
     /// # Arguments
@@ -56,7 +58,7 @@ unsafe impl MetaAPI for TagsMeta {
     type GstType = TagsMeta;
 
     fn meta_api() -> glib::Type {
-        unsafe { from_glib(sys::tags_meta_api_get_type()) }
+        sys::tags_meta_api_get_type()
     }
 }
 
@@ -79,6 +81,7 @@ pub fn get_tags(tag: &str) -> gst::TagList {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use glib::translate::from_glib;
 
     #[test]
     fn add_and_get_expect_tags_equal() {
@@ -124,4 +127,64 @@ mod tests {
             )
         }
     }
+
+    #[test]
+    fn meta_registered_with_c_compatible_names_and_layout() {
+        // Arrange
+        gst::init().unwrap();
+
+        // Act
+        let info = unsafe { &*sys::tags_meta_get_info() };
+        let implementation: glib::Type = unsafe { from_glib(info.type_) };
+
+        // Assert
+        assert_eq!(TagsMeta::meta_api().name(), "TagsMetaAPI");
+        assert_eq!(implementation.name(), "TagsMeta");
+        assert_eq!(
+            info.size,
+            std::mem::size_of::<gst_sys::GstMeta>()
+                + std::mem::size_of::<*mut gst_sys::GstTagList>()
+        );
+    }
+
+    #[test]
+    fn copy_expect_tags_present_on_copy() {
+        // Arrange
+        gst::init().unwrap();
+        let input_title = "example-tag";
+        let mut buffer = gst::Buffer::new();
+        let mut tags = get_tags(input_title);
+        TagsMeta::add(buffer.make_mut(), &mut tags);
+
+        // Act
+        let copy = buffer.copy();
+
+        // Assert
+        let tag_list = TagsMeta::get(&copy).get_tag_list();
+        assert_eq!(
+            tag_list.get::<gst::tags::Title>().unwrap().get(),
+            input_title
+        );
+    }
+
+    #[test]
+    fn copy_deep_expect_tags_copied() {
+        // Arrange
+        gst::init().unwrap();
+        let input_title = "example-tag";
+        let mut buffer = gst::Buffer::from_slice([9, 8, 7, 6]);
+        let mut tags = get_tags(input_title);
+        TagsMeta::add(buffer.make_mut(), &mut tags);
+
+        // Act
+        let copy = buffer.copy_deep().unwrap();
+
+        // Assert
+        assert_ne!(TagsMeta::get(&copy).tags, TagsMeta::get(&buffer).tags);
+        let tag_list = TagsMeta::get(&copy).get_tag_list();
+        assert_eq!(
+            tag_list.get::<gst::tags::Title>().unwrap().get(),
+            input_title
+        );
+    }
 }
@@ -5,7 +5,9 @@ GType buffer_meta_api_get_type(void) {
   static const gchar *tags[] = {NULL};
 
   if (g_once_init_enter(&type)) {
-    GType _type = gst_meta_api_type_register("BufferMetaAPI", tags);
+    // Reuse the API if it was already registered, e.g. by gst-depth-meta-rs
+    GType _type = g_type_from_name("BufferMetaAPI");
+    if (!_type) _type = gst_meta_api_type_register("BufferMetaAPI", tags);
     g_once_init_leave(&type, _type);
   }
   return type;
@@ -15,10 +17,49 @@ gboolean buffer_meta_init(GstMeta *meta, gpointer params, GstBuffer *buffer) {
   return TRUE;
 }
 
+gboolean buffer_meta_is_deep_copy(GstBuffer *dest_buf, GstBuffer *src_buf,
+                                  GQuark type, gpointer data) {
+  guint i, n_mem;
+
+  // Copies of a region (e.g. crop) and all other transforms (e.g. scale) share
+  // the contained data
+  if (!GST_META_TRANSFORM_IS_COPY(type) || !data ||
+      ((GstMetaTransformCopy *)data)->region)
+    return FALSE;
+
+  // Copy flags are not passed on to meta transforms, so deep copies are
+  // detected by every memory of dest_buf being a separate copy of the memory
+  // of src_buf with the same size
+  n_mem = gst_buffer_n_memory(src_buf);
+  if (n_mem == 0 || gst_buffer_n_memory(dest_buf) != n_mem) return FALSE;
+
+  for (i = 0; i < n_mem; i++) {
+    GstMemory *src_mem = gst_buffer_peek_memory(src_buf, i);
+    GstMemory *dest_mem = gst_buffer_peek_memory(dest_buf, i);
+    if (dest_mem == src_mem || dest_mem->parent == src_mem ||
+        (src_mem->parent && dest_mem->parent == src_mem->parent) ||
+        dest_mem->size != src_mem->size)
+      return FALSE;
+  }
+  return TRUE;
+}
+
 gboolean buffer_meta_transform(GstBuffer *dest_buf, GstMeta *src_meta,
                                GstBuffer *src_buf, GQuark type, gpointer data) {
   BufferMeta *src = (BufferMeta *)src_meta;
-  buffer_meta_add(dest_buf, src->buffer);
+  BufferMeta *meta;
+
+  meta =
+      (BufferMeta *)gst_buffer_add_meta(dest_buf, buffer_meta_get_info(), NULL);
+  if (!meta) return FALSE;
+
+  // Deep copies get their own copy of the contained buffer, all other
+  // transforms share it, such that it must be made writable before it is
+  // modified, i.e. it is copied on write
+  if (buffer_meta_is_deep_copy(dest_buf, src_buf, type, data))
+    meta->buffer = gst_buffer_copy_deep(src->buffer);
+  else
+    meta->buffer = gst_buffer_ref(src->buffer);
 
   return TRUE;
 }
@@ -32,7 +73,10 @@ const GstMetaInfo *buffer_meta_get_info(void) {
   static const GstMetaInfo *meta_info = NULL;
 
   if (g_once_init_enter(&meta_info)) {
-    const GstMetaInfo *meta = gst_meta_register(
+    // Reuse the implementation if it was already registered, e.g. by gst-depth-meta-rs
+    const GstMetaInfo *meta = gst_meta_get_info("BufferMeta");
+    if (!meta)
+      meta = gst_meta_register(
         buffer_meta_api_get_type(), "BufferMeta", sizeof(BufferMeta),
         (GstMetaInitFunction)buffer_meta_init,
         (GstMetaFreeFunction)buffer_meta_free,
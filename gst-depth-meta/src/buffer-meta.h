@@ -19,5 +19,7 @@ const GstMetaInfo *buffer_meta_get_info(void);
 BufferMeta *buffer_meta_get(GstBuffer *buffer);
 BufferMeta *buffer_meta_add(GstBuffer *buffer, GstBuffer *meta_buffer);
 gboolean buffer_meta_remove(GstBuffer *buffer, BufferMeta *meta_buffer);
+gboolean buffer_meta_is_deep_copy(GstBuffer *dest_buf, GstBuffer *src_buf,
+                                  GQuark type, gpointer data);
 
 G_END_DECLS
@@ -1,11 +1,15 @@
 #include "tags-meta.h"
+#include "buffer-meta.h"
 
 GType tags_meta_api_get_type(void) {
     static volatile GType type;
     static const gchar *tags[] = {NULL};
 
     if (g_once_init_enter(&type)) {
-        GType _type = gst_meta_api_type_register("TagsMetaAPI", tags);
+        // Reuse the API if it was already registered, e.g. by gst-depth-meta-rs
+        GType _type = g_type_from_name("TagsMetaAPI");
+        if (!_type)
+            _type = gst_meta_api_type_register("TagsMetaAPI", tags);
         g_once_init_leave(&type, _type);
     }
     return type;
@@ -18,7 +22,17 @@ gboolean tags_meta_init(GstMeta *meta, gpointer params, GstBuffer *buffer) {
 gboolean tags_meta_transform(GstBuffer *dest_buf, GstMeta *src_meta,
                                GstBuffer *src_buf, GQuark type, gpointer data) {
     TagsMeta *src = (TagsMeta *) src_meta;
-    tags_meta_add(dest_buf, src->tags);
+    TagsMeta *meta;
+
+    meta = (TagsMeta *) gst_buffer_add_meta(dest_buf, tags_meta_get_info(), NULL);
+    if (!meta)
+        return FALSE;
+
+    // Deep copies get their own copy of the tags, all other transforms share them
+    if (buffer_meta_is_deep_copy(dest_buf, src_buf, type, data))
+        meta->tags = gst_tag_list_copy(src->tags);
+    else
+        meta->tags = gst_tag_list_ref(src->tags);
 
     return TRUE;
 }
@@ -32,7 +46,10 @@ const GstMetaInfo *tags_meta_get_info(void) {
     static const GstMetaInfo *meta_info = NULL;
 
     if (g_once_init_enter(&meta_info)) {
-        const GstMetaInfo *meta = gst_meta_register(
+        // Reuse the implementation if it was already registered, e.g. by gst-depth-meta-rs
+        const GstMetaInfo *meta = gst_meta_get_info("TagsMeta");
+        if (!meta)
+            meta = gst_meta_register(
                 tags_meta_api_get_type(), "TagsMeta", sizeof(TagsMeta),
                 (GstMetaInitFunction) tags_meta_init,
                 (GstMetaFreeFunction) tags_meta_free,
@@ -9,4 +9,6 @@ class GstreamerFrameAligner(GstRustProject):
         "cmake/[^3.18.4]",
         "capnproto/[^0.8.0]",
     )
-    requires = (f"gst-depth-meta/{branch()}")
\ No newline at end of file
+
+    def requirements(self):
+        self.requires(f"gst-plugins-base/[~{self.settings.gstreamer}]")
\ No newline at end of file
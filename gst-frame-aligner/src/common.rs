@@ -17,6 +17,7 @@
  */
 
 use gst::glib;
+use gst_depth_meta::rgbd::RgbdFrameInfo;
 use once_cell::sync::Lazy;
 use std::error::Error;
 use std::fmt;
@@ -79,10 +80,8 @@ impl From<GstFrameAlignerError> for gst::LoggableError {
 /// Struct containing caps information, which is useful for extracting frames from buffers
 #[derive(Debug)]
 pub struct CapsVideoInfo {
-    /// Information about the depth video frame, e.g. height and width of the frame.
-    pub depth_video_info: gst_video::VideoInfo,
-    /// Information about the color video frame, e.g. height and width of the frame.
-    pub color_video_info: gst_video::VideoInfo,
+    /// Information about all streams, e.g. height and width of the depth frame.
+    pub frame_info: RgbdFrameInfo,
     /// Size the buffer allocated to read a block from the sink pads.
     pub sink_blocksize: usize,
     /// Size the buffer allocated to write a block to the src pads.
@@ -92,21 +91,14 @@ pub struct CapsVideoInfo {
 impl CapsVideoInfo {
     /// Function that creates a new CapsVideoInfo.
     /// # Arguments
-    /// * `depth_video_info` - The depth video info.
-    /// * `color_video_info` - The color video info, if present.
+    /// * `frame_info` - Information about all streams.
     /// * `sink_blocksize` - The size of the buffers on the sink pad.
     /// * `src_blocksize` - The size of the buffers on the src pad.
     /// # Returns
     /// A new instance of CapsVideoInfo.
-    pub fn new(
-        depth_video_info: gst_video::VideoInfo,
-        color_video_info: gst_video::VideoInfo,
-        sink_blocksize: usize,
-        src_blocksize: usize,
-    ) -> Self {
+    pub fn new(frame_info: RgbdFrameInfo, sink_blocksize: usize, src_blocksize: usize) -> Self {
         Self {
-            depth_video_info,
-            color_video_info,
+            frame_info,
             sink_blocksize: sink_blocksize as usize,
             src_blocksize: src_blocksize as usize,
         }
@@ -22,8 +22,10 @@ use gst::glib;
 use gst::subclass::prelude::*;
 use gst_base::prelude::*;
 use gst_base::subclass::prelude::*;
+use gst_depth_meta::camera_meta::{
+    Distortion, Intrinsics, RotationMatrix, Transformation, Translation, DEFAULT_ROTATION_TOLERANCE,
+};
 use gst_depth_meta::rgbd;
-use na::*;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::Mutex;
@@ -38,28 +40,33 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
 
 // This element is based on this formula x2 = K2*R*inverse(K1)*x1 + K2*t/x1_z. THe first of the sum we called transform1*x1.
 
-/// Depth factor for which we have to divide the depth values read from the camera,
-/// e.g. if a camera is using mm units, this should be 1000. If it is using half-mm
-/// units this should be 2000.
-const DEFAULT_DEPTH_FACTOR: f32 = 1000.0;
+/// Default value of `depth-factor` property, which indicates that the depth scale from the
+/// CameraMeta of the stream should be used.
+const DEFAULT_DEPTH_FACTOR: f32 = 0.0;
+
+/// Depth scale in metres that is used if neither `depth-factor` is set nor CameraMeta is attached
+/// to the stream, i.e. millimetres, which corresponds to the previous default `depth-factor` of
+/// 1000.
+const FALLBACK_DEPTH_SCALE: f32 = 0.001;
 
 /// This structure holds the `framealigner` state
 #[derive(Debug)]
 struct FrameAlignerState {
-    /// * `depth_factor` - The depth factor to use to convert the read depth data into meters.
-    depth_factor: f32,
+    /// * `depth_factor` - The depth factor for which we have to divide the depth values read from
+    /// the camera, e.g. 1000 if a camera is using mm units. If not set, `depth_scale` is used.
+    depth_factor: Option<f32>,
+    /// * `depth_scale` - Scaling factor of the depth map in metres, from CameraMeta of the stream.
+    depth_scale: Option<f32>,
     /// * `video_info_in` - CapsVideoInfo that carries info about the streams.
     video_info: Option<CapsVideoInfo>,
     /// * `calib_file` - Path to camera calibration file.
     calib_file: String,
-    /// * `transform1` - Struct that holds the except for the translation.
-    rotation: Matrix3<f32>,
-    /// * `k_color` - Struct that holds the color camera intrinsics parameters.
-    k_color: Matrix3<f32>,
-    /// * `k_depth` - Struct that holds the color camera intrinsics parameters.
-    k_depth: Matrix3<f32>,
-    /// * `translation` - Struct that holds the translation matrix.
-    translation: Matrix3x1<f32>,
+    /// * `extrinsics` - Transformation from the depth to the color camera coordinate frame.
+    extrinsics: Transformation,
+    /// * `intrinsics_color` - Struct that holds the color camera intrinsics parameters.
+    intrinsics_color: Intrinsics,
+    /// * `intrinsics_depth` - Struct that holds the depth camera intrinsics parameters.
+    intrinsics_depth: Intrinsics,
 }
 /// A struct representation of the `framealigner` element. The algorithm used in this element is
 /// loosely based on https://www.codefull.org/2016/03/align-depth-and-color-frames-depth-and-rgb-registration/.
@@ -75,6 +82,7 @@ impl FrameAligner {
     /// Code based in http://docs.ros.org/kinetic/api/librealsense2/html/align_8cpp_source.html#l00019
     /// # Arguments
     /// * `state` - The internal state of the framealigner.
+    /// * `depth_scale` - Scaling factor of the depth map, in metres.
     /// * `data` - Mutable reference to the in-place buffer
     /// * `width` - The width of the image.
     /// * `height` - The height of the image.
@@ -82,68 +90,41 @@ impl FrameAligner {
     fn get_new_depth_image(
         &self,
         state: &FrameAlignerState,
+        depth_scale: f32,
         data: &mut [u16],
         width: usize,
         height: usize,
     ) {
-        // Get camera parameters
-        let cx_d = state.k_depth[(0, 2)] as f32;
-        let cy_d = state.k_depth[(1, 2)] as f32;
-        let fx_d = state.k_depth[(0, 0)] as f32;
-        let fy_d = state.k_depth[(1, 1)] as f32;
-
-        let cx_c = state.k_color[(0, 2)] as f32;
-        let cy_c = state.k_color[(1, 2)] as f32;
-        let fx_c = state.k_color[(0, 0)] as f32;
-        let fy_c = state.k_color[(1, 1)] as f32;
-
-        let mut depth_point = Matrix3x1::new(0.0, 0.0, 0.0);
-        let mut other_pixel = Matrix2x1::new(0.0, 0.0);
-
         // Iterate over the image
         for x_d in 0..height {
             for y_d in 0..width {
                 if data[x_d * width + y_d] != 0 {
                     // Get depth data
-                    let z = (data[x_d * width + y_d] as f32) / state.depth_factor;
+                    let z = rgbd::raw_to_metres(data[x_d * width + y_d], depth_scale);
                     // Zero the vector
                     data[x_d * width + y_d] = 0;
 
                     // Map the top-left corner of the depth pixel onto the other image
-                    let tl_depth_pixel = Matrix2x1::new(x_d as f32 - 0.5, y_d as f32 - 0.5);
-                    // Get the 3D point in the depth world coordinate frame
-                    depth_point.x = (tl_depth_pixel.x as f32 - cx_d) * z / fx_d;
-                    depth_point.y = (tl_depth_pixel.y as f32 - cy_d) * z / fy_d;
-                    depth_point.z = z;
-
-                    // Rotate the 3D point to the color world coordinate frame
-                    let mut other_point = state.rotation * depth_point + state.translation;
-
-                    // Project the point into the image using camera intrinsics
-                    other_pixel.x = (other_point.x / other_point.z * fx_c) + cx_c;
-                    other_pixel.y = (other_point.y / other_point.z * fy_c) + cy_c;
+                    let other_pixel = Self::map_depth_pixel_to_color(
+                        state,
+                        [x_d as f32 - 0.5, y_d as f32 - 0.5],
+                        z,
+                    );
 
                     // Do naive intrpolation
-                    let other_x0 = (other_pixel.x + 0.5) as usize;
-                    let other_y0 = (other_pixel.y + 0.5) as usize;
+                    let other_x0 = (other_pixel[0] + 0.5) as usize;
+                    let other_y0 = (other_pixel[1] + 0.5) as usize;
 
                     // Map the bottom-right corner of the depth pixel onto the other image
-                    let br_depth_pixel = Matrix2x1::new(x_d as f32 + 0.5, y_d as f32 + 0.5);
-                    // Get the 3D point in the depth world coordinate frame
-                    depth_point.x = (br_depth_pixel.x as f32 - cx_d) * z / fx_d;
-                    depth_point.y = (br_depth_pixel.y as f32 - cy_d) * z / fy_d;
-                    depth_point.z = z;
-
-                    // Rotate the 3D point to the color world coordinate frame
-                    other_point = state.rotation * depth_point + state.translation;
-
-                    // Project the point into the image using camera intrinsics
-                    other_pixel.x = (other_point.x / other_point.z * fx_c) + cx_c;
-                    other_pixel.y = (other_point.y / other_point.z * fy_c) + cy_c;
+                    let other_pixel = Self::map_depth_pixel_to_color(
+                        state,
+                        [x_d as f32 + 0.5, y_d as f32 + 0.5],
+                        z,
+                    );
 
                     // DO naive interpolation
-                    let other_x1 = (other_pixel.x + 0.5).round() as usize;
-                    let other_y1 = (other_pixel.y + 0.5).round() as usize;
+                    let other_x1 = (other_pixel[0] + 0.5).round() as usize;
+                    let other_y1 = (other_pixel[1] + 0.5).round() as usize;
 
                     // Check if points are within the frame
                     if other_x1 >= height || other_y1 >= width {
@@ -153,7 +134,7 @@ impl FrameAligner {
                     // Write depth data into rectangle made by top-left and bottom-right corners
                     for i in other_x0..other_x1 {
                         for j in other_y0..other_y1 {
-                            data[i * width + j] = (z * state.depth_factor) as u16;
+                            data[i * width + j] = rgbd::metres_to_raw(z, depth_scale);
                         }
                     }
                 }
@@ -161,6 +142,30 @@ impl FrameAligner {
         }
     }
 
+    /// Deproject a depth pixel into 3D, transform it into the color coordinate frame and project
+    /// it onto the color image.
+    /// # Arguments
+    /// * `state` - The internal state of the framealigner.
+    /// * `depth_pixel` - The pixel in the depth image.
+    /// * `z` - The depth of the pixel, in metres.
+    /// # Returns
+    /// The corresponding pixel in the color image.
+    #[inline]
+    fn map_depth_pixel_to_color(
+        state: &FrameAlignerState,
+        depth_pixel: [f32; 2],
+        z: f32,
+    ) -> [f32; 2] {
+        // Get the 3D point in the depth world coordinate frame
+        let depth_point = state.intrinsics_depth.deproject(depth_pixel, z);
+
+        // Transform the 3D point to the color world coordinate frame
+        let other_point = state.extrinsics.transform_point(depth_point);
+
+        // Project the point into the image using camera intrinsics
+        state.intrinsics_color.project(other_point)
+    }
+
     /// Function that gets calibration matrices from a calib file
     /// # Arguments
     /// * `path_to_calib_file` - a path to the calib file.
@@ -201,11 +206,19 @@ impl FrameAligner {
         let cx_d = *map.get("cx_d").ok_or_else(|| error.clone())?;
         let cy_d = *map.get("cy_d").ok_or_else(|| error.clone())?;
 
-        // Build extrinsic and intrinsics matrices
-        state.rotation = Matrix3::new(r11, r12, r13, r21, r22, r23, r31, r32, r33);
-        state.translation = Matrix3x1::new(t1, t2, t3);
-        state.k_color = Matrix3::new(fx_c, 0.0, cx_c, 0.0, fy_c, cy_c, 0.0, 0.0, 1.0);
-        state.k_depth = Matrix3::new(fx_d, 0.0, cx_d, 0.0, fy_d, cy_d, 0.0, 0.0, 1.0);
+        // Build extrinsics and make sure that they describe a rigid body transformation
+        let extrinsics = Transformation::new(
+            Translation::new(t1, t2, t3),
+            RotationMatrix::new(r11, r12, r13, r21, r22, r23, r31, r32, r33),
+        );
+        extrinsics
+            .validate(DEFAULT_ROTATION_TOLERANCE)
+            .map_err(|e| GstFrameAlignerError::new(format!("Invalid extrinsics: {}", e)))?;
+        state.extrinsics = extrinsics;
+
+        // Build intrinsics
+        state.intrinsics_color = Intrinsics::new(fx_c, fy_c, cx_c, cy_c, Distortion::None);
+        state.intrinsics_depth = Intrinsics::new(fx_d, fy_d, cx_d, cy_d, Distortion::None);
         Ok(())
     }
 }
@@ -300,13 +313,13 @@ impl ObjectSubclass for FrameAligner {
     fn new() -> Self {
         Self {
             state: Mutex::new(FrameAlignerState {
-                depth_factor: DEFAULT_DEPTH_FACTOR,
+                depth_factor: None,
+                depth_scale: None,
                 video_info: None,
                 calib_file: "calib/rs728312070140.yaml".to_string(),
-                rotation: Matrix3::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0),
-                k_color: Matrix3::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0),
-                k_depth: Matrix3::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0),
-                translation: Matrix3x1::new(0.0, 0.0, 0.0),
+                extrinsics: Transformation::default(),
+                intrinsics_color: Intrinsics::new(0.0, 0.0, 0.0, 0.0, Distortion::None),
+                intrinsics_depth: Intrinsics::new(0.0, 0.0, 0.0, 0.0, Distortion::None),
             }),
         }
     }
@@ -320,8 +333,10 @@ impl ObjectImpl for FrameAligner {
                 glib::ParamSpecFloat::new(
                     "depth-factor",
                     "depth-factor",
-                    "The `depth_factor` to apply to the depth maps",
-                    1.0,
+                    "The `depth_factor` to apply to the depth maps, or 0 to use the depth scale of \
+                    CameraMeta contained in the stream. If the stream contains no CameraMeta, \
+                    depth is assumed to be in millimetres, i.e. a `depth_factor` of 1000",
+                    0.0,
                     30000.0,
                     DEFAULT_DEPTH_FACTOR,
                     glib::ParamFlags::READWRITE,
@@ -367,7 +382,11 @@ impl ObjectImpl for FrameAligner {
                     "Changing property `depth_factor` to {}",
                     depth_factor
                 );
-                state.depth_factor = depth_factor;
+                state.depth_factor = if depth_factor > 0.0 {
+                    Some(depth_factor)
+                } else {
+                    None
+                };
             }
             "calib-file" => {
                 let calib_file = value
@@ -396,7 +415,10 @@ impl ObjectImpl for FrameAligner {
     fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
         let state = &self.state.lock().unwrap();
         match pspec.name() {
-            "depth-factor" => state.depth_factor.to_value(),
+            "depth-factor" => state
+                .depth_factor
+                .unwrap_or(DEFAULT_DEPTH_FACTOR)
+                .to_value(),
             "calib-file" => state.calib_file.to_value(),
             _ => unimplemented!("Property is not implemented"),
         }
@@ -419,11 +441,9 @@ impl BaseTransformImpl for FrameAligner {
         // Get sink and src caps
         let sink_caps = sink_caps.structure(0).expect("No CAPS yet on framealigner");
 
-        // Create video info from sink caps
-        let depth_video_info = rgbd::get_video_info(sink_caps, "depth")
-            .map_err(|e| gst::loggable_error!(CAT, "{}", e))?;
-        let color_video_info = rgbd::get_video_info(sink_caps, "depth")
-            .map_err(|e| gst::loggable_error!(CAT, "{}", e))?;
+        // Create info about all streams from sink caps
+        let frame_info = rgbd::RgbdFrameInfo::from_structure(sink_caps)?;
+        let depth_video_info = frame_info.video_info(rgbd::DEPTH_STREAM_NAME)?;
         // Lock the state
         let state = &mut *self
             .state
@@ -434,10 +454,10 @@ impl BaseTransformImpl for FrameAligner {
         let sink_blocksize = depth_video_info.size();
         let src_blocksize = depth_video_info.size();
 
-        // Put these into the element's state
+        // Put these into the element's state and read the depth scale again from the new stream
+        state.depth_scale = None;
         state.video_info = Some(CapsVideoInfo::new(
-            depth_video_info,
-            color_video_info,
+            frame_info,
             sink_blocksize as usize,
             src_blocksize as usize,
         ));
@@ -482,30 +502,60 @@ impl BaseTransformImpl for FrameAligner {
         buffer_ref: &mut gst::BufferRef,
     ) -> Result<gst::FlowSuccess, gst::FlowError> {
         // Lock the state
-        let state = self
+        let state = &mut *self
             .state
             .lock()
             .expect("Failed to lock state in framealigner");
 
         {
-            let video_info = &state.video_info.as_ref().unwrap().depth_video_info;
+            let frame_info = &state.video_info.as_ref().unwrap().frame_info;
+            // Find the depth stream within the input buffer and map it writable
+            let mut frame = rgbd::RgbdFrameMut::from_buffer_ref(buffer_ref, frame_info)?;
+
+            // Determine the depth scale, either from the property or from the CameraMeta
+            let depth_scale = match state.depth_factor.map(|factor| 1.0 / factor) {
+                Some(depth_scale) => depth_scale,
+                None => match state.depth_scale {
+                    Some(depth_scale) => depth_scale,
+                    None => {
+                        let depth_scale = match frame.camera_meta() {
+                            Ok(camera_meta) => {
+                                gst_info!(
+                                    CAT,
+                                    "Using depth scale {} from CameraMeta",
+                                    camera_meta.depth_scale
+                                );
+                                camera_meta.depth_scale
+                            }
+                            Err(e) => {
+                                gst_warning!(
+                                    CAT,
+                                    "Depth scale is unknown, set `depth-factor` or attach \
+                                    CameraMeta to the stream. Falling back to depth scale {}: {}",
+                                    FALLBACK_DEPTH_SCALE,
+                                    e
+                                );
+                                FALLBACK_DEPTH_SCALE
+                            }
+                        };
+                        state.depth_scale = Some(depth_scale);
+                        depth_scale
+                    }
+                },
+            };
+
+            let mut depth_frame = frame.depth_mut()?;
             // Read depth information from the input buffer into the frame
-            let frame_height = video_info.height() as usize;
-            let frame_width = video_info.width() as usize;
-            // Create depth image frame from the input buffer together with corresponding data byte array
-            let mut input_frame =
-                gst_video::VideoFrameRef::from_buffer_ref_writable(buffer_ref, video_info)
-                    .map_err(|_| {
-                        GstFrameAlignerError::new("Failed to map input buffer readable")
-                    })?;
-            // Plane data
-            let plane_data = input_frame
-                .plane_data_mut(0)
-                .expect("Could not plane input data");
-            // Create depth frame from data
-            let depth_frame = rgbd::to_depth_buffer_mut(plane_data)?;
+            let frame_height = depth_frame.height();
+            let frame_width = depth_frame.width();
             // Write new depth data into buffer
-            self.get_new_depth_image(&state, depth_frame, frame_width, frame_height)
+            self.get_new_depth_image(
+                state,
+                depth_scale,
+                depth_frame.data_mut(),
+                frame_width,
+                frame_height,
+            )
         }
 
         Ok(gst::FlowSuccess::Ok)
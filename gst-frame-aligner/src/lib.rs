@@ -21,7 +21,6 @@ extern crate gst;
 extern crate gst_base;
 extern crate gst_depth_meta;
 extern crate gst_video;
-extern crate nalgebra as na;
 
 mod common;
 mod framealigner;
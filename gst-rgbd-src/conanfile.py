@@ -10,9 +10,11 @@ class GstRgbdSrc(GstRustProject):
         # "libk4a/[^1.4.1]",
         "librealsense/[^2.39.0]",
         "rust-libstd/[^1.0.0]",
-        f"gst-depth-meta/{branch()}",
     )
 
+    def requirements(self):
+        self.requires(f"gst-plugins-base/[~{self.settings.gstreamer}]")
+
     def build(self):
         # We need to rebuild the rust bindings every time we tag a new release.
         self.cargo(clean=[
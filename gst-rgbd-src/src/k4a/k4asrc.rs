@@ -13,7 +13,7 @@ use gst::subclass::prelude::*;
 use gst_base::prelude::*;
 use gst_base::subclass::base_src::CreateSuccess;
 use gst_base::subclass::prelude::*;
-use gst_depth_meta::{camera_meta, camera_meta::*, rgbd};
+use gst_depth_meta::{camera_meta, camera_meta::*, imu, rgbd};
 use libk4a::calibration::Calibration;
 use libk4a::camera_calibration::CameraCalibration;
 use libk4a::capture::Capture;
@@ -42,6 +42,9 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new("k4asrc", gst::DebugColorFlags::empty(), Some("K4A Source"))
 });
 
+/// Model name of K4A devices, used for identification in CameraMeta.
+const K4A_DEVICE_MODEL: &str = "Azure Kinect";
+
 /// Internals of the element that are under a mutex.
 struct K4aSrcInternals {
     /// Contains information about the utilised K4A source.
@@ -218,6 +221,8 @@ impl BaseSrcImpl for K4aSrc {
             // Add IMU stream, if enabled
             if desired_streams.imu {
                 selected_streams.push(STREAM_ID_IMU.to_string());
+                caps.set(&format!("{}_format", STREAM_ID_IMU), imu::IMU_MEDIA_TYPE);
+                caps.set(&format!("{}_schema", STREAM_ID_IMU), imu::IMU_SCHEMA);
                 caps.fixate_field_nearest_fraction("imu_sampling_rate", IMU_SAMPLING_RATE_HZ);
             }
 
@@ -389,6 +394,14 @@ impl K4aSrc {
           ));
         }
 
+        // IMU samples of a recording are not yet synchronised with its captures
+        if settings.desired_streams.imu {
+            return Err(K4aSrcError::Failure(
+                "k4asrc: IMU streaming from Playback is not yet supported. \
+                Please set the property `enable-imu` to false or stream from a device.",
+            ));
+        }
+
         // Make sure that Playback contains color stream if depth rectification is enabled
         if settings.rectify_depth && !available_streams.color {
             return Err(K4aSrcError::Failure(
@@ -397,10 +410,11 @@ impl K4aSrc {
           ));
         }
 
-        // Get Calibration from the Playback
+        // Get Calibration and identity of the recording device from the Playback
         let calibration = playback.get_calibration()?;
+        let device_info = Self::extract_device_info_from_playback(&playback);
         // Setup camera internals based on the extracted Calibration
-        Self::setup_camera_internals(&mut internals.camera, settings, calibration)?;
+        Self::setup_camera_internals(&mut internals.camera, settings, calibration, device_info)?;
 
         let stream_source = StreamSource::Playback(playback, record_configuration);
         let properties = Self::get_stream_properties(&stream_source).unwrap();
@@ -462,8 +476,9 @@ impl K4aSrc {
             device_configuration.depth_mode,
             device_configuration.color_resolution,
         )?;
+        let device_info = Self::extract_device_info_from_device(&device);
         // Setup camera internals based on the extracted Calibration
-        Self::setup_camera_internals(&mut internals.camera, settings, calibration)?;
+        Self::setup_camera_internals(&mut internals.camera, settings, calibration, device_info)?;
 
         let stream_source = StreamSource::Device(device, device_configuration);
         let properties = Self::get_stream_properties(&stream_source).unwrap();
@@ -484,6 +499,7 @@ impl K4aSrc {
     /// * `internals` - The internals of the element that contain timestamp internals.
     /// * `settings` - The settings of the element.
     /// * `calibration` - K4A Calibration of the utilised Device or Playback.
+    /// * `device_info` - Identity of the utilised Device, or of the Device used for the recording.
     ///
     /// # Returns
     /// * `Ok()` on success.
@@ -492,6 +508,7 @@ impl K4aSrc {
         camera: &mut CameraInternals,
         settings: &Settings,
         calibration: Calibration,
+        device_info: camera_meta::DeviceInfo,
     ) -> Result<(), K4aSrcError> {
         // Get Transformation if rectification is enabled
         if settings.rectify_depth {
@@ -501,6 +518,7 @@ impl K4aSrc {
         // Serialise the CameraMeta associated with Calibration, if attaching camera meta is desired.
         if settings.attach_camera_meta {
             camera.camera_meta_serialised = Self::extract_camera_meta(settings, &calibration)
+                .with_device(device_info)
                 .serialise()
                 .map_err(|_err| K4aSrcError::Failure("k4asrc: Cannot serialise camera meta"))?
         }
@@ -571,14 +589,15 @@ impl K4aSrc {
         })
     }
 
-    /// Extract all available ImuSamples from either Playback or Device. Unimplemented!
+    /// Extract all available ImuSamples from Device. IMU streaming from Playback is not yet
+    /// supported, which is why `enable-imu` is rejected when streaming from Playback.
     ///
     /// # Arguments
     /// * `internals` - The internals of the element that contain stream source.
     ///
     /// # Returns
     /// * `Ok(Vec<ImuSample>)` on success.
-    /// * `Err(K4aSrcError)` on failure.
+    /// * `Err(K4aSrcError)` on failure, or if streaming from Playback.
     fn get_available_imu_samples(
         internals: &K4aSrcInternals,
     ) -> Result<Vec<ImuSample>, K4aSrcError> {
@@ -595,7 +614,9 @@ impl K4aSrc {
         match stream_source {
             StreamSource::Playback(_playback, _record_configuration) => {
                 // TODO: implement streaming of ImuSamples from recording (synchronisation with capture must be resolved)
-                unimplemented!("k4asrc: IMU streaming from Playback is not yet implemented!");
+                return Err(K4aSrcError::Failure(
+                    "k4asrc: IMU streaming from Playback is not yet supported",
+                ));
             }
             StreamSource::Device(device, _device_configuration) => {
                 // If streaming from a physical device, get samples from Device
@@ -712,7 +733,7 @@ impl K4aSrc {
 
     /// Attach ImuSamples to `output_buffer`. This function outputs the frame as main buffer if
     /// `previous_streams` is empty or all `false`. If any of the `previous_streams` is enabled,
-    /// the frame is attached as meta buffer. Unimplemented!
+    /// the frame is attached as meta buffer.
     ///
     /// # Arguments
     /// * `push_src` - This element (k4asrc).
@@ -778,21 +799,41 @@ impl K4aSrc {
         Ok(())
     }
 
-    /// Convert/serialise ImuSamples into GStreamer Buffer. Unimplemented!
+    /// Serialise ImuSamples into GStreamer Buffer by the use of Cap'n Proto, see
+    /// `gst_depth_meta::imu::ImuSamples` for the matching deserialisation.
     ///
     /// # Arguments
-    /// * `imu_samples` - ImuSamples to attach to the `output_buffer`.
+    /// * `imu_samples` - ImuSamples to serialise.
     ///
     /// # Returns
     /// * `Ok(gst::Buffer)` on success.
     /// * `Err(K4aSrcError)` on failure.
     fn gst_buffer_from_imu_samples(
-        _imu_samples: Vec<ImuSample>,
+        imu_samples: Vec<ImuSample>,
     ) -> Result<gst::Buffer, K4aSrcError> {
-        // TODO: implement mapping of `Vec<ImuSample>` to `gst::Buffer`
-        unimplemented!(
-            "k4asrc: Mapping of `Vec<ImuSample>` to `gst::Buffer` is not yet implemented!"
+        let imu_samples = imu::ImuSamples::new(
+            imu_samples
+                .iter()
+                .map(|imu_sample| {
+                    let acc = imu_sample.get_acc();
+                    let gyro = imu_sample.get_gyro();
+                    imu::ImuSample {
+                        acc: imu::ImuVector::new(acc.x, acc.y, acc.z),
+                        acc_timestamp_usec: imu_sample.get_acc_timestamp(),
+                        gyro: imu::ImuVector::new(gyro.x, gyro.y, gyro.z),
+                        gyro_timestamp_usec: imu_sample.get_gyro_timestamp(),
+                        temperature: imu_sample.get_temperature(),
+                    }
+                })
+                .collect(),
         );
+
+        let serialised_imu_samples = imu_samples
+            .serialise()
+            .map_err(|_err| K4aSrcError::Failure("k4asrc: Cannot serialise IMU samples"))?;
+
+        // Form a gst buffer out of the serialised samples
+        Ok(gst::buffer::Buffer::from_mut_slice(serialised_imu_samples))
     }
 
     /// Attach Cap'n Proto serialised CameraMeta to `output_buffer`.
@@ -878,6 +919,63 @@ impl K4aSrc {
         CameraMeta::new(intrinsics, extrinsics, 0.001)
     }
 
+    /// Extract identity of a K4A `Device`. Entries that cannot be determined are left empty.
+    ///
+    /// # Arguments
+    /// * `device` - The opened Device.
+    ///
+    /// # Returns
+    /// * `camera_meta::DeviceInfo` containing the model, serial number and firmware version.
+    fn extract_device_info_from_device(device: &Device) -> camera_meta::DeviceInfo {
+        let firmware_version = device
+            .get_version()
+            .map(|version| {
+                format!(
+                    "color {}.{}.{}, depth {}.{}.{}",
+                    version.rgb.major,
+                    version.rgb.minor,
+                    version.rgb.iteration,
+                    version.depth.major,
+                    version.depth.minor,
+                    version.depth.iteration
+                )
+            })
+            .unwrap_or_default();
+
+        camera_meta::DeviceInfo::new(
+            K4A_DEVICE_MODEL.to_string(),
+            device.get_serial_number().unwrap_or_default(),
+            firmware_version,
+        )
+    }
+
+    /// Extract identity of the K4A `Device` that was used to create a recording, based on the tags
+    /// of the `Playback`. Entries that are not present in the recording are left empty.
+    ///
+    /// # Arguments
+    /// * `playback` - The opened Playback.
+    ///
+    /// # Returns
+    /// * `camera_meta::DeviceInfo` containing the model, serial number and firmware version.
+    fn extract_device_info_from_playback(playback: &Playback) -> camera_meta::DeviceInfo {
+        // Tag names are passed to C and must therefore be nul-terminated
+        let firmware_version = match (
+            playback.get_tag("K4A_COLOR_FIRMWARE_VERSION\0"),
+            playback.get_tag("K4A_DEPTH_FIRMWARE_VERSION\0"),
+        ) {
+            (Ok(color), Ok(depth)) => format!("color {}, depth {}", color, depth),
+            _ => String::new(),
+        };
+
+        camera_meta::DeviceInfo::new(
+            K4A_DEVICE_MODEL.to_string(),
+            playback
+                .get_tag("K4A_DEVICE_SERIAL_NUMBER\0")
+                .unwrap_or_default(),
+            firmware_version,
+        )
+    }
+
     /// Extract Intrinsics from K4A Calibration.
     ///
     /// # Arguments
@@ -896,19 +994,19 @@ impl K4aSrc {
         if desired_streams.depth {
             intrinsics.insert(
                 STREAM_ID_DEPTH.to_string(),
-                Self::k4a_intrinsics_to_camera_meta_intrinsics(&depth_calibration.intrinsics),
+                Self::k4a_intrinsics_to_camera_meta_intrinsics(depth_calibration),
             );
         }
         if desired_streams.ir {
             intrinsics.insert(
                 STREAM_ID_IR.to_string(),
-                Self::k4a_intrinsics_to_camera_meta_intrinsics(&depth_calibration.intrinsics),
+                Self::k4a_intrinsics_to_camera_meta_intrinsics(depth_calibration),
             );
         }
         if desired_streams.color {
             intrinsics.insert(
                 STREAM_ID_COLOR.to_string(),
-                Self::k4a_intrinsics_to_camera_meta_intrinsics(&color_calibration.intrinsics),
+                Self::k4a_intrinsics_to_camera_meta_intrinsics(color_calibration),
             );
         }
         intrinsics
@@ -984,17 +1082,19 @@ impl K4aSrc {
         extrinsics
     }
 
-    /// Convert K4A Intrinsics into CameraMeta Intrinsics.
+    /// Convert K4A Intrinsics of a camera into CameraMeta Intrinsics, including the resolution for
+    /// which they are valid.
     ///
     /// # Arguments
-    /// * `k4a_intrinsics` - K4a intrinsics to convert.
+    /// * `camera_calibration` - K4a camera calibration containing the intrinsics to convert.
     ///
     /// # Returns
     /// * `camera_meta::Intrinsics` containing the converted intrinsics.
     fn k4a_intrinsics_to_camera_meta_intrinsics(
-        k4a_intrinsics: &libk4a::intrinsics::Intrinsics,
+        camera_calibration: &CameraCalibration,
     ) -> camera_meta::Intrinsics {
         use libk4a::CalibrationModelType::*;
+        let k4a_intrinsics = &camera_calibration.intrinsics;
         let c = &k4a_intrinsics.parameters;
         let distortion = match k4a_intrinsics.type_ {
             K4A_CALIBRATION_LENS_DISTORTION_MODEL_BROWN_CONRADY => Distortion::K4aBrownConrady(
@@ -1018,6 +1118,8 @@ impl K4aSrc {
             cx: c.cx,
             cy: c.cy,
             distortion,
+            width: camera_calibration.resolution_width as u32,
+            height: camera_calibration.resolution_height as u32,
         }
     }
 
@@ -1207,7 +1309,7 @@ impl ObjectImpl for K4aSrc {
                 glib::ParamSpecBoolean::new(
                     "enable-imu",
                     "Enable IMU",
-                    "Enables IMU stream.",
+                    "Enables IMU stream. Not yet supported when streaming from Playback.",
                     DEFAULT_ENABLE_IMU,
                     glib::ParamFlags::READWRITE,
                 ),
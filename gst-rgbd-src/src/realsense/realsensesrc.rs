@@ -1011,8 +1011,9 @@ impl RealsenseSrc {
         desired_streams: &EnabledStreams,
         pipeline_profile: &rs2::PipelineProfile,
     ) -> Result<CameraMeta, rs2::Error> {
-        // Get the sensors and active stream profiles from the pipeline profile
-        let sensors = pipeline_profile.get_device().unwrap().query_sensors()?;
+        // Get the device, its sensors and active stream profiles from the pipeline profile
+        let device = pipeline_profile.get_device().unwrap();
+        let sensors = device.query_sensors()?;
         let stream_profiles = pipeline_profile.get_streams()?;
 
         // Create intrinsics and insert the appropriate streams
@@ -1022,11 +1023,34 @@ impl RealsenseSrc {
         let extrinsics = Self::extract_extrinsics(desired_streams, &stream_profiles)?;
 
         // Create camera meta from the intrinsics, extrinsics and depth scale
-        Ok(CameraMeta::new(
-            intrinsics,
-            extrinsics,
-            Self::get_depth_scale(sensors),
-        ))
+        Ok(
+            CameraMeta::new(intrinsics, extrinsics, Self::get_depth_scale(sensors))
+                .with_device(Self::extract_device_info(&device)),
+        )
+    }
+
+    /// Extract identity of the RealSense device. Entries that the device does not support, e.g.
+    /// firmware version during playback, are left empty.
+    ///
+    /// # Arguments
+    /// * `device` - Device to extract the identity from.
+    ///
+    /// # Returns
+    /// * `camera_meta::DeviceInfo` containing the model, serial number and firmware version.
+    fn extract_device_info(device: &rs2::Device) -> camera_meta::DeviceInfo {
+        use rs2::rs2_camera_info as rs2_info;
+
+        camera_meta::DeviceInfo::new(
+            device
+                .get_info(rs2_info::RS2_CAMERA_INFO_NAME)
+                .unwrap_or_default(),
+            device
+                .get_info(rs2_info::RS2_CAMERA_INFO_SERIAL_NUMBER)
+                .unwrap_or_default(),
+            device
+                .get_info(rs2_info::RS2_CAMERA_INFO_FIRMWARE_VERSION)
+                .unwrap_or_default(),
+        )
     }
 
     /// Extract Intrinsics from the active RealSense stream profiles, while taking into account what streams are enabled.
@@ -1105,6 +1129,8 @@ impl RealsenseSrc {
             cx: rs2_intrinsics.ppx,
             cy: rs2_intrinsics.ppy,
             distortion,
+            width: rs2_intrinsics.width as u32,
+            height: rs2_intrinsics.height as u32,
         }
     }
 
@@ -5,7 +5,7 @@ class Rgbd(GstRustProject):
     description = "GStreamer plugin for demuxing and muxing `video/rgbd` streams"
     license = "MIT"
     build_requires = ("rust/[^1.0.0]",)
-    requires = (
-        f"gst-depth-meta/{branch()}",
-        "capnproto/[^0.8.0]",
-    )
\ No newline at end of file
+    requires = ("capnproto/[^0.8.0]",)
+
+    def requirements(self):
+        self.requires(f"gst-plugins-base/[~{self.settings.gstreamer}]")
\ No newline at end of file
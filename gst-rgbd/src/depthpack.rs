@@ -0,0 +1,218 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use once_cell::sync::Lazy;
+
+use crate::depthpacking::*;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "depthpack",
+        gst::DebugColorFlags::empty(),
+        Some("Depth Packer"),
+    )
+});
+
+/// Direction of `depthpack`, which packs `GRAY16_LE` depth.
+pub enum Pack {}
+
+impl PackingDirection for Pack {
+    fn src_caps(
+        src_pad: &gst::Pad,
+        sink_info: &gst_video::VideoInfo,
+    ) -> Result<gst::Caps, gst::ErrorMessage> {
+        // Downstream decides between the formats of packed depth
+        let src_caps = gst::Caps::builder("video/x-raw")
+            .field("format", &packed_formats())
+            .field("width", &(sink_info.width() as i32))
+            .field("height", &(sink_info.height() as i32))
+            .field("framerate", &sink_info.fps())
+            .build();
+        let mut src_caps = src_pad.peer_query_caps(Some(&src_caps));
+        if src_caps.is_empty() {
+            return Err(gst::error_msg!(
+                gst::CoreError::Negotiation,
+                ["Downstream does not accept packed depth"]
+            ));
+        }
+        src_caps.fixate();
+        Ok(src_caps)
+    }
+
+    fn convert(
+        packing: &DepthPacking,
+        in_frame: &gst_video::VideoFrameRef<&gst::BufferRef>,
+        out_frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    ) -> Result<(), gst::FlowError> {
+        let (width, height) = (in_frame.width() as usize, in_frame.height() as usize);
+        let in_stride = in_frame.plane_stride()[0] as usize;
+        let in_data = in_frame.plane_data(0).map_err(|_| gst::FlowError::Error)?;
+        let packed: Vec<[u8; 3]> = (0..height)
+            .flat_map(|y| {
+                in_data[y * in_stride..y * in_stride + width * 2]
+                    .chunks_exact(2)
+                    .map(|value| packing.pack(u16::from_le_bytes([value[0], value[1]])))
+            })
+            .collect();
+
+        let format_info = out_frame.info().format_info();
+        for channel in 0..3 {
+            let stride = out_frame.plane_stride()[channel] as usize;
+            let (w_sub, h_sub) = (format_info.w_sub()[channel], format_info.h_sub()[channel]);
+            let plane = out_frame
+                .plane_data_mut(channel as u32)
+                .map_err(|_| gst::FlowError::Error)?;
+            // Subsampled channels contain the average of the covered pixels
+            for y in 0..(height + (1 << h_sub) - 1) >> h_sub {
+                for x in 0..(width + (1 << w_sub) - 1) >> w_sub {
+                    let (mut sum, mut count) = (0u32, 0u32);
+                    for py in (y << h_sub)..((y + 1) << h_sub).min(height) {
+                        for px in (x << w_sub)..((x + 1) << w_sub).min(width) {
+                            sum += packed[py * width + px][channel] as u32;
+                            count += 1;
+                        }
+                    }
+                    plane[y * stride + x] = ((sum + count / 2) / count) as u8;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A struct representation of the `depthpack` element.
+pub struct DepthPack {
+    /// Pads, properties and state shared with `depthunpack`.
+    depth_packing: DepthPackingElement<Pack>,
+}
+
+glib::wrapper! {
+    pub struct DepthPackObject(ObjectSubclass<DepthPack>)
+        @extends gst::Element, gst::Object;
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DepthPack {
+    const NAME: &'static str = "depthpack";
+    type Type = DepthPackObject;
+    type ParentType = gst::Element;
+    fn with_class(klass: &Self::Class) -> Self {
+        Self {
+            depth_packing: DepthPackingElement::new::<Self>(
+                *CAT,
+                &klass.pad_template("sink").unwrap(),
+                &klass.pad_template("src").unwrap(),
+            ),
+        }
+    }
+}
+
+impl DepthPackingImpl for DepthPack {
+    type Direction = Pack;
+
+    fn depth_packing(&self) -> &DepthPackingElement<Pack> {
+        &self.depth_packing
+    }
+}
+
+impl ElementImpl for DepthPack {
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        #[allow(clippy::single_match)]
+        match transition {
+            gst::StateChange::PausedToReady => {
+                self.depth_packing.reset();
+            }
+            _ => {}
+        }
+
+        // Chain up parent implementation
+        self.parent_change_state(element, transition)
+    }
+
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Depth Packer",
+                "Filter/Converter/Video/RGB-D",
+                "Packs 16-bit depth into three 8-bit channels that survive lossy video codecs",
+                "Raphael Duerscheid <rd@aivero.com>, Andrej Orsula <andrej.orsula@aivero.com>,
+                 Tobias Morell <tobias.morell@aivero.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 2]> = Lazy::new(|| {
+            [
+                gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder("video/x-raw")
+                        .field("format", &packed_formats())
+                        .field("width", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .field("height", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .build(),
+                )
+                .expect("depthpack: Failed to add 'src' pad template"),
+                gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder("video/x-raw")
+                        .field("format", &"GRAY16_LE")
+                        .field("width", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .field("height", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .build(),
+                )
+                .expect("depthpack: Failed to add 'sink' pad template"),
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl GstObjectImpl for DepthPack {}
+impl ObjectImpl for DepthPack {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<[glib::ParamSpec; 4]> = Lazy::new(Settings::properties);
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, element: &Self::Type) {
+        self.parent_constructed(element);
+
+        self.depth_packing.add_pads(element.upcast_ref());
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        self.depth_packing.set_property(value, pspec);
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        self.depth_packing.property(pspec)
+    }
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "depthpack",
+        gst::Rank::None,
+        DepthPack::type_(),
+    )
+}
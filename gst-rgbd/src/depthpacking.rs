@@ -0,0 +1,669 @@
+//! Packing of 16-bit depth into three 8-bit channels that survive lossy video codecs, which is
+//! shared by `depthpack` and `depthunpack`.
+//!
+//! Depth within the configured range is normalised into `[0, 1]` and coded with the triangle-wave
+//! scheme of F. Pece, J. Kautz and T. Weyrich, "Adapting Standard Video Codecs for Depth
+//! Streaming", EGVE 2011:
+//! * The first channel `L` contains the normalised depth itself, which is coarse after
+//!   quantisation and compression. Values below a margin signal invalid depth.
+//! * The second and third channels `Ha` and `Hb` contain two triangle waves of the normalised
+//!   depth, which are shifted by a quarter period against each other. Together they determine the
+//!   phase within the period, i.e. the fine depth.
+//!
+//! Unlike the original scheme, the phase is estimated from both triangle waves, and `L` only
+//! selects the period that is closest to it. Errors of `L` up to half a period are therefore
+//! corrected entirely.
+//!
+//! Both elements share their properties, event handling and the skeleton of their chain function
+//! in [DepthPackingElement](struct.DepthPackingElement.html), they only implement the direction
+//! of the packing with [PackingDirection](trait.PackingDirection.html).
+use glib::*;
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_depth_meta::rgbd;
+use std::marker::PhantomData;
+use std::sync::{Mutex, RwLock};
+
+use crate::common::*;
+
+/// Default value for `min-depth` property
+const DEFAULT_MIN_DEPTH: f32 = 0.0;
+/// Default value for `max-depth` property
+const DEFAULT_MAX_DEPTH: f32 = 10.0;
+/// Default value for `depth-scale` property
+const DEFAULT_DEPTH_SCALE: f32 = 0.001;
+/// Default value for `periods` property
+const DEFAULT_PERIODS: u32 = 8;
+/// Maximum value of `periods` property
+const MAX_PERIODS: u32 = 64;
+/// Fraction of the first channel that is reserved for invalid depth.
+const INVALID_MARGIN: f32 = 1.0 / 16.0;
+/// Channel values of invalid depth, which is black in YUV.
+const INVALID_PIXEL: [u8; 3] = [0, 128, 128];
+
+/// Formats of packed depth, which contain `L`, `Ha` and `Hb` in their Y, U and V planes.
+pub fn packed_formats() -> gst::List {
+    gst::List::new([&"Y444", &"I420"])
+}
+
+/// Value of a triangle wave with period 2 and amplitude 1 at `x`.
+#[inline]
+fn triangle(x: f32) -> f32 {
+    let x = x.rem_euclid(2.0);
+    if x <= 1.0 {
+        x
+    } else {
+        2.0 - x
+    }
+}
+
+/// Convert a value in `[0, 1]` into a channel value.
+#[inline]
+fn to_channel(value: f32) -> u8 {
+    (value * 255.0 + 0.5) as u8
+}
+
+/// Conversion of 16-bit depth from and into three 8-bit channels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthPacking {
+    /// Minimum raw depth of the range.
+    min_raw: f32,
+    /// Size of the range in raw depth.
+    range_raw: f32,
+    /// Number of triangle-wave periods over the range.
+    periods: f32,
+}
+
+impl DepthPacking {
+    /// Create a new DepthPacking.
+    /// # Arguments
+    /// * `min_depth` - Minimum depth in metres, smaller depth is clamped to it.
+    /// * `max_depth` - Maximum depth in metres, larger depth is clamped to it.
+    /// * `depth_scale` - Scaling factor of the depth map, in metres, see `CameraMeta::depth_scale`.
+    /// * `periods` - Number of triangle-wave periods over the range. More periods increase the
+    /// precision, but decrease the robustness against compression artefacts.
+    /// # Panics
+    /// * If `max_depth` is not larger than `min_depth` in raw depth, or if `periods` is 0.
+    pub fn new(min_depth: f32, max_depth: f32, depth_scale: f32, periods: u32) -> Self {
+        let min_raw = gst_depth_meta::rgbd::metres_to_raw(min_depth, depth_scale) as f32;
+        let max_raw = gst_depth_meta::rgbd::metres_to_raw(max_depth, depth_scale) as f32;
+        assert!(max_raw > min_raw, "Depth range must not be empty");
+        assert!(periods > 0, "Number of periods must be positive");
+        Self {
+            min_raw,
+            range_raw: max_raw - min_raw,
+            periods: periods as f32,
+        }
+    }
+
+    /// Pack a raw depth value into three channels.
+    /// # Arguments
+    /// * `raw` - The raw depth value, where 0 indicates invalid depth.
+    /// # Returns
+    /// * The channels `L`, `Ha` and `Hb`.
+    pub fn pack(&self, raw: u16) -> [u8; 3] {
+        if raw == 0 {
+            return INVALID_PIXEL;
+        }
+        let depth = ((raw as f32 - self.min_raw) / self.range_raw).clamp(0.0, 1.0);
+        let phase = 2.0 * depth * self.periods;
+        [
+            to_channel(INVALID_MARGIN + depth * (1.0 - INVALID_MARGIN)),
+            to_channel(triangle(phase)),
+            to_channel(triangle(phase - 0.5)),
+        ]
+    }
+
+    /// Unpack a raw depth value from three channels, which might contain compression artefacts.
+    /// # Arguments
+    /// * `channels` - The channels `L`, `Ha` and `Hb`.
+    /// # Returns
+    /// * The raw depth value, where 0 indicates invalid depth.
+    pub fn unpack(&self, channels: [u8; 3]) -> u16 {
+        let [l, ha, hb] = [
+            channels[0] as f32 / 255.0,
+            channels[1] as f32 / 255.0,
+            channels[2] as f32 / 255.0,
+        ];
+        if l < INVALID_MARGIN / 2.0 {
+            return 0;
+        }
+
+        // Phase within the period in [0, 2), estimated from the quarter of the period that is
+        // determined by both triangle waves
+        let phase = match (ha >= 0.5, hb >= 0.5) {
+            (false, false) => (ha + 0.5 - hb) / 2.0,
+            (true, false) => (ha + hb + 0.5) / 2.0,
+            (true, true) => (2.0 - ha + hb + 0.5) / 2.0,
+            (false, true) => (2.0 - ha + 2.5 - hb) / 2.0,
+        };
+        let fine = phase / 2.0;
+
+        // The coarse depth selects the closest period
+        let coarse = (l - INVALID_MARGIN) / (1.0 - INVALID_MARGIN) * self.periods;
+        let period = (coarse - fine).round();
+        let depth = ((period + fine) / self.periods).clamp(0.0, 1.0);
+
+        (self.min_raw + depth * self.range_raw)
+            .round()
+            .clamp(1.0, u16::MAX as f32) as u16
+    }
+
+    /// Get the theoretical precision of the packing, i.e. the change of raw depth per step of
+    /// the channels `Ha` and `Hb`.
+    pub fn precision(&self) -> f32 {
+        self.range_raw / self.periods / 2.0 / 255.0
+    }
+}
+
+/// A struct containing properties of `depthpack` and `depthunpack` elements, which must be equal
+/// on both ends.
+pub struct Settings {
+    /// Analogous to `min-depth` property
+    min_depth: f32,
+    /// Analogous to `max-depth` property
+    max_depth: f32,
+    /// Analogous to `depth-scale` property
+    depth_scale: f32,
+    /// Analogous to `periods` property
+    periods: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            min_depth: DEFAULT_MIN_DEPTH,
+            max_depth: DEFAULT_MAX_DEPTH,
+            depth_scale: DEFAULT_DEPTH_SCALE,
+            periods: DEFAULT_PERIODS,
+        }
+    }
+}
+
+impl Settings {
+    /// Get the properties of `depthpack` and `depthunpack`.
+    pub fn properties() -> [glib::ParamSpec; 4] {
+        [
+            ParamSpecFloat::new(
+                "min-depth",
+                "Min Depth",
+                "Minimum depth in metres, smaller depth is clamped to it.",
+                0.0,
+                f32::MAX,
+                DEFAULT_MIN_DEPTH,
+                ParamFlags::READWRITE,
+            ),
+            ParamSpecFloat::new(
+                "max-depth",
+                "Max Depth",
+                "Maximum depth in metres, larger depth is clamped to it.",
+                0.0,
+                f32::MAX,
+                DEFAULT_MAX_DEPTH,
+                ParamFlags::READWRITE,
+            ),
+            ParamSpecFloat::new(
+                "depth-scale",
+                "Depth Scale",
+                "Scaling factor of the depth map in metres, which is used until the `depth_scale`
+                 of a CameraMeta is received.",
+                f32::MIN_POSITIVE,
+                f32::MAX,
+                DEFAULT_DEPTH_SCALE,
+                ParamFlags::READWRITE,
+            ),
+            ParamSpecUInt::new(
+                "periods",
+                "Periods",
+                "Number of triangle-wave periods over the depth range. More periods increase the
+                 precision, but decrease the robustness against compression artefacts.",
+                1,
+                MAX_PERIODS,
+                DEFAULT_PERIODS,
+                ParamFlags::READWRITE,
+            ),
+        ]
+    }
+
+    /// Set one of the properties.
+    /// # Arguments
+    /// * `cat` - Debug category of the element.
+    /// * `value` - The new value of the property.
+    /// * `pspec` - Specification of the property.
+    pub fn set_property(
+        &mut self,
+        cat: gst::DebugCategory,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        match pspec.name() {
+            prop @ "min-depth" => {
+                self.min_depth = get_property_and_debug(cat, value, prop, self.min_depth);
+            }
+            prop @ "max-depth" => {
+                self.max_depth = get_property_and_debug(cat, value, prop, self.max_depth);
+            }
+            prop @ "depth-scale" => {
+                self.depth_scale = get_property_and_debug(cat, value, prop, self.depth_scale);
+            }
+            prop @ "periods" => {
+                self.periods = get_property_and_debug(cat, value, prop, self.periods);
+            }
+            _ => unimplemented!("Property is not implemented"),
+        };
+    }
+
+    /// Get one of the properties.
+    /// # Arguments
+    /// * `pspec` - Specification of the property.
+    pub fn property(&self, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "min-depth" => self.min_depth.to_value(),
+            "max-depth" => self.max_depth.to_value(),
+            "depth-scale" => self.depth_scale.to_value(),
+            "periods" => self.periods.to_value(),
+            _ => unimplemented!("Property is not implemented"),
+        }
+    }
+
+    /// Create the packing based on the properties.
+    /// # Arguments
+    /// * `camera_depth_scale` - The `depth_scale` of the received CameraMeta, if any, which
+    /// takes precedence over `depth-scale` property.
+    /// # Returns
+    /// * `Err(gst::ErrorMessage)` - If the depth range is empty.
+    pub fn packing(
+        &self,
+        camera_depth_scale: Option<f32>,
+    ) -> Result<DepthPacking, gst::ErrorMessage> {
+        let depth_scale = camera_depth_scale.unwrap_or(self.depth_scale);
+        if gst_depth_meta::rgbd::metres_to_raw(self.max_depth, depth_scale)
+            <= gst_depth_meta::rgbd::metres_to_raw(self.min_depth, depth_scale)
+        {
+            return Err(gst::error_msg!(
+                gst::LibraryError::Settings,
+                [
+                    "Depth range of {} - {} metres is empty with depth scale {}",
+                    self.min_depth,
+                    self.max_depth,
+                    depth_scale
+                ]
+            ));
+        }
+        Ok(DepthPacking::new(
+            self.min_depth,
+            self.max_depth,
+            depth_scale,
+            self.periods,
+        ))
+    }
+}
+
+/// Parse the `depth_scale` of the serialised CameraMeta in a `camera_meta_tag`.
+/// # Arguments
+/// * `event` - A tag event.
+/// # Returns
+/// * `None` - If the event does not contain a valid CameraMeta.
+fn camera_depth_scale(event: &gst::event::Tag) -> Option<f32> {
+    let camera_meta = event
+        .tag()
+        .get::<gst_depth_meta::camera_meta::CameraMetaTag>()?;
+    serde_json::from_str::<gst_depth_meta::camera_meta::CameraMeta>(camera_meta.get())
+        .ok()
+        .map(|camera_meta| camera_meta.depth_scale)
+}
+
+/// The direction of the packing, which is all that differs between `depthpack` and
+/// `depthunpack`.
+pub trait PackingDirection: Send + Sync + 'static {
+    /// Get the fixed src caps for the given sink caps.
+    /// # Arguments
+    /// * `src_pad` - The src pad of the element, e.g. to query downstream.
+    /// * `sink_info` - Info of the negotiated sink caps.
+    /// # Returns
+    /// * `Err(gst::ErrorMessage)` - If no src caps can be negotiated.
+    fn src_caps(
+        src_pad: &gst::Pad,
+        sink_info: &gst_video::VideoInfo,
+    ) -> Result<gst::Caps, gst::ErrorMessage>;
+
+    /// Pack or unpack a frame.
+    /// # Arguments
+    /// * `packing` - The packing of depth.
+    /// * `in_frame` - The incoming frame of the sink caps.
+    /// * `out_frame` - The outgoing frame of the src caps, which is filled.
+    fn convert(
+        packing: &DepthPacking,
+        in_frame: &gst_video::VideoFrameRef<&gst::BufferRef>,
+        out_frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    ) -> Result<(), gst::FlowError>;
+}
+
+/// Implementation of `depthpack` or `depthunpack` that contains a `DepthPackingElement`.
+pub trait DepthPackingImpl: ElementImpl {
+    /// The direction of the packing.
+    type Direction: PackingDirection;
+
+    /// Get the shared part of the element.
+    fn depth_packing(&self) -> &DepthPackingElement<Self::Direction>;
+}
+
+/// Mutable state of `depthpack` and `depthunpack` elements
+#[derive(Default)]
+struct State {
+    /// Info of the negotiated sink caps.
+    sink_info: Option<gst_video::VideoInfo>,
+    /// Info of the negotiated src caps.
+    src_info: Option<gst_video::VideoInfo>,
+    /// The `depth_scale` of the received CameraMeta.
+    camera_depth_scale: Option<f32>,
+    /// The packing of depth, or `None` if it needs to be created from the properties.
+    packing: Option<DepthPacking>,
+}
+
+/// Pads, properties and state that are shared by `depthpack` and `depthunpack`.
+pub struct DepthPackingElement<D: PackingDirection> {
+    /// Debug category of the element.
+    cat: gst::DebugCategory,
+    /// Settings based on properties of the element.
+    settings: RwLock<Settings>,
+    /// The state of the element.
+    state: Mutex<State>,
+    sink_pad: gst::Pad,
+    src_pad: gst::Pad,
+    direction: PhantomData<D>,
+}
+
+impl<D: PackingDirection> DepthPackingElement<D> {
+    /// Create a new DepthPackingElement, whose pads call into the element `T`.
+    /// # Arguments
+    /// * `cat` - Debug category of the element.
+    /// * `sink_templ` - Template of the sink pad.
+    /// * `src_templ` - Template of the src pad.
+    pub fn new<T>(
+        cat: gst::DebugCategory,
+        sink_templ: &gst::PadTemplate,
+        src_templ: &gst::PadTemplate,
+    ) -> Self
+    where
+        T: DepthPackingImpl<Direction = D>,
+        T::Type: IsA<gst::Element>,
+    {
+        let sink_pad = gst::Pad::builder_with_template(sink_templ, Some("sink"))
+            .chain_function(|_, parent, buffer| {
+                T::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this, element| {
+                        this.depth_packing()
+                            .sink_chain(element.upcast_ref(), buffer)
+                    },
+                )
+            })
+            .event_function(|pad, parent, event| {
+                T::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| {
+                        this.depth_packing()
+                            .sink_event(pad, element.upcast_ref(), event)
+                    },
+                )
+            })
+            .build();
+
+        let src_pad = gst::Pad::builder_with_template(src_templ, Some("src"))
+            .flags(gst::PadFlags::FIXED_CAPS)
+            .build();
+
+        Self {
+            cat,
+            settings: RwLock::new(Settings::default()),
+            state: Mutex::new(State::default()),
+            sink_pad,
+            src_pad,
+            direction: PhantomData,
+        }
+    }
+
+    /// Add the pads to the element, see `ObjectImpl::constructed()`.
+    pub fn add_pads(&self, element: &gst::Element) {
+        element
+            .add_pad(&self.sink_pad)
+            .expect("Failed to add sink pad to the element");
+        element
+            .add_pad(&self.src_pad)
+            .expect("Failed to add src pad to the element");
+    }
+
+    /// Reset the state, e.g. when the element is stopped.
+    pub fn reset(&self) {
+        *self.state.lock().unwrap() = State::default();
+    }
+
+    /// Set one of the properties, see `Settings::set_property()`.
+    pub fn set_property(&self, value: &glib::Value, pspec: &glib::ParamSpec) {
+        self.settings
+            .write()
+            .unwrap()
+            .set_property(self.cat, value, pspec);
+
+        // Create the packing again with the next buffer
+        self.state.lock().unwrap().packing = None;
+    }
+
+    /// Get one of the properties, see `Settings::property()`.
+    pub fn property(&self, pspec: &glib::ParamSpec) -> glib::Value {
+        self.settings.read().unwrap().property(pspec)
+    }
+
+    /// Called whenever an event is received at the sink pad. CAPS events are converted into the
+    /// src CAPS of the direction, tag events with CameraMeta update the depth scale, and all
+    /// events except CAPS are send further downstream.
+    /// # Arguments
+    /// * `pad` - The sink pad.
+    /// * `element` - The element that represents `depthpack` or `depthunpack` in GStreamer.
+    /// * `event` - The event that should be handled.
+    fn sink_event(&self, pad: &gst::Pad, element: &gst::Element, event: gst::Event) -> bool {
+        use gst::EventView;
+        match event.view() {
+            EventView::Caps(caps) => {
+                gst_debug!(self.cat, obj: element, "Got a new caps event: {:?}", caps);
+                let sink_info = match gst_video::VideoInfo::from_caps(caps.caps()) {
+                    Ok(sink_info) => sink_info,
+                    Err(e) => {
+                        gst_error!(self.cat, obj: element, "Invalid sink caps: {}", e);
+                        return false;
+                    }
+                };
+                let src_caps = match D::src_caps(&self.src_pad, &sink_info) {
+                    Ok(src_caps) => src_caps,
+                    Err(e) => {
+                        gst_error!(self.cat, obj: element, "{}", e);
+                        return false;
+                    }
+                };
+                let src_info = match gst_video::VideoInfo::from_caps(&src_caps) {
+                    Ok(src_info) => src_info,
+                    Err(e) => {
+                        gst_error!(self.cat, obj: element, "Invalid src caps: {}", e);
+                        return false;
+                    }
+                };
+
+                {
+                    let mut state = self.state.lock().unwrap();
+                    state.sink_info = Some(sink_info);
+                    state.src_info = Some(src_info);
+                }
+                self.src_pad.push_event(gst::event::Caps::new(&src_caps))
+            }
+            EventView::Tag(tag) => {
+                if let Some(depth_scale) = camera_depth_scale(tag) {
+                    gst_debug!(self.cat, obj: element, "Using depth scale {}", depth_scale);
+                    let mut state = self.state.lock().unwrap();
+                    state.camera_depth_scale = Some(depth_scale);
+                    state.packing = None;
+                }
+                pad.event_default(Some(element), event)
+            }
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+
+    /// Pack or unpack a frame and push it downstream with the timestamps, flags and tag of the
+    /// incoming buffer.
+    /// # Arguments
+    /// * `element` - The element that represents `depthpack` or `depthunpack` in GStreamer.
+    /// * `buffer` - The incoming buffer.
+    fn sink_chain(
+        &self,
+        element: &gst::Element,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let (sink_info, src_info, packing) = {
+            let mut state = self.state.lock().unwrap();
+            let (sink_info, src_info) = match (state.sink_info.clone(), state.src_info.clone()) {
+                (Some(sink_info), Some(src_info)) => (sink_info, src_info),
+                _ => {
+                    gst_warning!(self.cat, obj: element, "Received a buffer before CAPS");
+                    return Err(gst::FlowError::NotNegotiated);
+                }
+            };
+            if state.packing.is_none() {
+                let packing = self
+                    .settings
+                    .read()
+                    .unwrap()
+                    .packing(state.camera_depth_scale)
+                    .map_err(|e| {
+                        element.post_error_message(e);
+                        gst::FlowError::Error
+                    })?;
+                state.packing = Some(packing);
+            }
+            (sink_info, src_info, state.packing.unwrap())
+        };
+
+        let in_frame = gst_video::VideoFrameRef::from_buffer_ref_readable(&buffer, &sink_info)
+            .map_err(|_| {
+                gst_error!(self.cat, obj: element, "Cannot map incoming buffer");
+                gst::FlowError::Error
+            })?;
+        let mut output = gst::Buffer::with_size(src_info.size()).map_err(|_| {
+            gst_error!(self.cat, obj: element, "Cannot allocate outgoing buffer");
+            gst::FlowError::Error
+        })?;
+        {
+            let output = output.get_mut().unwrap();
+            let mut out_frame = gst_video::VideoFrameRef::from_buffer_ref_writable(
+                output, &src_info,
+            )
+            .map_err(|_| {
+                gst_error!(self.cat, obj: element, "Cannot map outgoing buffer");
+                gst::FlowError::Error
+            })?;
+            D::convert(&packing, &in_frame, &mut out_frame)?;
+        }
+        drop(in_frame);
+
+        {
+            let output = output.get_mut().unwrap();
+            buffer
+                .copy_into(
+                    output,
+                    gst::BufferCopyFlags::FLAGS | gst::BufferCopyFlags::TIMESTAMPS,
+                    0,
+                    None,
+                )
+                .map_err(|_| gst::FlowError::Error)?;
+            if let Ok(tag) = rgbd::get_tag(&buffer) {
+                rgbd::tag_buffer_with_title(output, &tag).map_err(|e| {
+                    gst_error!(self.cat, obj: element, "{}", e);
+                    gst::FlowError::Error
+                })?;
+            }
+        }
+        self.src_pad.push(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    /// Range of 0.2 - 10 metres with depth scale of 1 mm.
+    fn packing() -> DepthPacking {
+        DepthPacking::new(0.2, 10.0, 0.001, 8)
+    }
+
+    /// Root mean square and maximum error of unpacked depth, after adding uniform noise with the
+    /// given amplitude to the channels.
+    fn reconstruction_error(packing: &DepthPacking, noise: f32) -> (f32, f32) {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let (mut squared_sum, mut max, mut count) = (0.0f64, 0.0f32, 0);
+        for _ in 0..100_000 {
+            let raw = rng.gen_range(200..=10_000);
+            let mut channels = packing.pack(raw);
+            channels.iter_mut().for_each(|channel| {
+                *channel = (*channel as f32 + rng.gen_range(-noise..=noise))
+                    .round()
+                    .clamp(0.0, 255.0) as u8
+            });
+            let error = (packing.unpack(channels) as f32 - raw as f32).abs();
+            squared_sum += (error * error) as f64;
+            max = max.max(error);
+            count += 1;
+        }
+        ((squared_sum / count as f64).sqrt() as f32, max)
+    }
+
+    #[test]
+    fn round_trip_without_noise() {
+        let packing = packing();
+        for raw in 200..=10_000 {
+            let unpacked = packing.unpack(packing.pack(raw));
+            assert!(
+                (unpacked as f32 - raw as f32).abs() <= packing.precision(),
+                "{} was unpacked as {}",
+                raw,
+                unpacked
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_depth() {
+        let packing = packing();
+        assert_eq!(packing.unpack(packing.pack(0)), 0);
+        // Noise around black remains invalid
+        assert_eq!(packing.unpack([3, 125, 131]), 0);
+        // Valid depth never becomes invalid
+        assert_ne!(packing.unpack(packing.pack(200)), 0);
+    }
+
+    #[test]
+    fn clamped_to_range() {
+        let packing = packing();
+        let max = packing.unpack(packing.pack(u16::MAX)) as f32;
+        assert_eq!(packing.unpack(packing.pack(1)), 200);
+        assert!((max - 10_000.0).abs() <= packing.precision());
+    }
+
+    #[test]
+    fn error_with_channel_noise() {
+        // Noise of the channels in steps of 8 bits, similar to artefacts of decreasing bitrates
+        let packing = packing();
+        let mut previous_rms = 0.0;
+        for noise in [0.0, 1.0, 2.0, 4.0].iter() {
+            let (rms, max) = reconstruction_error(&packing, *noise);
+            assert!(rms >= previous_rms);
+            assert!(rms <= packing.precision() * (1.0 + noise));
+            // The closest period is always selected
+            assert!(max < packing.range_raw / packing.periods / 4.0);
+            previous_rms = rms;
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use once_cell::sync::Lazy;
+
+use crate::depthpacking::*;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "depthunpack",
+        gst::DebugColorFlags::empty(),
+        Some("Depth Unpacker"),
+    )
+});
+
+/// Direction of `depthunpack`, which unpacks packed depth into `GRAY16_LE`.
+pub enum Unpack {}
+
+impl PackingDirection for Unpack {
+    fn src_caps(
+        _src_pad: &gst::Pad,
+        sink_info: &gst_video::VideoInfo,
+    ) -> Result<gst::Caps, gst::ErrorMessage> {
+        gst_video::VideoInfo::builder(
+            gst_video::VideoFormat::Gray16Le,
+            sink_info.width(),
+            sink_info.height(),
+        )
+        .fps(sink_info.fps())
+        .build()
+        .and_then(|src_info| src_info.to_caps())
+        .map_err(|e| gst::error_msg!(gst::CoreError::Negotiation, ["Invalid depth caps: {}", e]))
+    }
+
+    fn convert(
+        packing: &DepthPacking,
+        in_frame: &gst_video::VideoFrameRef<&gst::BufferRef>,
+        out_frame: &mut gst_video::VideoFrameRef<&mut gst::BufferRef>,
+    ) -> Result<(), gst::FlowError> {
+        let (width, height) = (in_frame.width() as usize, in_frame.height() as usize);
+        let format_info = in_frame.info().format_info();
+        // Stride, subsampling and data of the planes with `L`, `Ha` and `Hb`
+        let mut planes = Vec::with_capacity(3);
+        for channel in 0..3 {
+            planes.push((
+                in_frame.plane_stride()[channel] as usize,
+                format_info.w_sub()[channel],
+                format_info.h_sub()[channel],
+                in_frame
+                    .plane_data(channel as u32)
+                    .map_err(|_| gst::FlowError::Error)?,
+            ));
+        }
+
+        let out_stride = out_frame.plane_stride()[0] as usize;
+        let out_data = out_frame
+            .plane_data_mut(0)
+            .map_err(|_| gst::FlowError::Error)?;
+        for y in 0..height {
+            for x in 0..width {
+                let mut channels = [0u8; 3];
+                for (channel, (stride, w_sub, h_sub, data)) in
+                    channels.iter_mut().zip(planes.iter())
+                {
+                    *channel = data[(y >> h_sub) * stride + (x >> w_sub)];
+                }
+                let offset = y * out_stride + x * 2;
+                out_data[offset..offset + 2]
+                    .copy_from_slice(&packing.unpack(channels).to_le_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A struct representation of the `depthunpack` element.
+pub struct DepthUnpack {
+    /// Pads, properties and state shared with `depthpack`.
+    depth_packing: DepthPackingElement<Unpack>,
+}
+
+glib::wrapper! {
+    pub struct DepthUnpackObject(ObjectSubclass<DepthUnpack>)
+        @extends gst::Element, gst::Object;
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DepthUnpack {
+    const NAME: &'static str = "depthunpack";
+    type Type = DepthUnpackObject;
+    type ParentType = gst::Element;
+    fn with_class(klass: &Self::Class) -> Self {
+        Self {
+            depth_packing: DepthPackingElement::new::<Self>(
+                *CAT,
+                &klass.pad_template("sink").unwrap(),
+                &klass.pad_template("src").unwrap(),
+            ),
+        }
+    }
+}
+
+impl DepthPackingImpl for DepthUnpack {
+    type Direction = Unpack;
+
+    fn depth_packing(&self) -> &DepthPackingElement<Unpack> {
+        &self.depth_packing
+    }
+}
+
+impl ElementImpl for DepthUnpack {
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        #[allow(clippy::single_match)]
+        match transition {
+            gst::StateChange::PausedToReady => {
+                self.depth_packing.reset();
+            }
+            _ => {}
+        }
+
+        // Chain up parent implementation
+        self.parent_change_state(element, transition)
+    }
+
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "Depth Unpacker",
+                "Filter/Converter/Video/RGB-D",
+                "Unpacks 16-bit depth that was packed by `depthpack` into three 8-bit channels",
+                "Raphael Duerscheid <rd@aivero.com>, Andrej Orsula <andrej.orsula@aivero.com>,
+                 Tobias Morell <tobias.morell@aivero.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 2]> = Lazy::new(|| {
+            [
+                gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder("video/x-raw")
+                        .field("format", &"GRAY16_LE")
+                        .field("width", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .field("height", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .build(),
+                )
+                .expect("depthunpack: Failed to add 'src' pad template"),
+                gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder("video/x-raw")
+                        .field("format", &packed_formats())
+                        .field("width", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .field("height", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .build(),
+                )
+                .expect("depthunpack: Failed to add 'sink' pad template"),
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl GstObjectImpl for DepthUnpack {}
+impl ObjectImpl for DepthUnpack {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<[glib::ParamSpec; 4]> = Lazy::new(Settings::properties);
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, element: &Self::Type) {
+        self.parent_constructed(element);
+
+        self.depth_packing.add_pads(element.upcast_ref());
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        self.depth_packing.set_property(value, pspec);
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        self.depth_packing.property(pspec)
+    }
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "depthunpack",
+        gst::Rank::None,
+        DepthUnpack::type_(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    const WIDTH: usize = 160;
+    const HEIGHT: usize = 120;
+    const FRAMES: usize = 30;
+
+    /// Create a `GRAY16_LE` frame of a tilted plane with a sphere in front of it that moves with
+    /// `index`, and a stripe of invalid depth.
+    fn depth_frame(index: usize) -> Vec<u16> {
+        let mut depth = Vec::with_capacity(WIDTH * HEIGHT);
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let (dx, dy) = (x as f32 - (40 + 2 * index) as f32, y as f32 - 60.0);
+                let value = if x < 8 {
+                    0.0
+                } else if dx * dx + dy * dy < 900.0 {
+                    1500.0 - 20.0 * (900.0 - dx * dx - dy * dy).sqrt()
+                } else {
+                    3000.0 + 10.0 * x as f32 + 5.0 * y as f32
+                };
+                depth.push(value as u16);
+            }
+        }
+        depth
+    }
+
+    /// Push frames through `depthpack`, the given elements and `depthunpack`.
+    /// # Arguments
+    /// * `codec` - Description of the elements between `depthpack` and `depthunpack`, in the
+    /// syntax of gst-launch.
+    /// # Returns
+    /// * Root mean square and maximum error of valid depth, in millimetres.
+    fn reconstruction_error(codec: &str) -> (f64, f64) {
+        let mut harness =
+            gst_check::Harness::new_parse(&format!("depthpack ! {} ! depthunpack", codec));
+        harness.set_src_caps(
+            gst::Caps::builder("video/x-raw")
+                .field("format", &"GRAY16_LE")
+                .field("width", &(WIDTH as i32))
+                .field("height", &(HEIGHT as i32))
+                .field("framerate", &gst::Fraction::new(30, 1))
+                .build(),
+        );
+
+        let frames: Vec<Vec<u16>> = (0..FRAMES).map(depth_frame).collect();
+        for (index, frame) in frames.iter().enumerate() {
+            let data: Vec<u8> = frame.iter().flat_map(|value| value.to_le_bytes()).collect();
+            let mut buffer = gst::Buffer::from_mut_slice(data);
+            {
+                let buffer = buffer.get_mut().unwrap();
+                buffer.set_pts(gst::ClockTime::from_mseconds(index as u64 * 33));
+                buffer.set_duration(gst::ClockTime::from_mseconds(33));
+            }
+            harness.push(buffer).unwrap();
+        }
+        harness.push_event(gst::event::Eos::new());
+
+        let (mut squared_sum, mut max, mut count) = (0.0f64, 0.0f64, 0usize);
+        for frame in frames.iter() {
+            let output = harness.try_pull().expect("Frame was not decoded");
+            let output = output.map_readable().unwrap();
+            for (expected, unpacked) in frame.iter().zip(output.chunks_exact(2)) {
+                let unpacked = u16::from_le_bytes([unpacked[0], unpacked[1]]);
+                if *expected != 0 {
+                    let error = (unpacked as f64 - *expected as f64).abs();
+                    squared_sum += error * error;
+                    max = max.max(error);
+                    count += 1;
+                }
+            }
+        }
+        ((squared_sum / count as f64).sqrt(), max)
+    }
+
+    #[test]
+    fn round_trip_without_codec() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        // Without chroma subsampling, only the quantisation of the default packing over 10
+        // metres remains
+        let precision = crate::depthpacking::DepthPacking::new(0.0, 10.0, 0.001, 8).precision();
+        let (rms, max) = reconstruction_error("video/x-raw,format=Y444");
+        assert!(max <= precision as f64, "Max error {} mm", max);
+        assert!(rms <= precision as f64, "RMS error {} mm", rms);
+    }
+
+    #[test]
+    fn error_at_bitrates() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+        if ["x264enc", "avdec_h264"]
+            .iter()
+            .any(|name| gst::ElementFactory::find(name).is_none())
+        {
+            eprintln!(
+                "Skipping test: `x264enc` of gst-plugins-ugly and `avdec_h264` of gst-libav are \
+                 required"
+            );
+            return;
+        }
+
+        // Bounds of the RMS error in millimetres for each bitrate in kbit/s
+        for (bitrate, max_rms) in [(100, 500.0), (400, 150.0), (1600, 50.0)].iter() {
+            let (rms, max) = reconstruction_error(&format!(
+                "x264enc bitrate={} tune=zerolatency speed-preset=ultrafast ! avdec_h264",
+                bitrate
+            ));
+            eprintln!(
+                "RMS error {:.1} mm and max error {} mm at {} kbit/s",
+                rms, max, bitrate
+            );
+            assert!(
+                rms < *max_rms,
+                "RMS error {:.1} mm at {} kbit/s exceeds {} mm",
+                rms,
+                bitrate,
+                max_rms
+            );
+        }
+    }
+}
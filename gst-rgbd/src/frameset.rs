@@ -0,0 +1,178 @@
+//! Serialisation of whole framesets of `video/rgbd`, i.e. the main buffer together with all its
+//! auxiliary buffers, which is shared by `rtprgbdpay`/`rtprgbddepay` and
+//! `rgbdfilesink`/`rgbdfilesrc`.
+//!
+//! The blob starts with the format version and the number of buffers, followed by each buffer
+//! (main buffer first) as its stream index, PTS offset to the main buffer, duration, flags, size
+//! and data. Stream indices refer to the names of the streams, e.g. the `streams` field of the
+//! `video/rgbd` caps. All integers are big-endian.
+use gst_depth_meta::rgbd;
+use std::convert::TryInto;
+
+/// Version of the serialised frameset.
+const FORMAT_VERSION: u8 = 1;
+/// PTS offset of a buffer without PTS.
+const NO_PTS_OFFSET: i64 = i64::MIN;
+/// Duration of a buffer without duration.
+const NO_DURATION: u64 = u64::MAX;
+/// Buffer flags that are serialised, all others are dropped.
+const SERIALISED_FLAGS: gst::BufferFlags = gst::BufferFlags::from_bits_truncate(
+    gst::BufferFlags::DELTA_UNIT.bits()
+        | gst::BufferFlags::GAP.bits()
+        | gst::BufferFlags::DROPPABLE.bits()
+        | gst::BufferFlags::HEADER.bits(),
+);
+
+/// Serialise the main buffer together with all its auxiliary buffers.
+/// # Arguments
+/// * `main_buffer` - The main buffer of `video/rgbd` stream.
+/// * `streams` - Names of the streams, which determine the stream index of each buffer.
+/// # Returns
+/// * `Ok(Vec<u8>)` - The serialised frameset.
+/// * `Err(gst::ErrorMessage)` - If a buffer is not tagged with one of `streams`, or it cannot be
+/// mapped.
+pub fn serialize_frameset(
+    main_buffer: &gst::BufferRef,
+    streams: &[String],
+) -> Result<Vec<u8>, gst::ErrorMessage> {
+    let aux_buffers = rgbd::get_all_aux_buffers(main_buffer).collect::<Vec<gst::Buffer>>();
+    if aux_buffers.len() >= u8::MAX as usize {
+        return Err(gst::error_msg!(
+            gst::StreamError::Format,
+            ["Frameset contains too many buffers"]
+        ));
+    }
+    let buffers = std::iter::once(main_buffer).chain(aux_buffers.iter().map(|b| &**b));
+
+    let mut data = vec![FORMAT_VERSION, aux_buffers.len() as u8 + 1];
+    for buffer in buffers {
+        let tag = rgbd::get_tag(buffer)?;
+        let stream_index = streams.iter().position(|s| *s == tag).ok_or_else(|| {
+            gst::error_msg!(
+                gst::StreamError::Format,
+                ["Buffer of stream {} is not contained in the caps", tag]
+            )
+        })?;
+        let pts_offset = match (main_buffer.pts(), buffer.pts()) {
+            (Some(main_pts), Some(pts)) => pts.nseconds() as i64 - main_pts.nseconds() as i64,
+            _ => NO_PTS_OFFSET,
+        };
+        let map = buffer.map_readable().map_err(|_| {
+            gst::error_msg!(
+                gst::ResourceError::Read,
+                ["Cannot map buffer of stream {}", tag]
+            )
+        })?;
+
+        data.push(stream_index as u8);
+        data.extend_from_slice(&pts_offset.to_be_bytes());
+        data.extend_from_slice(
+            &buffer
+                .duration()
+                .map_or(NO_DURATION, |duration| duration.nseconds())
+                .to_be_bytes(),
+        );
+        data.extend_from_slice(&(buffer.flags() & SERIALISED_FLAGS).bits().to_be_bytes());
+        data.extend_from_slice(&(map.size() as u32).to_be_bytes());
+        data.extend_from_slice(map.as_slice());
+    }
+    Ok(data)
+}
+
+/// Deserialise a frameset into the main buffer with all its auxiliary buffers.
+/// # Arguments
+/// * `data` - The serialised frameset.
+/// * `streams` - Names of the streams, which are used to tag the buffers.
+/// * `pts` - PTS of the main buffer. The PTS of the auxiliary buffers is offset from it.
+/// # Returns
+/// * `Ok(gst::Buffer)` - The main buffer.
+/// * `Err(gst::ErrorMessage)` - If `data` is not a valid frameset.
+pub fn deserialize_frameset(
+    data: &[u8],
+    streams: &[String],
+    pts: Option<gst::ClockTime>,
+) -> Result<gst::Buffer, gst::ErrorMessage> {
+    let mut reader = Reader { data };
+    let version = reader.read::<1>()?[0];
+    if version != FORMAT_VERSION {
+        return Err(gst::error_msg!(
+            gst::StreamError::Format,
+            ["Unsupported frameset version {}", version]
+        ));
+    }
+    let buffer_count = reader.read::<1>()?[0];
+
+    let mut main_buffer: Option<gst::Buffer> = None;
+    for _ in 0..buffer_count {
+        let stream_index = reader.read::<1>()?[0] as usize;
+        let tag = streams.get(stream_index).ok_or_else(|| {
+            gst::error_msg!(
+                gst::StreamError::Format,
+                ["Invalid stream index {}", stream_index]
+            )
+        })?;
+        let pts_offset = i64::from_be_bytes(reader.read()?);
+        let duration = u64::from_be_bytes(reader.read()?);
+        let flags = u32::from_be_bytes(reader.read()?);
+        let size = u32::from_be_bytes(reader.read()?) as usize;
+
+        let mut buffer = gst::Buffer::from_mut_slice(reader.read_slice(size)?.to_vec());
+        let buffer_mut = buffer.get_mut().unwrap();
+        buffer_mut.set_pts(match (pts, pts_offset) {
+            (_, NO_PTS_OFFSET) => None,
+            (Some(pts), offset) if offset >= 0 => {
+                Some(pts + gst::ClockTime::from_nseconds(offset as u64))
+            }
+            (Some(pts), offset) => {
+                pts.checked_sub(gst::ClockTime::from_nseconds(offset.unsigned_abs()))
+            }
+            (None, _) => None,
+        });
+        if duration != NO_DURATION {
+            buffer_mut.set_duration(gst::ClockTime::from_nseconds(duration));
+        }
+        buffer_mut.set_flags(gst::BufferFlags::from_bits_truncate(flags) & SERIALISED_FLAGS);
+
+        match main_buffer.as_mut() {
+            None => {
+                buffer_mut.set_pts(pts);
+                rgbd::tag_buffer_with_title(buffer_mut, tag)?;
+                main_buffer = Some(buffer);
+            }
+            Some(main_buffer) => {
+                rgbd::attach_aux_buffer_and_tag(main_buffer.get_mut().unwrap(), &mut buffer, tag)?
+            }
+        }
+    }
+
+    main_buffer
+        .ok_or_else(|| gst::error_msg!(gst::StreamError::Format, ["Frameset contains no buffers"]))
+}
+
+/// Utility struct for reading a serialised frameset.
+struct Reader<'a> {
+    /// The data that was not read yet.
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Read the next `len` bytes.
+    /// # Arguments
+    /// * `len` - The number of bytes to read.
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8], gst::ErrorMessage> {
+        if self.data.len() < len {
+            return Err(gst::error_msg!(
+                gst::StreamError::Format,
+                ["Frameset is truncated"]
+            ));
+        }
+        let (slice, data) = self.data.split_at(len);
+        self.data = data;
+        Ok(slice)
+    }
+
+    /// Read the next `N` bytes as an array, e.g. to convert them into an integer.
+    fn read<const N: usize>(&mut self) -> Result<[u8; N], gst::ErrorMessage> {
+        Ok(self.read_slice(N)?.try_into().unwrap())
+    }
+}
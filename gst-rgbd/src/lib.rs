@@ -2,14 +2,51 @@
 extern crate gst;
 extern crate gst_base;
 extern crate gst_depth_meta;
+extern crate gst_rtp;
+extern crate gst_util;
+
+use std::sync::Once;
 
 mod common;
+mod depthpack;
+mod depthpacking;
+mod depthunpack;
+mod frameset;
+mod rgbdbin;
 mod rgbddemux;
+mod rgbdfile;
+mod rgbdfilesink;
+mod rgbdfilesrc;
 mod rgbdmux;
+mod rgbdremap;
+mod rtpbase;
+mod rtprgbd;
+mod rtprgbddepay;
+mod rtprgbdpay;
+mod rvl;
+mod rvldec;
+mod rvlenc;
+
+static TAGS: Once = Once::new();
 
 fn plugin_init(plugin: &gst::Plugin) -> Result<(), gst::glib::BoolError> {
     rgbddemux::register(plugin)?;
     rgbdmux::register(plugin)?;
+    rgbdbin::register(plugin)?;
+    rgbdremap::register(plugin)?;
+    rtprgbdpay::register(plugin)?;
+    rtprgbddepay::register(plugin)?;
+    rgbdfilesink::register(plugin)?;
+    rgbdfilesrc::register(plugin)?;
+    rvlenc::register(plugin)?;
+    rvldec::register(plugin)?;
+    depthpack::register(plugin)?;
+    depthunpack::register(plugin)?;
+
+    TAGS.call_once(|| {
+        gst::tags::register::<gst_depth_meta::camera_meta::CameraMetaTag>();
+    });
+
     Ok(())
 }
 
@@ -0,0 +1,546 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_util::bin::{Add, BinExtension, Remove};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::rgbddemux::RgbdDemuxObject;
+use crate::rgbdmux::{RgbdMuxObject, SyncPolicy};
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new("rgbdbin", gst::DebugColorFlags::empty(), Some("RGB-D Bin"))
+});
+
+/// Default value for `stream` property
+const DEFAULT_STREAM: &str = "color";
+
+/// A struct containing properties of `rgbdbin` element
+struct Settings {
+    /// Analogous to `stream` property
+    stream: String,
+    /// Analogous to `bin-description` property
+    bin_description: Option<String>,
+}
+
+/// Elements that connect a single `src_%s` pad of the internal `rgbddemux` with the
+/// corresponding `sink_%s` pad of the internal `rgbdmux`.
+struct StreamBranch {
+    /// Queue that decouples the stream from all other streams of the `rgbddemux`.
+    queue: gst::Element,
+    /// The bin that processes the stream, if this is the selected stream.
+    processing_bin: Option<gst::Bin>,
+    /// The requested sink pad of the `rgbdmux`.
+    mux_pad: gst::Pad,
+}
+
+struct State {
+    /// The bin parsed from `bin-description`, while it is not linked to its stream.
+    processing_bin: Option<gst::Bin>,
+    /// Branches of all streams, where key is the name of the stream.
+    branches: HashMap<String, StreamBranch>,
+}
+
+/// A struct representation of the `rgbdbin` element, which demuxes `video/rgbd`, runs a single
+/// stream through a bin and muxes all streams back together.
+pub struct RgbdBin {
+    /// Settings based on properties of the element.
+    settings: Mutex<Settings>,
+    state: Mutex<State>,
+    /// The internal `rgbddemux`, whose sink pad is ghosted as the sink pad of the bin.
+    demux: gst::Element,
+    /// The internal `rgbdmux`, whose src pad is ghosted as the src pad of the bin.
+    mux: gst::Element,
+}
+
+glib::wrapper! {
+    pub struct RgbdBinObject(ObjectSubclass<RgbdBin>)
+        @extends gst::Bin, gst::Element, gst::Object;
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RgbdBin {
+    const NAME: &'static str = "rgbdbin";
+    type Type = RgbdBinObject;
+    type ParentType = gst::Bin;
+
+    fn new() -> Self {
+        let demux = glib::Object::new::<RgbdDemuxObject>(&[("name", &"demux")])
+            .expect("rgbdbin: Failed to create rgbddemux")
+            .upcast::<gst::Element>();
+        // All streams of a frameset share the timestamp of the main buffer, which is why buffers
+        // are matched by their timestamps. Buffers that the processing bin drops are thereby
+        // dropped from the other streams as well, instead of shifting all following framesets.
+        let mux = glib::Object::new::<RgbdMuxObject>(&[
+            ("name", &"mux"),
+            ("sync-policy", &SyncPolicy::Nearest),
+            ("tolerance", &0u64),
+        ])
+        .expect("rgbdbin: Failed to create rgbdmux")
+        .upcast::<gst::Element>();
+
+        Self {
+            settings: Mutex::new(Settings {
+                stream: DEFAULT_STREAM.to_string(),
+                bin_description: None,
+            }),
+            state: Mutex::new(State {
+                processing_bin: None,
+                branches: HashMap::new(),
+            }),
+            demux,
+            mux,
+        }
+    }
+}
+
+impl ElementImpl for RgbdBin {
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        if transition == gst::StateChange::NullToReady {
+            self.prepare_processing_bin(element)?;
+        }
+
+        // Chain up parent implementation
+        self.parent_change_state(element, transition)
+    }
+
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RGB-D Bin",
+                "Generic/Bin/RGB-D",
+                "Runs a single stream of `video/rgbd` through a bin, while all other streams pass \
+                 through untouched",
+                "Andrej Orsula <andrej.orsula@aivero.com>, \
+                 Tobias Morell <tobias.morell@aivero.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 2]> = Lazy::new(|| {
+            let caps = gst::Caps::new_simple("video/rgbd", &[]);
+            [
+                gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &caps,
+                )
+                .expect("rgbdbin: Failed to add 'sink' pad template"),
+                gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &caps,
+                )
+                .expect("rgbdbin: Failed to add 'src' pad template"),
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BinImpl for RgbdBin {}
+
+impl RgbdBin {
+    /// Parse `bin-description` into the bin that processes the selected stream. Nothing happens
+    /// if the bin was already parsed and linked to its stream, e.g. when the element is started
+    /// again with the same CAPS.
+    /// # Arguments
+    /// * `element` - The element that represents the `rgbdbin` in GStreamer.
+    fn prepare_processing_bin(&self, element: &RgbdBinObject) -> Result<(), gst::StateChangeError> {
+        let mut state = self.state.lock().unwrap();
+        let in_use = state
+            .branches
+            .values()
+            .any(|branch| branch.processing_bin.is_some());
+        if in_use {
+            return Ok(());
+        }
+
+        let settings = self.settings.lock().unwrap();
+        let bin_description = match &settings.bin_description {
+            Some(bin_description) => bin_description,
+            None => {
+                gst_warning!(
+                    CAT,
+                    obj: element,
+                    "No `bin-description` is set, all streams pass through untouched"
+                );
+                state.processing_bin = None;
+                return Ok(());
+            }
+        };
+
+        let processing_bin =
+            gst::parse_bin_from_description(bin_description, true).map_err(|err| {
+                gst::element_error!(
+                    element,
+                    gst::CoreError::Failed,
+                    ["Failed to parse `bin-description`: {}", err]
+                );
+                gst::StateChangeError
+            })?;
+        gst_debug!(
+            CAT,
+            obj: element,
+            "Parsed `{}` for stream {}",
+            bin_description,
+            settings.stream
+        );
+        state.processing_bin = Some(processing_bin);
+        Ok(())
+    }
+
+    /// Called whenever the internal `rgbddemux` adds a src pad for a stream. The stream is
+    /// linked to the `rgbdmux`, either directly or through the processing bin.
+    /// # Arguments
+    /// * `element` - The element that represents the `rgbdbin` in GStreamer.
+    /// * `demux_pad` - The new `src_%s` pad of the `rgbddemux`.
+    fn demux_pad_added(&self, element: &RgbdBinObject, demux_pad: &gst::Pad) {
+        let pad_name = demux_pad.name();
+        let stream_name = pad_name.trim_start_matches("src_");
+        if let Err(err) = self.link_stream(element, demux_pad, stream_name) {
+            gst::element_error!(
+                element,
+                gst::CoreError::Negotiation,
+                ["Failed to link stream {}: {}", stream_name, err]
+            );
+        }
+    }
+
+    /// Link the stream that flows through `demux_pad` to a new sink pad of the `rgbdmux`. The
+    /// selected stream additionally flows through the processing bin.
+    /// # Arguments
+    /// * `element` - The element that represents the `rgbdbin` in GStreamer.
+    /// * `demux_pad` - The `src_%s` pad of the `rgbddemux`.
+    /// * `stream_name` - The name of the stream, e.g. `color`.
+    fn link_stream(
+        &self,
+        element: &RgbdBinObject,
+        demux_pad: &gst::Pad,
+        stream_name: &str,
+    ) -> Result<(), glib::BoolError> {
+        let mut state = self.state.lock().unwrap();
+        if state.branches.contains_key(stream_name) {
+            return Ok(());
+        }
+
+        let queue = gst::ElementFactory::make("queue", Some(&format!("queue_{}", stream_name)))?;
+        let processing_bin = if stream_name == self.settings.lock().unwrap().stream {
+            state.processing_bin.take()
+        } else {
+            None
+        };
+        let mut elements = vec![queue.clone()];
+        if let Some(processing_bin) = &processing_bin {
+            gst_info!(
+                CAT,
+                obj: element,
+                "Processing stream {} with {}",
+                stream_name,
+                processing_bin.name()
+            );
+            elements.push(processing_bin.clone().upcast());
+        } else {
+            gst_debug!(CAT, obj: element, "Passing stream {} through", stream_name);
+        }
+
+        // Link the whole branch before it starts streaming
+        element.add_iter(Add::link(), elements.iter())?;
+        let mux_pad = self
+            .mux
+            .request_pad_simple(&format!("sink_{}", stream_name))
+            .ok_or_else(|| glib::bool_error!("rgbdmux has no pad for stream {}", stream_name))?;
+        demux_pad
+            .link(&queue.static_pad("sink").unwrap())
+            .map_err(|err| glib::bool_error!("{:?}", err))?;
+        elements
+            .last()
+            .unwrap()
+            .static_pad("src")
+            .ok_or_else(|| glib::bool_error!("The processing bin has no src pad"))?
+            .link(&mux_pad)
+            .map_err(|err| glib::bool_error!("{:?}", err))?;
+        for element in elements.iter().rev() {
+            element.sync_state_with_parent()?;
+        }
+
+        state.branches.insert(
+            stream_name.to_string(),
+            StreamBranch {
+                queue,
+                processing_bin,
+                mux_pad,
+            },
+        );
+        Ok(())
+    }
+
+    /// Called whenever the internal `rgbddemux` removes the src pad of a stream, e.g. after
+    /// renegotiation. The branch of the stream is removed, but its processing bin is kept, such
+    /// that it can be linked again.
+    /// # Arguments
+    /// * `element` - The element that represents the `rgbdbin` in GStreamer.
+    /// * `demux_pad` - The removed `src_%s` pad of the `rgbddemux`.
+    fn demux_pad_removed(&self, element: &RgbdBinObject, demux_pad: &gst::Pad) {
+        let pad_name = demux_pad.name();
+        let stream_name = pad_name.trim_start_matches("src_");
+        let mut state = self.state.lock().unwrap();
+        let branch = match state.branches.remove(stream_name) {
+            Some(branch) => branch,
+            None => return,
+        };
+        gst_debug!(CAT, obj: element, "Removing branch of stream {}", stream_name);
+
+        self.mux.release_request_pad(&branch.mux_pad);
+        let mut elements = vec![branch.queue];
+        if let Some(processing_bin) = &branch.processing_bin {
+            elements.push(processing_bin.clone().upcast());
+        }
+        if let Err(err) = element.remove_iter(Remove::null(), elements.iter()) {
+            gst_warning!(
+                CAT,
+                obj: element,
+                "Failed to remove branch of stream {}: {}",
+                stream_name,
+                err
+            );
+        }
+        if branch.processing_bin.is_some() {
+            state.processing_bin = branch.processing_bin;
+        }
+    }
+}
+
+impl GstObjectImpl for RgbdBin {}
+impl ObjectImpl for RgbdBin {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<[glib::ParamSpec; 2]> = Lazy::new(|| {
+            [
+                glib::ParamSpecString::new(
+                    "stream",
+                    "Stream",
+                    "Name of the stream that is processed by the bin, e.g. `color`.",
+                    Some(DEFAULT_STREAM),
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecString::new(
+                    "bin-description",
+                    "Bin Description",
+                    "Description of the bin that processes the stream, in the syntax of
+                     gst-launch, e.g. `videoflip method=horizontal-flip`. It must have a single
+                     sink and src pad and keep the timestamps of the buffers, as the processed
+                     stream is matched with all other streams by timestamps. All streams pass
+                     through untouched if it is not set.",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+
+        obj.add_many(&[&self.demux, &self.mux])
+            .expect("rgbdbin: Failed to add rgbddemux and rgbdmux");
+
+        let sink_pad = gst::GhostPad::from_template_with_target(
+            &obj.pad_template("sink").unwrap(),
+            Some("sink"),
+            &self.demux.static_pad("sink").unwrap(),
+        )
+        .expect("rgbdbin: Failed to ghost sink pad");
+        let src_pad = gst::GhostPad::from_template_with_target(
+            &obj.pad_template("src").unwrap(),
+            Some("src"),
+            &self.mux.static_pad("src").unwrap(),
+        )
+        .expect("rgbdbin: Failed to ghost src pad");
+        obj.add_pad(&sink_pad)
+            .expect("rgbdbin: Failed to add sink pad");
+        obj.add_pad(&src_pad)
+            .expect("rgbdbin: Failed to add src pad");
+
+        let weak_obj = obj.downgrade();
+        self.demux.connect_pad_added(move |_, pad| {
+            if let Some(obj) = weak_obj.upgrade() {
+                RgbdBin::from_instance(&obj).demux_pad_added(&obj, pad);
+            }
+        });
+        let weak_obj = obj.downgrade();
+        self.demux.connect_pad_removed(move |_, pad| {
+            if let Some(obj) = weak_obj.upgrade() {
+                RgbdBin::from_instance(&obj).demux_pad_removed(&obj, pad);
+            }
+        });
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.lock().unwrap();
+
+        match pspec.name() {
+            "stream" => {
+                let stream = value
+                    .get::<Option<String>>()
+                    .unwrap()
+                    .unwrap_or_else(|| DEFAULT_STREAM.to_string());
+                gst_info!(
+                    CAT,
+                    "Changing property `stream` from {} to {}",
+                    settings.stream,
+                    stream
+                );
+                settings.stream = stream;
+            }
+            "bin-description" => {
+                let bin_description = value.get::<Option<String>>().unwrap();
+                gst_info!(
+                    CAT,
+                    "Changing property `bin-description` from {:?} to {:?}",
+                    settings.bin_description,
+                    bin_description
+                );
+                settings.bin_description = bin_description;
+            }
+            _ => unimplemented!("Property is not implemented"),
+        };
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.lock().unwrap();
+
+        match pspec.name() {
+            "stream" => settings.stream.to_value(),
+            "bin-description" => settings.bin_description.to_value(),
+            _ => unimplemented!("Property is not implemented"),
+        }
+    }
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(Some(plugin), "rgbdbin", gst::Rank::None, RgbdBin::type_())
+}
+
+#[cfg(test)]
+mod tests {
+    use gst::prelude::*;
+    use gst_depth_meta::rgbd;
+
+    const WIDTH: i32 = 4;
+    const HEIGHT: i32 = 4;
+
+    /// Create `video/rgbd` caps with `depth` and `color` streams.
+    fn rgbd_caps() -> gst::Caps {
+        let video = |format: &str| {
+            gst::Structure::builder("video/x-raw")
+                .field("format", format)
+                .field("width", WIDTH)
+                .field("height", HEIGHT)
+                .build()
+        };
+        rgbd::RgbdCaps::new()
+            .with_framerate(gst::Fraction::new(30, 1))
+            .with_stream(rgbd::RgbdStreamCaps::new("depth", video("GRAY16_LE")))
+            .with_stream(rgbd::RgbdStreamCaps::new("color", video("RGB")))
+            .to_caps()
+    }
+
+    /// Create the `index`-th frameset, whose buffers are filled with `index`. The `color` buffer
+    /// is flagged as delta unit if `drop_color` is set.
+    fn rgbd_buffer(index: u8, drop_color: bool) -> gst::Buffer {
+        let size = (WIDTH * HEIGHT) as usize;
+        let mut main_buffer = gst::Buffer::from_mut_slice(vec![index; size * 2]);
+        {
+            let main_buffer = main_buffer.get_mut().unwrap();
+            main_buffer.set_pts(gst::ClockTime::from_mseconds(index as u64 * 33));
+            rgbd::tag_buffer_with_title(main_buffer, "depth").unwrap();
+        }
+        let mut color_buffer = gst::Buffer::from_mut_slice(vec![index; size * 3]);
+        if drop_color {
+            color_buffer
+                .get_mut()
+                .unwrap()
+                .set_flags(gst::BufferFlags::DELTA_UNIT);
+        }
+        rgbd::attach_aux_buffer_and_tag(main_buffer.make_mut(), &mut color_buffer, "color")
+            .unwrap();
+        main_buffer
+    }
+
+    /// Get the value that the buffer is filled with.
+    fn fill_value(buffer: &gst::BufferRef) -> u8 {
+        buffer.map_readable().unwrap()[0]
+    }
+
+    #[test]
+    fn identity_keeps_framesets() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        let bin = gst::ElementFactory::make("rgbdbin", None).unwrap();
+        bin.set_property("bin-description", &"identity");
+        let mut harness = gst_check::Harness::with_element(&bin, Some("sink"), Some("src"));
+        harness.set_src_caps(rgbd_caps());
+
+        for index in 0..3 {
+            harness.push(rgbd_buffer(index, false)).unwrap();
+        }
+        for index in 0..3 {
+            let output = harness.pull().unwrap();
+            assert_eq!(
+                output.pts(),
+                Some(gst::ClockTime::from_mseconds(index as u64 * 33))
+            );
+            assert_eq!(rgbd::get_tag(&output).unwrap(), "depth");
+            assert_eq!(fill_value(&output), index);
+            let aux_buffers = rgbd::get_all_aux_buffers(&output).collect::<Vec<_>>();
+            assert_eq!(aux_buffers.len(), 1, "Found a wrong number of buffers");
+            assert_eq!(rgbd::get_tag(&aux_buffers[0]).unwrap(), "color");
+            assert_eq!(fill_value(&aux_buffers[0]), index);
+        }
+    }
+
+    #[test]
+    fn dropped_buffer_drops_frameset() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        let bin = gst::ElementFactory::make("rgbdbin", None).unwrap();
+        bin.set_property("bin-description", &"identity drop-buffer-flags=delta-unit");
+        let mut harness = gst_check::Harness::with_element(&bin, Some("sink"), Some("src"));
+        harness.set_src_caps(rgbd_caps());
+
+        // The processing bin drops the `color` buffer of the second frameset
+        harness.push(rgbd_buffer(0, false)).unwrap();
+        harness.push(rgbd_buffer(1, true)).unwrap();
+        harness.push(rgbd_buffer(2, false)).unwrap();
+
+        // Buffers of the remaining framesets must not be shifted against each other
+        for index in [0, 2].iter() {
+            let output = harness.pull().unwrap();
+            assert_eq!(fill_value(&output), *index);
+            let aux_buffers = rgbd::get_all_aux_buffers(&output).collect::<Vec<_>>();
+            assert_eq!(aux_buffers.len(), 1, "Found a wrong number of buffers");
+            assert_eq!(fill_value(&aux_buffers[0]), *index);
+        }
+    }
+}
@@ -19,6 +19,8 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
 
 /// Default value for to `distribute-timestamps` property
 const DEFAULT_DISTRIBUTE_TIMESTAMPS: bool = true;
+/// Default value for to `allow-not-linked` property
+const DEFAULT_ALLOW_NOT_LINKED: bool = false;
 
 /// A struct that identifies a stream.
 struct StreamIdentifier {
@@ -44,6 +46,12 @@ struct DemuxPad {
     // todo: Remove this flag - the stream-start event is sticky, therefore we can get this state from the pad itself
     /// A flag to indicate whether or not we have sent the "stream-start" event on the pad.
     pushed_stream_start: bool,
+    /// Whether the stream has its own framerate, i.e. `{stream}_framerate` in the sink CAPS that
+    /// differs from the shared framerate. Buffers of such streams keep their own timestamps.
+    own_framerate: bool,
+    /// Timestamp of the last buffer that was pushed for a stream with its own framerate. Slower
+    /// streams are contained in several framesets with the same buffer, which is pushed once.
+    last_pts: Mutex<Option<gst::ClockTime>>,
 }
 
 impl DemuxPad {
@@ -56,6 +64,8 @@ impl DemuxPad {
         Self {
             pad,
             pushed_stream_start: false,
+            own_framerate: false,
+            last_pts: Mutex::new(None),
         }
     }
 
@@ -78,6 +88,19 @@ type SrcPads = HashMap<String, DemuxPad>;
 struct Settings {
     /// Analogous to `distribute-timestamps` property
     distribute_timestamps: bool,
+    /// Analogous to `streams` property. All streams are selected if it is empty.
+    streams: Vec<String>,
+    /// Analogous to `allow-not-linked` property
+    allow_not_linked: bool,
+}
+
+impl Settings {
+    /// Determine whether the stream with the given name is selected by `streams` property.
+    /// # Arguments
+    /// * `stream_name` - The name of the stream, e.g. `depth`.
+    fn is_selected(&self, stream_name: &str) -> bool {
+        self.streams.is_empty() || self.streams.iter().any(|stream| stream == stream_name)
+    }
 }
 
 /// A struct representation of the `rgbddemux` element.
@@ -93,6 +116,11 @@ pub struct RgbdDemux {
     /// Utility struct that groups received Tags
     tags_not_sent: Mutex<TagList>,
     pad_to_send_tags_on: Mutex<Option<gst::Pad>>,
+    /// Information about the streams of the negotiated sink CAPS.
+    frame_info: Mutex<Option<rgbd::RgbdFrameInfo>>,
+    /// Sequence number of the last seek that was forwarded upstream, together with its result.
+    /// Used to forward a seek only once, even if it arrives on multiple src pads.
+    last_seek: Mutex<Option<(gst::Seqnum, bool)>>,
     sink_pad: gst::Pad,
 }
 
@@ -128,12 +156,16 @@ impl ObjectSubclass for RgbdDemux {
         Self {
             settings: RwLock::new(Settings {
                 distribute_timestamps: DEFAULT_DISTRIBUTE_TIMESTAMPS,
+                streams: Vec::new(),
+                allow_not_linked: DEFAULT_ALLOW_NOT_LINKED,
             }),
             src_pads: RwLock::new(HashMap::new()),
             flow_combiner: Mutex::new(gst_base::UniqueFlowCombiner::new()),
             stream_identifier: Mutex::new(None),
             tags_not_sent: Mutex::new(TagList::new()),
             pad_to_send_tags_on: Mutex::new(None),
+            frame_info: Mutex::new(None),
+            last_seek: Mutex::new(None),
             sink_pad,
         }
     }
@@ -173,12 +205,9 @@ impl ElementImpl for RgbdDemux {
 
     fn pad_templates() -> &'static [gst::PadTemplate] {
         static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 2]> = Lazy::new(|| {
-            let mut src_caps = gst::Caps::new_simple("video/x-raw", &[]);
-            {
-                let src_caps = src_caps.get_mut().unwrap();
-                src_caps.append(gst::Caps::new_simple("meta/x-klv", &[("parsed", &true)]));
-                src_caps.append(gst::Caps::new_simple("image/jpeg", &[]));
-            }
+            // Besides `video/x-raw`, `image/jpeg` and `meta/x-klv`, streams of any
+            // `application/x-*` media type are demuxed, which cannot be expressed in template CAPS
+            let src_caps = gst::Caps::new_any();
 
             [
                 gst::PadTemplate::new(
@@ -258,10 +287,87 @@ impl RgbdDemux {
                 true
             }
 
+            EventView::FlushStop(_) | EventView::Segment(_) => {
+                // Timestamps of the new segment may be lower than those that were pushed before
+                self.reset_last_pts();
+                self.sink_pad.event_default(Some(element), event)
+            }
+
             _ => self.sink_pad.event_default(Some(element), event),
         }
     }
 
+    /// Called whenever an event is received at one of the src pads. All of these events travel
+    /// upstream, e.g. seek, QoS and reconfigure, and are therefore forwarded to the sink pad.
+    /// A seek is forwarded only once, even if it arrives on several src pads, e.g. because each
+    /// sink of the pipeline sends it upstream.
+    /// # Arguments
+    /// * `pad` - The src pad that received the event.
+    /// * `element` - The element that represents the `rgbddemux` in GStreamer.
+    /// * `event` - The event that should be handled.
+    fn src_event(&self, pad: &gst::Pad, element: &RgbdDemuxObject, event: gst::Event) -> bool {
+        if let gst::EventView::Seek(_) = event.view() {
+            let seqnum = event.seqnum();
+            {
+                let mut last_seek = self.last_seek.lock().unwrap();
+                if let Some((last_seqnum, result)) = *last_seek {
+                    if last_seqnum == seqnum {
+                        gst_debug!(
+                            CAT,
+                            obj: pad,
+                            "Seek {:?} was already forwarded upstream, skipping",
+                            seqnum
+                        );
+                        return result;
+                    }
+                }
+                // Record the seek before forwarding it, without holding the lock while it travels
+                // upstream. Duplicates that arrive meanwhile assume that it succeeds.
+                *last_seek = Some((seqnum, true));
+            }
+
+            gst_debug!(CAT, obj: pad, "Forwarding seek {:?} upstream", seqnum);
+            let result = self.sink_pad.push_event(event);
+            if !result {
+                gst_warning!(CAT, obj: element, "Upstream failed to handle seek");
+            }
+            let mut last_seek = self.last_seek.lock().unwrap();
+            if matches!(*last_seek, Some((last_seqnum, _)) if last_seqnum == seqnum) {
+                *last_seek = Some((seqnum, result));
+            }
+            return result;
+        }
+
+        gst_log!(CAT, obj: pad, "Forwarding {:?} event upstream", event.type_());
+        self.sink_pad.push_event(event)
+    }
+
+    /// Called whenever a query is received at one of the src pads. Queries about the position,
+    /// duration, latency and seeking capabilities of the stream are answered by upstream, all
+    /// other queries are handled by the default handler.
+    /// # Arguments
+    /// * `pad` - The src pad that received the query.
+    /// * `element` - The element that represents the `rgbddemux` in GStreamer.
+    /// * `query` - The query that should be answered.
+    fn src_query(
+        &self,
+        pad: &gst::Pad,
+        element: &RgbdDemuxObject,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        use gst::QueryView;
+        match query.view() {
+            QueryView::Position(_)
+            | QueryView::Duration(_)
+            | QueryView::Latency(_)
+            | QueryView::Seeking(_) => {
+                gst_log!(CAT, obj: pad, "Forwarding {:?} query upstream", query);
+                self.sink_pad.peer_query(query)
+            }
+            _ => pad.query_default(Some(element), query),
+        }
+    }
+
     /// Create additional src pads, which happens as a result of a CAPS renegotiation.
     /// # Arguments
     /// * `element` - The element that represents `rgbddemux` in GStreamer.
@@ -277,48 +383,38 @@ impl RgbdDemux {
             rgbd_caps
         );
 
-        // Extract the `video/rgbd` caps fields from gst::CapsRef
-        let rgbd_caps = rgbd_caps.structure(0).ok_or_else(|| {
+        // Parse the `video/rgbd` caps to determine what streams are contained within them
+        let rgbd_caps = rgbd::RgbdCaps::from_caps(rgbd_caps).map_err(|err| {
             gst::error_msg!(
-                gst::CoreError::Tag,
-                ["Invalid `video/rgbd` caps for creation of additional src pads",]
-            )
-        })?;
-
-        // Determine what streams are contained within the caps
-        let streams = rgbd_caps.get::<gst::Array>("streams").map_err(|err| {
-            gst::error_msg!(
-                gst::CoreError::Tag,
+                gst::CoreError::Caps,
                 [
-                    "No `streams` field detected in `video/rgbd` caps: {:?}",
+                    "Invalid `video/rgbd` caps for creation of additional src pads: {}",
                     err
                 ]
             )
         })?;
+        // Create pads only for the streams that are selected by `streams` property
+        let streams: Vec<&str> = {
+            let settings = self.settings.read().unwrap();
+            for selected in settings.streams.iter() {
+                if rgbd_caps.stream(selected).is_err() {
+                    gst_warning!(
+                        CAT,
+                        obj: element,
+                        "Selected stream {} is not contained in the CAPS",
+                        selected
+                    );
+                }
+            }
+            rgbd_caps
+                .stream_names()
+                .filter(|stream_name| settings.is_selected(stream_name))
+                .collect()
+        };
 
-        let streams: Vec<&str> = streams
-            .as_slice()
-            .iter()
-            .map(|s| s.get::<&str>().unwrap())
-            .collect();
-
-        if streams.is_empty() {
-            return Err(gst::error_msg!(
-                gst::CoreError::Tag,
-                ["Cannot detect any streams in `video/rgbd` caps under field `streams`",]
-            ));
-        }
-
-        // Get a common framerate for all streams
-        let common_framerate = rgbd_caps.get::<gst::Fraction>("framerate").map_err(|err| {
-            gst::error_msg!(
-                gst::CoreError::Tag,
-                [
-                    "Cannot detect any `framerate` in `video/rgbd` caps: {:?}",
-                    err
-                ]
-            )
-        })?;
+        // Determine how to find the streams in the incoming buffers
+        let frame_info = rgbd::RgbdFrameInfo::from_rgbd_caps(&rgbd_caps)
+            .map_err(|err| gst::error_msg!(gst::CoreError::Caps, ["{}", err]))?;
 
         let mut src_pads = self.src_pads.write().unwrap();
 
@@ -329,8 +425,9 @@ impl RgbdDemux {
         let mut flow_combiner = self.flow_combiner.lock().unwrap();
         for stream_name in streams.iter() {
             // Determine the appropriate caps for the stream
-            let new_pad_caps =
-                self.extract_stream_caps(stream_name, rgbd_caps, common_framerate)?;
+            let new_pad_caps = rgbd_caps
+                .stream_caps(stream_name)
+                .map_err(|err| gst::error_msg!(gst::CoreError::Caps, ["{}", err]))?;
 
             self.create_new_src_pad(
                 element,
@@ -339,86 +436,20 @@ impl RgbdDemux {
                 stream_name,
                 &new_pad_caps,
             );
+
+            let own_framerate = rgbd_caps.stream(stream_name).map_or(false, |stream| {
+                stream.framerate().is_some() && stream.framerate() != rgbd_caps.framerate()
+            });
+            if let Some(src_pad) = src_pads.get_mut(*stream_name) {
+                src_pad.own_framerate = own_framerate;
+            }
         }
 
         element.no_more_pads();
+        *self.frame_info.lock().unwrap() = Some(frame_info);
         Ok(())
     }
 
-    /// Extract CAPS for the given stream from the given rgbd_caps.
-    /// # Arguments
-    /// * `stream_name` - The name of the stream to extract CAPS for, e.g. `depth`.
-    /// * `rgbd_caps` - A reference to the `video/rgbd` CAPS, from which the stream's CAPS should be extracted.
-    /// * `common_framerate` - The framerate of all the streams.
-    fn extract_stream_caps(
-        &self,
-        stream_name: &str,
-        rgbd_caps: &gst::StructureRef,
-        common_framerate: gst::Fraction,
-    ) -> Result<gst::Caps, gst::ErrorMessage> {
-        // Return "meta/x-klv" if we are dealing with metadata stream
-        if stream_name.contains("meta") {
-            return Ok(gst::Caps::new_simple("meta/x-klv", &[("parsed", &true)]));
-        };
-        // Get the format of a stream
-        let stream_format = rgbd_caps
-            .get::<String>(&format!("{}_format", stream_name))
-            .map_err(|err| {
-                gst::error_msg!(
-                    gst::CoreError::Tag,
-                    [
-                        "Cannot detect any `format` in `video/rgbd` caps for `{}` stream: {:?}",
-                        stream_name,
-                        err
-                    ]
-                )
-            })?;
-
-        // Return "image/jpeg" CAPS if the format is MJPG
-        if stream_format.contains("jpeg") {
-            return Ok(gst::Caps::new_simple("image/jpeg", &[]));
-        }
-
-        // Get the width of a stream
-        let stream_width = rgbd_caps
-            .get::<i32>(&format!("{}_width", stream_name))
-            .map_err(|err| {
-                gst::error_msg!(
-                    gst::CoreError::Tag,
-                    [
-                        "Cannot detect any `width` in `video/rgbd` caps for `{}` stream: {:?}",
-                        stream_name,
-                        err
-                    ]
-                )
-            })?;
-
-        // Get the height of a stream
-        let stream_height = rgbd_caps
-            .get::<i32>(&format!("{}_height", stream_name))
-            .map_err(|err| {
-                gst::error_msg!(
-                    gst::CoreError::Tag,
-                    [
-                        "Cannot detect any `height` in `video/rgbd` caps for `{}` stream: {:?}",
-                        stream_name,
-                        err
-                    ]
-                )
-            })?;
-
-        // Create caps for the new "video/x-raw" src pad
-        Ok(gst::Caps::new_simple(
-            "video/x-raw",
-            &[
-                ("format", &stream_format),
-                ("width", &stream_width),
-                ("height", &stream_height),
-                ("framerate", &common_framerate),
-            ],
-        ))
-    }
-
     /// Remove pads from `src_pads` that are no longer needed in a set of `allowed_streams`.
     /// Note that this function removes the pads both from element and `src_pads`.
     /// # Arguments
@@ -514,6 +545,20 @@ impl RgbdDemux {
             Some(new_src_pad_name),
         )
         .flags(gst::PadFlags::FIXED_CAPS)
+        .event_function(|pad, parent, event| {
+            Self::catch_panic_pad_function(
+                parent,
+                || false,
+                |this, element| this.src_event(pad, element, event),
+            )
+        })
+        .query_function(|pad, parent, query| {
+            Self::catch_panic_pad_function(
+                parent,
+                || false,
+                |this, element| this.src_query(pad, element, query),
+            )
+        })
         .build();
 
         // Add the pad to the element
@@ -585,7 +630,10 @@ impl RgbdDemux {
         main_buffer: gst::Buffer,
     ) -> Result<gst::FlowSuccess, gst::FlowError> {
         // Distribute the timestamp of the main buffer to the auxiliary buffers, if enabled
-        let distrubute_timestamps = self.settings.read().unwrap().distribute_timestamps;
+        let (distrubute_timestamps, allow_not_linked) = {
+            let settings = self.settings.read().unwrap();
+            (settings.distribute_timestamps, settings.allow_not_linked)
+        };
 
         let common_pts = main_buffer.pts();
         let common_dts = main_buffer.dts();
@@ -596,24 +644,86 @@ impl RgbdDemux {
             "Pushing buffers to their corresponding pads",
         );
 
+        // Find the buffers of all streams within the main buffer
+        let frame_info = self.frame_info.lock().unwrap();
+        let frame_info = frame_info.as_ref().ok_or_else(|| {
+            gst_warning!(CAT, obj: element, "Received a buffer before CAPS");
+            gst::FlowError::NotNegotiated
+        })?;
+        let frame = rgbd::RgbdFrame::from_buffer_ref(&main_buffer, frame_info).map_err(|e| {
+            gst_warning!(CAT, obj: element, "Failed to get buffer tag: {}", e);
+            gst::FlowError::Error
+        })?;
+
         // Go through all buffers in order to extract them and push to the corresponding src pads
         let src_pads = self.src_pads.read().unwrap();
 
         let mut flow_combiner = self.flow_combiner.lock().unwrap();
-        for mut buffer in rgbd::get_all_buffers(main_buffer) {
-            let buffer = buffer.make_mut();
-            if distrubute_timestamps {
-                buffer.set_dts(common_dts);
-                buffer.set_pts(common_pts);
-                buffer.set_duration(common_duration);
+        for (stream_name, buffer) in frame.buffers() {
+            // Streams that are not selected have no pad, drop their buffers right away
+            if !src_pads.contains_key(stream_name) {
+                gst_trace!(
+                    CAT,
+                    obj: element,
+                    "Dropping buffer of stream {}, which is not selected",
+                    stream_name
+                );
+                continue;
             }
 
-            rgbd::remove_aux_buffers(buffer);
-            flow_combiner.update_flow(self.push_buffer_to_corresponding_pad(
+            // Streams with their own framerate keep their own timestamps, which the `rgbdmux`
+            // assigned. A slower stream repeats its most recent buffer, which is pushed only once.
+            let own_framerate = match src_pads.get(stream_name) {
+                Some(src_pad) if src_pad.own_framerate => {
+                    let mut last_pts = src_pad.last_pts.lock().unwrap();
+                    if buffer.pts().is_some() && buffer.pts() <= *last_pts {
+                        gst_trace!(
+                            CAT,
+                            obj: element,
+                            "Buffer of stream {} was already pushed, skipping",
+                            stream_name
+                        );
+                        continue;
+                    }
+                    *last_pts = buffer.pts();
+                    true
+                }
+                _ => false,
+            };
+
+            let mut buffer = buffer.to_owned();
+            let buffer_mut = buffer.make_mut();
+            if distrubute_timestamps && !own_framerate {
+                buffer_mut.set_dts(common_dts);
+                buffer_mut.set_pts(common_pts);
+                buffer_mut.set_duration(common_duration);
+            }
+
+            rgbd::remove_aux_buffers(buffer_mut);
+
+            // Make regions of interest of all streams available on each stream
+            rgbd::remove_roi_metas(buffer_mut);
+            rgbd::copy_roi_metas(&main_buffer, buffer_mut);
+            rgbd::roi_meta_to_video_roi_metas(buffer_mut, stream_name);
+
+            let flow = match self.push_buffer_to_corresponding_pad(
                 element,
                 &src_pads,
-                buffer.copy(),
-            ))?;
+                stream_name,
+                buffer,
+            ) {
+                Err(gst::FlowError::NotLinked) if allow_not_linked => {
+                    gst_trace!(
+                        CAT,
+                        obj: element,
+                        "Ignoring not-linked downstream of stream {}",
+                        stream_name
+                    );
+                    Ok(gst::FlowSuccess::Ok)
+                }
+                flow => flow,
+            };
+            flow_combiner.update_flow(flow)?;
         }
         Ok(gst::FlowSuccess::Ok)
     }
@@ -621,29 +731,25 @@ impl RgbdDemux {
     /// Push the given buffer to the src pad that was allocated for it.
     /// # Arguments
     /// * `element` - The element that represents the `rgbddemux` in GStreamer.
-    /// * `src_pads` - A hash map that associates buffer title tags with their corresponding pad.
+    /// * `src_pads` - A hash map that associates stream names with their corresponding pad.
+    /// * `stream_name` - The name of the stream that the buffer belongs to.
     /// * `buffer` - The buffer that should be pushed further downstream.
     fn push_buffer_to_corresponding_pad(
         &self,
         element: &RgbdDemuxObject,
         src_pads: &HashMap<String, DemuxPad>,
+        stream_name: &str,
         buffer: gst::Buffer,
     ) -> Result<gst::FlowSuccess, gst::FlowError> {
-        // Extract tag title from the buffer
-        let tag_title = rgbd::get_tag(&buffer).map_err(|e| {
-            gst_warning!(CAT, obj: element, "Failed to get buffer tag: {}", e);
-            gst::FlowError::Error
-        })?;
-
-        // Match the tag title with a corresponding src pad
-        if let Some(src_pad) = src_pads.get(&tag_title) {
+        // Match the stream name with a corresponding src pad
+        if let Some(src_pad) = src_pads.get(stream_name) {
             // Do not attempt to push buffers, if pad is not linked
             if !src_pad.pad.is_linked() {
                 gst_debug!(
                     CAT,
                     obj: element,
                     "Buffer {} is not pushed because its pad is not linked",
-                    tag_title
+                    stream_name
                 );
                 return Ok(gst::FlowSuccess::Ok);
             }
@@ -653,7 +759,7 @@ impl RgbdDemux {
                 CAT,
                 obj: element,
                 "Pushing buffer for stream {} to the corresponding pad",
-                tag_title
+                stream_name
             );
             src_pad.pad.push(buffer)
         } else {
@@ -664,7 +770,7 @@ impl RgbdDemux {
                 obj: element,
                 "Cannot push buffer tagged as {} because no corresponding pad was \
                  created. Caps: {:?}",
-                tag_title,
+                stream_name,
                 self.sink_pad.current_caps(),
             );
             Ok(gst::FlowSuccess::Ok)
@@ -717,25 +823,59 @@ impl RgbdDemux {
         // Reset internals (except for settings)
         self.flow_combiner.lock().unwrap().reset();
         *self.stream_identifier.lock().unwrap() = None;
+        *self.last_seek.lock().unwrap() = None;
         // Reset stream start tracker, but keep the pads
         for (_, src_pad) in self.src_pads.write().unwrap().iter_mut() {
             src_pad.pushed_stream_start = false;
         }
+        self.reset_last_pts();
+    }
+
+    /// Forget the timestamps of the buffers that were last pushed for streams with their own
+    /// framerate, e.g. after a flush or a new segment.
+    fn reset_last_pts(&self) {
+        for src_pad in self.src_pads.read().unwrap().values() {
+            *src_pad.last_pts.lock().unwrap() = None;
+        }
     }
 }
 
 impl GstObjectImpl for RgbdDemux {}
 impl ObjectImpl for RgbdDemux {
     fn properties() -> &'static [glib::ParamSpec] {
-        static PROPERTIES: Lazy<[glib::ParamSpec; 1]> = Lazy::new(|| {
-            [ParamSpecBoolean::new(
-                "distribute-timestamps",
-                "Distribute Timestamps",
-                "If enabled, timestamps of the main buffers will be distributed to the
-                 auxiliary buffers embedded within the `video/rbgd` stream.",
-                DEFAULT_DISTRIBUTE_TIMESTAMPS,
-                ParamFlags::READWRITE,
-            )]
+        static PROPERTIES: Lazy<[glib::ParamSpec; 3]> = Lazy::new(|| {
+            [
+                ParamSpecBoolean::new(
+                    "distribute-timestamps",
+                    "Distribute Timestamps",
+                    "If enabled, timestamps of the main buffers will be distributed to the
+                     auxiliary buffers embedded within the `video/rbgd` stream.",
+                    DEFAULT_DISTRIBUTE_TIMESTAMPS,
+                    ParamFlags::READWRITE,
+                ),
+                gst::ParamSpecArray::new(
+                    "streams",
+                    "Streams",
+                    "Names of the streams for which `src_%s` pads are created, e.g. <depth,color>.
+                     Buffers of all other streams are dropped. All streams are selected if empty.",
+                    Some(&ParamSpecString::new(
+                        "stream",
+                        "Stream",
+                        "Name of a stream",
+                        None,
+                        ParamFlags::READWRITE,
+                    )),
+                    ParamFlags::READWRITE,
+                ),
+                ParamSpecBoolean::new(
+                    "allow-not-linked",
+                    "Allow Not Linked",
+                    "If enabled, src pads whose downstream is not linked are ignored instead of
+                     returning not-linked, e.g. for dynamic pipelines.",
+                    DEFAULT_ALLOW_NOT_LINKED,
+                    ParamFlags::READWRITE,
+                ),
+            ]
         });
 
         PROPERTIES.as_ref()
@@ -751,7 +891,7 @@ impl ObjectImpl for RgbdDemux {
 
     fn set_property(
         &self,
-        _obj: &Self::Type,
+        obj: &Self::Type,
         _id: usize,
         value: &glib::Value,
         pspec: &glib::ParamSpec,
@@ -764,6 +904,35 @@ impl ObjectImpl for RgbdDemux {
                     get_property_and_debug(*CAT, value, prop, settings.distribute_timestamps);
                 settings.distribute_timestamps = distribute_timestamps;
             }
+            "streams" => {
+                let streams = value
+                    .get::<gst::Array>()
+                    .unwrap()
+                    .as_slice()
+                    .iter()
+                    .map(|stream| stream.get::<String>().unwrap())
+                    .collect::<Vec<String>>();
+                gst_info!(
+                    CAT,
+                    "Changing property `streams` from {:?} to {:?}",
+                    settings.streams,
+                    streams
+                );
+                settings.streams = streams;
+                drop(settings);
+
+                // Add or remove pads right away if the CAPS are already known
+                if let Some(caps) = self.sink_pad.current_caps() {
+                    if let Err(e) = self.create_src_pads_from_sink_caps(obj, &caps) {
+                        gst_error!(CAT, obj: obj, "{}", e);
+                    }
+                }
+            }
+            prop @ "allow-not-linked" => {
+                let allow_not_linked =
+                    get_property_and_debug(*CAT, value, prop, settings.allow_not_linked);
+                settings.allow_not_linked = allow_not_linked;
+            }
             _ => unimplemented!("Property is not implemented"),
         };
     }
@@ -773,6 +942,11 @@ impl ObjectImpl for RgbdDemux {
 
         match pspec.name() {
             "distribute-timestamps" => settings.distribute_timestamps.to_value(),
+            "streams" => gst::Array::from_values(
+                settings.streams.iter().map(|stream| stream.to_send_value()),
+            )
+            .to_value(),
+            "allow-not-linked" => settings.allow_not_linked.to_value(),
             _ => unimplemented!("Property is not implemented"),
         }
     }
@@ -786,3 +960,225 @@ pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
         RgbdDemux::type_(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use gst::prelude::*;
+    use gst_depth_meta::rgbd;
+    use std::sync::{Arc, Mutex};
+
+    /// Duration of a frame at 30 FPS, in nanoseconds
+    const FRAME_NS: u64 = 33_333_333;
+
+    /// Get the duration of the given number of frames at 30 FPS.
+    fn frames(count: u64) -> gst::ClockTime {
+        gst::ClockTime::from_nseconds(count * FRAME_NS)
+    }
+
+    /// Create `rgbddemux` with a harness on its sink pad. The CAPS are not set yet, such that
+    /// properties can be changed before the src pads are created.
+    fn setup() -> (gst::Element, gst_check::Harness) {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        let harness = gst_check::Harness::with_padnames("rgbddemux", Some("sink"), None);
+        let demux = harness.element().unwrap();
+        (demux, harness)
+    }
+
+    /// Link the `src_{stream}` pad of `demux` to a new sink pad, which collects all buffers that
+    /// are pushed to it and returns `flow`.
+    /// # Returns
+    /// * Tuple of the new sink pad and the collected buffers.
+    fn collect(
+        demux: &gst::Element,
+        stream_name: &str,
+        flow: Result<gst::FlowSuccess, gst::FlowError>,
+    ) -> (gst::Pad, Arc<Mutex<Vec<gst::Buffer>>>) {
+        let buffers = Arc::new(Mutex::new(Vec::new()));
+        let sink_pad = {
+            let buffers = buffers.clone();
+            gst::Pad::builder(Some("sink"), gst::PadDirection::Sink)
+                .chain_function(move |_, _, buffer| {
+                    buffers.lock().unwrap().push(buffer);
+                    flow
+                })
+                .event_function(|_, _, _| true)
+                .build()
+        };
+        sink_pad.set_active(true).unwrap();
+        demux
+            .static_pad(&format!("src_{}", stream_name))
+            .unwrap()
+            .link(&sink_pad)
+            .unwrap();
+        (sink_pad, buffers)
+    }
+
+    /// Create a frameset with a `depth` main buffer and an auxiliary buffer of each of the given
+    /// streams.
+    /// # Arguments
+    /// * `pts` - Timestamp of the main buffer.
+    /// * `aux_streams` - Names of the auxiliary streams with the timestamps of their buffers.
+    fn frameset(pts: gst::ClockTime, aux_streams: &[(&str, gst::ClockTime)]) -> gst::Buffer {
+        let mut main_buffer = gst::Buffer::with_size(4 * 4 * 2).unwrap();
+        {
+            let main_buffer = main_buffer.get_mut().unwrap();
+            main_buffer.set_pts(pts);
+            rgbd::tag_buffer_with_title(main_buffer, "depth").unwrap();
+        }
+        for (stream_name, pts) in aux_streams {
+            let mut buffer = gst::Buffer::with_size(4 * 4 * 3).unwrap();
+            buffer.get_mut().unwrap().set_pts(*pts);
+            rgbd::attach_aux_buffer_and_tag(
+                main_buffer.get_mut().unwrap(),
+                &mut buffer,
+                stream_name,
+            )
+            .unwrap();
+        }
+        main_buffer
+    }
+
+    /// Get the timestamps of the given buffers.
+    fn timestamps(buffers: &Mutex<Vec<gst::Buffer>>) -> Vec<Option<gst::ClockTime>> {
+        buffers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|buffer| buffer.pts())
+            .collect()
+    }
+
+    #[test]
+    fn select_streams_and_allow_not_linked() {
+        let (demux, mut harness) = setup();
+        demux.set_property(
+            "streams",
+            gst::Array::from_values(std::iter::once("depth".to_send_value())),
+        );
+        demux.set_property("allow-not-linked", true);
+        harness.set_src_caps_str(
+            "video/rgbd,streams=<depth,color,ir>,framerate=30/1,\
+             depth_format=GRAY16_LE,depth_width=4,depth_height=4,\
+             color_format=RGB,color_width=4,color_height=4,\
+             ir_format=GRAY8,ir_width=4,ir_height=4",
+        );
+
+        // Only the selected stream has a pad
+        let pad_names = demux
+            .src_pads()
+            .iter()
+            .map(|pad| pad.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(pad_names, ["src_depth"]);
+
+        // A selected pad that is not linked does not stop the stream
+        let input = frameset(frames(0), &[("color", frames(0)), ("ir", frames(0))]);
+        assert_eq!(harness.push(input), Ok(gst::FlowSuccess::Ok));
+
+        // Neither does a selected pad whose downstream is not linked
+        let (_sink_pad, depth) = collect(&demux, "depth", Err(gst::FlowError::NotLinked));
+        let input = frameset(frames(1), &[("color", frames(1)), ("ir", frames(1))]);
+        let aux_buffers = rgbd::get_all_aux_buffers(&input).collect::<Vec<_>>();
+        assert_eq!(harness.push(input), Ok(gst::FlowSuccess::Ok));
+        assert_eq!(timestamps(&depth), [Some(frames(1))]);
+        // Buffers of the streams that are not selected are dropped together with the frameset
+        assert!(aux_buffers.iter().all(|buffer| buffer.is_writable()));
+
+        demux.set_property("allow-not-linked", false);
+        let input = frameset(frames(2), &[("color", frames(2)), ("ir", frames(2))]);
+        assert_eq!(harness.push(input), Err(gst::FlowError::NotLinked));
+    }
+
+    #[test]
+    fn own_framerate_buffer_pushed_once() {
+        let (demux, mut harness) = setup();
+        harness.set_src_caps_str(
+            "video/rgbd,streams=<depth,color>,framerate=30/1,\
+             depth_format=GRAY16_LE,depth_width=4,depth_height=4,\
+             color_format=RGB,color_width=4,color_height=4,color_framerate=15/1",
+        );
+        let (_depth_pad, depth) = collect(&demux, "depth", Ok(gst::FlowSuccess::Ok));
+        let (_color_pad, color) = collect(&demux, "color", Ok(gst::FlowSuccess::Ok));
+
+        // The slower stream repeats its buffer in every other frameset
+        for count in 0..4 {
+            let color_pts = frames(count / 2 * 2);
+            harness
+                .push(frameset(frames(count), &[("color", color_pts)]))
+                .unwrap();
+        }
+        assert_eq!(
+            timestamps(&depth),
+            (0..4).map(|count| Some(frames(count))).collect::<Vec<_>>()
+        );
+        assert_eq!(timestamps(&color), [Some(frames(0)), Some(frames(2))]);
+
+        // The repeated buffer is pushed again after a flush
+        harness.push_event(gst::event::FlushStart::new());
+        harness.push_event(gst::event::FlushStop::new(true));
+        let segment = gst::FormattedSegment::<gst::ClockTime>::new();
+        harness.push_event(gst::event::Segment::new(&segment));
+        harness
+            .push(frameset(frames(2), &[("color", frames(2))]))
+            .unwrap();
+        assert_eq!(
+            timestamps(&color),
+            [Some(frames(0)), Some(frames(2)), Some(frames(2))]
+        );
+    }
+
+    #[test]
+    fn seek_reentering_from_upstream_probe() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        let mut harness = gst_check::Harness::with_padnames("rgbddemux", Some("sink"), None);
+        harness.set_src_caps_str(
+            "video/rgbd,streams=<depth,color>,framerate=30/1,\
+             depth_format=GRAY16_LE,depth_width=4,depth_height=4,\
+             color_format=RGB,color_width=4,color_height=4",
+        );
+        let demux = harness.element().unwrap();
+        let src_depth = demux.static_pad("src_depth").unwrap();
+        let src_color = demux.static_pad("src_color").unwrap();
+
+        // Send the same seek on the other src pad while the first one travels upstream
+        let reentrant_result = Arc::new(Mutex::new(None));
+        {
+            let reentrant_result = reentrant_result.clone();
+            harness.srcpad().unwrap().add_probe(
+                gst::PadProbeType::EVENT_UPSTREAM,
+                move |_, info| {
+                    if let Some(gst::PadProbeData::Event(ref event)) = info.data {
+                        if event.type_() == gst::EventType::Seek {
+                            *reentrant_result.lock().unwrap() =
+                                Some(src_color.send_event(event.clone()));
+                        }
+                    }
+                    gst::PadProbeReturn::Ok
+                },
+            );
+        }
+
+        let seek = gst::event::Seek::new(
+            1.0,
+            gst::SeekFlags::FLUSH,
+            gst::SeekType::Set,
+            Some(gst::ClockTime::ZERO),
+            gst::SeekType::None,
+            gst::ClockTime::NONE,
+        );
+        assert!(src_depth.send_event(seek));
+        assert_eq!(*reentrant_result.lock().unwrap(), Some(true));
+        // The duplicate must not have been forwarded upstream a second time
+        let mut seeks = 0;
+        while let Some(event) = harness.try_pull_upstream_event() {
+            if event.type_() == gst::EventType::Seek {
+                seeks += 1;
+            }
+        }
+        assert_eq!(seeks, 1);
+    }
+}
@@ -0,0 +1,470 @@
+//! On-disk format of `video/rgbd` recordings, which is written by `rgbdfilesink` and read by
+//! `rgbdfilesrc`.
+//!
+//! A recording starts with the 8-byte magic `RGBDFILE` and the 32-bit format version, followed by
+//! records. Each record consists of its 1-byte type, the 32-bit length of its payload and the
+//! payload itself:
+//! * `C` - The `video/rgbd` caps as string. It precedes all framesets that use these caps.
+//! * `M` - The serialised `CameraMeta`, as carried by the `camera_meta_tag`.
+//! * `F` - The PTS and DTS of the main buffer as 64-bit nanoseconds (`u64::MAX` if none), followed
+//!   by the frameset serialised as in [frameset](../frameset/index.html).
+//! * `I` - The seek index, which contains the PTS, the offset of the `F` record and the offset of
+//!   the preceding `C` record of each frameset, all as 64-bit integers.
+//!
+//! A complete recording ends with the 64-bit offset of the `I` record and the 8-byte magic
+//! `RGBDINDX`. Recordings without index, e.g. if recording was interrupted, are indexed while
+//! opening them. All integers are big-endian.
+use std::convert::TryInto;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Magic bytes at the start of a recording.
+const FILE_MAGIC: &[u8; 8] = b"RGBDFILE";
+/// Version of the format.
+const FILE_VERSION: u32 = 1;
+/// Magic bytes at the end of a recording with index.
+const INDEX_MAGIC: &[u8; 8] = b"RGBDINDX";
+/// Length of the header at the start of a recording.
+const FILE_HEADER_LEN: u64 = 12;
+/// Length of the footer at the end of a recording with index.
+const FOOTER_LEN: u64 = 16;
+/// Length of the header of each record.
+const RECORD_HEADER_LEN: u64 = 5;
+/// Length of an entry of the seek index.
+const INDEX_ENTRY_LEN: usize = 24;
+/// Timestamp of a buffer without timestamp.
+const NO_TIMESTAMP: u64 = u64::MAX;
+
+/// Types of records in a recording.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RecordType {
+    Caps = b'C' as isize,
+    CameraMeta = b'M' as isize,
+    Frameset = b'F' as isize,
+    Index = b'I' as isize,
+}
+
+impl RecordType {
+    /// Parse the type of a record.
+    /// # Arguments
+    /// * `record_type` - The type byte of the record.
+    fn from_u8(record_type: u8) -> io::Result<Self> {
+        match record_type {
+            b'C' => Ok(Self::Caps),
+            b'M' => Ok(Self::CameraMeta),
+            b'F' => Ok(Self::Frameset),
+            b'I' => Ok(Self::Index),
+            _ => Err(invalid_data(&format!(
+                "Unknown record type {}",
+                record_type
+            ))),
+        }
+    }
+}
+
+/// A record read from a recording.
+#[derive(Debug)]
+pub enum Record {
+    /// The `video/rgbd` caps of the following framesets.
+    Caps(gst::Caps),
+    /// The serialised `CameraMeta`.
+    CameraMeta(String),
+    /// A serialised frameset with the timestamps of its main buffer.
+    Frameset {
+        pts: Option<gst::ClockTime>,
+        dts: Option<gst::ClockTime>,
+        data: Vec<u8>,
+    },
+}
+
+/// An entry of the seek index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexEntry {
+    /// PTS of the main buffer of the frameset.
+    pub pts: gst::ClockTime,
+    /// Offset of the frameset record.
+    pub offset: u64,
+    /// Offset of the caps record that precedes the frameset.
+    pub caps_offset: u64,
+}
+
+/// Writer of `video/rgbd` recordings.
+pub struct RgbdFileWriter<W: Write> {
+    /// The underlying writer.
+    writer: W,
+    /// Offset of the next record.
+    position: u64,
+    /// Offset of the last caps record.
+    caps_offset: Option<u64>,
+    /// The seek index, containing all framesets that were written.
+    index: Vec<IndexEntry>,
+}
+
+impl<W: Write> RgbdFileWriter<W> {
+    /// Start a new recording by writing its header.
+    /// # Arguments
+    /// * `writer` - The writer, e.g. a buffered file.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(FILE_MAGIC)?;
+        writer.write_all(&FILE_VERSION.to_be_bytes())?;
+        Ok(Self {
+            writer,
+            position: FILE_HEADER_LEN,
+            caps_offset: None,
+            index: Vec::new(),
+        })
+    }
+
+    /// Write `video/rgbd` caps, which apply to all following framesets.
+    /// # Arguments
+    /// * `caps` - The `video/rgbd` caps.
+    pub fn write_caps(&mut self, caps: &gst::CapsRef) -> io::Result<()> {
+        self.caps_offset = Some(self.position);
+        self.write_record(RecordType::Caps, &[caps.to_string().as_bytes()])
+    }
+
+    /// Write serialised `CameraMeta`.
+    /// # Arguments
+    /// * `camera_meta` - The serialised `CameraMeta`, as carried by the `camera_meta_tag`.
+    pub fn write_camera_meta(&mut self, camera_meta: &str) -> io::Result<()> {
+        self.write_record(RecordType::CameraMeta, &[camera_meta.as_bytes()])
+    }
+
+    /// Write a serialised frameset and add it to the seek index.
+    /// # Arguments
+    /// * `pts` - PTS of the main buffer.
+    /// * `dts` - DTS of the main buffer.
+    /// * `data` - The serialised frameset.
+    /// # Returns
+    /// * `Err(io::Error)` - If writing fails, or if no caps were written before.
+    pub fn write_frameset(
+        &mut self,
+        pts: Option<gst::ClockTime>,
+        dts: Option<gst::ClockTime>,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let caps_offset = self
+            .caps_offset
+            .ok_or_else(|| invalid_data("Frameset cannot be written before caps"))?;
+        if let Some(pts) = pts {
+            self.index.push(IndexEntry {
+                pts,
+                offset: self.position,
+                caps_offset,
+            });
+        }
+        self.write_record(
+            RecordType::Frameset,
+            &[&timestamp_to_bytes(pts), &timestamp_to_bytes(dts), data],
+        )
+    }
+
+    /// Finish the recording by writing the seek index and the footer.
+    /// # Returns
+    /// * `Ok(W)` - The underlying writer, which is flushed.
+    pub fn finish(mut self) -> io::Result<W> {
+        let index_offset = self.position;
+        let mut index = Vec::with_capacity(self.index.len() * INDEX_ENTRY_LEN);
+        for entry in self.index.iter() {
+            index.extend_from_slice(&entry.pts.nseconds().to_be_bytes());
+            index.extend_from_slice(&entry.offset.to_be_bytes());
+            index.extend_from_slice(&entry.caps_offset.to_be_bytes());
+        }
+        self.write_record(RecordType::Index, &[&index])?;
+        self.writer.write_all(&index_offset.to_be_bytes())?;
+        self.writer.write_all(INDEX_MAGIC)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Write a record that consists of the concatenated `parts`.
+    /// # Arguments
+    /// * `record_type` - The type of the record.
+    /// * `parts` - The parts of the payload of the record.
+    fn write_record(&mut self, record_type: RecordType, parts: &[&[u8]]) -> io::Result<()> {
+        let len = parts.iter().map(|part| part.len()).sum::<usize>();
+        let len: u32 = len
+            .try_into()
+            .map_err(|_| invalid_data("Record is too large"))?;
+        self.writer.write_all(&[record_type as u8])?;
+        self.writer.write_all(&len.to_be_bytes())?;
+        for part in parts {
+            self.writer.write_all(part)?;
+        }
+        self.position += RECORD_HEADER_LEN + len as u64;
+        Ok(())
+    }
+}
+
+/// Reader of `video/rgbd` recordings.
+pub struct RgbdFileReader<R: Read + Seek> {
+    /// The underlying reader.
+    reader: R,
+    /// The seek index of the recording.
+    index: Vec<IndexEntry>,
+}
+
+impl<R: Read + Seek> RgbdFileReader<R> {
+    /// Open a recording and load its seek index, or build it if the recording has none. The
+    /// reader is positioned at the first record afterwards.
+    /// # Arguments
+    /// * `reader` - The reader, e.g. a buffered file.
+    pub fn open(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; FILE_HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        if &header[..8] != FILE_MAGIC {
+            return Err(invalid_data("Not a `video/rgbd` recording"));
+        }
+        let version = u32::from_be_bytes(header[8..].try_into().unwrap());
+        if version != FILE_VERSION {
+            return Err(invalid_data(&format!(
+                "Unsupported recording version {}",
+                version
+            )));
+        }
+
+        let mut file = Self {
+            reader,
+            index: Vec::new(),
+        };
+        file.index = match file.read_index()? {
+            Some(index) => index,
+            None => file.build_index()?,
+        };
+        file.seek(FILE_HEADER_LEN)?;
+        Ok(file)
+    }
+
+    /// Get the seek index, which is sorted by PTS.
+    pub fn index(&self) -> &[IndexEntry] {
+        &self.index
+    }
+
+    /// Position the reader at the record with the given offset, e.g. of an index entry.
+    /// # Arguments
+    /// * `offset` - The offset of the record.
+    pub fn seek(&mut self, offset: u64) -> io::Result<()> {
+        self.reader.seek(SeekFrom::Start(offset)).map(|_| ())
+    }
+
+    /// Read the next record.
+    /// # Returns
+    /// * `Ok(None)` - At the end of the recording, i.e. at its index or the end of the file.
+    pub fn next_record(&mut self) -> io::Result<Option<Record>> {
+        let (record_type, payload) = match self.read_record()? {
+            Some((RecordType::Index, _)) | None => return Ok(None),
+            Some(record) => record,
+        };
+        let record = match record_type {
+            RecordType::Caps => Record::Caps(
+                std::str::from_utf8(&payload)
+                    .ok()
+                    .and_then(|caps| caps.parse::<gst::Caps>().ok())
+                    .ok_or_else(|| invalid_data("Invalid caps record"))?,
+            ),
+            RecordType::CameraMeta => Record::CameraMeta(
+                String::from_utf8(payload)
+                    .map_err(|_| invalid_data("Invalid CameraMeta record"))?,
+            ),
+            RecordType::Frameset => {
+                if payload.len() < 16 {
+                    return Err(invalid_data("Frameset record is truncated"));
+                }
+                Record::Frameset {
+                    pts: timestamp_from_bytes(&payload[..8]),
+                    dts: timestamp_from_bytes(&payload[8..16]),
+                    data: payload[16..].to_vec(),
+                }
+            }
+            RecordType::Index => unreachable!(),
+        };
+        Ok(Some(record))
+    }
+
+    /// Read the type and payload of the next record.
+    /// # Returns
+    /// * `Ok(None)` - At the end of the file, or if the last record is truncated.
+    fn read_record(&mut self) -> io::Result<Option<(RecordType, Vec<u8>)>> {
+        let mut header = [0u8; RECORD_HEADER_LEN as usize];
+        match self.reader.read_exact(&mut header) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+        let record_type = RecordType::from_u8(header[0])?;
+        let len = u32::from_be_bytes(header[1..].try_into().unwrap()) as usize;
+        let mut payload = vec![0u8; len];
+        match self.reader.read_exact(&mut payload) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            result => result.map(|_| Some((record_type, payload))),
+        }
+    }
+
+    /// Read the seek index that is referenced by the footer.
+    /// # Returns
+    /// * `Ok(None)` - If the recording has no footer.
+    fn read_index(&mut self) -> io::Result<Option<Vec<IndexEntry>>> {
+        let len = self.reader.seek(SeekFrom::End(0))?;
+        if len < FILE_HEADER_LEN + FOOTER_LEN {
+            return Ok(None);
+        }
+        let mut footer = [0u8; FOOTER_LEN as usize];
+        self.seek(len - FOOTER_LEN)?;
+        self.reader.read_exact(&mut footer)?;
+        if &footer[8..] != INDEX_MAGIC {
+            return Ok(None);
+        }
+
+        self.seek(u64::from_be_bytes(footer[..8].try_into().unwrap()))?;
+        let payload = match self.read_record()? {
+            Some((RecordType::Index, payload)) if payload.len() % INDEX_ENTRY_LEN == 0 => payload,
+            _ => return Err(invalid_data("Invalid index record")),
+        };
+        let index = payload
+            .chunks(INDEX_ENTRY_LEN)
+            .map(|entry| {
+                let value = |i: usize| u64::from_be_bytes(entry[i * 8..][..8].try_into().unwrap());
+                IndexEntry {
+                    pts: gst::ClockTime::from_nseconds(value(0)),
+                    offset: value(1),
+                    caps_offset: value(2),
+                }
+            })
+            .collect();
+        Ok(Some(index))
+    }
+
+    /// Build the seek index by reading all records.
+    fn build_index(&mut self) -> io::Result<Vec<IndexEntry>> {
+        let mut index = Vec::new();
+        let mut offset = FILE_HEADER_LEN;
+        let mut caps_offset = None;
+        self.seek(offset)?;
+        while let Some((record_type, payload)) = self.read_record()? {
+            match record_type {
+                RecordType::Caps => caps_offset = Some(offset),
+                RecordType::Frameset => {
+                    if let (Some(pts), Some(caps_offset)) = (
+                        timestamp_from_bytes(payload.get(..8).unwrap_or(&[])),
+                        caps_offset,
+                    ) {
+                        index.push(IndexEntry {
+                            pts,
+                            offset,
+                            caps_offset,
+                        });
+                    }
+                }
+                RecordType::CameraMeta => {}
+                RecordType::Index => break,
+            }
+            offset += RECORD_HEADER_LEN + payload.len() as u64;
+        }
+        Ok(index)
+    }
+}
+
+/// Create an error for invalid data in a recording.
+/// # Arguments
+/// * `message` - Description of the error.
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Convert a timestamp into its on-disk representation.
+fn timestamp_to_bytes(timestamp: Option<gst::ClockTime>) -> [u8; 8] {
+    timestamp
+        .map_or(NO_TIMESTAMP, |timestamp| timestamp.nseconds())
+        .to_be_bytes()
+}
+
+/// Convert the on-disk representation of a timestamp into the timestamp.
+fn timestamp_from_bytes(bytes: &[u8]) -> Option<gst::ClockTime> {
+    bytes
+        .try_into()
+        .ok()
+        .map(u64::from_be_bytes)
+        .filter(|timestamp| *timestamp != NO_TIMESTAMP)
+        .map(gst::ClockTime::from_nseconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Create a recording with two caps records and three framesets, where the last frameset is
+    /// optionally truncated, e.g. by an interrupted recording.
+    fn recording(finish: bool) -> Vec<u8> {
+        let mut writer = RgbdFileWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer
+            .write_caps(&gst::Caps::new_simple(
+                "video/rgbd",
+                &[("streams", &"depth")],
+            ))
+            .unwrap();
+        writer.write_camera_meta("{}").unwrap();
+        for i in 0..3u64 {
+            if i == 2 {
+                writer
+                    .write_caps(&gst::Caps::new_simple(
+                        "video/rgbd",
+                        &[("streams", &"color")],
+                    ))
+                    .unwrap();
+            }
+            let pts = gst::ClockTime::from_mseconds(i * 100);
+            writer
+                .write_frameset(Some(pts), None, &[i as u8; 4])
+                .unwrap();
+        }
+        if finish {
+            writer.finish().unwrap().into_inner()
+        } else {
+            writer.writer.into_inner()
+        }
+    }
+
+    #[test]
+    fn write_and_read_records() {
+        gst::init().unwrap();
+        let mut reader = RgbdFileReader::open(Cursor::new(recording(true))).unwrap();
+
+        match reader.next_record().unwrap() {
+            Some(Record::Caps(caps)) => {
+                assert_eq!(caps.to_string(), "video/rgbd, streams=(string)depth")
+            }
+            record => panic!("Expected caps, got {:?}", record),
+        }
+        match reader.next_record().unwrap() {
+            Some(Record::CameraMeta(camera_meta)) => assert_eq!(camera_meta, "{}"),
+            record => panic!("Expected CameraMeta, got {:?}", record),
+        }
+        match reader.next_record().unwrap() {
+            Some(Record::Frameset { pts, dts, data }) => {
+                assert_eq!(pts, Some(gst::ClockTime::ZERO));
+                assert_eq!(dts, None);
+                assert_eq!(data, [0u8; 4]);
+            }
+            record => panic!("Expected frameset, got {:?}", record),
+        }
+        let mut remaining_records = 0;
+        while reader.next_record().unwrap().is_some() {
+            remaining_records += 1;
+        }
+        assert_eq!(remaining_records, 3, "Found a wrong number of records");
+    }
+
+    #[test]
+    fn index_is_rebuilt_without_footer() {
+        gst::init().unwrap();
+        let indexed = RgbdFileReader::open(Cursor::new(recording(true))).unwrap();
+        let rebuilt = RgbdFileReader::open(Cursor::new(recording(false))).unwrap();
+
+        assert_eq!(indexed.index().len(), 3);
+        assert_eq!(indexed.index(), rebuilt.index());
+        assert_ne!(
+            indexed.index()[1].caps_offset,
+            indexed.index()[2].caps_offset
+        );
+        assert_eq!(indexed.index()[2].pts, gst::ClockTime::from_mseconds(200));
+    }
+}
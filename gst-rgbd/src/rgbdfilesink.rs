@@ -0,0 +1,298 @@
+use glib::*;
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::prelude::*;
+use gst_base::subclass::prelude::*;
+use gst_depth_meta::{camera_meta::CameraMetaTag, rgbd};
+use once_cell::sync::Lazy;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::{Mutex, RwLock};
+
+use crate::frameset::*;
+use crate::rgbdfile::*;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "rgbdfilesink",
+        gst::DebugColorFlags::empty(),
+        Some("RGB-D File Sink"),
+    )
+});
+
+/// A struct containing properties of `rgbdfilesink` element
+#[derive(Default)]
+struct Settings {
+    /// Analogous to `location` property
+    location: Option<String>,
+}
+
+/// Mutable state of the `rgbdfilesink` element
+struct State {
+    /// Writer of the recording, which is open between `start()` and `stop()`.
+    writer: Option<RgbdFileWriter<BufWriter<File>>>,
+    /// Names of the streams of the negotiated caps.
+    streams: Vec<String>,
+    /// The segment of the incoming buffers, used to compute running time.
+    segment: gst::FormattedSegment<gst::ClockTime>,
+    /// Running time of the first frameset, which is stored with PTS zero.
+    first_running_time: Option<gst::ClockTime>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            writer: None,
+            streams: Vec::new(),
+            segment: gst::FormattedSegment::new(),
+            first_running_time: None,
+        }
+    }
+}
+
+/// A struct representation of the `rgbdfilesink` element.
+#[derive(Default)]
+pub struct RgbdFileSink {
+    /// Settings based on properties of the element.
+    settings: RwLock<Settings>,
+    /// The state of the element.
+    state: Mutex<State>,
+}
+
+glib::wrapper! {
+    pub struct RgbdFileSinkObject(ObjectSubclass<RgbdFileSink>)
+        @extends gst_base::BaseSink, gst::Element, gst::Object;
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RgbdFileSink {
+    const NAME: &'static str = "rgbdfilesink";
+    type Type = RgbdFileSinkObject;
+    type ParentType = gst_base::BaseSink;
+}
+
+impl ElementImpl for RgbdFileSink {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RGB-D File Sink",
+                "Sink/File/RGB-D",
+                "Records a `video/rgbd` stream losslessly into a file with a seek index",
+                "Raphael Duerscheid <rd@aivero.com>, Andrej Orsula <andrej.orsula@aivero.com>,
+                 Tobias Morell <tobias.morell@aivero.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 1]> = Lazy::new(|| {
+            [gst::PadTemplate::new(
+                "sink",
+                gst::PadDirection::Sink,
+                gst::PadPresence::Always,
+                &gst::Caps::new_simple("video/rgbd", &[]),
+            )
+            .expect("rgbdfilesink: Failed to add 'sink' pad template")]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BaseSinkImpl for RgbdFileSink {
+    fn start(&self, element: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        let location = self
+            .settings
+            .read()
+            .unwrap()
+            .location
+            .clone()
+            .ok_or_else(|| {
+                gst::error_msg!(
+                    gst::ResourceError::Settings,
+                    ["The `location` property must be set"]
+                )
+            })?;
+        let writer = File::create(&location)
+            .and_then(|file| RgbdFileWriter::new(BufWriter::new(file)))
+            .map_err(|e| {
+                gst::error_msg!(
+                    gst::ResourceError::OpenWrite,
+                    ["Cannot create recording {}: {}", location, e]
+                )
+            })?;
+        gst_info!(CAT, obj: element, "Recording into {}", location);
+
+        *self.state.lock().unwrap() = State {
+            writer: Some(writer),
+            ..State::default()
+        };
+        Ok(())
+    }
+
+    fn stop(&self, element: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        let writer = self.state.lock().unwrap().writer.take();
+        if let Some(writer) = writer {
+            writer.finish().map_err(|e| {
+                gst::error_msg!(
+                    gst::ResourceError::Write,
+                    ["Cannot finish recording: {}", e]
+                )
+            })?;
+            gst_info!(CAT, obj: element, "Recording finished");
+        }
+        Ok(())
+    }
+
+    fn set_caps(&self, element: &Self::Type, caps: &gst::Caps) -> Result<(), gst::LoggableError> {
+        gst_debug!(CAT, obj: element, "Got new caps: {:?}", caps);
+        let streams = rgbd::RgbdCaps::from_caps(caps)
+            .map_err(|e| gst::loggable_error!(CAT, "Invalid `video/rgbd` caps: {}", e))?
+            .stream_names()
+            .map(String::from)
+            .collect();
+
+        let mut state = self.state.lock().unwrap();
+        let writer = state
+            .writer
+            .as_mut()
+            .ok_or_else(|| gst::loggable_error!(CAT, "Recording is not open"))?;
+        writer
+            .write_caps(caps)
+            .map_err(|e| gst::loggable_error!(CAT, "Cannot write caps: {}", e))?;
+        state.streams = streams;
+        Ok(())
+    }
+
+    fn event(&self, element: &Self::Type, event: gst::Event) -> bool {
+        use gst::EventView;
+        match event.view() {
+            EventView::Segment(segment) => {
+                if let Some(segment) = segment.segment().downcast_ref::<gst::ClockTime>() {
+                    self.state.lock().unwrap().segment = segment.clone();
+                }
+            }
+            EventView::Tag(tag) => {
+                // Store the serialised CameraMeta, such that it is available during replay
+                if let Some(camera_meta) = tag.tag().get::<CameraMetaTag>() {
+                    let mut state = self.state.lock().unwrap();
+                    if let Some(writer) = state.writer.as_mut() {
+                        if let Err(e) = writer.write_camera_meta(camera_meta.get()) {
+                            gst::element_error!(
+                                element,
+                                gst::ResourceError::Write,
+                                ["Cannot write CameraMeta: {}", e]
+                            );
+                            return false;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.parent_event(element, event)
+    }
+
+    fn render(
+        &self,
+        element: &Self::Type,
+        buffer: &gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mut state = self.state.lock().unwrap();
+        let data = serialize_frameset(buffer, &state.streams).map_err(|e| {
+            gst_error!(CAT, obj: element, "{}", e);
+            gst::FlowError::Error
+        })?;
+
+        // Timestamps are stored as running time relative to the first frameset
+        let pts = state.segment.to_running_time(buffer.pts());
+        let dts = state.segment.to_running_time(buffer.dts());
+        if state.first_running_time.is_none() {
+            state.first_running_time = pts.or(dts);
+        }
+        let first_running_time = state.first_running_time.unwrap_or(gst::ClockTime::ZERO);
+        let pts = pts.map(|pts| pts.saturating_sub(first_running_time));
+        let dts = dts.map(|dts| dts.saturating_sub(first_running_time));
+
+        let writer = state.writer.as_mut().ok_or(gst::FlowError::Flushing)?;
+        writer.write_frameset(pts, dts, &data).map_err(|e| {
+            gst::element_error!(
+                element,
+                gst::ResourceError::Write,
+                ["Cannot write frameset: {}", e]
+            );
+            gst::FlowError::Error
+        })?;
+        gst_trace!(CAT, obj: element, "Wrote frameset with PTS {}", pts.display());
+        Ok(gst::FlowSuccess::Ok)
+    }
+}
+
+impl GstObjectImpl for RgbdFileSink {}
+impl ObjectImpl for RgbdFileSink {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<[glib::ParamSpec; 1]> = Lazy::new(|| {
+            [ParamSpecString::new(
+                "location",
+                "Location",
+                "Location of the recording to write.",
+                None,
+                ParamFlags::READWRITE,
+            )]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, element: &Self::Type) {
+        self.parent_constructed(element);
+
+        // Recording is not synchronised to the clock, same as `filesink`
+        element.set_sync(false);
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.write().unwrap();
+
+        match pspec.name() {
+            "location" => {
+                let location = value.get::<Option<String>>().unwrap();
+                gst_info!(
+                    CAT,
+                    "Changing property `location` from {:?} to {:?}",
+                    settings.location,
+                    location
+                );
+                settings.location = location;
+            }
+            _ => unimplemented!("Property is not implemented"),
+        };
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.read().unwrap();
+
+        match pspec.name() {
+            "location" => settings.location.to_value(),
+            _ => unimplemented!("Property is not implemented"),
+        }
+    }
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "rgbdfilesink",
+        gst::Rank::None,
+        RgbdFileSink::type_(),
+    )
+}
@@ -0,0 +1,586 @@
+use glib::*;
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_base::prelude::*;
+use gst_base::subclass::base_src::CreateSuccess;
+use gst_base::subclass::prelude::*;
+use gst_depth_meta::{camera_meta::CameraMetaTag, rgbd};
+use gst_util::taglist::*;
+use once_cell::sync::Lazy;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::{Mutex, RwLock};
+
+use crate::frameset::*;
+use crate::rgbdfile::*;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "rgbdfilesrc",
+        gst::DebugColorFlags::empty(),
+        Some("RGB-D File Source"),
+    )
+});
+
+/// A struct containing properties of `rgbdfilesrc` element
+#[derive(Default)]
+struct Settings {
+    /// Analogous to `location` property
+    location: Option<String>,
+}
+
+/// Mutable state of the `rgbdfilesrc` element
+#[derive(Default)]
+struct State {
+    /// Reader of the recording, which is open between `start()` and `stop()`.
+    reader: Option<RgbdFileReader<BufReader<File>>>,
+    /// The caps of the framesets that are read next.
+    caps: Option<gst::Caps>,
+    /// Names of the streams of `caps`.
+    streams: Vec<String>,
+    /// Whether `caps` differ from the caps that were negotiated last.
+    caps_changed: bool,
+}
+
+impl State {
+    /// Replace the caps of the framesets that are read next.
+    /// # Arguments
+    /// * `caps` - The `video/rgbd` caps read from the recording.
+    fn update_caps(&mut self, caps: gst::Caps) -> Result<(), gst::ErrorMessage> {
+        if self.caps.as_ref() == Some(&caps) {
+            return Ok(());
+        }
+        self.streams = rgbd::RgbdCaps::from_caps(&caps)
+            .map_err(|e| gst::error_msg!(gst::StreamError::Decode, ["{}", e]))?
+            .stream_names()
+            .map(String::from)
+            .collect();
+        self.caps = Some(caps);
+        self.caps_changed = true;
+        Ok(())
+    }
+}
+
+/// A struct representation of the `rgbdfilesrc` element.
+#[derive(Default)]
+pub struct RgbdFileSrc {
+    /// Settings based on properties of the element.
+    settings: RwLock<Settings>,
+    /// The state of the element.
+    state: Mutex<State>,
+}
+
+glib::wrapper! {
+    pub struct RgbdFileSrcObject(ObjectSubclass<RgbdFileSrc>)
+        @extends gst_base::PushSrc, gst_base::BaseSrc, gst::Element, gst::Object;
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RgbdFileSrc {
+    const NAME: &'static str = "rgbdfilesrc";
+    type Type = RgbdFileSrcObject;
+    type ParentType = gst_base::PushSrc;
+}
+
+impl ElementImpl for RgbdFileSrc {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RGB-D File Source",
+                "Source/File/RGB-D",
+                "Replays a `video/rgbd` stream recorded by `rgbdfilesink`",
+                "Raphael Duerscheid <rd@aivero.com>, Andrej Orsula <andrej.orsula@aivero.com>,
+                 Tobias Morell <tobias.morell@aivero.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 1]> = Lazy::new(|| {
+            [gst::PadTemplate::new(
+                "src",
+                gst::PadDirection::Src,
+                gst::PadPresence::Always,
+                &gst::Caps::new_simple("video/rgbd", &[]),
+            )
+            .expect("rgbdfilesrc: Failed to add 'src' pad template")]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl BaseSrcImpl for RgbdFileSrc {
+    fn start(&self, element: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        let location = self
+            .settings
+            .read()
+            .unwrap()
+            .location
+            .clone()
+            .ok_or_else(|| {
+                gst::error_msg!(
+                    gst::ResourceError::Settings,
+                    ["The `location` property must be set"]
+                )
+            })?;
+        let mut reader = File::open(&location)
+            .and_then(|file| RgbdFileReader::open(BufReader::new(file)))
+            .map_err(|e| {
+                gst::error_msg!(
+                    gst::ResourceError::OpenRead,
+                    ["Cannot open recording {}: {}", location, e]
+                )
+            })?;
+        gst_info!(
+            CAT,
+            obj: element,
+            "Replaying {} with {} indexed framesets",
+            location,
+            reader.index().len()
+        );
+
+        // The caps of the first framesets are needed for negotiation
+        let mut state = State::default();
+        if let Some(entry) = reader.index().first().copied() {
+            reader
+                .seek(entry.caps_offset)
+                .map_err(|e| gst::error_msg!(gst::ResourceError::Seek, ["{}", e]))?;
+            match reader.next_record() {
+                Ok(Some(Record::Caps(caps))) => state.update_caps(caps)?,
+                _ => {
+                    return Err(gst::error_msg!(
+                        gst::StreamError::Decode,
+                        ["Index of {} does not refer to caps", location]
+                    ))
+                }
+            }
+            reader
+                .seek(entry.offset)
+                .map_err(|e| gst::error_msg!(gst::ResourceError::Seek, ["{}", e]))?;
+        }
+        state.reader = Some(reader);
+        *self.state.lock().unwrap() = state;
+        Ok(())
+    }
+
+    fn stop(&self, element: &Self::Type) -> Result<(), gst::ErrorMessage> {
+        *self.state.lock().unwrap() = State::default();
+        self.parent_stop(element)
+    }
+
+    fn caps(&self, element: &Self::Type, filter: Option<&gst::Caps>) -> Option<gst::Caps> {
+        let caps = match self.state.lock().unwrap().caps.clone() {
+            Some(caps) => caps,
+            None => return self.parent_caps(element, filter),
+        };
+        match filter {
+            Some(filter) => Some(filter.intersect_with_mode(&caps, gst::CapsIntersectMode::First)),
+            None => Some(caps),
+        }
+    }
+
+    fn is_seekable(&self, _element: &Self::Type) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .reader
+            .as_ref()
+            .map_or(false, |reader| !reader.index().is_empty())
+    }
+
+    fn do_seek(&self, element: &Self::Type, segment: &mut gst::Segment) -> bool {
+        let segment = match segment.downcast_ref::<gst::ClockTime>() {
+            Some(segment) if segment.rate() > 0.0 => segment,
+            _ => {
+                gst_error!(CAT, obj: element, "Only forward seeks in TIME format are supported");
+                return false;
+            }
+        };
+        let target = segment.start().unwrap_or(gst::ClockTime::ZERO);
+
+        let mut state = self.state.lock().unwrap();
+        let reader = match state.reader.as_mut() {
+            Some(reader) => reader,
+            None => return false,
+        };
+        // Start from the last frameset before the target, downstream clips to the segment
+        let index = reader.index();
+        let entry = match index.partition_point(|entry| entry.pts <= target) {
+            0 => index.first().copied(),
+            n => index.get(n - 1).copied(),
+        };
+        let entry = match entry {
+            Some(entry) => entry,
+            None => return target == gst::ClockTime::ZERO,
+        };
+        gst_debug!(
+            CAT,
+            obj: element,
+            "Seeking to {} from frameset at {}",
+            target,
+            entry.pts
+        );
+
+        let caps = reader
+            .seek(entry.caps_offset)
+            .and_then(|_| reader.next_record())
+            .and_then(|record| reader.seek(entry.offset).map(|_| record));
+        match caps {
+            Ok(Some(Record::Caps(caps))) => match state.update_caps(caps) {
+                Ok(_) => true,
+                Err(e) => {
+                    gst_error!(CAT, obj: element, "{}", e);
+                    false
+                }
+            },
+            _ => {
+                gst_error!(CAT, obj: element, "Failed to seek to {}", target);
+                false
+            }
+        }
+    }
+
+    fn query(&self, element: &Self::Type, query: &mut gst::QueryRef) -> bool {
+        use gst::QueryView;
+        match query.view_mut() {
+            QueryView::Duration(ref mut q) if q.format() == gst::Format::Time => {
+                let state = self.state.lock().unwrap();
+                match state
+                    .reader
+                    .as_ref()
+                    .and_then(|reader| reader.index().last())
+                {
+                    Some(entry) => {
+                        q.set(entry.pts);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => BaseSrcImplExt::parent_query(self, element, query),
+        }
+    }
+}
+
+impl PushSrcImpl for RgbdFileSrc {
+    fn create(
+        &self,
+        element: &Self::Type,
+        _buffer: Option<&mut gst::BufferRef>,
+    ) -> Result<CreateSuccess, gst::FlowError> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let record = state
+                .reader
+                .as_mut()
+                .ok_or(gst::FlowError::Flushing)?
+                .next_record()
+                .map_err(|e| {
+                    gst::element_error!(
+                        element,
+                        gst::ResourceError::Read,
+                        ["Cannot read recording: {}", e]
+                    );
+                    gst::FlowError::Error
+                })?;
+
+            match record {
+                None => {
+                    gst_debug!(CAT, obj: element, "End of recording");
+                    return Err(gst::FlowError::Eos);
+                }
+                Some(Record::Caps(caps)) => state.update_caps(caps).map_err(|e| {
+                    element.post_error_message(e);
+                    gst::FlowError::Error
+                })?,
+                Some(Record::CameraMeta(camera_meta)) => {
+                    // Same as live sources, CameraMeta is carried by a sticky tag event
+                    let tags = gst::TagList::new_single::<CameraMetaTag>(&camera_meta.as_str());
+                    if let Err(e) = element
+                        .static_pad("src")
+                        .unwrap()
+                        .store_sticky_event(&gst::event::Tag::new(tags))
+                    {
+                        gst_warning!(CAT, obj: element, "Failed to store CameraMeta: {}", e);
+                    }
+                }
+                Some(Record::Frameset { pts, dts, data }) => {
+                    if state.caps_changed {
+                        let caps = state.caps.clone().unwrap();
+                        drop(state);
+                        gst_debug!(CAT, obj: element, "Switching to caps {:?}", caps);
+                        element
+                            .set_caps(&caps)
+                            .map_err(|_| gst::FlowError::NotNegotiated)?;
+                        state = self.state.lock().unwrap();
+                        state.caps_changed = false;
+                    }
+
+                    let mut buffer =
+                        deserialize_frameset(&data, &state.streams, pts).map_err(|e| {
+                            element.post_error_message(e);
+                            gst::FlowError::Error
+                        })?;
+                    buffer.get_mut().unwrap().set_dts(dts);
+                    return Ok(CreateSuccess::NewBuffer(buffer));
+                }
+            }
+        }
+    }
+}
+
+impl GstObjectImpl for RgbdFileSrc {}
+impl ObjectImpl for RgbdFileSrc {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<[glib::ParamSpec; 1]> = Lazy::new(|| {
+            [ParamSpecString::new(
+                "location",
+                "Location",
+                "Location of the recording to replay.",
+                None,
+                ParamFlags::READWRITE,
+            )]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, element: &Self::Type) {
+        self.parent_constructed(element);
+
+        // Set format to time
+        element.set_format(gst::Format::Time);
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.write().unwrap();
+
+        match pspec.name() {
+            "location" => {
+                let location = value.get::<Option<String>>().unwrap();
+                gst_info!(
+                    CAT,
+                    "Changing property `location` from {:?} to {:?}",
+                    settings.location,
+                    location
+                );
+                settings.location = location;
+            }
+            _ => unimplemented!("Property is not implemented"),
+        };
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.read().unwrap();
+
+        match pspec.name() {
+            "location" => settings.location.to_value(),
+            _ => unimplemented!("Property is not implemented"),
+        }
+    }
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "rgbdfilesrc",
+        gst::Rank::None,
+        RgbdFileSrc::type_(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use gst::prelude::*;
+    use gst_depth_meta::{camera_meta::CameraMetaTag, rgbd};
+    use std::path::{Path, PathBuf};
+
+    const WIDTH: i32 = 4;
+    const HEIGHT: i32 = 4;
+    /// Number of framesets in the recording.
+    const FRAME_COUNT: u64 = 3;
+    /// Duration of each frameset in milliseconds.
+    const FRAME_MS: u64 = 100;
+    const CAMERA_META: &str = "{\"intrinsics\":{}}";
+
+    /// Create `video/rgbd` caps with `depth` and `color` streams.
+    fn rgbd_caps() -> gst::Caps {
+        let video = |format: &str| {
+            gst::Structure::builder("video/x-raw")
+                .field("format", format)
+                .field("width", WIDTH)
+                .field("height", HEIGHT)
+                .build()
+        };
+        rgbd::RgbdCaps::new()
+            .with_framerate(gst::Fraction::new(10, 1))
+            .with_stream(rgbd::RgbdStreamCaps::new("depth", video("GRAY16_LE")))
+            .with_stream(rgbd::RgbdStreamCaps::new("color", video("RGB")))
+            .to_caps()
+    }
+
+    /// Create the `index`-th frameset, whose `depth` main buffer and `color` auxiliary buffer are
+    /// filled with `index`.
+    fn frameset(index: u64) -> gst::Buffer {
+        let size = (WIDTH * HEIGHT) as usize;
+        let mut main_buffer = gst::Buffer::from_mut_slice(vec![index as u8; size * 2]);
+        {
+            let main_buffer = main_buffer.get_mut().unwrap();
+            main_buffer.set_pts(gst::ClockTime::from_mseconds(index * FRAME_MS));
+            main_buffer.set_dts(gst::ClockTime::from_mseconds(index * FRAME_MS));
+            rgbd::tag_buffer_with_title(main_buffer, "depth").unwrap();
+        }
+        let mut color_buffer = gst::Buffer::from_mut_slice(vec![index as u8; size * 3]);
+        rgbd::attach_aux_buffer_and_tag(main_buffer.make_mut(), &mut color_buffer, "color")
+            .unwrap();
+        main_buffer
+    }
+
+    /// Determine whether `buffer` contains only `value`.
+    fn is_filled_with(buffer: &gst::BufferRef, value: u8) -> bool {
+        buffer.map_readable().unwrap().iter().all(|v| *v == value)
+    }
+
+    /// Record `FRAME_COUNT` framesets and CameraMeta with `rgbdfilesink`.
+    /// # Returns
+    /// * The location of the recording.
+    fn record(name: &str) -> PathBuf {
+        let location =
+            std::env::temp_dir().join(format!("rgbdfilesrc-{}-{}.rgbd", name, std::process::id()));
+        let mut harness = gst_check::Harness::new_parse(&format!(
+            "rgbdfilesink location=\"{}\"",
+            location.display()
+        ));
+        harness.set_src_caps(rgbd_caps());
+        let tags = gst::TagList::new_single::<CameraMetaTag>(&CAMERA_META);
+        harness.push_event(gst::event::Tag::new(tags));
+        for index in 0..FRAME_COUNT {
+            harness.push(frameset(index)).unwrap();
+        }
+        harness.push_event(gst::event::Eos::new());
+        // Dropping the harness stops `rgbdfilesink`, which finishes the recording
+        location
+    }
+
+    /// Create a harness that replays the recording at `location`.
+    fn replay(location: &Path) -> gst_check::Harness {
+        gst_check::Harness::new_parse(&format!("rgbdfilesrc location=\"{}\"", location.display()))
+    }
+
+    /// Pull all events that `harness` received so far and return the first one that `f` accepts.
+    fn find_event<T>(
+        harness: &mut gst_check::Harness,
+        f: impl Fn(gst::EventView) -> Option<T>,
+    ) -> Option<T> {
+        let mut found = None;
+        while let Some(event) = harness.try_pull_event() {
+            if found.is_none() {
+                found = f(event.view());
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn record_and_replay() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+        let location = record("replay");
+
+        let mut harness = replay(&location);
+        harness.play();
+        for index in 0..FRAME_COUNT {
+            let buffer = harness.pull().unwrap();
+            let timestamp = gst::ClockTime::from_mseconds(index * FRAME_MS);
+            assert_eq!(buffer.pts(), Some(timestamp));
+            assert_eq!(buffer.dts(), Some(timestamp));
+            assert_eq!(rgbd::get_tag(&buffer).unwrap(), "depth");
+            assert!(is_filled_with(&buffer, index as u8));
+
+            let aux_buffers = rgbd::get_all_aux_buffers(&buffer).collect::<Vec<_>>();
+            assert_eq!(aux_buffers.len(), 1, "Found a wrong number of buffers");
+            assert_eq!(rgbd::get_tag(&aux_buffers[0]).unwrap(), "color");
+            assert_eq!(aux_buffers[0].pts(), Some(timestamp));
+            assert!(is_filled_with(&aux_buffers[0], index as u8));
+        }
+
+        let output_caps = harness.sinkpad().unwrap().current_caps().unwrap();
+        assert_eq!(
+            rgbd::RgbdCaps::from_caps(&output_caps).unwrap(),
+            rgbd::RgbdCaps::from_caps(&rgbd_caps()).unwrap()
+        );
+        let camera_meta = find_event(&mut harness, |event| match event {
+            gst::EventView::Tag(tag) => tag
+                .tag()
+                .get::<CameraMetaTag>()
+                .map(|camera_meta| camera_meta.get().to_string()),
+            _ => None,
+        });
+        assert_eq!(camera_meta.as_deref(), Some(CAMERA_META));
+
+        drop(harness);
+        std::fs::remove_file(location).unwrap();
+    }
+
+    #[test]
+    fn seek_starts_at_preceding_frameset() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+        let location = record("seek");
+
+        // Pairs of seek target and the index of the first replayed frameset
+        let seeks = [
+            (0, 0),
+            (FRAME_MS * 3 / 2, 1),
+            (FRAME_MS * 2, 2),
+            (FRAME_MS * 5 / 2, 2),
+        ];
+        for (target, expected_index) in seeks.iter() {
+            let target = gst::ClockTime::from_mseconds(*target);
+            let mut harness = replay(&location);
+
+            // Seeks before start are performed once the source starts
+            let src = harness.find_element("rgbdfilesrc").unwrap();
+            src.set_state(gst::State::Ready).unwrap();
+            assert!(src.send_event(gst::event::Seek::new(
+                1.0,
+                gst::SeekFlags::FLUSH,
+                gst::SeekType::Set,
+                Some(target),
+                gst::SeekType::None,
+                gst::ClockTime::NONE,
+            )));
+            harness.play();
+
+            let buffer = harness.pull().unwrap();
+            assert_eq!(
+                buffer.pts(),
+                Some(gst::ClockTime::from_mseconds(expected_index * FRAME_MS)),
+                "Seek to {} started at a wrong frameset",
+                target
+            );
+            assert!(is_filled_with(&buffer, *expected_index as u8));
+
+            let segment_start = find_event(&mut harness, |event| match event {
+                gst::EventView::Segment(segment) => segment
+                    .segment()
+                    .downcast_ref::<gst::ClockTime>()
+                    .and_then(|segment| segment.start()),
+                _ => None,
+            });
+            assert_eq!(segment_start, Some(target));
+        }
+
+        std::fs::remove_file(location).unwrap();
+    }
+}
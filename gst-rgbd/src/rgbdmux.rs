@@ -6,7 +6,10 @@ use gst_base::AggregatorPad;
 use gst_depth_meta::buffer::BufferMeta;
 use gst_depth_meta::rgbd;
 use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+
+use crate::common::*;
 
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     gst::DebugCategory::new(
@@ -16,15 +19,73 @@ static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
     )
 });
 
+/// Default value for `sync-policy` property
+const DEFAULT_SYNC_POLICY: SyncPolicy = SyncPolicy::Strict;
+/// Default value for `tolerance` property, in nanoseconds
+const DEFAULT_TOLERANCE: u64 = 5_000_000;
+
+/// Policy that determines which buffers of the sink pads are muxed together.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "GstRgbdMuxSyncPolicy")]
+pub enum SyncPolicy {
+    /// Mux buffers that fall into the frame window of the output, determined by the framerate.
+    #[enum_value(
+        name = "Strict: Mux buffers that fall into the frame window derived from the framerate. Send a gap event if any of the buffers is missing or outside of the window.",
+        nick = "strict"
+    )]
+    Strict = 0,
+    /// Mux buffers whose timestamps are within `tolerance` of each other.
+    #[enum_value(
+        name = "Nearest: Mux buffers whose running times are within `tolerance` of the newest buffer. Older buffers are dropped.",
+        nick = "nearest"
+    )]
+    Nearest = 1,
+    /// Mux the newest available buffer of each sink pad.
+    #[enum_value(
+        name = "Latest: Mux the newest queued buffer of each sink pad, once all pads have a buffer or the deadline is reached. Older queued buffers are dropped and pads without a new buffer reuse their previous buffer.",
+        nick = "latest"
+    )]
+    Latest = 2,
+    /// Wait until all sink pads have a buffer, regardless of their timestamps.
+    #[enum_value(
+        name = "WaitAll: Wait until all sink pads have a buffer and mux them regardless of their timestamps. Buffers are never dropped and no gap events are sent.",
+        nick = "wait_all"
+    )]
+    WaitAll = 3,
+}
+
+/// A struct containing properties of `rgbdmux` element
+struct Settings {
+    /// Analogous to `sync-policy` property
+    sync_policy: SyncPolicy,
+    /// Analogous to `tolerance` property
+    tolerance: gst::ClockTime,
+}
+
 struct State {
     /// The current framerate out the rgbd output
     framerate: Option<gst::Fraction>,
     /// Whether we are outputting our first buffer (after flush)
     first: bool,
+    /// Number of dropped buffers of each stream
+    dropped: HashMap<String, u64>,
+    /// The last muxed buffer of each stream, which is reused by `SyncPolicy::Latest`
+    last_buffers: HashMap<String, gst::Buffer>,
+    /// Streams of the negotiated src CAPS. Only sink pads of these streams are muxed, such that
+    /// muxed buffers always match the CAPS until pads that were added or removed are renegotiated.
+    streams: Vec<String>,
+    /// Streams of the negotiated src CAPS with their own framerate, which differs from the
+    /// framerate of the output
+    stream_framerates: HashMap<String, gst::Fraction>,
+    /// Buffers of streams with their own framerate, which are collected for the next frameset
+    batches: HashMap<String, Vec<gst::Buffer>>,
 }
 
 /// A struct representation of the `rgbdmux` element.
 pub struct RgbdMux {
+    /// Settings based on properties of the element.
+    settings: RwLock<Settings>,
     state: Mutex<State>,
 }
 
@@ -41,9 +102,18 @@ impl ObjectSubclass for RgbdMux {
 
     fn new() -> Self {
         Self {
+            settings: RwLock::new(Settings {
+                sync_policy: DEFAULT_SYNC_POLICY,
+                tolerance: gst::ClockTime::from_nseconds(DEFAULT_TOLERANCE),
+            }),
             state: Mutex::new(State {
                 framerate: None,
                 first: true,
+                dropped: HashMap::new(),
+                last_buffers: HashMap::new(),
+                streams: Vec::new(),
+                stream_framerates: HashMap::new(),
+                batches: HashMap::new(),
             }),
         }
     }
@@ -101,7 +171,10 @@ impl AggregatorImpl for RgbdMux {
     }
 
     fn flush(&self, _aggregator: &RgbdMuxObject) -> Result<gst::FlowSuccess, gst::FlowError> {
-        self.state.lock().unwrap().first = true;
+        let mut state = self.state.lock().unwrap();
+        state.first = true;
+        state.last_buffers.clear();
+        state.batches.clear();
         Ok(gst::FlowSuccess::Ok)
     }
 
@@ -116,121 +189,56 @@ impl AggregatorImpl for RgbdMux {
         timeout: bool,
     ) -> Result<gst::FlowSuccess, gst::FlowError> {
         let src_pads = aggregator.src_pads();
-        let sink_pads = aggregator.sink_pads();
-        let mut sink_pads = sink_pads
-            .iter()
-            .filter_map(|sp| sp.downcast_ref::<gst_base::AggregatorPad>());
+        let negotiated_pads = self.negotiated_sink_pads(aggregator);
         let agg_pad = src_pads[0]
             .downcast_ref::<gst_base::AggregatorPad>()
             .unwrap();
 
-        let any_pad_is_eos = sink_pads
-            .clone()
+        let any_pad_is_eos = negotiated_pads
+            .iter()
             .any(|pad| pad.is_eos() && !pad.has_buffer());
 
         if any_pad_is_eos {
             gst_debug!(CAT, "Got EOS. We are done");
             return Err(gst::FlowError::Eos);
         }
-        if agg_pad.caps().is_none() {
-            gst_debug!(CAT, "We don't know caps yet");
-            return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
-        }
 
-        // Get the current deadline time or desired output time
-        let segment: gst::FormattedSegment<gst::ClockTime> = agg_pad.segment().downcast().unwrap();
-        let segment_position = segment.position().or_else(|| segment.start());
-        let position_running_time = segment.to_running_time(segment_position).unwrap();
-
-        let (duration, first) = {
+        // The sync policy applies only to streams at the framerate of the output. Buffers of
+        // streams with their own framerate are collected separately for each frameset.
+        let (sink_pads, independent_pads): (Vec<_>, Vec<_>) = {
             let state = self.state.lock().unwrap();
-            (
-                Self::get_duration_from_fps(state.framerate.unwrap()).unwrap(),
-                state.first,
-            )
+            negotiated_pads.into_iter().partition(|pad| {
+                !state
+                    .stream_framerates
+                    .contains_key(&Self::stream_name(pad))
+            })
         };
+        if agg_pad.caps().is_none() || sink_pads.is_empty() {
+            gst_debug!(CAT, "We don't know caps yet");
+            return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
+        }
 
-        gst_debug!(
+        let sync_policy = self.settings.read().unwrap().sync_policy;
+        gst_trace!(
             CAT,
-            "Segment position: {:?}, start: {:?}, end: {:?}",
-            segment.position(),
-            segment.start(),
-            segment.stop()
+            obj: aggregator,
+            "Aggregating with {:?} policy, timeout: {}",
+            sync_policy,
+            timeout
         );
-
-        let mut has_all_buffers_in_range = true;
-        for sink_pad in sink_pads.clone() {
-            let buffer_in_range = self.drop_out_of_range_buffers_on_pad(
-                aggregator,
-                position_running_time,
-                duration,
-                sink_pad,
-                first,
-            )?;
-            has_all_buffers_in_range = has_all_buffers_in_range && buffer_in_range;
-        }
-
-        if timeout {
-            let basetime = aggregator.base_time();
-
-            // HACK: In certain pipelines, aggregate gets called before the transition from
-            // PausedToPlaying. In that case, the basetime has not yet been set to something
-            // useful for the aggregator. This causes all kinds of problems, and we are not
-            // sure of the right solution, so for now we just ignore basetimes of zero.
-            if basetime.unwrap_or(gst::ClockTime::ZERO) == gst::ClockTime::ZERO {
-                return Ok(gst::FlowSuccess::Ok);
+        match sync_policy {
+            SyncPolicy::Strict => {
+                self.aggregate_strict(aggregator, &sink_pads, &independent_pads, timeout)
+            }
+            SyncPolicy::Nearest => {
+                self.aggregate_nearest(aggregator, &sink_pads, &independent_pads, timeout)
+            }
+            SyncPolicy::Latest => {
+                self.aggregate_latest(aggregator, &sink_pads, &independent_pads, timeout)
+            }
+            SyncPolicy::WaitAll => {
+                self.aggregate_wait_all(aggregator, &sink_pads, &independent_pads)
             }
-
-            gst_debug!(CAT, "Timeout. Sending Gap event");
-            self.send_gap_event(aggregator).map_err(|_| {
-                self.advance_segment_position(aggregator);
-                gst::FlowError::Error
-            })?;
-            self.advance_segment_position(aggregator);
-            return Ok(gst::FlowSuccess::Ok);
-        }
-
-        let all_pads_have_buffers = sink_pads.all(|pad| pad.has_buffer());
-        if all_pads_have_buffers && !has_all_buffers_in_range {
-            gst_debug!(
-                CAT,
-                "Queues are full, but not all buffers are in range. Sending Gap event",
-            );
-
-            self.send_gap_event(aggregator).map_err(|_| {
-                self.advance_segment_position(aggregator);
-                gst::FlowError::Error
-            })?;
-            self.advance_segment_position(aggregator);
-            return Ok(gst::FlowSuccess::Ok);
-        }
-
-        if has_all_buffers_in_range {
-            gst_debug!(CAT, "All buffers in range, muxing");
-
-            self.advance_segment_position(aggregator);
-
-            // https://gstreamer.freedesktop.org/documentation/base/gstaggregator.html?gi-language=c#gst_aggregator_selected_samples
-            aggregator.selected_samples(
-                position_running_time,
-                position_running_time,
-                duration,
-                None,
-            );
-            let outbuf = self
-                .mux_buffers_set_ts(aggregator, segment_position, Some(duration))
-                .map_err(|_| gst::FlowError::Error)?;
-
-            gst_debug!(CAT, "Muxed buffers, finishing");
-
-            self.finish_buffer(aggregator, outbuf)?;
-
-            self.state.lock().unwrap().first = false;
-
-            Ok(gst::FlowSuccess::Ok)
-        } else {
-            gst_debug!(CAT, "Need more data");
-            Err(gst_base::AGGREGATOR_FLOW_NEED_DATA)
         }
     }
 
@@ -251,52 +259,62 @@ impl AggregatorImpl for RgbdMux {
             .iter()
             .filter_map(|pad| pad.downcast_ref::<gst_base::AggregatorPad>());
 
-        // Find the lowest framerate defined across all the caps of our sinkpads
-        // The framerate is the lowest framerate found across all sinkpads,
-        // since we will drop all frames if one frame is missing
-        let min_framerate = sink_pads
-            .clone()
-            .filter_map(|pad| {
-                let caps = pad.current_caps()?;
-                let structure = caps.structure(0).unwrap();
-                structure.get::<gst::Fraction>("framerate").ok()
-            })
-            .min();
-        self.state.lock().unwrap().framerate = min_framerate;
-
-        if min_framerate.is_none() {
-            gst_debug!(CAT, "None of the sink pads had a framerate");
-            return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
-        }
-
-        if sink_pads.clone().any(|pad| pad.current_caps().is_none()) {
-            gst_debug!(CAT, "No sink pads yet");
-            return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
-        }
-
-        let sink_pad_caps = sink_pads.clone().map(|pad| pad.current_caps().unwrap());
-        let sink_pad_names = sink_pads.clone().map(|pad| pad.name());
-
-        let streams = sink_pad_names
-            .clone()
-            .map(|s| s.trim_start_matches("sink_").to_send_value());
-
-        let mut downstream_caps = gst::Caps::new_simple(
-            "video/rgbd",
-            &[
-                ("streams", &gst::Array::from_values(streams)),
-                ("framerate", &min_framerate.unwrap()),
-            ],
-        );
+        // The framerate of the output is the framerate of the main stream, i.e. the first stream
+        // with a framerate. Streams with a different framerate keep their own framerate, which
+        // is stored as `{stream}_framerate`.
+        let framerate_of = |pad: &AggregatorPad| {
+            let caps = pad.current_caps()?;
+            let structure = caps.structure(0).unwrap();
+            structure.get::<gst::Fraction>("framerate").ok()
+        };
+        let framerate = sink_pads.clone().find_map(framerate_of);
+        self.state.lock().unwrap().framerate = framerate;
 
-        let mut_caps = downstream_caps.make_mut().structure_mut(0).unwrap();
+        let framerate = match framerate {
+            Some(framerate) => framerate,
+            None => {
+                gst_debug!(CAT, "None of the sink pads had a framerate");
+                return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
+            }
+        };
 
-        // Map the caps into their corresponding stream formats
-        for (caps, pad_name) in sink_pad_caps.zip(sink_pad_names) {
-            self.compose_elementary_caps_to_rgbd_caps(&caps, &pad_name, mut_caps)
+        // Map the caps of each sink pad into their corresponding stream of the `video/rgbd` caps.
+        // Pads that were added while streaming have no caps until upstream negotiates them, which
+        // marks the src pad for reconfiguration again. Until then, they are not part of the caps.
+        let mut downstream_caps = rgbd::RgbdCaps::new().with_framerate(framerate);
+        for pad in sink_pads {
+            let pad_name = pad.name();
+            let stream_name = pad_name.trim_start_matches("sink_");
+            let pad_caps = match pad.current_caps() {
+                Some(pad_caps) => pad_caps,
+                None => {
+                    gst_debug!(CAT, obj: aggregator, "`{}` has no CAPS yet", pad_name);
+                    continue;
+                }
+            };
+            let mut stream_caps =
+                rgbd::RgbdStreamCaps::from_caps(stream_name, &pad_caps).map_err(|err| {
+                    gst_error!(CAT, obj: aggregator, "Invalid CAPS on `{}`: {}", pad_name, err);
+                    gst::FlowError::NotNegotiated
+                })?;
+            if let Some(stream_framerate) = framerate_of(pad) {
+                // Streams without video do not receive the shared framerate, therefore they
+                // keep their framerate in order to restore their CAPS in `rgbddemux`
+                if stream_framerate != framerate || !stream_caps.has_shared_framerate() {
+                    gst_debug!(
+                        CAT,
+                        obj: aggregator,
+                        "`{}` has its own framerate {}",
+                        pad_name,
+                        stream_framerate
+                    );
+                    stream_caps = stream_caps.with_framerate(stream_framerate);
+                }
+            }
+            downstream_caps = downstream_caps.with_stream(stream_caps);
         }
 
-        Ok(downstream_caps.to_owned())
+        Ok(downstream_caps.to_caps())
     }
 
     /// Called when the element needs to know the running time of the next rendered buffer for live pipelines.
@@ -306,6 +324,14 @@ impl AggregatorImpl for RgbdMux {
     /// # Arguments
     /// * `aggregator` - A reference to the element that represents `rgbdmux` in GStreamer.
     fn next_time(&self, aggregator: &RgbdMuxObject) -> Option<gst::ClockTime> {
+        // Without a deadline, the base class waits for buffers on all sink pads. There is no
+        // deadline for a variable framerate either, as the position does not advance by a frame.
+        if self.settings.read().unwrap().sync_policy == SyncPolicy::WaitAll
+            || self.frame_duration().is_none()
+        {
+            return None;
+        }
+
         let nt = aggregator.simple_get_next_time();
         gst_debug!(CAT, "Aggregator next_time: {:?}", nt);
         nt
@@ -318,22 +344,86 @@ impl AggregatorImpl for RgbdMux {
         aggregator: &Self::Type,
         caps: &gst::Caps,
     ) -> Result<(), gst::LoggableError> {
-        // Framerate should be known at this point in time
-        let framerate = self.state.lock().unwrap().framerate.unwrap();
-        let duration = RgbdMux::get_duration_from_fps(framerate).unwrap();
+        gst_debug!(CAT, "Negotiated src caps are: {}", caps);
 
-        gst_debug!(CAT, "Setting latency to {}ms", duration.mseconds());
-        aggregator.set_latency(duration, duration);
+        // Mux only the streams that are contained in the negotiated caps
+        let rgbd_caps = rgbd::RgbdCaps::from_caps(caps)?;
+        {
+            let mut state = self.state.lock().unwrap();
+            state.streams = rgbd_caps.stream_names().map(str::to_string).collect();
+            state.stream_framerates = rgbd_caps
+                .streams()
+                .filter_map(|stream| Some((stream.name().to_string(), stream.framerate()?)))
+                .filter(|(_, framerate)| Some(*framerate) != rgbd_caps.framerate())
+                .collect();
+        }
+
+        // Framerate should be known at this point in time
+        self.update_latency(aggregator);
 
-        gst_debug!(CAT, "Negotiated src caps are: {}", caps);
         self.parent_negotiated_src_caps(aggregator, caps)
     }
 
+    /// Called whenever a query is received at one of the sink pads. CAPS are accepted only if
+    /// their media type can be contained in `video/rgbd` CAPS, as the template CAPS of the sink
+    /// pads are ANY.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    /// * `aggregator_pad` - The pad that received the query.
+    /// * `query` - The query that should be answered.
+    fn sink_query(
+        &self,
+        aggregator: &Self::Type,
+        aggregator_pad: &AggregatorPad,
+        query: &mut gst::QueryRef,
+    ) -> bool {
+        if let gst::QueryViewMut::AcceptCaps(accept_caps) = query.view_mut() {
+            let result = rgbd::RgbdStreamCaps::from_caps(
+                &Self::stream_name(aggregator_pad),
+                accept_caps.caps(),
+            );
+            if let Err(err) = &result {
+                gst_warning!(CAT, obj: aggregator_pad, "Rejecting CAPS: {}", err);
+            }
+            accept_caps.set_result(result.is_ok());
+            return true;
+        }
+        self.parent_sink_query(aggregator, aggregator_pad, query)
+    }
+
+    /// Called whenever a query is received at the src pad. Latency queries are answered by the
+    /// base class, which combines the latency of all upstream elements with the latency of the
+    /// `rgbdmux` and the `latency` property.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    /// * `query` - The query that should be answered.
+    fn src_query(&self, aggregator: &Self::Type, query: &mut gst::QueryRef) -> bool {
+        let result = self.parent_src_query(aggregator, query);
+        if let gst::QueryView::Latency(latency) = query.view() {
+            let (live, min, max) = latency.result();
+            gst_debug!(
+                CAT,
+                obj: aggregator,
+                "Reporting latency: live: {}, min: {}, max: {}",
+                live,
+                min,
+                max.display()
+            );
+        }
+        result
+    }
+
     /// Called when the element goes from PAUSED to READY.
     /// # Arguments
     /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
     fn stop(&self, aggregator: &RgbdMuxObject) -> Result<(), gst::ErrorMessage> {
         // Reset internals (except for settings)
+        {
+            let mut state = self.state.lock().unwrap();
+            state.dropped.clear();
+            state.last_buffers.clear();
+            state.batches.clear();
+        }
         self.parent_stop(aggregator)
     }
 }
@@ -345,19 +435,23 @@ impl ElementImpl for RgbdMux {
     /// * `element` - The element that represents `rgbdmux` in GStreamer.
     /// * `pad` - The pad that is soon to be released.
     fn release_pad(&self, element: &Self::Type, pad: &gst::Pad) {
-        // De-activate the pad
-        pad.set_active(false)
-            .unwrap_or_else(|_| panic!("Could not deactivate a sink pad: {:?}", pad));
-
-        // Remove the pad from the element
-        element
-            .remove_pad(pad)
-            .unwrap_or_else(|_| panic!("Could not remove a sink pad: {:?}", pad));
-
-        // Remove the pad from our internal reference HashMap
         let pad_name = pad.name().as_str().to_string();
         gst_debug!(CAT, obj: element, "release_pad: {}", pad_name);
 
+        // Stop muxing the stream of the pad right away, even before renegotiation
+        {
+            let stream_name = pad_name.trim_start_matches("sink_");
+            let mut state = self.state.lock().unwrap();
+            state.streams.retain(|stream| stream != stream_name);
+            state.dropped.remove(stream_name);
+            state.last_buffers.remove(stream_name);
+            state.stream_framerates.remove(stream_name);
+            state.batches.remove(stream_name);
+        }
+
+        // Let the base class flush, deactivate and remove the pad from the element
+        self.parent_release_pad(element, pad);
+
         // Mark src pad for reconfiguration and let the base class renegotiate right before the next call to aggregate()
         let src_pad = element
             .static_pad("src")
@@ -382,12 +476,10 @@ impl ElementImpl for RgbdMux {
 
     fn pad_templates() -> &'static [gst::PadTemplate] {
         static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 2]> = Lazy::new(|| {
-            let mut sink_caps = gst::Caps::new_simple("video/x-raw", &[]);
-            {
-                let sink_caps = sink_caps.get_mut().unwrap();
-                sink_caps.append(gst::Caps::new_simple("meta/x-klv", &[("parsed", &true)]));
-                sink_caps.append(gst::Caps::new_simple("image/jpeg", &[]));
-            }
+            // Besides `video/x-raw`, `image/jpeg` and `meta/x-klv`, any `application/x-*` media
+            // type is accepted, which cannot be expressed in template CAPS. The CAPS of sink pads
+            // are therefore checked in `sink_query()` instead.
+            let sink_caps = gst::Caps::new_any();
 
             [
                 gst::PadTemplate::with_gtype(
@@ -414,12 +506,616 @@ impl ElementImpl for RgbdMux {
 }
 
 impl RgbdMux {
+    /// Aggregate buffers according to `SyncPolicy::Strict`, i.e. mux buffers that fall into the
+    /// frame window of the current output position.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    /// * `sink_pads` - The sink pads of streams at the framerate of the output.
+    /// * `independent_pads` - The sink pads of streams with their own framerate.
+    /// * `timeout` - Whether this is the last chance to produce data for the current position.
+    fn aggregate_strict(
+        &self,
+        aggregator: &RgbdMuxObject,
+        sink_pads: &[AggregatorPad],
+        independent_pads: &[AggregatorPad],
+        timeout: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        // Get the current deadline time or desired output time
+        let segment = Self::src_segment(aggregator);
+        let segment_position = segment.position().or_else(|| segment.start());
+        let position_running_time = segment.to_running_time(segment_position).unwrap();
+
+        // The frame window is undefined for a variable framerate
+        let duration = self.frame_duration().ok_or_else(|| {
+            gst::element_error!(
+                aggregator,
+                gst::StreamError::Format,
+                ["`strict` sync-policy requires a fixed framerate, use `nearest` or `wait_all` instead"]
+            );
+            gst::FlowError::NotNegotiated
+        })?;
+        let first = self.state.lock().unwrap().first;
+
+        gst_debug!(
+            CAT,
+            "Segment position: {:?}, start: {:?}, end: {:?}",
+            segment.position(),
+            segment.start(),
+            segment.stop()
+        );
+
+        let mut has_all_buffers_in_range = true;
+        for sink_pad in sink_pads {
+            let buffer_in_range = self.drop_out_of_range_buffers_on_pad(
+                aggregator,
+                position_running_time,
+                duration,
+                sink_pad,
+                first,
+            )?;
+            has_all_buffers_in_range = has_all_buffers_in_range && buffer_in_range;
+        }
+
+        if has_all_buffers_in_range {
+            // The window of buffers is shifted by the latency, see `drop_out_of_range_buffers_on_pad()`
+            let latency = aggregator.latency().unwrap_or_default();
+            let window_end = (position_running_time + duration).saturating_sub(latency);
+            if !self.collect_independent_buffers(
+                aggregator,
+                independent_pads,
+                window_end,
+                timeout,
+            )? {
+                gst_debug!(
+                    CAT,
+                    "Waiting for buffers of streams with their own framerate"
+                );
+                return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
+            }
+
+            gst_debug!(CAT, "All buffers in range, muxing");
+            let buffers = Self::pop_buffers(sink_pads)?;
+            self.finish_frameset(aggregator, buffers, independent_pads, segment_position)?;
+            self.state.lock().unwrap().first = false;
+            return Ok(gst::FlowSuccess::Ok);
+        }
+
+        if timeout {
+            return self.handle_timeout(aggregator);
+        }
+
+        let all_pads_have_buffers = sink_pads.iter().all(|pad| pad.has_buffer());
+        if all_pads_have_buffers {
+            gst_debug!(
+                CAT,
+                "Queues are full, but not all buffers are in range. Sending Gap event",
+            );
+            return self.skip_frame(aggregator);
+        }
+
+        gst_debug!(CAT, "Need more data");
+        Err(gst_base::AGGREGATOR_FLOW_NEED_DATA)
+    }
+
+    /// Aggregate buffers according to `SyncPolicy::Nearest`, i.e. mux buffers whose running times
+    /// are within `tolerance` of the newest queued buffer. Buffers that are older cannot be
+    /// matched with the newest buffer anymore, therefore they are dropped.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    /// * `sink_pads` - The sink pads of streams at the framerate of the output.
+    /// * `independent_pads` - The sink pads of streams with their own framerate.
+    /// * `timeout` - Whether this is the last chance to produce data for the current position.
+    fn aggregate_nearest(
+        &self,
+        aggregator: &RgbdMuxObject,
+        sink_pads: &[AggregatorPad],
+        independent_pads: &[AggregatorPad],
+        timeout: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let tolerance = self.settings.read().unwrap().tolerance;
+        loop {
+            let running_times = Self::queued_running_times(sink_pads)?;
+            let newest = match running_times.iter().flatten().max() {
+                Some(newest) => *newest,
+                None => break,
+            };
+
+            let mut dropped_any = false;
+            for (pad, running_time) in sink_pads.iter().zip(&running_times) {
+                if let Some(running_time) = running_time {
+                    if *running_time + tolerance < newest {
+                        self.drop_buffer(
+                            pad,
+                            &format!(
+                                "running time {} is more than {} older than {}",
+                                running_time, tolerance, newest
+                            ),
+                        );
+                        dropped_any = true;
+                    }
+                }
+            }
+            if dropped_any {
+                // Look at the next queued buffers
+                continue;
+            }
+
+            if running_times.iter().all(Option::is_some) {
+                if !self.collect_independent_buffers(
+                    aggregator,
+                    independent_pads,
+                    newest + self.frame_duration().unwrap_or_default(),
+                    timeout,
+                )? {
+                    gst_debug!(
+                        CAT,
+                        "Waiting for buffers of streams with their own framerate"
+                    );
+                    return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
+                }
+
+                gst_debug!(
+                    CAT,
+                    obj: aggregator,
+                    "All buffers are within {} of {}, muxing",
+                    tolerance,
+                    newest
+                );
+                let buffers = Self::pop_buffers(sink_pads)?;
+                return self.finish_frameset(
+                    aggregator,
+                    buffers,
+                    independent_pads,
+                    Self::output_position(aggregator, newest),
+                );
+            }
+            break;
+        }
+
+        if timeout {
+            return self.handle_timeout(aggregator);
+        }
+        gst_debug!(CAT, "Need more data");
+        Err(gst_base::AGGREGATOR_FLOW_NEED_DATA)
+    }
+
+    /// Aggregate buffers according to `SyncPolicy::Latest`, i.e. mux the newest queued buffer of
+    /// each sink pad once all pads have a buffer, or once the deadline is reached. Pads without a
+    /// queued buffer reuse the buffer that was muxed previously.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    /// * `sink_pads` - The sink pads of streams at the framerate of the output.
+    /// * `independent_pads` - The sink pads of streams with their own framerate.
+    /// * `timeout` - Whether this is the last chance to produce data for the current position.
+    fn aggregate_latest(
+        &self,
+        aggregator: &RgbdMuxObject,
+        sink_pads: &[AggregatorPad],
+        independent_pads: &[AggregatorPad],
+        timeout: bool,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let any_queued = sink_pads.iter().any(|pad| pad.has_buffer());
+        let all_queued = sink_pads.iter().all(|pad| pad.has_buffer());
+        let all_available = {
+            let state = self.state.lock().unwrap();
+            sink_pads.iter().all(|pad| {
+                pad.has_buffer() || state.last_buffers.contains_key(&Self::stream_name(pad))
+            })
+        };
+        if !all_available || !(all_queued || (timeout && any_queued)) {
+            if timeout {
+                return self.handle_timeout(aggregator);
+            }
+            gst_debug!(CAT, "Need more data");
+            return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
+        }
+
+        let mut buffers = Vec::with_capacity(sink_pads.len());
+        let mut newest_running_time = None;
+        for pad in sink_pads {
+            let stream_name = Self::stream_name(pad);
+
+            // Keep only the newest queued buffer
+            let mut newest = None;
+            while let Some(buffer) = pad.pop_buffer() {
+                if newest.replace(buffer).is_some() {
+                    self.record_drop(pad, "a newer buffer is queued");
+                }
+            }
+
+            let buffer = match newest {
+                Some(buffer) => {
+                    newest_running_time =
+                        newest_running_time.max(Some(Self::running_time(pad, &buffer)?));
+                    self.state
+                        .lock()
+                        .unwrap()
+                        .last_buffers
+                        .insert(stream_name.clone(), buffer.clone());
+                    buffer
+                }
+                None => {
+                    gst_debug!(
+                        CAT,
+                        obj: pad,
+                        "No new buffer is queued, reusing the previous one"
+                    );
+                    self.state
+                        .lock()
+                        .unwrap()
+                        .last_buffers
+                        .get(&stream_name)
+                        .cloned()
+                        .ok_or(gst::FlowError::Error)?
+                }
+            };
+            buffers.push((stream_name, buffer));
+        }
+
+        // Streams with their own framerate contribute whatever is available, without waiting
+        if let Some(newest_running_time) = newest_running_time {
+            self.collect_independent_buffers(
+                aggregator,
+                independent_pads,
+                newest_running_time + self.frame_duration().unwrap_or_default(),
+                true,
+            )?;
+        }
+
+        let position = newest_running_time
+            .and_then(|running_time| Self::output_position(aggregator, running_time));
+        self.finish_frameset(aggregator, buffers, independent_pads, position)
+    }
+
+    /// Aggregate buffers according to `SyncPolicy::WaitAll`, i.e. mux the queued buffers once all
+    /// sink pads have one, regardless of their timestamps.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    /// * `sink_pads` - The sink pads of streams at the framerate of the output.
+    /// * `independent_pads` - The sink pads of streams with their own framerate.
+    fn aggregate_wait_all(
+        &self,
+        aggregator: &RgbdMuxObject,
+        sink_pads: &[AggregatorPad],
+        independent_pads: &[AggregatorPad],
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let running_times = Self::queued_running_times(sink_pads)?;
+        if !running_times.iter().all(Option::is_some) {
+            gst_debug!(CAT, "Need more data");
+            return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
+        }
+
+        let newest = running_times.into_iter().flatten().max().unwrap();
+        if !self.collect_independent_buffers(
+            aggregator,
+            independent_pads,
+            newest + self.frame_duration().unwrap_or_default(),
+            false,
+        )? {
+            gst_debug!(
+                CAT,
+                "Waiting for buffers of streams with their own framerate"
+            );
+            return Err(gst_base::AGGREGATOR_FLOW_NEED_DATA);
+        }
+
+        let buffers = Self::pop_buffers(sink_pads)?;
+        self.finish_frameset(
+            aggregator,
+            buffers,
+            independent_pads,
+            Self::output_position(aggregator, newest),
+        )
+    }
+
+    /// Collect the queued buffers of streams with their own framerate, whose running times are
+    /// before the end of the current frameset. The collected buffers are timestamped in the
+    /// segment of the src pad and muxed with the next frameset by `finish_frameset()`. Streams
+    /// that are faster than the output thereby contribute several buffers to a frameset.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    /// * `independent_pads` - The sink pads of streams with their own framerate.
+    /// * `window_end` - Running time at which the next frameset starts.
+    /// * `timeout` - Whether this is the last chance to produce data for the current position.
+    /// # Returns
+    /// * `Ok(true)` if all buffers of the current frameset were collected, i.e. each pad has a
+    /// buffer of a later frameset queued, is EOS or the deadline was reached.
+    /// * `Ok(false)` if any of the pads may still receive a buffer of the current frameset.
+    fn collect_independent_buffers(
+        &self,
+        aggregator: &RgbdMuxObject,
+        independent_pads: &[AggregatorPad],
+        window_end: gst::ClockTime,
+        timeout: bool,
+    ) -> Result<bool, gst::FlowError> {
+        let segment = Self::src_segment(aggregator);
+        let mut complete = true;
+        for pad in independent_pads {
+            while let Some(buffer) = pad.peek_buffer() {
+                let running_time = Self::running_time(pad, &buffer)?;
+                if running_time >= window_end {
+                    break;
+                }
+
+                let mut buffer = pad.pop_buffer().unwrap();
+                let position = segment.position_from_running_time(running_time);
+                {
+                    let bufref = buffer.make_mut();
+                    bufref.set_pts(position);
+                    bufref.set_dts(position);
+                }
+                self.state
+                    .lock()
+                    .unwrap()
+                    .batches
+                    .entry(Self::stream_name(pad))
+                    .or_default()
+                    .push(buffer);
+            }
+
+            if !pad.has_buffer() && !pad.is_eos() && !timeout {
+                complete = false;
+            }
+        }
+        Ok(complete)
+    }
+
+    /// Handle a timeout of the aggregator, i.e. the deadline for the current output position was
+    /// reached without muxing a frameset. A gap event is sent for the missing frameset.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    fn handle_timeout(
+        &self,
+        aggregator: &RgbdMuxObject,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        gst_debug!(CAT, "Timeout. Sending Gap event");
+        self.skip_frame(aggregator)
+    }
+
+    /// Update the latency of the `rgbdmux`, which is reported to the pipeline. The muxer waits up
+    /// to one frame for buffers of all streams, and additionally up to `tolerance` for buffers
+    /// with a phase offset if `SyncPolicy::Nearest` is used. Nothing happens if the framerate is
+    /// not yet known or variable.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    fn update_latency(&self, aggregator: &RgbdMuxObject) {
+        let mut latency = match self.frame_duration() {
+            Some(duration) => duration,
+            None => return,
+        };
+        {
+            let settings = self.settings.read().unwrap();
+            if settings.sync_policy == SyncPolicy::Nearest {
+                latency += settings.tolerance;
+            }
+        }
+
+        gst_debug!(CAT, obj: aggregator, "Setting latency to {}", latency);
+        // The base class posts a latency message if the latency changed
+        aggregator.set_latency(latency, latency);
+    }
+
+    /// Get the duration of a single frameset, derived from the framerate of the output.
+    /// # Returns
+    /// * `None` if the framerate is not yet known or variable, i.e. `0/1`.
+    fn frame_duration(&self) -> Option<gst::ClockTime> {
+        self.state
+            .lock()
+            .unwrap()
+            .framerate
+            .and_then(Self::get_duration_from_fps)
+    }
+
+    /// Skip the frameset at the current output position by sending a gap event downstream and
+    /// advancing the position.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    fn skip_frame(&self, aggregator: &RgbdMuxObject) -> Result<gst::FlowSuccess, gst::FlowError> {
+        self.send_gap_event(aggregator).map_err(|_| {
+            self.advance_segment_position(aggregator);
+            gst::FlowError::Error
+        })?;
+        self.advance_segment_position(aggregator);
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    /// Mux `buffers` into a single frameset at the given output `position`, advance the output
+    /// position by one frame and push the frameset downstream.
+    /// # Arguments
+    /// * `aggregator` - The element that represents the `rgbdmux` in GStreamer.
+    /// * `buffers` - The buffers to mux, together with the name of their stream. The first buffer
+    /// becomes the main buffer.
+    /// * `independent_pads` - The sink pads of streams with their own framerate, whose collected
+    /// buffers are muxed as well.
+    /// * `position` - Position of the frameset in the segment of the src pad.
+    fn finish_frameset(
+        &self,
+        aggregator: &RgbdMuxObject,
+        buffers: Vec<(String, gst::Buffer)>,
+        independent_pads: &[AggregatorPad],
+        position: Option<gst::ClockTime>,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let duration = self.frame_duration();
+
+        let mut segment = Self::src_segment(aggregator);
+        if let Some(position) = position {
+            segment.set_position(position);
+            aggregator.update_segment(&segment);
+        }
+        let running_time = segment.to_running_time(position);
+        self.advance_segment_position(aggregator);
+
+        // https://gstreamer.freedesktop.org/documentation/base/gstaggregator.html?gi-language=c#gst_aggregator_selected_samples
+        aggregator.selected_samples(running_time, running_time, duration, None);
+        let outbuf = self
+            .mux_buffers_set_ts(aggregator, buffers, position, duration)
+            .and_then(|mut outbuf| {
+                self.attach_independent_buffers(independent_pads, outbuf.make_mut())?;
+                Ok(outbuf)
+            })
+            .map_err(|e| {
+                gst_error!(CAT, obj: aggregator, "Failed to mux buffers: {}", e);
+                gst::FlowError::Error
+            })?;
+
+        gst_debug!(CAT, "Muxed buffers, finishing");
+        self.finish_buffer(aggregator, outbuf)
+    }
+
+    /// Get the sink pads of the aggregator whose streams are contained in the negotiated src
+    /// CAPS, in the order of the streams.
+    /// # Arguments
+    /// * `aggregator` - The aggregator to consider.
+    fn negotiated_sink_pads(&self, aggregator: &RgbdMuxObject) -> Vec<AggregatorPad> {
+        let streams = self.state.lock().unwrap().streams.clone();
+        streams
+            .iter()
+            .filter_map(|stream_name| aggregator.static_pad(&format!("sink_{}", stream_name)))
+            .filter_map(|pad| pad.downcast::<AggregatorPad>().ok())
+            .collect()
+    }
+
+    /// Get the name of the stream that flows through the given sink pad.
+    /// # Arguments
+    /// * `sink_pad` - The sink pad, e.g. `sink_depth`.
+    fn stream_name(sink_pad: &AggregatorPad) -> String {
+        sink_pad.name().trim_start_matches("sink_").to_string()
+    }
+
+    /// Get the segment of the src pad of the aggregator.
+    /// # Arguments
+    /// * `aggregator` - The aggregator to consider.
+    fn src_segment(aggregator: &RgbdMuxObject) -> gst::FormattedSegment<gst::ClockTime> {
+        let src_pads = aggregator.src_pads();
+        let agg_pad: &gst_base::AggregatorPad = src_pads[0].downcast_ref().unwrap();
+        agg_pad.segment().downcast().unwrap()
+    }
+
+    /// Convert the running time of a muxed buffer into a position in the segment of the src pad.
+    /// The position never moves backwards, such that framesets are output in order.
+    /// # Arguments
+    /// * `aggregator` - The aggregator to consider.
+    /// * `running_time` - The running time of the muxed buffer.
+    fn output_position(
+        aggregator: &RgbdMuxObject,
+        running_time: gst::ClockTime,
+    ) -> Option<gst::ClockTime> {
+        let segment = Self::src_segment(aggregator);
+        let current_position = segment.position().or_else(|| segment.start());
+        segment
+            .position_from_running_time(running_time)
+            .max(current_position)
+    }
+
+    /// Get the running time of a buffer that is queued on the given sink pad.
+    /// # Arguments
+    /// * `sink_pad` - The sink pad on which the buffer is queued.
+    /// * `buffer` - The buffer to consider.
+    /// # Returns
+    /// * `Err(gst::FlowError::Error)` if the buffer has no timestamp.
+    fn running_time(
+        sink_pad: &AggregatorPad,
+        buffer: &gst::Buffer,
+    ) -> Result<gst::ClockTime, gst::FlowError> {
+        let segment = sink_pad.segment().downcast::<gst::ClockTime>().unwrap();
+        let pts = buffer.pts().ok_or_else(|| {
+            gst_error!(CAT, obj: sink_pad, "Buffer has no timestamp");
+            gst::FlowError::Error
+        })?;
+        Ok(segment.to_running_time(pts).unwrap_or(gst::ClockTime::ZERO))
+    }
+
+    /// Get the running time of the first queued buffer of each sink pad.
+    /// # Arguments
+    /// * `sink_pads` - The sink pads to consider.
+    /// # Returns
+    /// * Running time for each pad, in the same order as `sink_pads`. `None` for pads without a
+    /// queued buffer.
+    fn queued_running_times(
+        sink_pads: &[AggregatorPad],
+    ) -> Result<Vec<Option<gst::ClockTime>>, gst::FlowError> {
+        sink_pads
+            .iter()
+            .map(|pad| {
+                pad.peek_buffer()
+                    .map(|buffer| Self::running_time(pad, &buffer))
+                    .transpose()
+            })
+            .collect()
+    }
+
+    /// Pop the first queued buffer of each sink pad.
+    /// # Arguments
+    /// * `sink_pads` - The sink pads to consider.
+    /// # Returns
+    /// * The buffers together with the name of their stream, in the same order as `sink_pads`.
+    /// * `Err(gst::FlowError::Error)` if any of the pads has no queued buffer.
+    fn pop_buffers(
+        sink_pads: &[AggregatorPad],
+    ) -> Result<Vec<(String, gst::Buffer)>, gst::FlowError> {
+        sink_pads
+            .iter()
+            .map(|pad| {
+                let buffer = pad.pop_buffer().ok_or_else(|| {
+                    gst_error!(CAT, obj: pad, "No buffer is queued");
+                    gst::FlowError::Error
+                })?;
+                Ok((Self::stream_name(pad), buffer))
+            })
+            .collect()
+    }
+
+    /// Drop the first queued buffer of the given sink pad and count it as dropped.
+    /// # Arguments
+    /// * `sink_pad` - The sink pad to drop the buffer from.
+    /// * `reason` - The reason for dropping the buffer, used for logging.
+    fn drop_buffer(&self, sink_pad: &AggregatorPad, reason: &str) {
+        sink_pad.drop_buffer();
+        self.record_drop(sink_pad, reason);
+    }
+
+    /// Count a buffer of the given sink pad as dropped, which is reported by `stats` property.
+    /// # Arguments
+    /// * `sink_pad` - The sink pad that the buffer was queued on.
+    /// * `reason` - The reason for dropping the buffer, used for logging.
+    fn record_drop(&self, sink_pad: &AggregatorPad, reason: &str) {
+        let mut state = self.state.lock().unwrap();
+        let dropped = state
+            .dropped
+            .entry(Self::stream_name(sink_pad))
+            .or_default();
+        *dropped += 1;
+        gst_info!(
+            CAT,
+            obj: sink_pad,
+            "Dropped buffer ({} in total): {}",
+            dropped,
+            reason
+        );
+    }
+
+    /// Get the statistics of the muxer, which contain the number of dropped buffers of each
+    /// stream as `{stream}_dropped` fields.
+    fn stats(&self) -> gst::Structure {
+        let state = self.state.lock().unwrap();
+        let mut stats = gst::Structure::new_empty("application/x-rgbdmux-stats");
+        for (stream_name, dropped) in state.dropped.iter() {
+            stats.set(&format!("{}_dropped", stream_name), *dropped);
+        }
+        stats
+    }
+
     /// Advances the segment position of an gst_base::Aggregator's srcpad.segment
     /// This is a requirement for aggregator subclasses that use the `aggregator.simple_get_next_time()`
-    /// for the required trait impl function `next_time()`
+    /// for the required trait impl function `next_time()`. The position is kept for a variable
+    /// framerate, as it is then set from the running times of the muxed buffers.
     /// # Arguments
     /// * `aggregator` - The aggregator to consider.
     fn advance_segment_position(&self, aggregator: &RgbdMuxObject) {
+        let duration = match self.frame_duration() {
+            Some(duration) => duration,
+            None => return,
+        };
         let src_pads = aggregator.src_pads();
         let agg_pad = src_pads[0]
             .downcast_ref::<gst_base::AggregatorPad>()
@@ -432,12 +1128,10 @@ impl RgbdMux {
         let end: gst::ClockTime = segment.stop().unwrap_or(gst::ClockTime::MAX);
         let pts: gst::ClockTime = segment.position().unwrap_or(start);
 
-        let framerate = self.state.lock().unwrap().framerate.unwrap();
-        let duration = Self::get_duration_from_fps(framerate);
         let new_position = if segment.rate() > 0.0 {
-            pts + duration.unwrap()
+            pts + duration
         } else {
-            pts - duration.unwrap()
+            pts.saturating_sub(duration)
         };
 
         let new_position = new_position.clamp(start, end);
@@ -453,8 +1147,11 @@ impl RgbdMux {
     /// * `framerate` - fraction specified in seconds 30fps -> 30/1
     /// # returns
     /// * Some(duration) - if could convert the fraction to its duration
-    /// * None - if the numerator was zero
+    /// * None - if the framerate is variable (`0/1`) or invalid
     fn get_duration_from_fps(framerate: gst::Fraction) -> Option<gst::ClockTime> {
+        if framerate.numer() <= 0 || framerate.denom() <= 0 {
+            return None;
+        }
         gst::ClockTime::SECOND.mul_div_floor(framerate.denom() as u64, framerate.numer() as u64)
     }
 
@@ -488,14 +1185,12 @@ impl RgbdMux {
             // }
 
             if buffer_running_time + buffer_duration < position_running_time {
-                sink_pad.drop_buffer();
-                gst_info!(
-                    CAT,
-                    "Dropped buffer: {} {} {} {}",
-                    sink_pad.name(),
-                    buffer_running_time,
-                    position_running_time,
-                    latency,
+                self.drop_buffer(
+                    sink_pad,
+                    &format!(
+                        "running time {} is before position {} (latency {})",
+                        buffer_running_time, position_running_time, latency
+                    ),
                 );
             } else if first || buffer_running_time < position_running_time + duration {
                 return Ok(true);
@@ -515,82 +1210,121 @@ impl RgbdMux {
     /// Mux all buffers to a single output buffer. All buffers are properly tagget with a title.
     /// # Arguments
     /// * `aggregator` - The aggregator to consider.
+    /// * `buffers` - The buffers to mux, together with the name of their stream. The first buffer
+    /// becomes the main buffer.
+    /// * `ts` - Timestamp of the output buffer.
+    /// * `duration` - Duration of the output buffer.
     /// # Returns:
     /// * OK(buf) - The main buffer, containing aux buffers as BufferMeta
     /// * Err
     fn mux_buffers_set_ts(
         &self,
         aggregator: &RgbdMuxObject,
+        buffers: Vec<(String, gst::Buffer)>,
         ts: Option<gst::ClockTime>,
         duration: Option<gst::ClockTime>,
     ) -> Result<gst::Buffer, gst::ErrorMessage> {
-        let sink_pads = aggregator.sink_pads();
-        let mut sink_pads = sink_pads
-            .iter()
-            .filter_map(|sp| sp.downcast_ref::<gst_base::AggregatorPad>());
+        let mut buffers = buffers.into_iter();
 
-        // Use the first buffer in the aggregator.sink_pads as the buffer we send out
-        let first_pad = sink_pads.next().unwrap();
-        let first_pad_name = first_pad.name().to_string();
-
-        let mut main_buffer = match first_pad.pop_buffer() {
+        // Use the first buffer as the buffer we send out
+        let mut main_buffer = match buffers.next() {
             // We have a buffer, let's tag it
-            Some(mut buf) => {
+            Some((stream_name, mut buf)) => {
                 let bref = buf.make_mut();
                 bref.set_dts(ts);
                 bref.set_pts(ts);
                 bref.set_duration(duration);
-                let stream_name = first_pad_name.trim_start_matches("sink_");
-                rgbd::tag_buffer_with_title(bref, stream_name)?;
+                rgbd::tag_buffer_with_title(bref, &stream_name)?;
+                rgbd::video_roi_metas_to_roi_meta(bref, &stream_name);
                 buf
             }
-            // There is no buffer, let's send a gap event
             None => {
                 return Err(gst::error_msg!(gst::CoreError::Pad, ["No buffer found"]));
             }
         };
 
-        // Iterate over all other sink pads, excluding the first one (already processed)
-        // For each pad, get a tagged buffer and attach it to the main buffer
-        // If a sink pad has no buffer queued, create an empty GAP buffer and attach it to the main buffer as well
-        for agg_pad in sink_pads {
-            self.attach_aux_buffers(agg_pad, main_buffer.make_mut(), ts, duration)?;
+        // Iterate over all other buffers, excluding the first one (already processed)
+        // For each buffer, tag it and attach it to the main buffer
+        for (stream_name, buffer) in buffers {
+            self.attach_aux_buffer(&stream_name, buffer, main_buffer.make_mut(), ts, duration)?;
         }
 
         gst_debug!(CAT, obj: aggregator, "A frameset was muxed.");
         Ok(main_buffer)
     }
 
-    /// Get a tagged buffer from pad `sink_pad_name` and attach it to `main_buffer`. If a sink pad has no buffer queued,
-    /// create an empty GAP buffer and attach it to the main buffer as well.
+    /// Tag `buffer` of the stream with the given name and attach it to `main_buffer`.
     /// # Arguments
-    /// * `aggregator` - The aggregator to consider.
-    /// * `sink_pad_names` - The vector containing all sink pad names.
+    /// * `stream_name` - The name of the stream that the buffer belongs to.
+    /// * `buffer` - The auxiliary buffer.
     /// * `main_buffer` - Mutable reference to the main buffer to which we attach all auxiliary buffers.
-    fn attach_aux_buffers(
+    /// * `ts` - Timestamp of the output buffer.
+    /// * `duration` - Duration of the output buffer.
+    fn attach_aux_buffer(
         &self,
-        sink_pad: &AggregatorPad,
+        stream_name: &str,
+        mut buffer: gst::Buffer,
         main_buffer: &mut gst::BufferRef,
         ts: Option<gst::ClockTime>,
         duration: Option<gst::ClockTime>,
     ) -> Result<(), gst::ErrorMessage> {
-        let stream_name = sink_pad.name();
-        let stream_name = stream_name.as_str().trim_start_matches("sink_");
-        match sink_pad.pop_buffer() {
-            Some(mut buffer) => {
+        let bufref = buffer.make_mut();
+        bufref.set_dts(ts);
+        bufref.set_pts(ts);
+        bufref.set_duration(duration);
+        rgbd::tag_buffer_with_title(bufref, stream_name)?;
+        // Carry regions of interest of all streams on the main buffer
+        rgbd::video_roi_metas_to_roi_meta(bufref, stream_name);
+        rgbd::move_roi_metas(bufref, main_buffer);
+        BufferMeta::add(main_buffer, &mut buffer);
+        Ok(())
+    }
+
+    /// Attach the collected buffers of streams with their own framerate to `main_buffer`. These
+    /// buffers keep their own timestamps. Streams that are slower than the output attach their
+    /// most recent buffer again if no new buffer was collected for this frameset.
+    /// # Arguments
+    /// * `independent_pads` - The sink pads of streams with their own framerate.
+    /// * `main_buffer` - Mutable reference to the main buffer to which we attach all auxiliary buffers.
+    fn attach_independent_buffers(
+        &self,
+        independent_pads: &[AggregatorPad],
+        main_buffer: &mut gst::BufferRef,
+    ) -> Result<(), gst::ErrorMessage> {
+        for pad in independent_pads {
+            let stream_name = Self::stream_name(pad);
+            let buffers = {
+                let mut state = self.state.lock().unwrap();
+                let batch = state.batches.remove(&stream_name).unwrap_or_default();
+                match batch.last() {
+                    Some(last) => {
+                        state.last_buffers.insert(stream_name.clone(), last.clone());
+                        batch
+                    }
+                    None => state
+                        .last_buffers
+                        .get(&stream_name)
+                        .cloned()
+                        .into_iter()
+                        .collect(),
+                }
+            };
+            gst_trace!(
+                CAT,
+                obj: pad,
+                "Attaching {} buffer(s) with their own timestamps",
+                buffers.len()
+            );
+
+            for mut buffer in buffers {
                 let bufref = buffer.make_mut();
-                bufref.set_dts(ts);
-                bufref.set_pts(ts);
-                bufref.set_duration(duration);
-                rgbd::tag_buffer_with_title(bufref, stream_name)?;
+                rgbd::tag_buffer_with_title(bufref, &stream_name)?;
+                rgbd::video_roi_metas_to_roi_meta(bufref, &stream_name);
+                rgbd::move_roi_metas(bufref, main_buffer);
                 BufferMeta::add(main_buffer, &mut buffer);
-                Ok(())
             }
-            None => Err(gst::error_msg!(
-                gst::CoreError::Pad,
-                ["No buffer is queued on auxiliary `{}` pad.", stream_name]
-            )),
         }
+        Ok(())
     }
 
     /// Sends a gap event downstream.
@@ -609,8 +1343,7 @@ impl RgbdMux {
         //todo: Unclear if we have to use the running_time to create the gap event
         // let running_time = segment.to_running_time(pts).unwrap();
 
-        let framerate = self.state.lock().unwrap().framerate.unwrap();
-        let duration = RgbdMux::get_duration_from_fps(framerate);
+        let duration = self.frame_duration();
 
         // Create a GAP event with duration
         let gap_event = gst::event::Gap::new(pts, duration);
@@ -628,47 +1361,494 @@ impl RgbdMux {
 
         Ok(())
     }
+}
 
-    /// Extracts the relevant fields from the pad's CAPS and converts them into a tuple containing
-    /// the field's name as the first and its value as second.
-    /// # Arguments
-    /// * `pad_caps` - A reference to the pad's CAPS.
-    /// * `pad_name` - The name of the stream we're currently generating CAPS for.
-    fn compose_elementary_caps_to_rgbd_caps(
+impl GstObjectImpl for RgbdMux {}
+impl ObjectImpl for RgbdMux {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<[glib::ParamSpec; 3]> = Lazy::new(|| {
+            [
+                glib::ParamSpecEnum::new(
+                    "sync-policy",
+                    "Synchronisation Policy",
+                    "The policy that determines which buffers of the sink pads are muxed together.",
+                    SyncPolicy::static_type(),
+                    DEFAULT_SYNC_POLICY as i32,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecUInt64::new(
+                    "tolerance",
+                    "Tolerance",
+                    "The largest difference between running times of buffers that are muxed
+                     together, in nanoseconds. Used only with `nearest` sync-policy.",
+                    0,
+                    std::u64::MAX,
+                    DEFAULT_TOLERANCE,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecBoxed::new(
+                    "stats",
+                    "Statistics",
+                    "Statistics of the muxer, which contain the number of dropped buffers of each
+                     stream as `{stream}_dropped` fields.",
+                    gst::Structure::static_type(),
+                    glib::ParamFlags::READABLE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, obj: &Self::Type) {
+        self.parent_constructed(obj);
+        // Start the output at the running time of the first buffers instead of zero, such that
+        // live sources that started before the pipeline do not cause timeouts for the time in
+        // between.
+        obj.set_start_time_selection(gst_base::AggregatorStartTimeSelection::First);
+    }
+
+    fn set_property(
         &self,
-        pad_caps: &gst::Caps,
-        pad_name: &str,
-        src_caps: &mut gst::StructureRef,
+        obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
     ) {
-        let stream_name = pad_name.trim_start_matches("sink_");
-        // Set the format for MJPG stream
-        if pad_caps.is_subset(gst::Caps::new_simple("image/jpeg", &[]).as_ref()) {
-            let src_field_name = format!("{}_format", stream_name);
-            src_caps.set(&src_field_name, "image/jpeg");
+        let mut settings = self.settings.write().unwrap();
+
+        match pspec.name() {
+            "sync-policy" => {
+                let sync_policy = value.get::<SyncPolicy>().unwrap();
+                gst_info!(
+                    CAT,
+                    "Changing property `sync-policy` from {:?} to {:?}",
+                    settings.sync_policy,
+                    sync_policy
+                );
+                settings.sync_policy = sync_policy;
+            }
+            prop @ "tolerance" => {
+                let tolerance =
+                    get_property_and_debug(*CAT, value, prop, settings.tolerance.nseconds());
+                settings.tolerance = gst::ClockTime::from_nseconds(tolerance);
+            }
+            _ => unimplemented!("Property is not implemented"),
+        };
+        drop(settings);
+
+        // Both properties affect the latency of the muxer
+        self.update_latency(obj);
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        match pspec.name() {
+            "sync-policy" => self.settings.read().unwrap().sync_policy.to_value(),
+            "tolerance" => self
+                .settings
+                .read()
+                .unwrap()
+                .tolerance
+                .nseconds()
+                .to_value(),
+            "stats" => self.stats().to_value(),
+            _ => unimplemented!("Property is not implemented"),
         }
+    }
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(Some(plugin), "rgbdmux", gst::Rank::None, RgbdMux::type_())
+}
 
-        // Filter out all CAPS we don't care about and map those we do into strings
-        let pad_caps = pad_caps.iter().next().expect("rgbdmux: Got empty CAPS");
-        if let Ok(format) = pad_caps.value("format") {
-            src_caps.set_value(&format!("{}_format", stream_name), format.clone());
+#[cfg(test)]
+mod tests {
+    use gst::prelude::*;
+    use gst_depth_meta::buffer::BufferMeta;
+
+    /// Duration of a frame at 30 FPS, in nanoseconds
+    const FRAME_NS: u64 = 33_333_333;
+
+    /// Get the duration of the given number of frames at 30 FPS.
+    fn frames(count: u64) -> gst::ClockTime {
+        gst::ClockTime::from_nseconds(count * FRAME_NS)
+    }
+
+    /// Create `rgbdmux` with the given `sync-policy` and a harness for each of its `depth` and
+    /// `color` sink pads. Muxed framesets are pulled from the harness of `depth`.
+    fn setup(
+        sync_policy: &str,
+        framerate: &str,
+    ) -> (gst::Element, gst_check::Harness, gst_check::Harness) {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        let mux = gst::ElementFactory::make("rgbdmux", None).unwrap();
+        mux.set_property_from_str("sync-policy", sync_policy);
+        let mut depth = gst_check::Harness::with_element(&mux, Some("sink_depth"), Some("src"));
+        let mut color = gst_check::Harness::with_element(&mux, Some("sink_color"), None);
+        // Both harnesses set their own clock, make sure that deadlines are cranked on `depth`
+        depth.use_testclock();
+        depth.set_src_caps_str(&format!(
+            "video/x-raw,format=GRAY16_LE,width=4,height=4,framerate={}",
+            framerate
+        ));
+        color.set_src_caps_str(&format!(
+            "video/x-raw,format=RGB,width=4,height=4,framerate={}",
+            framerate
+        ));
+        (mux, depth, color)
+    }
+
+    /// Create a buffer with the given timestamp and the duration of a frame.
+    fn buffer(pts: gst::ClockTime) -> gst::Buffer {
+        let mut buffer = gst::Buffer::with_size(4 * 4 * 3).unwrap();
+        {
+            let buffer = buffer.get_mut().unwrap();
+            buffer.set_pts(pts);
+            buffer.set_duration(frames(1));
         }
-        if let Ok(width) = pad_caps.value("width") {
-            src_caps.set_value(&format!("{}_width", stream_name), width.clone());
+        buffer
+    }
+
+    /// Pull the next frameset and check that the `color` buffer is attached to it.
+    fn pull_frameset(harness: &mut gst_check::Harness) -> gst::Buffer {
+        let frameset = harness.pull().unwrap();
+        assert_eq!(frameset.iter_meta::<BufferMeta>().count(), 1);
+        frameset
+    }
+
+    /// Pull events until a gap event is received and return its timestamp.
+    fn pull_gap(harness: &mut gst_check::Harness) -> gst::ClockTime {
+        loop {
+            let event = harness.pull_event().unwrap();
+            if let gst::EventView::Gap(gap) = event.view() {
+                return gap.get().0;
+            }
         }
-        if let Ok(height) = pad_caps.value("height") {
-            src_caps.set_value(&format!("{}_height", stream_name), height.clone());
+    }
+
+    /// Get the number of dropped buffers of the stream from the `stats` property.
+    fn dropped(mux: &gst::Element, stream_name: &str) -> u64 {
+        mux.property::<gst::Structure>("stats")
+            .get::<u64>(&format!("{}_dropped", stream_name))
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn strict_matched() {
+        let (mux, mut depth, mut color) = setup("strict", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        color.push(buffer(frames(0))).unwrap();
+
+        assert_eq!(pull_frameset(&mut depth).pts(), Some(frames(0)));
+        assert_eq!(dropped(&mux, "depth"), 0);
+        assert_eq!(dropped(&mux, "color"), 0);
+    }
+
+    #[test]
+    fn strict_dropped() {
+        let (mux, mut depth, mut color) = setup("strict", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        color.push(buffer(frames(0))).unwrap();
+        assert_eq!(pull_frameset(&mut depth).pts(), Some(frames(0)));
+
+        // The frame window is shifted by the latency of one frame, therefore the next buffers are
+        // early for the following position and are muxed after a gap
+        depth.push(buffer(frames(1))).unwrap();
+        color.push(buffer(frames(1))).unwrap();
+        assert_eq!(pull_gap(&mut depth), frames(1));
+        assert_eq!(pull_frameset(&mut depth).pts(), Some(frames(2)));
+
+        // A buffer that ended before the frame window is dropped
+        depth.push(buffer(frames(0))).unwrap();
+        depth.push(buffer(frames(2))).unwrap();
+        color.push(buffer(frames(2))).unwrap();
+        assert_eq!(pull_frameset(&mut depth).pts(), Some(frames(3)));
+        assert_eq!(dropped(&mux, "depth"), 1);
+        assert_eq!(dropped(&mux, "color"), 0);
+    }
+
+    #[test]
+    fn strict_timeout() {
+        let (_mux, mut depth, mut color) = setup("strict", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        depth.crank_single_clock_wait().unwrap();
+        assert_eq!(pull_gap(&mut depth), frames(0));
+
+        // The queued buffer is still within the frame window of the next position
+        color.push(buffer(frames(0))).unwrap();
+        assert_eq!(pull_frameset(&mut depth).pts(), Some(frames(1)));
+    }
+
+    #[test]
+    fn nearest_matched() {
+        let (mux, mut depth, mut color) = setup("nearest", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        color
+            .push(buffer(gst::ClockTime::from_mseconds(2)))
+            .unwrap();
+
+        assert_eq!(
+            pull_frameset(&mut depth).pts(),
+            Some(gst::ClockTime::from_mseconds(2))
+        );
+        assert_eq!(dropped(&mux, "depth"), 0);
+        assert_eq!(dropped(&mux, "color"), 0);
+    }
+
+    #[test]
+    fn nearest_dropped() {
+        let (mux, mut depth, mut color) = setup("nearest", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        // More than the default tolerance of 5 ms after the `depth` buffer
+        color
+            .push(buffer(gst::ClockTime::from_mseconds(20)))
+            .unwrap();
+        depth
+            .push(buffer(gst::ClockTime::from_mseconds(21)))
+            .unwrap();
+
+        assert_eq!(
+            pull_frameset(&mut depth).pts(),
+            Some(gst::ClockTime::from_mseconds(21))
+        );
+        assert_eq!(dropped(&mux, "depth"), 1);
+        assert_eq!(dropped(&mux, "color"), 0);
+    }
+
+    #[test]
+    fn nearest_timeout() {
+        let (_mux, mut depth, _color) = setup("nearest", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        depth.crank_single_clock_wait().unwrap();
+        assert_eq!(pull_gap(&mut depth), frames(0));
+    }
+
+    #[test]
+    fn nearest_variable_framerate() {
+        let (_mux, mut depth, mut color) = setup("nearest", "0/1");
+        depth.push(buffer(frames(0))).unwrap();
+        color
+            .push(buffer(gst::ClockTime::from_mseconds(2)))
+            .unwrap();
+
+        let frameset = pull_frameset(&mut depth);
+        assert_eq!(frameset.pts(), Some(gst::ClockTime::from_mseconds(2)));
+        assert_eq!(frameset.duration(), None);
+    }
+
+    #[test]
+    fn latest_matched() {
+        let (mux, mut depth, mut color) = setup("latest", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        color.push(buffer(frames(0))).unwrap();
+
+        assert_eq!(pull_frameset(&mut depth).pts(), Some(frames(0)));
+        assert_eq!(dropped(&mux, "depth"), 0);
+        assert_eq!(dropped(&mux, "color"), 0);
+    }
+
+    #[test]
+    fn latest_dropped() {
+        let (mux, mut depth, mut color) = setup("latest", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        depth.push(buffer(frames(1))).unwrap();
+        color.push(buffer(frames(1))).unwrap();
+
+        assert_eq!(pull_frameset(&mut depth).pts(), Some(frames(1)));
+        assert_eq!(dropped(&mux, "depth"), 1);
+        assert_eq!(dropped(&mux, "color"), 0);
+    }
+
+    #[test]
+    fn latest_timeout_reuses_previous_buffer() {
+        let (mux, mut depth, mut color) = setup("latest", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        color.push(buffer(frames(0))).unwrap();
+        assert_eq!(pull_frameset(&mut depth).pts(), Some(frames(0)));
+
+        depth.push(buffer(frames(1))).unwrap();
+        depth.crank_single_clock_wait().unwrap();
+        assert_eq!(pull_frameset(&mut depth).pts(), Some(frames(1)));
+        assert_eq!(dropped(&mux, "color"), 0);
+    }
+
+    #[test]
+    fn wait_all_matched() {
+        let (mux, mut depth, mut color) = setup("wait_all", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        color.push(buffer(gst::ClockTime::SECOND)).unwrap();
+
+        assert_eq!(
+            pull_frameset(&mut depth).pts(),
+            Some(gst::ClockTime::SECOND)
+        );
+        assert_eq!(dropped(&mux, "depth"), 0);
+        assert_eq!(dropped(&mux, "color"), 0);
+    }
+
+    #[test]
+    fn wait_all_without_timeout() {
+        let (_mux, mut depth, mut color) = setup("wait_all", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        depth.set_time(gst::ClockTime::from_seconds(10)).unwrap();
+        assert!(depth.try_pull().is_none());
+
+        color.push(buffer(frames(0))).unwrap();
+        assert_eq!(pull_frameset(&mut depth).pts(), Some(frames(0)));
+    }
+
+    /// Get the streams of the current `video/rgbd` caps on the src pad of `rgbdmux`.
+    fn src_streams(harness: &gst_check::Harness) -> Vec<String> {
+        let caps = harness.sinkpad().unwrap().current_caps().unwrap();
+        gst_depth_meta::rgbd::RgbdCaps::from_caps(&caps)
+            .unwrap()
+            .stream_names()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn request_and_release_pad_while_playing() {
+        let (mux, mut depth, mut color) = setup("wait_all", "30/1");
+        depth.push(buffer(frames(0))).unwrap();
+        color.push(buffer(frames(0))).unwrap();
+        pull_frameset(&mut depth);
+        assert_eq!(mux.current_state(), gst::State::Playing);
+        assert_eq!(src_streams(&depth), ["depth", "color"]);
+
+        // Add a stream at runtime
+        let ir_sink = mux.request_pad_simple("sink_ir").unwrap();
+        let ir_src = gst::Pad::new(Some("src"), gst::PadDirection::Src);
+        ir_src.set_active(true).unwrap();
+        ir_src.link(&ir_sink).unwrap();
+        ir_src.push_event(gst::event::StreamStart::new("ir"));
+        ir_src.push_event(gst::event::Caps::new(
+            &"video/x-raw,format=GRAY8,width=4,height=4,framerate=30/1"
+                .parse::<gst::Caps>()
+                .unwrap(),
+        ));
+        let segment = gst::FormattedSegment::<gst::ClockTime>::new();
+        ir_src.push_event(gst::event::Segment::new(&segment));
+        ir_src.push(buffer(frames(1))).unwrap();
+        depth.push(buffer(frames(1))).unwrap();
+        color.push(buffer(frames(1))).unwrap();
+
+        let frameset = depth.pull().unwrap();
+        assert_eq!(frameset.iter_meta::<BufferMeta>().count(), 2);
+        assert_eq!(src_streams(&depth), ["depth", "color", "ir"]);
+
+        // Remove the stream again
+        mux.release_request_pad(&ir_sink);
+        depth.push(buffer(frames(2))).unwrap();
+        color.push(buffer(frames(2))).unwrap();
+
+        pull_frameset(&mut depth);
+        assert_eq!(src_streams(&depth), ["depth", "color"]);
+    }
+
+    #[test]
+    fn latency_adds_frame_duration() {
+        let upstream_latency = gst::ClockTime::from_mseconds(20);
+        for (sync_policy, mux_latency) in [
+            ("strict", frames(1)),
+            ("nearest", frames(1) + gst::ClockTime::from_mseconds(5)),
+        ]
+        .iter()
+        {
+            let (_mux, mut depth, mut color) = setup(sync_policy, "30/1");
+            depth.set_upstream_latency(gst::ClockTime::from_mseconds(10));
+            color.set_upstream_latency(upstream_latency);
+            depth.push(buffer(frames(0))).unwrap();
+            color.push(buffer(frames(0))).unwrap();
+            pull_frameset(&mut depth);
+
+            // The muxer waits for the slowest upstream element and then for its own latency
+            assert_eq!(
+                depth.query_latency(),
+                Some(upstream_latency + *mux_latency),
+                "Wrong latency with `{}` sync-policy",
+                sync_policy
+            );
         }
     }
-}
 
-impl GstObjectImpl for RgbdMux {}
-impl ObjectImpl for RgbdMux {
-    fn constructed(&self, obj: &Self::Type) {
-        self.parent_constructed(obj);
-        // obj.set_start_time_selection(gst_base::AggregatorStartTimeSelection::First);
+    #[test]
+    fn independent_framerates() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        // `color` is slower and `ir` is faster than `depth`, which determines the output framerate
+        let mux = gst::ElementFactory::make("rgbdmux", None).unwrap();
+        mux.set_property_from_str("sync-policy", "wait_all");
+        let mut depth = gst_check::Harness::with_element(&mux, Some("sink_depth"), Some("src"));
+        let mut color = gst_check::Harness::with_element(&mux, Some("sink_color"), None);
+        let mut ir = gst_check::Harness::with_element(&mux, Some("sink_ir"), None);
+        depth.set_src_caps_str("video/x-raw,format=GRAY16_LE,width=4,height=4,framerate=30/1");
+        color.set_src_caps_str("video/x-raw,format=RGB,width=4,height=4,framerate=15/1");
+        ir.set_src_caps_str("video/x-raw,format=GRAY8,width=4,height=4,framerate=60/1");
+        let half_frames = |count: u64| gst::ClockTime::from_nseconds(count * FRAME_NS / 2);
+        let aux_buffers = |frameset: &gst::Buffer| {
+            frameset
+                .iter_meta::<BufferMeta>()
+                .map(|meta| {
+                    let buffer = meta.buffer();
+                    (
+                        gst_depth_meta::rgbd::get_tag(buffer).unwrap(),
+                        buffer.pts().unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Each frameset is complete once a buffer of the next frameset is queued on all streams
+        depth.push(buffer(frames(0))).unwrap();
+        color.push(buffer(frames(0))).unwrap();
+        color.push(buffer(frames(2))).unwrap();
+        for count in 0..3 {
+            ir.push(buffer(half_frames(count))).unwrap();
+        }
+        let frameset = depth.pull().unwrap();
+        assert_eq!(frameset.pts(), Some(frames(0)));
+        assert_eq!(
+            aux_buffers(&frameset),
+            [
+                ("color".to_string(), frames(0)),
+                ("ir".to_string(), half_frames(0)),
+                ("ir".to_string(), half_frames(1)),
+            ]
+        );
+
+        // The faster stream is batched, while the slower stream reuses its latest buffer
+        depth.push(buffer(frames(1))).unwrap();
+        for count in 3..5 {
+            ir.push(buffer(half_frames(count))).unwrap();
+        }
+        let frameset = depth.pull().unwrap();
+        assert_eq!(frameset.pts(), Some(frames(1)));
+        assert_eq!(
+            aux_buffers(&frameset),
+            [
+                ("color".to_string(), frames(0)),
+                ("ir".to_string(), half_frames(2)),
+                ("ir".to_string(), half_frames(3)),
+            ]
+        );
+        assert_eq!(dropped(&mux, "color"), 0);
+        assert_eq!(dropped(&mux, "ir"), 0);
     }
-}
 
-pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
-    gst::Element::register(Some(plugin), "rgbdmux", gst::Rank::None, RgbdMux::type_())
+    #[test]
+    fn eos() {
+        for sync_policy in ["strict", "nearest", "latest", "wait_all"].iter() {
+            let (_mux, mut depth, mut color) = setup(sync_policy, "30/1");
+            depth.push(buffer(frames(0))).unwrap();
+            color.push_event(gst::event::Eos::new());
+
+            let eos = std::iter::from_fn(|| depth.pull_event().ok())
+                .find(|event| event.type_() == gst::EventType::Eos);
+            assert!(eos.is_some(), "No EOS with `{}` sync-policy", sync_policy);
+        }
+    }
 }
@@ -0,0 +1,700 @@
+use glib::*;
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_depth_meta::rgbd;
+use once_cell::sync::Lazy;
+use std::sync::{Mutex, RwLock};
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "rgbdremap",
+        gst::DebugColorFlags::empty(),
+        Some("RGB-D Stream Remapper"),
+    )
+});
+
+/// A pair of stream names, the name of the stream in the sink CAPS and its name in the src CAPS.
+type StreamMapping = (String, String);
+
+/// A struct containing properties of `rgbdremap` element
+#[derive(Default)]
+struct Settings {
+    /// Analogous to `drop` property
+    drop: Vec<String>,
+    /// Analogous to `keep` property. All streams are kept if it is empty.
+    keep: Vec<String>,
+    /// Analogous to `rename` property
+    rename: Option<gst::Structure>,
+    /// Analogous to `main-stream` property
+    main_stream: Option<String>,
+}
+
+impl Settings {
+    /// Determine the streams of the src CAPS, in their order, based on the streams of the sink
+    /// CAPS.
+    /// # Arguments
+    /// * `sink_caps` - The `video/rgbd` CAPS received on the sink pad.
+    /// # Returns
+    /// * `Ok` - The mapping of the remaining streams, with the main stream first.
+    /// * `Err(gst::ErrorMessage)` - If no stream remains, or if two streams have the same name.
+    fn stream_mapping(
+        &self,
+        sink_caps: &rgbd::RgbdCaps,
+    ) -> Result<Vec<StreamMapping>, gst::ErrorMessage> {
+        // Streams of `keep` property are ordered as in the property, all others as in the CAPS
+        let mut streams: Vec<&str> = if self.keep.is_empty() {
+            sink_caps.stream_names().collect()
+        } else {
+            self.keep
+                .iter()
+                .map(String::as_str)
+                .filter(|stream_name| sink_caps.stream(stream_name).is_ok())
+                .collect()
+        };
+        streams.retain(|stream_name| !self.drop.iter().any(|dropped| dropped == stream_name));
+
+        if let Some(main_stream) = self.main_stream.as_deref() {
+            let index = streams
+                .iter()
+                .position(|stream_name| *stream_name == main_stream)
+                .ok_or_else(|| {
+                    gst::error_msg!(
+                        gst::CoreError::Negotiation,
+                        [
+                            "Main stream {} is not among the remaining streams",
+                            main_stream
+                        ]
+                    )
+                })?;
+            let main_stream = streams.remove(index);
+            streams.insert(0, main_stream);
+        }
+
+        if streams.is_empty() {
+            return Err(gst::error_msg!(
+                gst::CoreError::Negotiation,
+                ["No stream remains after applying `drop` and `keep` properties"]
+            ));
+        }
+
+        let mut mapping: Vec<StreamMapping> = Vec::with_capacity(streams.len());
+        for stream_name in streams {
+            let new_name = self
+                .rename
+                .as_ref()
+                .and_then(|rename| rename.get::<String>(stream_name).ok())
+                .unwrap_or_else(|| stream_name.to_string());
+            if mapping.iter().any(|(_, name)| *name == new_name) {
+                return Err(gst::error_msg!(
+                    gst::CoreError::Negotiation,
+                    [
+                        "Stream {} cannot be renamed to {}, which is already taken",
+                        stream_name,
+                        new_name
+                    ]
+                ));
+            }
+            mapping.push((stream_name.to_string(), new_name));
+        }
+        Ok(mapping)
+    }
+}
+
+/// Mutable state of the `rgbdremap` element
+#[derive(Default)]
+struct State {
+    /// The `video/rgbd` CAPS received on the sink pad.
+    sink_caps: Option<rgbd::RgbdCaps>,
+    /// The mapping of the streams that are pushed, or `None` if src CAPS need to be negotiated.
+    mapping: Option<Vec<StreamMapping>>,
+}
+
+/// A struct representation of the `rgbdremap` element.
+pub struct RgbdRemap {
+    /// Settings based on properties of the element.
+    settings: RwLock<Settings>,
+    /// The state of the element.
+    state: Mutex<State>,
+    sink_pad: gst::Pad,
+    src_pad: gst::Pad,
+}
+
+glib::wrapper! {
+    pub struct RgbdRemapObject(ObjectSubclass<RgbdRemap>)
+        @extends gst::Element, gst::Object;
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RgbdRemap {
+    const NAME: &'static str = "rgbdremap";
+    type Type = RgbdRemapObject;
+    type ParentType = gst::Element;
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sink_pad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this, element| this.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| this.sink_event(pad, element, event),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let src_pad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .flags(gst::PadFlags::FIXED_CAPS)
+            .build();
+
+        Self {
+            settings: RwLock::new(Settings::default()),
+            state: Mutex::new(State::default()),
+            sink_pad,
+            src_pad,
+        }
+    }
+}
+
+impl ElementImpl for RgbdRemap {
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        #[allow(clippy::single_match)]
+        match transition {
+            gst::StateChange::PausedToReady => {
+                *self.state.lock().unwrap() = State::default();
+            }
+            _ => {}
+        }
+
+        // Chain up parent implementation
+        self.parent_change_state(element, transition)
+    }
+
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RGB-D Stream Remapper",
+                "Filter/RGB-D",
+                "Drops, renames and reorders the streams of a `video/rgbd` stream in place",
+                "Raphael Duerscheid <rd@aivero.com>, Andrej Orsula <andrej.orsula@aivero.com>,
+                 Tobias Morell <tobias.morell@aivero.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 2]> = Lazy::new(|| {
+            let caps = gst::Caps::new_simple("video/rgbd", &[]);
+            [
+                gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &caps,
+                )
+                .expect("rgbdremap: Failed to add 'src' pad template"),
+                gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &caps,
+                )
+                .expect("rgbdremap: Failed to add 'sink' pad template"),
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl RgbdRemap {
+    /// Called whenever an event is received at the sink pad. CAPS events are rewritten according
+    /// to the properties, all other events are send further downstream.
+    /// # Arguments
+    /// * `pad` - The sink pad.
+    /// * `element` - The element that represents the `rgbdremap` in GStreamer.
+    /// * `event` - The event that should be handled.
+    fn sink_event(&self, pad: &gst::Pad, element: &RgbdRemapObject, event: gst::Event) -> bool {
+        use gst::EventView;
+        match event.view() {
+            EventView::Caps(caps) => {
+                gst_debug!(CAT, obj: element, "Got a new caps event: {:?}", caps);
+                let sink_caps = match rgbd::RgbdCaps::from_caps(caps.caps()) {
+                    Ok(sink_caps) => sink_caps,
+                    Err(e) => {
+                        gst_error!(CAT, obj: element, "Invalid `video/rgbd` caps: {}", e);
+                        return false;
+                    }
+                };
+                {
+                    let mut state = self.state.lock().unwrap();
+                    state.sink_caps = Some(sink_caps);
+                    state.mapping = None;
+                }
+                self.stream_mapping(element).is_ok()
+            }
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+
+    /// Get the mapping of the streams that are pushed. If the sink CAPS or the properties have
+    /// changed since the last call, new src CAPS are pushed downstream first.
+    /// # Arguments
+    /// * `element` - The element that represents the `rgbdremap` in GStreamer.
+    /// # Returns
+    /// * `Ok` - The mapping of the streams, with the main stream first.
+    /// * `Err(gst::FlowError::NotNegotiated)` - If the CAPS cannot be rewritten or downstream
+    /// does not accept them.
+    fn stream_mapping(
+        &self,
+        element: &RgbdRemapObject,
+    ) -> Result<Vec<StreamMapping>, gst::FlowError> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(mapping) = state.mapping.as_ref() {
+            return Ok(mapping.clone());
+        }
+
+        let sink_caps = state.sink_caps.as_ref().ok_or_else(|| {
+            gst_warning!(CAT, obj: element, "Received a buffer before CAPS");
+            gst::FlowError::NotNegotiated
+        })?;
+        let mapping = self
+            .settings
+            .read()
+            .unwrap()
+            .stream_mapping(sink_caps)
+            .map_err(|e| {
+                gst_error!(CAT, obj: element, "{}", e);
+                gst::FlowError::NotNegotiated
+            })?;
+
+        // Rename the streams of the sink CAPS and put them in the new order
+        let mut src_caps = rgbd::RgbdCaps::new();
+        if let Some(framerate) = sink_caps.framerate() {
+            src_caps = src_caps.with_framerate(framerate);
+        }
+        for (stream_name, new_name) in mapping.iter() {
+            let stream = sink_caps.stream(stream_name).map_err(|e| {
+                gst_error!(CAT, obj: element, "{}", e);
+                gst::FlowError::NotNegotiated
+            })?;
+            let mut new_stream = rgbd::RgbdStreamCaps::new(new_name, stream.structure().to_owned());
+            if let Some(framerate) = stream.framerate() {
+                new_stream = new_stream.with_framerate(framerate);
+            }
+            src_caps = src_caps.with_stream(new_stream);
+        }
+        state.mapping = Some(mapping.clone());
+        drop(state);
+
+        let src_caps = src_caps.to_caps();
+        gst_debug!(CAT, obj: element, "Pushing new src caps: {:?}", src_caps);
+        if !self.src_pad.push_event(gst::event::Caps::new(&src_caps)) {
+            gst_error!(CAT, obj: element, "Downstream did not accept {:?}", src_caps);
+            self.state.lock().unwrap().mapping = None;
+            return Err(gst::FlowError::NotNegotiated);
+        }
+        Ok(mapping)
+    }
+
+    /// Drop, rename and reorder the buffers of all streams within the main buffer and push it
+    /// downstream.
+    /// # Arguments
+    /// * `element` - The element that represents the `rgbdremap` in GStreamer.
+    /// * `main_buffer` - The main buffer of the `video/rgbd` stream.
+    fn sink_chain(
+        &self,
+        _: &gst::Pad,
+        element: &RgbdRemapObject,
+        main_buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mapping = self.stream_mapping(element)?;
+
+        // Regions of interest are carried by the main buffer, which might change
+        let regions: Vec<(String, Vec<rgbd::RegionOfInterest>)> = main_buffer
+            .iter_meta::<rgbd::RoiMeta>()
+            .map(|meta| (meta.stream_name().to_string(), meta.regions().to_vec()))
+            .collect();
+        let pts = main_buffer.pts();
+        let dts = main_buffer.dts();
+        let duration = main_buffer.duration();
+
+        let mut buffers = rgbd::get_all_buffers(main_buffer)
+            .into_iter()
+            .map(|buffer| rgbd::get_tag(&buffer).map(|tag| (tag, buffer)))
+            .collect::<Result<Vec<(String, gst::Buffer)>, gst::ErrorMessage>>()
+            .map_err(|e| {
+                gst_warning!(CAT, obj: element, "Failed to get buffer tag: {}", e);
+                gst::FlowError::Error
+            })?;
+
+        // Pick the buffers of all remaining streams in their new order, all others are dropped
+        let mut remapped = Vec::with_capacity(buffers.len());
+        for (stream_name, new_name) in mapping.iter() {
+            while let Some(index) = buffers.iter().position(|(tag, _)| tag == stream_name) {
+                remapped.push((new_name, buffers.remove(index).1));
+            }
+        }
+        drop(buffers);
+        let mut remapped = remapped.into_iter();
+
+        let (main_stream, mut main_buffer) = remapped.next().ok_or_else(|| {
+            gst_warning!(
+                CAT,
+                obj: element,
+                "Main buffer does not contain the main stream {}",
+                mapping[0].0
+            );
+            gst::FlowError::Error
+        })?;
+        let main_buffer_mut = main_buffer.make_mut();
+        main_buffer_mut.set_pts(pts);
+        main_buffer_mut.set_dts(dts);
+        main_buffer_mut.set_duration(duration);
+        rgbd::remove_aux_buffers(main_buffer_mut);
+        rgbd::remove_roi_metas(main_buffer_mut);
+        rgbd::replace_tag(main_buffer_mut, main_stream).map_err(|e| {
+            gst_error!(CAT, obj: element, "{}", e);
+            gst::FlowError::Error
+        })?;
+        for (stream_name, stream_regions) in regions {
+            if let Some((_, new_name)) = mapping.iter().find(|(name, _)| *name == stream_name) {
+                rgbd::RoiMeta::merge(main_buffer_mut, new_name, stream_regions);
+            }
+        }
+
+        for (stream_name, mut buffer) in remapped {
+            let buffer_mut = buffer.make_mut();
+            rgbd::remove_aux_buffers(buffer_mut);
+            rgbd::remove_roi_metas(buffer_mut);
+            rgbd::clear_tags(buffer_mut);
+            rgbd::attach_aux_buffer_and_tag(main_buffer_mut, &mut buffer, stream_name).map_err(
+                |e| {
+                    gst_error!(CAT, obj: element, "{}", e);
+                    gst::FlowError::Error
+                },
+            )?;
+        }
+
+        self.src_pad.push(main_buffer)
+    }
+}
+
+impl GstObjectImpl for RgbdRemap {}
+impl ObjectImpl for RgbdRemap {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<[glib::ParamSpec; 4]> = Lazy::new(|| {
+            let stream_spec = ParamSpecString::new(
+                "stream",
+                "Stream",
+                "Name of a stream",
+                None,
+                ParamFlags::READWRITE,
+            );
+            [
+                gst::ParamSpecArray::new(
+                    "drop",
+                    "Drop",
+                    "Names of the streams that are dropped, e.g. <infra1,infra2>.",
+                    Some(&stream_spec),
+                    ParamFlags::READWRITE,
+                ),
+                gst::ParamSpecArray::new(
+                    "keep",
+                    "Keep",
+                    "Names of the streams that are kept, in their new order, e.g. <color,depth>.
+                     All other streams are dropped. All streams are kept if empty.",
+                    Some(&stream_spec),
+                    ParamFlags::READWRITE,
+                ),
+                ParamSpecBoxed::new(
+                    "rename",
+                    "Rename",
+                    "Structure that maps the names of the streams to their new names, e.g.
+                     `rename, infra1=left, infra2=right`. The structure name is ignored.",
+                    gst::Structure::static_type(),
+                    ParamFlags::READWRITE,
+                ),
+                ParamSpecString::new(
+                    "main-stream",
+                    "Main Stream",
+                    "Name of the stream that is moved to the front and carries all other streams
+                     as auxiliary buffers. The first remaining stream is kept as the main stream
+                     if unset.",
+                    None,
+                    ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, element: &Self::Type) {
+        self.parent_constructed(element);
+
+        element
+            .add_pad(&self.sink_pad)
+            .expect("rgbdremap: Failed to add sink pad to the element");
+        element
+            .add_pad(&self.src_pad)
+            .expect("rgbdremap: Failed to add src pad to the element");
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.write().unwrap();
+
+        match pspec.name() {
+            prop @ "drop" | prop @ "keep" => {
+                let streams = value
+                    .get::<gst::Array>()
+                    .unwrap()
+                    .as_slice()
+                    .iter()
+                    .map(|stream| stream.get::<String>().unwrap())
+                    .collect::<Vec<String>>();
+                let setting = if prop == "drop" {
+                    &mut settings.drop
+                } else {
+                    &mut settings.keep
+                };
+                gst_info!(
+                    CAT,
+                    "Changing property `{}` from {:?} to {:?}",
+                    prop,
+                    setting,
+                    streams
+                );
+                *setting = streams;
+            }
+            "rename" => {
+                let rename = value.get::<Option<gst::Structure>>().unwrap();
+                gst_info!(
+                    CAT,
+                    "Changing property `rename` from {:?} to {:?}",
+                    settings.rename,
+                    rename
+                );
+                settings.rename = rename;
+            }
+            "main-stream" => {
+                let main_stream = value.get::<Option<String>>().unwrap();
+                gst_info!(
+                    CAT,
+                    "Changing property `main-stream` from {:?} to {:?}",
+                    settings.main_stream,
+                    main_stream
+                );
+                settings.main_stream = main_stream;
+            }
+            _ => unimplemented!("Property is not implemented"),
+        };
+        drop(settings);
+
+        // Negotiate new src CAPS with the next buffer
+        self.state.lock().unwrap().mapping = None;
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.read().unwrap();
+
+        match pspec.name() {
+            "drop" => {
+                gst::Array::from_values(settings.drop.iter().map(|stream| stream.to_send_value()))
+                    .to_value()
+            }
+            "keep" => {
+                gst::Array::from_values(settings.keep.iter().map(|stream| stream.to_send_value()))
+                    .to_value()
+            }
+            "rename" => settings.rename.to_value(),
+            "main-stream" => settings.main_stream.to_value(),
+            _ => unimplemented!("Property is not implemented"),
+        }
+    }
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "rgbdremap",
+        gst::Rank::None,
+        RgbdRemap::type_(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use gst::prelude::*;
+    use gst_depth_meta::rgbd;
+
+    const WIDTH: i32 = 4;
+    const HEIGHT: i32 = 4;
+    const DEPTH_VALUE: u8 = 7;
+    const COLOR_VALUE: u8 = 42;
+    const INFRA_VALUE: u8 = 99;
+
+    /// Create `video/rgbd` caps with `depth`, `color` and `infra` streams.
+    fn rgbd_caps() -> gst::Caps {
+        let video = |format: &str| {
+            gst::Structure::builder("video/x-raw")
+                .field("format", format)
+                .field("width", WIDTH)
+                .field("height", HEIGHT)
+                .build()
+        };
+        rgbd::RgbdCaps::new()
+            .with_framerate(gst::Fraction::new(30, 1))
+            .with_stream(rgbd::RgbdStreamCaps::new("depth", video("GRAY16_LE")))
+            .with_stream(rgbd::RgbdStreamCaps::new("color", video("RGB")))
+            .with_stream(rgbd::RgbdStreamCaps::new("infra", video("GRAY8")))
+            .to_caps()
+    }
+
+    /// Region of interest of the given stream, which is carried by the main buffer.
+    fn region(stream_name: &str) -> rgbd::RegionOfInterest {
+        rgbd::RegionOfInterest::new(stream_name, 1, 1, 2, 2)
+    }
+
+    /// Create a `video/rgbd` buffer with `depth` stream as the main buffer and `color` and
+    /// `infra` streams as auxiliary buffers. The main buffer carries regions of interest of
+    /// `depth` and `color` streams.
+    fn rgbd_buffer() -> gst::Buffer {
+        let size = (WIDTH * HEIGHT) as usize;
+        let mut main_buffer = gst::Buffer::from_mut_slice(vec![DEPTH_VALUE; size * 2]);
+        {
+            let main_buffer = main_buffer.get_mut().unwrap();
+            main_buffer.set_pts(gst::ClockTime::ZERO);
+            rgbd::tag_buffer_with_title(main_buffer, "depth").unwrap();
+            rgbd::RoiMeta::add(main_buffer, "depth", vec![region("depth")]);
+            rgbd::RoiMeta::add(main_buffer, "color", vec![region("color")]);
+        }
+        let mut color_buffer = gst::Buffer::from_mut_slice(vec![COLOR_VALUE; size * 3]);
+        rgbd::attach_aux_buffer_and_tag(main_buffer.make_mut(), &mut color_buffer, "color")
+            .unwrap();
+        let mut infra_buffer = gst::Buffer::from_mut_slice(vec![INFRA_VALUE; size]);
+        rgbd::attach_aux_buffer_and_tag(main_buffer.make_mut(), &mut infra_buffer, "infra")
+            .unwrap();
+        main_buffer
+    }
+
+    /// Push a single frameset through `rgbdremap` with the given properties.
+    /// # Returns
+    /// * The output buffer and the names of the streams of the src CAPS.
+    fn remap(properties: &[(&str, &str)]) -> (gst::Buffer, Vec<String>) {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        let mut harness = gst_check::Harness::new("rgbdremap");
+        {
+            let remap = harness.element().unwrap();
+            for (name, value) in properties {
+                remap.set_property_from_str(name, value);
+            }
+        }
+        harness.set_src_caps(rgbd_caps());
+        let output = harness.push_and_pull(rgbd_buffer()).unwrap();
+
+        let src_caps = harness.sinkpad().unwrap().current_caps().unwrap();
+        let streams = rgbd::RgbdCaps::from_caps(&src_caps)
+            .unwrap()
+            .stream_names()
+            .map(str::to_string)
+            .collect();
+        (output, streams)
+    }
+
+    /// Get the tag of each auxiliary buffer and the value that it is filled with.
+    fn aux_buffers(main_buffer: &gst::BufferRef) -> Vec<(String, u8)> {
+        rgbd::get_all_aux_buffers(main_buffer)
+            .map(|buffer| {
+                let value = buffer.map_readable().unwrap()[0];
+                (rgbd::get_tag(&buffer).unwrap(), value)
+            })
+            .collect()
+    }
+
+    /// Get the regions of interest of the given stream, which are carried by `main_buffer`.
+    fn regions(main_buffer: &gst::BufferRef, stream_name: &str) -> Vec<rgbd::RegionOfInterest> {
+        main_buffer
+            .iter_meta::<rgbd::RoiMeta>()
+            .filter(|meta| meta.stream_name() == stream_name)
+            .flat_map(|meta| meta.regions().to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn rename_streams() {
+        let (output, streams) = remap(&[("rename", "rename, depth=range, infra=left")]);
+
+        assert_eq!(streams, ["range", "color", "left"]);
+        assert_eq!(rgbd::get_tag(&output).unwrap(), "range");
+        assert_eq!(
+            aux_buffers(&output),
+            [
+                ("color".to_string(), COLOR_VALUE),
+                ("left".to_string(), INFRA_VALUE)
+            ]
+        );
+        assert_eq!(regions(&output, "range"), [region("depth")]);
+        assert_eq!(regions(&output, "color"), [region("color")]);
+        assert!(regions(&output, "depth").is_empty());
+    }
+
+    #[test]
+    fn drop_stream() {
+        let (output, streams) = remap(&[("drop", "<color>")]);
+
+        assert_eq!(streams, ["depth", "infra"]);
+        assert_eq!(rgbd::get_tag(&output).unwrap(), "depth");
+        assert_eq!(aux_buffers(&output), [("infra".to_string(), INFRA_VALUE)]);
+        // Regions of dropped streams are dropped as well
+        assert_eq!(regions(&output, "depth"), [region("depth")]);
+        assert!(regions(&output, "color").is_empty());
+    }
+
+    #[test]
+    fn change_main_stream() {
+        let (output, streams) = remap(&[("main-stream", "color")]);
+
+        assert_eq!(streams, ["color", "depth", "infra"]);
+        assert_eq!(rgbd::get_tag(&output).unwrap(), "color");
+        assert_eq!(output.map_readable().unwrap()[0], COLOR_VALUE);
+        assert_eq!(
+            aux_buffers(&output),
+            [
+                ("depth".to_string(), DEPTH_VALUE),
+                ("infra".to_string(), INFRA_VALUE)
+            ]
+        );
+
+        // The previous main buffer carries neither auxiliary buffers nor regions of interest
+        let previous_main_buffer = rgbd::get_all_aux_buffers(&output).next().unwrap();
+        assert_eq!(rgbd::get_all_aux_buffers(&previous_main_buffer).count(), 0);
+        assert_eq!(previous_main_buffer.iter_meta::<rgbd::RoiMeta>().count(), 0);
+
+        // Regions of all streams are merged onto the new main buffer, each of them only once
+        assert_eq!(regions(&output, "depth"), [region("depth")]);
+        assert_eq!(regions(&output, "color"), [region("color")]);
+        assert_eq!(output.pts(), Some(gst::ClockTime::ZERO));
+    }
+}
@@ -0,0 +1,270 @@
+//! Subclassing support for `GstRTPBasePayload` and `GstRTPBaseDepayload`, which `gstreamer-rtp`
+//! 0.18 does not provide. Only the virtual methods and helpers that are needed by `rtprgbdpay`
+//! and `rtprgbddepay` are bound, everything else is left to the default implementations of the
+//! base classes.
+use gst::glib;
+use gst::glib::translate::*;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_rtp::ffi;
+use std::os::raw::c_char;
+use std::ptr;
+
+glib::wrapper! {
+    /// Wrapper of `GstRTPBasePayload` that can be used as `ParentType` of a subclass.
+    pub struct RtpBasePayload(Object<ffi::GstRTPBasePayload, ffi::GstRTPBasePayloadClass>)
+        @extends gst::Element, gst::Object;
+
+    match fn {
+        type_ => || ffi::gst_rtp_base_payload_get_type(),
+    }
+}
+
+unsafe impl Send for RtpBasePayload {}
+unsafe impl Sync for RtpBasePayload {}
+
+/// Virtual methods of `GstRTPBasePayload`.
+pub trait RtpBasePayloadImpl: ElementImpl {
+    /// Configure the payloader for the given sink caps. Implementations must call
+    /// `RtpBasePayloadExt::set_outcaps()` before returning successfully.
+    fn set_caps(&self, element: &Self::Type, caps: &gst::Caps) -> Result<(), gst::LoggableError>;
+
+    /// Payload `buffer` and push the resulting RTP packets downstream.
+    fn handle_buffer(
+        &self,
+        element: &Self::Type,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError>;
+}
+
+unsafe impl<T: RtpBasePayloadImpl> IsSubclassable<T> for RtpBasePayload {
+    fn class_init(klass: &mut glib::Class<Self>) {
+        Self::parent_class_init::<T>(klass);
+        let klass = klass.as_mut();
+        klass.set_caps = Some(rtp_base_payload_set_caps::<T>);
+        klass.handle_buffer = Some(rtp_base_payload_handle_buffer::<T>);
+    }
+
+    fn instance_init(instance: &mut glib::subclass::InitializingObject<T>) {
+        Self::parent_instance_init::<T>(instance);
+    }
+}
+
+unsafe extern "C" fn rtp_base_payload_set_caps<T: RtpBasePayloadImpl>(
+    ptr: *mut ffi::GstRTPBasePayload,
+    caps: *mut gst::ffi::GstCaps,
+) -> glib::ffi::gboolean {
+    let wrap: Borrowed<RtpBasePayload> = from_glib_borrow(ptr);
+    let element = wrap.unsafe_cast_ref::<T::Type>();
+    let imp = T::from_instance(element);
+
+    gst::panic_to_error!(&wrap, imp.panicked(), false, {
+        match imp.set_caps(element, &from_glib_borrow::<_, gst::Caps>(caps)) {
+            Ok(()) => true,
+            Err(err) => {
+                err.log_with_object(&*wrap);
+                false
+            }
+        }
+    })
+    .into_glib()
+}
+
+unsafe extern "C" fn rtp_base_payload_handle_buffer<T: RtpBasePayloadImpl>(
+    ptr: *mut ffi::GstRTPBasePayload,
+    buffer: *mut gst::ffi::GstBuffer,
+) -> gst::ffi::GstFlowReturn {
+    let wrap: Borrowed<RtpBasePayload> = from_glib_borrow(ptr);
+    let element = wrap.unsafe_cast_ref::<T::Type>();
+    let imp = T::from_instance(element);
+
+    gst::panic_to_error!(&wrap, imp.panicked(), gst::FlowReturn::Error, {
+        imp.handle_buffer(element, from_glib_full(buffer)).into()
+    })
+    .into_glib()
+}
+
+/// Helpers of `GstRTPBasePayload` for its subclasses.
+pub trait RtpBasePayloadExt: IsA<RtpBasePayload> {
+    /// Set the static options of the output caps, see `gst_rtp_base_payload_set_options()`.
+    fn set_options(&self, media: &str, dynamic: bool, encoding_name: &str, clock_rate: u32) {
+        unsafe {
+            ffi::gst_rtp_base_payload_set_options(
+                self.as_ref().to_glib_none().0,
+                media.to_glib_none().0,
+                dynamic.into_glib(),
+                encoding_name.to_glib_none().0,
+                clock_rate,
+            );
+        }
+    }
+
+    /// Negotiate the output caps, which contain the options and the given string `fields`.
+    /// `gst_rtp_base_payload_set_outcaps()` takes the fields as variadic arguments and GStreamer
+    /// 1.18 has no variant that takes a structure, therefore at most two fields are supported.
+    fn set_outcaps(&self, fields: &[(&str, &str)]) -> Result<(), glib::BoolError> {
+        let string_type = glib::Type::STRING.into_glib();
+        let fields = fields
+            .iter()
+            .map(|(name, value)| (name.to_glib_none(), value.to_glib_none()))
+            .collect::<Vec<(Stash<*const c_char, str>, Stash<*const c_char, str>)>>();
+        let payload = self.as_ref().to_glib_none().0;
+        let end = ptr::null::<c_char>();
+
+        let negotiated = unsafe {
+            match fields.as_slice() {
+                [] => ffi::gst_rtp_base_payload_set_outcaps(payload, end),
+                [(name, value)] => ffi::gst_rtp_base_payload_set_outcaps(
+                    payload,
+                    name.0,
+                    string_type,
+                    value.0,
+                    end,
+                ),
+                [(name1, value1), (name2, value2)] => ffi::gst_rtp_base_payload_set_outcaps(
+                    payload,
+                    name1.0,
+                    string_type,
+                    value1.0,
+                    name2.0,
+                    string_type,
+                    value2.0,
+                    end,
+                ),
+                _ => return Err(glib::bool_error!("At most two caps fields are supported")),
+            }
+        };
+        if negotiated == glib::ffi::GFALSE {
+            return Err(glib::bool_error!("Failed to negotiate RTP caps"));
+        }
+        Ok(())
+    }
+
+    /// Push `list` of RTP packets downstream. The base class sets SSRC, payload type, sequence
+    /// number and RTP timestamp of each packet.
+    fn push_list(&self, list: gst::BufferList) -> Result<gst::FlowSuccess, gst::FlowError> {
+        unsafe {
+            from_glib::<_, gst::FlowReturn>(ffi::gst_rtp_base_payload_push_list(
+                self.as_ref().to_glib_none().0,
+                list.into_ptr(),
+            ))
+            .into_result()
+        }
+    }
+}
+
+impl<O: IsA<RtpBasePayload>> RtpBasePayloadExt for O {}
+
+glib::wrapper! {
+    /// Wrapper of `GstRTPBaseDepayload` that can be used as `ParentType` of a subclass.
+    pub struct RtpBaseDepayload(Object<ffi::GstRTPBaseDepayload, ffi::GstRTPBaseDepayloadClass>)
+        @extends gst::Element, gst::Object;
+
+    match fn {
+        type_ => || ffi::gst_rtp_base_depayload_get_type(),
+    }
+}
+
+unsafe impl Send for RtpBaseDepayload {}
+unsafe impl Sync for RtpBaseDepayload {}
+
+/// Virtual methods of `GstRTPBaseDepayload`.
+pub trait RtpBaseDepayloadImpl: ElementImpl {
+    /// Configure the depayloader for the given sink caps and set the caps of its src pad.
+    fn set_caps(&self, element: &Self::Type, caps: &gst::Caps) -> Result<(), gst::LoggableError>;
+
+    /// Process an RTP `packet`. The returned buffer is pushed downstream by the base class, which
+    /// sets its PTS and DISCONT flag unless already set.
+    fn process(&self, element: &Self::Type, packet: &gst::Buffer) -> Option<gst::Buffer>;
+
+    /// Handle an event that was received at the sink pad.
+    fn handle_event(&self, element: &Self::Type, event: gst::Event) -> bool {
+        self.parent_handle_event(element, event)
+    }
+}
+
+/// Chaining up to the implementations of `GstRTPBaseDepayload`.
+pub trait RtpBaseDepayloadImplExt: ObjectSubclass {
+    fn parent_handle_event(&self, element: &Self::Type, event: gst::Event) -> bool;
+}
+
+impl<T: RtpBaseDepayloadImpl> RtpBaseDepayloadImplExt for T {
+    fn parent_handle_event(&self, element: &Self::Type, event: gst::Event) -> bool {
+        unsafe {
+            let data = Self::type_data();
+            let parent_class = data.as_ref().parent_class() as *mut ffi::GstRTPBaseDepayloadClass;
+            match (*parent_class).handle_event {
+                Some(f) => from_glib(f(
+                    element
+                        .unsafe_cast_ref::<RtpBaseDepayload>()
+                        .to_glib_none()
+                        .0,
+                    event.into_ptr(),
+                )),
+                None => false,
+            }
+        }
+    }
+}
+
+unsafe impl<T: RtpBaseDepayloadImpl> IsSubclassable<T> for RtpBaseDepayload {
+    fn class_init(klass: &mut glib::Class<Self>) {
+        Self::parent_class_init::<T>(klass);
+        let klass = klass.as_mut();
+        klass.set_caps = Some(rtp_base_depayload_set_caps::<T>);
+        klass.process = Some(rtp_base_depayload_process::<T>);
+        klass.handle_event = Some(rtp_base_depayload_handle_event::<T>);
+    }
+
+    fn instance_init(instance: &mut glib::subclass::InitializingObject<T>) {
+        Self::parent_instance_init::<T>(instance);
+    }
+}
+
+unsafe extern "C" fn rtp_base_depayload_set_caps<T: RtpBaseDepayloadImpl>(
+    ptr: *mut ffi::GstRTPBaseDepayload,
+    caps: *mut gst::ffi::GstCaps,
+) -> glib::ffi::gboolean {
+    let wrap: Borrowed<RtpBaseDepayload> = from_glib_borrow(ptr);
+    let element = wrap.unsafe_cast_ref::<T::Type>();
+    let imp = T::from_instance(element);
+
+    gst::panic_to_error!(&wrap, imp.panicked(), false, {
+        match imp.set_caps(element, &from_glib_borrow::<_, gst::Caps>(caps)) {
+            Ok(()) => true,
+            Err(err) => {
+                err.log_with_object(&*wrap);
+                false
+            }
+        }
+    })
+    .into_glib()
+}
+
+unsafe extern "C" fn rtp_base_depayload_process<T: RtpBaseDepayloadImpl>(
+    ptr: *mut ffi::GstRTPBaseDepayload,
+    packet: *mut gst::ffi::GstBuffer,
+) -> *mut gst::ffi::GstBuffer {
+    let wrap: Borrowed<RtpBaseDepayload> = from_glib_borrow(ptr);
+    let element = wrap.unsafe_cast_ref::<T::Type>();
+    let imp = T::from_instance(element);
+
+    gst::panic_to_error!(&wrap, imp.panicked(), ptr::null_mut(), {
+        imp.process(element, &from_glib_borrow::<_, gst::Buffer>(packet))
+            .map_or(ptr::null_mut(), |buffer| buffer.into_ptr())
+    })
+}
+
+unsafe extern "C" fn rtp_base_depayload_handle_event<T: RtpBaseDepayloadImpl>(
+    ptr: *mut ffi::GstRTPBaseDepayload,
+    event: *mut gst::ffi::GstEvent,
+) -> glib::ffi::gboolean {
+    let wrap: Borrowed<RtpBaseDepayload> = from_glib_borrow(ptr);
+    let element = wrap.unsafe_cast_ref::<T::Type>();
+    let imp = T::from_instance(element);
+
+    gst::panic_to_error!(&wrap, imp.panicked(), false, {
+        imp.handle_event(element, from_glib_full(event))
+    })
+    .into_glib()
+}
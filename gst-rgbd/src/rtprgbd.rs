@@ -0,0 +1,102 @@
+//! RTP payload format of `video/rgbd` that is shared by `rtprgbdpay` and `rtprgbddepay`.
+//!
+//! Each frameset is serialised into a single blob, see [frameset](../frameset/index.html), that is
+//! fragmented into RTP packets. All packets of a frameset share the same RTP timestamp, the last
+//! one has the marker bit set. The payload of each packet starts with the 32-bit big-endian offset
+//! of the fragment within the blob. Stream indices of the blob refer to the `streams` field of the
+//! RTP caps, which is carried in the SDP `fmtp` together with the base64 encoded `video/rgbd` caps.
+use gst_depth_meta::rgbd;
+
+/// Encoding name of `video/rgbd` in RTP caps and SDP.
+pub const ENCODING_NAME: &str = "X-GST-RGBD";
+/// Clock rate of RTP timestamps.
+pub const CLOCK_RATE: u32 = 90_000;
+/// Length of the RTP header without CSRCs and extensions.
+pub const RTP_HEADER_LEN: usize = 12;
+/// Length of the header that precedes each fragment of a frameset.
+pub const FRAGMENT_HEADER_LEN: usize = 4;
+
+/// Name of the RTP caps field that contains the base64 encoded `video/rgbd` caps.
+const RGBD_CAPS_FIELD: &str = "rgbd-caps";
+/// Name of the RTP caps field that contains the comma separated names of the streams.
+const STREAMS_FIELD: &str = "streams";
+
+/// Create the fields of RTP caps, i.e. the SDP `fmtp` parameters, that carry the given
+/// `video/rgbd` caps.
+/// # Arguments
+/// * `rgbd_caps` - The `video/rgbd` caps.
+/// # Returns
+/// * `Ok` - The names and values of the fields and the names of the streams.
+/// * `Err(gst::ErrorMessage)` - If `rgbd_caps` are not valid `video/rgbd` caps.
+pub fn rgbd_fields(
+    rgbd_caps: &gst::CapsRef,
+) -> Result<([(&'static str, String); 2], Vec<String>), gst::ErrorMessage> {
+    let streams = rgbd::RgbdCaps::from_caps(rgbd_caps)
+        .map_err(|e| gst::error_msg!(gst::CoreError::Caps, ["{}", e]))?
+        .stream_names()
+        .map(String::from)
+        .collect::<Vec<String>>();
+
+    let fields = [
+        (STREAMS_FIELD, streams.join(",")),
+        (RGBD_CAPS_FIELD, base64::encode(rgbd_caps.to_string())),
+    ];
+    Ok((fields, streams))
+}
+
+/// Create RTP caps that carry the given `video/rgbd` caps.
+/// # Arguments
+/// * `rgbd_caps` - The `video/rgbd` caps.
+/// * `payload_type` - The RTP payload type.
+/// # Returns
+/// * `Ok` - The `application/x-rtp` caps and the names of the streams.
+/// * `Err(gst::ErrorMessage)` - If `rgbd_caps` are not valid `video/rgbd` caps.
+pub fn rtp_caps(
+    rgbd_caps: &gst::CapsRef,
+    payload_type: u8,
+) -> Result<(gst::Caps, Vec<String>), gst::ErrorMessage> {
+    let (fields, streams) = rgbd_fields(rgbd_caps)?;
+
+    let mut caps = gst::Caps::builder("application/x-rtp")
+        .field("media", "application")
+        .field("clock-rate", CLOCK_RATE as i32)
+        .field("encoding-name", ENCODING_NAME)
+        .field("payload", payload_type as i32);
+    for (name, value) in fields.iter() {
+        caps = caps.field(name, value);
+    }
+    Ok((caps.build(), streams))
+}
+
+/// Extract the `video/rgbd` caps from RTP caps.
+/// # Arguments
+/// * `rtp_caps` - The `application/x-rtp` caps, e.g. created from SDP.
+/// # Returns
+/// * `Ok` - The `video/rgbd` caps and the names of the streams.
+/// * `Err(gst::ErrorMessage)` - If `rtp_caps` do not carry valid `video/rgbd` caps.
+pub fn rgbd_caps(rtp_caps: &gst::CapsRef) -> Result<(gst::Caps, Vec<String>), gst::ErrorMessage> {
+    let structure = rtp_caps
+        .structure(0)
+        .ok_or_else(|| gst::error_msg!(gst::CoreError::Caps, ["RTP caps are empty"]))?;
+    let field = |name: &str| {
+        structure.get::<String>(name).map_err(|_| {
+            gst::error_msg!(gst::CoreError::Caps, ["RTP caps have no `{}` field", name])
+        })
+    };
+
+    let caps = base64::decode(field(RGBD_CAPS_FIELD)?)
+        .ok()
+        .and_then(|caps| String::from_utf8(caps).ok())
+        .and_then(|caps| caps.parse::<gst::Caps>().ok())
+        .ok_or_else(|| {
+            gst::error_msg!(
+                gst::CoreError::Caps,
+                ["Invalid `{}` field in RTP caps", RGBD_CAPS_FIELD]
+            )
+        })?;
+    let streams = field(STREAMS_FIELD)?
+        .split(',')
+        .map(String::from)
+        .collect::<Vec<String>>();
+    Ok((caps, streams))
+}
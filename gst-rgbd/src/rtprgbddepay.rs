@@ -0,0 +1,359 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+use crate::frameset::*;
+use crate::rtpbase::*;
+use crate::rtprgbd::*;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "rtprgbddepay",
+        gst::DebugColorFlags::empty(),
+        Some("RTP RGB-D Depayloader"),
+    )
+});
+
+/// Mutable state of the `rtprgbddepay` element
+#[derive(Default)]
+struct State {
+    /// Names of the streams of the `video/rgbd` caps, as carried by the RTP caps.
+    streams: Vec<String>,
+    /// The fragments of the current frameset that were received so far.
+    frameset: Vec<u8>,
+    /// RTP timestamp of the current frameset.
+    timestamp: Option<u32>,
+    /// PTS of the first packet of the current frameset.
+    pts: Option<gst::ClockTime>,
+}
+
+impl State {
+    /// Drop the current frameset, e.g. after packet loss.
+    fn drop_frameset(&mut self) {
+        self.frameset.clear();
+        self.timestamp = None;
+    }
+}
+
+/// A struct representation of the `rtprgbddepay` element. Packet loss is detected by
+/// `GstRTPBaseDepayload`, which marks the next packet and the next pushed frameset as
+/// discontinuous.
+#[derive(Default)]
+pub struct RtpRgbdDepay {
+    /// The state of the element.
+    state: Mutex<State>,
+}
+
+glib::wrapper! {
+    pub struct RtpRgbdDepayObject(ObjectSubclass<RtpRgbdDepay>)
+        @extends RtpBaseDepayload, gst::Element, gst::Object;
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpRgbdDepay {
+    const NAME: &'static str = "rtprgbddepay";
+    type Type = RtpRgbdDepayObject;
+    type ParentType = RtpBaseDepayload;
+}
+
+impl ElementImpl for RtpRgbdDepay {
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        #[allow(clippy::single_match)]
+        match transition {
+            gst::StateChange::PausedToReady => {
+                *self.state.lock().unwrap() = State::default();
+            }
+            _ => {}
+        }
+
+        // Chain up parent implementation
+        self.parent_change_state(element, transition)
+    }
+
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP RGB-D Depayloader",
+                "Codec/Depayloader/Network/RTP",
+                "Rebuilds framesets of a `video/rgbd` stream from RTP packets",
+                "Raphael Duerscheid <rd@aivero.com>, Andrej Orsula <andrej.orsula@aivero.com>,
+                 Tobias Morell <tobias.morell@aivero.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 2]> = Lazy::new(|| {
+            [
+                gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &gst::Caps::new_simple("video/rgbd", &[]),
+                )
+                .expect("rtprgbddepay: Failed to add 'src' pad template"),
+                gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder("application/x-rtp")
+                        .field("media", "application")
+                        .field("clock-rate", CLOCK_RATE as i32)
+                        .field("encoding-name", ENCODING_NAME)
+                        .build(),
+                )
+                .expect("rtprgbddepay: Failed to add 'sink' pad template"),
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl RtpBaseDepayloadImpl for RtpRgbdDepay {
+    /// Extract the `video/rgbd` caps from the RTP caps and set them on the src pad.
+    fn set_caps(&self, element: &Self::Type, caps: &gst::Caps) -> Result<(), gst::LoggableError> {
+        gst_debug!(CAT, obj: element, "Got new caps: {:?}", caps);
+        let (rgbd_caps, streams) =
+            rgbd_caps(caps).map_err(|e| gst::loggable_error!(CAT, "{}", e))?;
+        self.state.lock().unwrap().streams = streams;
+
+        let src_pad = element.static_pad("src").unwrap();
+        if !src_pad.push_event(gst::event::Caps::new(&rgbd_caps)) {
+            return Err(gst::loggable_error!(CAT, "Failed to set `video/rgbd` caps"));
+        }
+        Ok(())
+    }
+
+    /// Collect the fragment of `packet` and return the frameset once it is complete. Framesets
+    /// with missing fragments are dropped.
+    /// # Arguments
+    /// * `element` - The element that represents the `rtprgbddepay` in GStreamer.
+    /// * `packet` - The RTP packet.
+    fn process(&self, element: &Self::Type, packet: &gst::Buffer) -> Option<gst::Buffer> {
+        let rtp = match gst_rtp::RTPBuffer::from_buffer_readable(packet) {
+            Ok(rtp) => rtp,
+            Err(e) => {
+                gst_warning!(CAT, obj: element, "Dropping invalid RTP packet: {}", e);
+                return None;
+            }
+        };
+        let payload = rtp.payload().unwrap_or(&[]);
+        if payload.len() < FRAGMENT_HEADER_LEN {
+            gst_warning!(CAT, obj: element, "Dropping RTP packet without fragment");
+            return None;
+        }
+        let (offset, fragment) = payload.split_at(FRAGMENT_HEADER_LEN);
+        let offset = u32::from_be_bytes([offset[0], offset[1], offset[2], offset[3]]) as usize;
+
+        let mut state = self.state.lock().unwrap();
+        if packet.flags().contains(gst::BufferFlags::DISCONT) && !state.frameset.is_empty() {
+            gst_warning!(CAT, obj: element, "Packet loss detected");
+            state.drop_frameset();
+        }
+
+        if offset == 0 {
+            if !state.frameset.is_empty() {
+                gst_warning!(CAT, obj: element, "Dropping incomplete frameset");
+                state.drop_frameset();
+            }
+            state.timestamp = Some(rtp.timestamp());
+            state.pts = packet.pts();
+        } else if state.timestamp != Some(rtp.timestamp()) || offset != state.frameset.len() {
+            gst_trace!(
+                CAT,
+                obj: element,
+                "Dropping fragment of an incomplete frameset"
+            );
+            state.drop_frameset();
+            return None;
+        }
+        state.frameset.extend_from_slice(fragment);
+
+        if !rtp.marker() {
+            return None;
+        }
+
+        let frameset = std::mem::take(&mut state.frameset);
+        state.timestamp = None;
+        match deserialize_frameset(&frameset, &state.streams, state.pts) {
+            Ok(main_buffer) => Some(main_buffer),
+            Err(e) => {
+                gst_warning!(CAT, obj: element, "Dropping invalid frameset: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Drop the current frameset on flushes and chain up.
+    fn handle_event(&self, element: &Self::Type, event: gst::Event) -> bool {
+        if let gst::EventView::FlushStop(_) = event.view() {
+            self.state.lock().unwrap().drop_frameset();
+        }
+        self.parent_handle_event(element, event)
+    }
+}
+
+impl GstObjectImpl for RtpRgbdDepay {}
+impl ObjectImpl for RtpRgbdDepay {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "rtprgbddepay",
+        gst::Rank::None,
+        RtpRgbdDepay::type_(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use gst::prelude::*;
+    use gst_depth_meta::rgbd;
+
+    const WIDTH: i32 = 16;
+    const HEIGHT: i32 = 16;
+    const DEPTH_VALUE: u8 = 7;
+    const COLOR_VALUE: u8 = 42;
+
+    /// Create `video/rgbd` caps with `depth` and `color` streams.
+    fn rgbd_caps() -> gst::Caps {
+        let video = |format: &str| {
+            gst::Structure::builder("video/x-raw")
+                .field("format", format)
+                .field("width", WIDTH)
+                .field("height", HEIGHT)
+                .build()
+        };
+        rgbd::RgbdCaps::new()
+            .with_framerate(gst::Fraction::new(30, 1))
+            .with_stream(rgbd::RgbdStreamCaps::new("depth", video("GRAY16_LE")))
+            .with_stream(rgbd::RgbdStreamCaps::new("color", video("RGB")))
+            .to_caps()
+    }
+
+    /// Create a `video/rgbd` buffer with `depth` stream as the main buffer and `color` stream as
+    /// an auxiliary buffer.
+    fn rgbd_buffer() -> gst::Buffer {
+        let size = (WIDTH * HEIGHT) as usize;
+        let mut main_buffer = gst::Buffer::from_mut_slice(vec![DEPTH_VALUE; size * 2]);
+        rgbd::tag_buffer_with_title(main_buffer.make_mut(), "depth").unwrap();
+        let mut color_buffer = gst::Buffer::from_mut_slice(vec![COLOR_VALUE; size * 3]);
+        rgbd::attach_aux_buffer_and_tag(main_buffer.make_mut(), &mut color_buffer, "color")
+            .unwrap();
+        main_buffer
+    }
+
+    /// Determine whether `buffer` contains only `value`.
+    fn is_filled_with(buffer: &gst::BufferRef, value: u8) -> bool {
+        buffer.map_readable().unwrap().iter().all(|v| *v == value)
+    }
+
+    #[test]
+    fn udp_loopback() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+        if ["udpsrc", "udpsink"]
+            .iter()
+            .any(|name| gst::ElementFactory::find(name).is_none())
+        {
+            eprintln!("Skipping test: `udpsrc` and `udpsink` of gst-plugins-good are required");
+            return;
+        }
+
+        // Let the system pick a free port, which is known once `udpsrc` is bound
+        let (rtp_caps, _) = crate::rtprgbd::rtp_caps(&rgbd_caps(), 96).unwrap();
+        let mut receiver =
+            gst_check::Harness::new_parse("udpsrc address=127.0.0.1 port=0 ! rtprgbddepay");
+        let udpsrc = receiver.find_element("udpsrc").unwrap();
+        udpsrc.set_property("caps", &rtp_caps);
+        receiver.play();
+        let port = udpsrc.property::<i32>("port");
+        assert_ne!(port, 0, "`udpsrc` is not bound");
+
+        // Small packets, such that each frameset is fragmented
+        let mut sender = gst_check::Harness::new_parse(&format!(
+            "rtprgbdpay mtu=100 ! udpsink host=127.0.0.1 port={} sync=false async=false",
+            port
+        ));
+        sender.set_src_caps(rgbd_caps());
+        sender.push(rgbd_buffer()).unwrap();
+
+        let output = receiver.pull().unwrap();
+        assert_eq!(rgbd::get_tag(&output).unwrap(), "depth");
+        assert!(is_filled_with(&output, DEPTH_VALUE));
+        let aux_buffers = rgbd::get_all_aux_buffers(&output).collect::<Vec<_>>();
+        assert_eq!(aux_buffers.len(), 1, "Found a wrong number of buffers");
+        assert_eq!(rgbd::get_tag(&aux_buffers[0]).unwrap(), "color");
+        assert!(is_filled_with(&aux_buffers[0], COLOR_VALUE));
+
+        let output_caps = receiver.sinkpad().unwrap().current_caps().unwrap();
+        assert_eq!(
+            rgbd::RgbdCaps::from_caps(&output_caps).unwrap(),
+            rgbd::RgbdCaps::from_caps(&rgbd_caps()).unwrap()
+        );
+    }
+
+    #[test]
+    fn base_class_properties() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        let mut pay = gst_check::Harness::new_parse(
+            "rtprgbdpay mtu=100 pt=97 seqnum-offset=100 timestamp-offset=1000",
+        );
+        pay.set_src_caps(rgbd_caps());
+        let mut buffer = rgbd_buffer();
+        buffer.make_mut().set_pts(gst::ClockTime::ZERO);
+        pay.push(buffer).unwrap();
+
+        let mut depay = gst_check::Harness::new("rtprgbddepay");
+        depay.set_src_caps(pay.sinkpad().unwrap().current_caps().unwrap());
+        let packet_count = pay.buffers_in_queue();
+        assert!(packet_count > 1, "The frameset was not fragmented");
+        for index in 0..packet_count {
+            let packet = pay.pull().unwrap();
+            {
+                let rtp = gst_rtp::RTPBuffer::from_buffer_readable(&packet).unwrap();
+                assert_eq!(rtp.payload_type(), 97);
+                assert_eq!(rtp.seq(), 100 + index as u16);
+                assert_eq!(rtp.timestamp(), 1000);
+                assert_eq!(rtp.marker(), index + 1 == packet_count);
+            }
+            depay.push(packet).unwrap();
+        }
+
+        let output = depay.pull().unwrap();
+        assert_eq!(output.pts(), Some(gst::ClockTime::ZERO));
+        assert!(is_filled_with(&output, DEPTH_VALUE));
+        let aux_buffers = rgbd::get_all_aux_buffers(&output).collect::<Vec<_>>();
+        assert_eq!(aux_buffers.len(), 1, "Found a wrong number of buffers");
+        assert!(is_filled_with(&aux_buffers[0], COLOR_VALUE));
+    }
+
+    #[test]
+    fn caps_survive_sdp() {
+        gst::init().unwrap();
+        let (rtp_caps, streams) = crate::rtprgbd::rtp_caps(&rgbd_caps(), 96).unwrap();
+
+        let mut media = gst_sdp::SDPMedia::new();
+        media.set_media_from_caps(&rtp_caps).unwrap();
+        let sdp_caps = media.caps_from_media(96).unwrap();
+        let (caps, sdp_streams) = crate::rtprgbd::rgbd_caps(&sdp_caps).unwrap();
+
+        assert_eq!(streams, ["depth", "color"]);
+        assert_eq!(sdp_streams, streams);
+        assert_eq!(
+            rgbd::RgbdCaps::from_caps(&caps).unwrap(),
+            rgbd::RgbdCaps::from_caps(&rgbd_caps()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_rtp::prelude::*;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+use crate::frameset::*;
+use crate::rtpbase::*;
+use crate::rtprgbd::*;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "rtprgbdpay",
+        gst::DebugColorFlags::empty(),
+        Some("RTP RGB-D Payloader"),
+    )
+});
+
+/// Mutable state of the `rtprgbdpay` element
+#[derive(Default)]
+struct State {
+    /// Names of the streams of the negotiated `video/rgbd` caps.
+    streams: Vec<String>,
+}
+
+/// A struct representation of the `rtprgbdpay` element. SSRC, payload type, sequence numbers
+/// and RTP timestamps are handled by `GstRTPBasePayload`, which also provides the `mtu`, `pt`,
+/// `seqnum-offset`, `timestamp-offset` and `stats` properties.
+#[derive(Default)]
+pub struct RtpRgbdPay {
+    /// The state of the element.
+    state: Mutex<State>,
+}
+
+glib::wrapper! {
+    pub struct RtpRgbdPayObject(ObjectSubclass<RtpRgbdPay>)
+        @extends RtpBasePayload, gst::Element, gst::Object;
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RtpRgbdPay {
+    const NAME: &'static str = "rtprgbdpay";
+    type Type = RtpRgbdPayObject;
+    type ParentType = RtpBasePayload;
+}
+
+impl ElementImpl for RtpRgbdPay {
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        #[allow(clippy::single_match)]
+        match transition {
+            gst::StateChange::ReadyToPaused => {
+                *self.state.lock().unwrap() = State::default();
+            }
+            _ => {}
+        }
+
+        // Chain up parent implementation
+        self.parent_change_state(element, transition)
+    }
+
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RTP RGB-D Payloader",
+                "Codec/Payloader/Network/RTP",
+                "Payloads whole framesets of a `video/rgbd` stream into RTP packets",
+                "Raphael Duerscheid <rd@aivero.com>, Andrej Orsula <andrej.orsula@aivero.com>,
+                 Tobias Morell <tobias.morell@aivero.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 2]> = Lazy::new(|| {
+            [
+                gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder("application/x-rtp")
+                        .field("media", "application")
+                        .field("clock-rate", CLOCK_RATE as i32)
+                        .field("encoding-name", ENCODING_NAME)
+                        .build(),
+                )
+                .expect("rtprgbdpay: Failed to add 'src' pad template"),
+                gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &gst::Caps::new_simple("video/rgbd", &[]),
+                )
+                .expect("rtprgbdpay: Failed to add 'sink' pad template"),
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl RtpBasePayloadImpl for RtpRgbdPay {
+    /// Convert the `video/rgbd` caps into RTP caps that carry them in their `streams` and
+    /// `rgbd-caps` fields.
+    fn set_caps(&self, element: &Self::Type, caps: &gst::Caps) -> Result<(), gst::LoggableError> {
+        gst_debug!(CAT, obj: element, "Got new caps: {:?}", caps);
+        let (fields, streams) =
+            rgbd_fields(caps).map_err(|e| gst::loggable_error!(CAT, "{}", e))?;
+        self.state.lock().unwrap().streams = streams;
+
+        element.set_options("application", true, ENCODING_NAME, CLOCK_RATE);
+        element
+            .set_outcaps(
+                &fields
+                    .iter()
+                    .map(|(name, value)| (*name, value.as_str()))
+                    .collect::<Vec<_>>(),
+            )
+            .map_err(|e| gst::loggable_error!(CAT, "{}", e))
+    }
+
+    /// Serialise the frameset of `main_buffer`, fragment it into RTP packets and push them
+    /// downstream as a buffer list.
+    /// # Arguments
+    /// * `element` - The element that represents the `rtprgbdpay` in GStreamer.
+    /// * `main_buffer` - The main buffer of the `video/rgbd` stream.
+    fn handle_buffer(
+        &self,
+        element: &Self::Type,
+        main_buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let mtu = element.property::<u32>("mtu") as usize;
+        let max_fragment_len = mtu
+            .checked_sub(RTP_HEADER_LEN + FRAGMENT_HEADER_LEN)
+            .filter(|len| *len > 0)
+            .ok_or_else(|| {
+                gst::element_error!(
+                    element,
+                    gst::LibraryError::Settings,
+                    ["MTU of {} bytes is too small to carry any fragment", mtu]
+                );
+                gst::FlowError::Error
+            })?;
+
+        let frameset = serialize_frameset(&main_buffer, &self.state.lock().unwrap().streams)
+            .map_err(|e| {
+                gst_error!(CAT, obj: element, "{}", e);
+                gst::FlowError::Error
+            })?;
+
+        let fragment_count = (frameset.len() + max_fragment_len - 1) / max_fragment_len;
+        let mut packets = gst::BufferList::new_sized(fragment_count);
+        let packets_mut = packets.get_mut().unwrap();
+        for (index, fragment) in frameset.chunks(max_fragment_len).enumerate() {
+            let mut packet = gst::Buffer::new_rtp_with_sizes(
+                (FRAGMENT_HEADER_LEN + fragment.len()) as u32,
+                0,
+                0,
+            )
+            .map_err(|e| {
+                gst_error!(CAT, obj: element, "Failed to allocate RTP packet: {}", e);
+                gst::FlowError::Error
+            })?;
+            {
+                // The base class derives the RTP timestamp from the PTS of each packet
+                let packet_mut = packet.get_mut().unwrap();
+                packet_mut.set_pts(main_buffer.pts());
+                let mut rtp = gst_rtp::RTPBuffer::from_buffer_writable(packet_mut).unwrap();
+                rtp.set_marker(index + 1 == fragment_count);
+                let payload = rtp.payload_mut().unwrap();
+                let offset = (index * max_fragment_len) as u32;
+                payload[..FRAGMENT_HEADER_LEN].copy_from_slice(&offset.to_be_bytes());
+                payload[FRAGMENT_HEADER_LEN..].copy_from_slice(fragment);
+            }
+            packets_mut.add(packet);
+        }
+
+        gst_trace!(
+            CAT,
+            obj: element,
+            "Pushing frameset of {} bytes in {} packets",
+            frameset.len(),
+            fragment_count
+        );
+        element.push_list(packets)
+    }
+}
+
+impl GstObjectImpl for RtpRgbdPay {}
+impl ObjectImpl for RtpRgbdPay {}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(
+        Some(plugin),
+        "rtprgbdpay",
+        gst::Rank::None,
+        RtpRgbdPay::type_(),
+    )
+}
@@ -0,0 +1,266 @@
+//! Lossless compression of 16-bit depth maps, which is shared by `rvlenc` and `rvldec`.
+//!
+//! Depth maps are compressed with run-length and variable-length (RVL) coding, see A. D. Wilson,
+//! "Fast Lossless Depth Image Compression", ISS 2017. Pixels are coded in raster order as
+//! alternating runs of zeros, i.e. invalid depth, and runs of valid depth. Each run starts with
+//! its length, and each valid depth value is coded as the zigzag encoded difference to the
+//! previous valid depth value. All integers are coded in nibbles, 3 bits of value (least
+//! significant first) and a continuation bit, which are packed into bytes with the first nibble in
+//! the most significant half. The compressed data is optionally compressed further with zstd.
+
+/// Value of the `compression` field of `video/x-rvl` CAPS without zstd stage.
+pub const COMPRESSION_NONE: &str = "none";
+/// Value of the `compression` field of `video/x-rvl` CAPS with zstd stage.
+pub const COMPRESSION_ZSTD: &str = "zstd";
+
+/// Writer of nibbles into a byte vector.
+struct NibbleWriter<'a> {
+    output: &'a mut Vec<u8>,
+    /// Whether the last byte of `output` has its less significant nibble still free.
+    half_byte: bool,
+}
+
+impl<'a> NibbleWriter<'a> {
+    fn new(output: &'a mut Vec<u8>) -> Self {
+        Self {
+            output,
+            half_byte: false,
+        }
+    }
+
+    fn write_nibble(&mut self, nibble: u8) {
+        if self.half_byte {
+            *self.output.last_mut().unwrap() |= nibble;
+        } else {
+            self.output.push(nibble << 4);
+        }
+        self.half_byte = !self.half_byte;
+    }
+
+    /// Write `value` as variable-length integer.
+    fn write_vle(&mut self, mut value: u32) {
+        loop {
+            let nibble = (value & 0x7) as u8;
+            value >>= 3;
+            if value == 0 {
+                self.write_nibble(nibble);
+                return;
+            }
+            self.write_nibble(nibble | 0x8);
+        }
+    }
+}
+
+/// Reader of nibbles from a byte slice.
+struct NibbleReader<'a> {
+    input: &'a [u8],
+    /// Index of the next nibble.
+    position: usize,
+}
+
+impl<'a> NibbleReader<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Self { input, position: 0 }
+    }
+
+    fn read_nibble(&mut self) -> Result<u8, gst::ErrorMessage> {
+        let byte = self
+            .input
+            .get(self.position / 2)
+            .ok_or_else(|| gst::error_msg!(gst::StreamError::Decode, ["RVL data is truncated"]))?;
+        let nibble = if self.position % 2 == 0 {
+            byte >> 4
+        } else {
+            byte & 0xf
+        };
+        self.position += 1;
+        Ok(nibble)
+    }
+
+    /// Read a variable-length integer.
+    fn read_vle(&mut self) -> Result<u32, gst::ErrorMessage> {
+        let mut value = 0u32;
+        let mut shift = 0;
+        loop {
+            let nibble = self.read_nibble()?;
+            // 11 nibbles cover 33 bits, anything longer is corrupt
+            if shift > 30 {
+                return Err(gst::error_msg!(
+                    gst::StreamError::Decode,
+                    ["RVL data contains an invalid integer"]
+                ));
+            }
+            value |= ((nibble & 0x7) as u32) << shift;
+            if nibble & 0x8 == 0 {
+                return Ok(value);
+            }
+            shift += 3;
+        }
+    }
+}
+
+/// Compress a depth map with RVL coding.
+/// # Arguments
+/// * `depth` - The depth values in raster order, where 0 indicates invalid depth.
+/// * `output` - The vector to which the compressed depth map is appended.
+pub fn encode(depth: &[u16], output: &mut Vec<u8>) {
+    let mut writer = NibbleWriter::new(output);
+    let mut previous = 0i32;
+    let mut remaining = depth;
+    while !remaining.is_empty() {
+        let zeros = remaining.iter().take_while(|value| **value == 0).count();
+        remaining = &remaining[zeros..];
+        let non_zeros = remaining.iter().take_while(|value| **value != 0).count();
+        writer.write_vle(zeros as u32);
+        writer.write_vle(non_zeros as u32);
+        for value in &remaining[..non_zeros] {
+            let delta = *value as i32 - previous;
+            writer.write_vle(((delta << 1) ^ (delta >> 31)) as u32);
+            previous = *value as i32;
+        }
+        remaining = &remaining[non_zeros..];
+    }
+}
+
+/// Get the largest possible size of a depth map of `pixel_count` values compressed by
+/// [encode](fn.encode.html). Each value takes at most 6 nibbles, as zigzag encoded differences
+/// have at most 17 bits, and each run of length `n > 0` takes at most `n` nibbles. Only the first
+/// and last run may be empty, which take a nibble each.
+/// # Arguments
+/// * `pixel_count` - Number of depth values of the depth map.
+pub fn max_encoded_size(pixel_count: usize) -> usize {
+    (7 * pixel_count + 2 + 1) / 2
+}
+
+/// Decompress a depth map compressed by [encode](fn.encode.html).
+/// # Arguments
+/// * `data` - The compressed depth map.
+/// * `depth` - Output depth values, which must have the size of the compressed depth map.
+/// # Returns
+/// * `Err(gst::ErrorMessage)` - If `data` is corrupt or does not contain exactly `depth.len()`
+/// depth values.
+pub fn decode(data: &[u8], depth: &mut [u16]) -> Result<(), gst::ErrorMessage> {
+    let corrupt = || gst::error_msg!(gst::StreamError::Decode, ["RVL data is corrupt"]);
+    let mut reader = NibbleReader::new(data);
+    let mut previous = 0i32;
+    let mut position = 0;
+    while position < depth.len() {
+        let zeros = reader.read_vle()? as usize;
+        let zeros_end = position.checked_add(zeros).ok_or_else(corrupt)?;
+        depth
+            .get_mut(position..zeros_end)
+            .ok_or_else(corrupt)?
+            .iter_mut()
+            .for_each(|value| *value = 0);
+        position = zeros_end;
+
+        let non_zeros = reader.read_vle()? as usize;
+        let non_zeros_end = position.checked_add(non_zeros).ok_or_else(corrupt)?;
+        for value in depth.get_mut(position..non_zeros_end).ok_or_else(corrupt)? {
+            let zigzag = reader.read_vle()?;
+            let delta = (zigzag >> 1) as i32 ^ -((zigzag & 1) as i32);
+            previous += delta;
+            if !(1..=u16::MAX as i32).contains(&previous) {
+                return Err(corrupt());
+            }
+            *value = previous as u16;
+        }
+        position = non_zeros_end;
+    }
+
+    // Only padding of the last byte may remain
+    if (reader.position + 1) / 2 != data.len() {
+        return Err(gst::error_msg!(
+            gst::StreamError::Decode,
+            ["RVL data contains more than {} depth values", depth.len()]
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    /// Create a synthetic depth map of a tilted plane with a sphere in front of it and holes of
+    /// invalid depth, similar to depth maps of stereo cameras.
+    fn synthetic_depth(width: usize, height: usize) -> Vec<u16> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let mut depth = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let (dx, dy) = (
+                    x as f32 - width as f32 / 2.0,
+                    y as f32 - height as f32 / 2.0,
+                );
+                let radius = height as f32 / 4.0;
+                let value = if dx * dx + dy * dy < radius * radius {
+                    800.0 - (radius * radius - dx * dx - dy * dy).sqrt()
+                } else {
+                    2000.0 + 2.0 * x as f32 + y as f32
+                };
+                // Holes and noise
+                if x < 40 || rng.gen_ratio(1, 50) {
+                    depth.push(0);
+                } else {
+                    depth.push(value as u16 + rng.gen_range(0..4));
+                }
+            }
+        }
+        depth
+    }
+
+    #[test]
+    fn round_trip_synthetic_depth() {
+        gst::init().unwrap();
+
+        let depth = synthetic_depth(1280, 720);
+        let mut data = Vec::new();
+        encode(&depth, &mut data);
+        assert!(data.len() < depth.len());
+
+        let mut decoded = vec![u16::MAX; depth.len()];
+        decode(&data, &mut decoded).unwrap();
+        assert_eq!(decoded, depth);
+    }
+
+    #[test]
+    fn round_trip_edge_cases() {
+        gst::init().unwrap();
+
+        let cases: [&[u16]; 7] = [
+            &[],
+            &[0; 100],
+            &[u16::MAX; 100],
+            &[1, u16::MAX, 1, u16::MAX, 0, 0, 1],
+            &[0, 0, 0, 7],
+            &[7, 0, 0, 0],
+            &[0, u16::MAX, 0, 1, 0, u16::MAX, 0, 1],
+        ];
+        for depth in cases.iter() {
+            let mut data = Vec::new();
+            encode(depth, &mut data);
+            assert!(data.len() <= max_encoded_size(depth.len()));
+            let mut decoded = vec![1; depth.len()];
+            decode(&data, &mut decoded).unwrap();
+            assert_eq!(&decoded[..], *depth);
+        }
+    }
+
+    #[test]
+    fn decode_corrupt_data() {
+        gst::init().unwrap();
+
+        let depth = synthetic_depth(64, 48);
+        let mut data = Vec::new();
+        encode(&depth, &mut data);
+
+        let mut decoded = vec![0; depth.len()];
+        assert!(decode(&data[..data.len() / 2], &mut decoded).is_err());
+        let mut smaller = vec![0; depth.len() - 1];
+        assert!(decode(&data, &mut smaller).is_err());
+        let mut larger = vec![0; depth.len() + 1];
+        assert!(decode(&data, &mut larger).is_err());
+    }
+}
@@ -0,0 +1,424 @@
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_depth_meta::rgbd;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+use crate::rvl;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "rvldec",
+        gst::DebugColorFlags::empty(),
+        Some("RVL Depth Decoder"),
+    )
+});
+
+/// Mutable state of the `rvldec` element
+#[derive(Default)]
+struct State {
+    /// Video info of the negotiated src caps.
+    src_info: Option<gst_video::VideoInfo>,
+    /// Whether the negotiated caps contain a zstd stage.
+    zstd: bool,
+}
+
+/// A struct representation of the `rvldec` element.
+pub struct RvlDec {
+    /// The state of the element.
+    state: Mutex<State>,
+    sink_pad: gst::Pad,
+    src_pad: gst::Pad,
+}
+
+glib::wrapper! {
+    pub struct RvlDecObject(ObjectSubclass<RvlDec>)
+        @extends gst::Element, gst::Object;
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RvlDec {
+    const NAME: &'static str = "rvldec";
+    type Type = RvlDecObject;
+    type ParentType = gst::Element;
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sink_pad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this, element| this.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| this.sink_event(pad, element, event),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let src_pad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .flags(gst::PadFlags::FIXED_CAPS)
+            .build();
+
+        Self {
+            state: Mutex::new(State::default()),
+            sink_pad,
+            src_pad,
+        }
+    }
+}
+
+impl ElementImpl for RvlDec {
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        #[allow(clippy::single_match)]
+        match transition {
+            gst::StateChange::PausedToReady => {
+                *self.state.lock().unwrap() = State::default();
+            }
+            _ => {}
+        }
+
+        // Chain up parent implementation
+        self.parent_change_state(element, transition)
+    }
+
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RVL Depth Decoder",
+                "Codec/Decoder/Video/RGB-D",
+                "Decompresses depth video compressed by `rvlenc` into 16-bit depth",
+                "Raphael Duerscheid <rd@aivero.com>, Andrej Orsula <andrej.orsula@aivero.com>,
+                 Tobias Morell <tobias.morell@aivero.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 2]> = Lazy::new(|| {
+            [
+                gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder("video/x-raw")
+                        .field("format", &"GRAY16_LE")
+                        .field("width", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .field("height", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .build(),
+                )
+                .expect("rvldec: Failed to add 'src' pad template"),
+                gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder(rgbd::RVL_MEDIA_TYPE)
+                        .field("width", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .field("height", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .field(
+                            "compression",
+                            &gst::List::new([&rvl::COMPRESSION_NONE, &rvl::COMPRESSION_ZSTD]),
+                        )
+                        .build(),
+                )
+                .expect("rvldec: Failed to add 'sink' pad template"),
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl RvlDec {
+    /// Called whenever an event is received at the sink pad. CAPS events are converted into
+    /// `video/x-raw` CAPS, all other events are send further downstream.
+    /// # Arguments
+    /// * `pad` - The sink pad.
+    /// * `element` - The element that represents the `rvldec` in GStreamer.
+    /// * `event` - The event that should be handled.
+    fn sink_event(&self, pad: &gst::Pad, element: &RvlDecObject, event: gst::Event) -> bool {
+        use gst::EventView;
+        match event.view() {
+            EventView::Caps(caps) => {
+                gst_debug!(CAT, obj: element, "Got a new caps event: {:?}", caps);
+                let structure = match caps.caps().structure(0) {
+                    Some(structure) => structure,
+                    None => return false,
+                };
+                let (width, height) = match (
+                    structure.get::<i32>("width"),
+                    structure.get::<i32>("height"),
+                ) {
+                    (Ok(width), Ok(height)) => (width, height),
+                    _ => {
+                        gst_error!(CAT, obj: element, "Caps are missing width or height");
+                        return false;
+                    }
+                };
+                let zstd = match structure.get::<&str>("compression") {
+                    Ok(rvl::COMPRESSION_ZSTD) => true,
+                    Ok(rvl::COMPRESSION_NONE) | Err(_) => false,
+                    Ok(compression) => {
+                        gst_error!(CAT, obj: element, "Unknown compression {}", compression);
+                        return false;
+                    }
+                };
+
+                let mut src_caps = gst::Caps::builder("video/x-raw")
+                    .field("format", &"GRAY16_LE")
+                    .field("width", &width)
+                    .field("height", &height)
+                    .build();
+                if let Ok(framerate) = structure.value("framerate") {
+                    src_caps
+                        .get_mut()
+                        .unwrap()
+                        .structure_mut(0)
+                        .unwrap()
+                        .set_value("framerate", framerate.clone());
+                }
+
+                let src_info = match gst_video::VideoInfo::from_caps(&src_caps) {
+                    Ok(src_info) => src_info,
+                    Err(e) => {
+                        gst_error!(CAT, obj: element, "Invalid depth caps: {}", e);
+                        return false;
+                    }
+                };
+
+                *self.state.lock().unwrap() = State {
+                    src_info: Some(src_info),
+                    zstd,
+                };
+                self.src_pad.push_event(gst::event::Caps::new(&src_caps))
+            }
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+
+    /// Decompress a depth frame and push it downstream with the timestamps, flags and metas of
+    /// the incoming buffer, e.g. its tags. Frames whose size does not match the CAPS are
+    /// rejected.
+    /// # Arguments
+    /// * `element` - The element that represents the `rvldec` in GStreamer.
+    /// * `buffer` - The buffer with compressed depth.
+    fn sink_chain(
+        &self,
+        _: &gst::Pad,
+        element: &RvlDecObject,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let (src_info, zstd) = {
+            let state = self.state.lock().unwrap();
+            let src_info = state.src_info.clone().ok_or_else(|| {
+                gst_warning!(CAT, obj: element, "Received a buffer before CAPS");
+                gst::FlowError::NotNegotiated
+            })?;
+            (src_info, state.zstd)
+        };
+        let width = src_info.width() as usize;
+        let pixel_count = width * src_info.height() as usize;
+
+        let map = buffer.map_readable().map_err(|_| {
+            gst_error!(CAT, obj: element, "Cannot map compressed depth buffer");
+            gst::FlowError::Error
+        })?;
+        let decompressed;
+        let data = if zstd {
+            // Decompress no more than a frame of RVL data can take, instead of whatever the
+            // buffer claims
+            decompressed =
+                zstd::bulk::decompress(map.as_slice(), rvl::max_encoded_size(pixel_count))
+                    .map_err(|e| {
+                        gst::element_error!(
+                            element,
+                            gst::StreamError::Decode,
+                            ["zstd failed: {}", e]
+                        );
+                        gst::FlowError::Error
+                    })?;
+            &decompressed[..]
+        } else {
+            map.as_slice()
+        };
+        let mut depth = vec![0u16; pixel_count];
+        rvl::decode(data, &mut depth).map_err(|e| {
+            element.post_error_message(e);
+            gst::FlowError::Error
+        })?;
+        drop(map);
+
+        // Rows of the output are padded as described by the CAPS
+        let mut output = gst::Buffer::with_size(src_info.size()).map_err(|_| {
+            gst_error!(CAT, obj: element, "Cannot allocate depth buffer");
+            gst::FlowError::Error
+        })?;
+        {
+            let output = output.get_mut().unwrap();
+            let mut frame = gst_video::VideoFrameRef::from_buffer_ref_writable(output, &src_info)
+                .map_err(|_| {
+                gst_error!(CAT, obj: element, "Cannot map depth buffer");
+                gst::FlowError::Error
+            })?;
+            let stride = frame.plane_stride()[0] as usize;
+            let data = frame.plane_data_mut(0).map_err(|_| gst::FlowError::Error)?;
+            for (y, row) in depth.chunks_exact(width).enumerate() {
+                for (x, value) in row.iter().enumerate() {
+                    let offset = y * stride + x * 2;
+                    data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+        buffer
+            .copy_into(
+                output.get_mut().unwrap(),
+                gst::BufferCopyFlags::FLAGS
+                    | gst::BufferCopyFlags::TIMESTAMPS
+                    | gst::BufferCopyFlags::META,
+                0,
+                None,
+            )
+            .map_err(|_| {
+                gst_error!(CAT, obj: element, "Cannot copy metadata of depth buffer");
+                gst::FlowError::Error
+            })?;
+        self.src_pad.push(output)
+    }
+}
+
+impl GstObjectImpl for RvlDec {}
+impl ObjectImpl for RvlDec {
+    fn constructed(&self, element: &Self::Type) {
+        self.parent_constructed(element);
+
+        element
+            .add_pad(&self.sink_pad)
+            .expect("rvldec: Failed to add sink pad to the element");
+        element
+            .add_pad(&self.src_pad)
+            .expect("rvldec: Failed to add src pad to the element");
+    }
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(Some(plugin), "rvldec", gst::Rank::None, RvlDec::type_())
+}
+
+#[cfg(test)]
+mod tests {
+    use gst_depth_meta::rgbd;
+
+    /// Create the video info of `GRAY16_LE` depth, whose rows are padded to 4 bytes.
+    fn depth_info(width: u32, height: u32) -> gst_video::VideoInfo {
+        gst_video::VideoInfo::builder(gst_video::VideoFormat::Gray16Le, width, height)
+            .fps(gst::Fraction::new(30, 1))
+            .build()
+            .unwrap()
+    }
+
+    /// Create a `GRAY16_LE` buffer of a synthetic depth ramp with a hole of invalid depth. The
+    /// padding at the end of each row is filled with garbage, which must not be encoded.
+    fn depth_buffer(info: &gst_video::VideoInfo) -> gst::Buffer {
+        let stride = info.stride()[0] as usize;
+        let mut data = vec![0xffu8; info.size()];
+        for y in 0..info.height() as usize {
+            for x in 0..info.width() as usize {
+                let value = if x < 8 {
+                    0u16
+                } else {
+                    1000 + (x + 2 * y) as u16
+                };
+                data[y * stride + x * 2..y * stride + x * 2 + 2]
+                    .copy_from_slice(&value.to_le_bytes());
+            }
+        }
+        gst::Buffer::from_mut_slice(data)
+    }
+
+    /// Get the depth values of a `GRAY16_LE` buffer without the padding of its rows.
+    fn depth_values(buffer: &gst::BufferRef, info: &gst_video::VideoInfo) -> Vec<u8> {
+        let stride = info.stride()[0] as usize;
+        let row_size = info.width() as usize * 2;
+        let map = buffer.map_readable().unwrap();
+        (0..info.height() as usize)
+            .flat_map(|y| map[y * stride..y * stride + row_size].to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_keeps_timestamps_and_tags() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        // Rows of odd width are padded
+        for (width, height) in [(64, 48), (63, 47)].iter() {
+            for zstd_level in [0, 3].iter() {
+                let info = depth_info(*width, *height);
+                let mut harness = gst_check::Harness::new_parse(&format!(
+                    "rvlenc zstd-level={} ! rvldec",
+                    zstd_level
+                ));
+                harness.set_src_caps(info.to_caps().unwrap());
+
+                let mut input = depth_buffer(&info);
+                {
+                    let input = input.get_mut().unwrap();
+                    input.set_pts(gst::ClockTime::from_mseconds(100));
+                    input.set_dts(gst::ClockTime::from_mseconds(90));
+                    input.set_duration(gst::ClockTime::from_mseconds(33));
+                    rgbd::tag_buffer_with_title(input, "depth").unwrap();
+                }
+                harness.push(input.clone()).unwrap();
+
+                let output = harness.pull().unwrap();
+                assert_eq!(output.size(), info.size());
+                assert_eq!(depth_values(&output, &info), depth_values(&input, &info));
+                assert_eq!(output.pts(), input.pts());
+                assert_eq!(output.dts(), input.dts());
+                assert_eq!(output.duration(), input.duration());
+                assert_eq!(rgbd::get_tag(&output).unwrap(), "depth");
+
+                let caps = harness.sinkpad().unwrap().current_caps().unwrap();
+                let structure = caps.structure(0).unwrap();
+                assert_eq!(structure.get::<&str>("format").unwrap(), "GRAY16_LE");
+                assert_eq!(structure.get::<i32>("width").unwrap(), *width as i32);
+                assert_eq!(
+                    structure.get::<gst::Fraction>("framerate").unwrap(),
+                    gst::Fraction::new(30, 1)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reject_oversized_zstd_frame() {
+        gst::init().unwrap();
+        crate::plugin_register_static().unwrap();
+
+        let mut harness = gst_check::Harness::new("rvldec");
+        harness.set_src_caps(
+            gst::Caps::builder(rgbd::RVL_MEDIA_TYPE)
+                .field("width", &4)
+                .field("height", &4)
+                .field("compression", &crate::rvl::COMPRESSION_ZSTD)
+                .build(),
+        );
+
+        // Far more data than RVL coding of 16 depth values can take
+        let data = zstd::bulk::compress(&vec![0u8; 1 << 20], 3).unwrap();
+        assert!(harness.push(gst::Buffer::from_mut_slice(data)).is_err());
+        assert!(harness.try_pull().is_none());
+    }
+}
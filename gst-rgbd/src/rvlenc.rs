@@ -0,0 +1,365 @@
+use glib::*;
+use gst::glib;
+use gst::prelude::*;
+use gst::subclass::prelude::*;
+use gst_depth_meta::rgbd;
+use once_cell::sync::Lazy;
+use std::sync::{Mutex, RwLock};
+
+use crate::common::*;
+use crate::rvl;
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        "rvlenc",
+        gst::DebugColorFlags::empty(),
+        Some("RVL Depth Encoder"),
+    )
+});
+
+/// Default value for to `zstd-level` property
+const DEFAULT_ZSTD_LEVEL: i32 = 0;
+/// Maximum value of `zstd-level` property
+const MAX_ZSTD_LEVEL: i32 = 22;
+
+/// A struct containing properties of `rvlenc` element
+struct Settings {
+    /// Analogous to `zstd-level` property
+    zstd_level: i32,
+}
+
+/// Mutable state of the `rvlenc` element
+#[derive(Default)]
+struct State {
+    /// Video info of the negotiated sink caps.
+    sink_info: Option<gst_video::VideoInfo>,
+    /// The zstd level of the negotiated caps, or `None` if the zstd stage is disabled.
+    zstd_level: Option<i32>,
+}
+
+/// A struct representation of the `rvlenc` element.
+pub struct RvlEnc {
+    /// Settings based on properties of the element.
+    settings: RwLock<Settings>,
+    /// The state of the element.
+    state: Mutex<State>,
+    sink_pad: gst::Pad,
+    src_pad: gst::Pad,
+}
+
+glib::wrapper! {
+    pub struct RvlEncObject(ObjectSubclass<RvlEnc>)
+        @extends gst::Element, gst::Object;
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for RvlEnc {
+    const NAME: &'static str = "rvlenc";
+    type Type = RvlEncObject;
+    type ParentType = gst::Element;
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sink_pad = gst::Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this, element| this.sink_chain(pad, element, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Self::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this, element| this.sink_event(pad, element, event),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let src_pad = gst::Pad::builder_with_template(&templ, Some("src"))
+            .flags(gst::PadFlags::FIXED_CAPS)
+            .build();
+
+        Self {
+            settings: RwLock::new(Settings {
+                zstd_level: DEFAULT_ZSTD_LEVEL,
+            }),
+            state: Mutex::new(State::default()),
+            sink_pad,
+            src_pad,
+        }
+    }
+}
+
+impl ElementImpl for RvlEnc {
+    fn change_state(
+        &self,
+        element: &Self::Type,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        #[allow(clippy::single_match)]
+        match transition {
+            gst::StateChange::PausedToReady => {
+                *self.state.lock().unwrap() = State::default();
+            }
+            _ => {}
+        }
+
+        // Chain up parent implementation
+        self.parent_change_state(element, transition)
+    }
+
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "RVL Depth Encoder",
+                "Codec/Encoder/Video/RGB-D",
+                "Compresses 16-bit depth video losslessly with RVL coding and optionally zstd",
+                "Raphael Duerscheid <rd@aivero.com>, Andrej Orsula <andrej.orsula@aivero.com>,
+                 Tobias Morell <tobias.morell@aivero.com>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<[gst::PadTemplate; 2]> = Lazy::new(|| {
+            [
+                gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder(rgbd::RVL_MEDIA_TYPE)
+                        .field("width", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .field("height", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .field(
+                            "compression",
+                            &gst::List::new([&rvl::COMPRESSION_NONE, &rvl::COMPRESSION_ZSTD]),
+                        )
+                        .build(),
+                )
+                .expect("rvlenc: Failed to add 'src' pad template"),
+                gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &gst::Caps::builder("video/x-raw")
+                        .field("format", &"GRAY16_LE")
+                        .field("width", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .field("height", &gst::IntRange::<i32>::new(1, i32::MAX))
+                        .build(),
+                )
+                .expect("rvlenc: Failed to add 'sink' pad template"),
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+}
+
+impl RvlEnc {
+    /// Called whenever an event is received at the sink pad. CAPS events are converted into
+    /// `video/x-rvl` CAPS, all other events are send further downstream.
+    /// # Arguments
+    /// * `pad` - The sink pad.
+    /// * `element` - The element that represents the `rvlenc` in GStreamer.
+    /// * `event` - The event that should be handled.
+    fn sink_event(&self, pad: &gst::Pad, element: &RvlEncObject, event: gst::Event) -> bool {
+        use gst::EventView;
+        match event.view() {
+            EventView::Caps(caps) => {
+                gst_debug!(CAT, obj: element, "Got a new caps event: {:?}", caps);
+                let structure = match caps.caps().structure(0) {
+                    Some(structure) => structure,
+                    None => return false,
+                };
+                let sink_info = match gst_video::VideoInfo::from_caps(caps.caps()) {
+                    Ok(sink_info) => sink_info,
+                    Err(e) => {
+                        gst_error!(CAT, obj: element, "Invalid depth caps: {}", e);
+                        return false;
+                    }
+                };
+                let (width, height) = (sink_info.width() as i32, sink_info.height() as i32);
+
+                // The zstd level is fixed with the CAPS, such that all buffers match them
+                let zstd_level = match self.settings.read().unwrap().zstd_level {
+                    0 => None,
+                    level => Some(level),
+                };
+                let mut src_caps = gst::Caps::builder(rgbd::RVL_MEDIA_TYPE)
+                    .field("width", &width)
+                    .field("height", &height)
+                    .field(
+                        "compression",
+                        &if zstd_level.is_some() {
+                            rvl::COMPRESSION_ZSTD
+                        } else {
+                            rvl::COMPRESSION_NONE
+                        },
+                    )
+                    .build();
+                if let Ok(framerate) = structure.value("framerate") {
+                    src_caps
+                        .get_mut()
+                        .unwrap()
+                        .structure_mut(0)
+                        .unwrap()
+                        .set_value("framerate", framerate.clone());
+                }
+
+                *self.state.lock().unwrap() = State {
+                    sink_info: Some(sink_info),
+                    zstd_level,
+                };
+                self.src_pad.push_event(gst::event::Caps::new(&src_caps))
+            }
+            _ => pad.event_default(Some(element), event),
+        }
+    }
+
+    /// Compress a depth frame and push it downstream with the timestamps, flags and metas of
+    /// the incoming buffer, e.g. its tags. Rows of the frame may be padded, only the depth
+    /// values within the width of the frame are encoded.
+    /// # Arguments
+    /// * `element` - The element that represents the `rvlenc` in GStreamer.
+    /// * `buffer` - The buffer with `GRAY16_LE` depth.
+    fn sink_chain(
+        &self,
+        _: &gst::Pad,
+        element: &RvlEncObject,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let (sink_info, zstd_level) = {
+            let state = self.state.lock().unwrap();
+            let sink_info = state.sink_info.clone().ok_or_else(|| {
+                gst_warning!(CAT, obj: element, "Received a buffer before CAPS");
+                gst::FlowError::NotNegotiated
+            })?;
+            (sink_info, state.zstd_level)
+        };
+
+        let frame = gst_video::VideoFrameRef::from_buffer_ref_readable(&buffer, &sink_info)
+            .map_err(|_| {
+                gst::element_error!(
+                    element,
+                    gst::StreamError::Format,
+                    [
+                        "Depth buffer of {} bytes does not match CAPS of {}x{} pixels",
+                        buffer.size(),
+                        sink_info.width(),
+                        sink_info.height()
+                    ]
+                );
+                gst::FlowError::Error
+            })?;
+        let (width, height) = (frame.width() as usize, frame.height() as usize);
+        let pixel_count = width * height;
+        let stride = frame.plane_stride()[0] as usize;
+        let data = frame.plane_data(0).map_err(|_| gst::FlowError::Error)?;
+        let depth: Vec<u16> = (0..height)
+            .flat_map(|y| {
+                data[y * stride..y * stride + width * 2]
+                    .chunks_exact(2)
+                    .map(|value| u16::from_le_bytes([value[0], value[1]]))
+            })
+            .collect();
+        drop(frame);
+
+        let mut data = Vec::with_capacity(pixel_count);
+        rvl::encode(&depth, &mut data);
+        if let Some(level) = zstd_level {
+            data = zstd::bulk::compress(&data, level).map_err(|e| {
+                gst::element_error!(element, gst::StreamError::Encode, ["zstd failed: {}", e]);
+                gst::FlowError::Error
+            })?;
+        }
+        gst_trace!(
+            CAT,
+            obj: element,
+            "Compressed {} bytes of depth into {} bytes",
+            pixel_count * 2,
+            data.len()
+        );
+
+        let mut output = gst::Buffer::from_mut_slice(data);
+        buffer
+            .copy_into(
+                output.get_mut().unwrap(),
+                gst::BufferCopyFlags::FLAGS
+                    | gst::BufferCopyFlags::TIMESTAMPS
+                    | gst::BufferCopyFlags::META,
+                0,
+                None,
+            )
+            .map_err(|_| {
+                gst_error!(CAT, obj: element, "Cannot copy metadata of depth buffer");
+                gst::FlowError::Error
+            })?;
+        self.src_pad.push(output)
+    }
+}
+
+impl GstObjectImpl for RvlEnc {}
+impl ObjectImpl for RvlEnc {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<[glib::ParamSpec; 1]> = Lazy::new(|| {
+            [ParamSpecInt::new(
+                "zstd-level",
+                "zstd Level",
+                "Level of the zstd stage after RVL coding, 0 disables it. Changes apply with the
+                 next CAPS.",
+                0,
+                MAX_ZSTD_LEVEL,
+                DEFAULT_ZSTD_LEVEL,
+                ParamFlags::READWRITE,
+            )]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn constructed(&self, element: &Self::Type) {
+        self.parent_constructed(element);
+
+        element
+            .add_pad(&self.sink_pad)
+            .expect("rvlenc: Failed to add sink pad to the element");
+        element
+            .add_pad(&self.src_pad)
+            .expect("rvlenc: Failed to add src pad to the element");
+    }
+
+    fn set_property(
+        &self,
+        _obj: &Self::Type,
+        _id: usize,
+        value: &glib::Value,
+        pspec: &glib::ParamSpec,
+    ) {
+        let mut settings = self.settings.write().unwrap();
+
+        match pspec.name() {
+            prop @ "zstd-level" => {
+                settings.zstd_level =
+                    get_property_and_debug(*CAT, value, prop, settings.zstd_level);
+            }
+            _ => unimplemented!("Property is not implemented"),
+        };
+    }
+
+    fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = self.settings.read().unwrap();
+
+        match pspec.name() {
+            "zstd-level" => settings.zstd_level.to_value(),
+            _ => unimplemented!("Property is not implemented"),
+        }
+    }
+}
+
+pub fn register(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
+    gst::Element::register(Some(plugin), "rvlenc", gst::Rank::None, RvlEnc::type_())
+}
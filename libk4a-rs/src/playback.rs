@@ -132,7 +132,7 @@ impl Playback {
     /// * `name` - The name of the tag to read.
     ///
     /// # Returns
-    /// * `Ok(String)` containing the tag value on success.
+    /// * `Ok(String)` containing the tag value without its null terminator on success.
     /// * `Err(K4aError::Failure)` on failure.
     pub fn get_tag(&self, name: &str) -> Result<String> {
         let mut tag_length: u64 = 0;
@@ -162,7 +162,9 @@ impl Playback {
             )
         } {
             k4a_buffer_result_t::K4A_BUFFER_RESULT_SUCCEEDED => {
-                Ok(unsafe { String::from_utf8_unchecked(tag_value) })
+                // Strip null character (Cstring terminator), just like `Device` does
+                let tag_value = unsafe { String::from_utf8_unchecked(tag_value) };
+                Ok(tag_value.trim_end_matches('\0').to_string())
             }
             _ => Err(K4aError::Failure(
                 "Failed to acquire serial number from `Playback`",